@@ -138,6 +138,7 @@ mod original {
             ((a & 0x20) | ((a & 0x1f) >> 1) | ((a & 0x01) << 4)) as usize
         }
 
+        #[allow(clippy::needless_range_loop)]
         pub fn key_schedule(key: &[u8], schedule: &mut [Vec<u8>], mode: u32) {
             let key_rnd_shift: [u32; 16] = [1, 1, 2, 2, 2, 2, 2, 2, 1, 2, 2, 2, 2, 2, 2, 1];
             let key_perm_c: [usize; 28] = [
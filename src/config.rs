@@ -56,6 +56,14 @@ pub struct AmllConfig {
     #[serde(default)]
     /// AMLL 数据库的镜像源配置。
     pub mirror: AmllMirror,
+    #[serde(default)]
+    /// 离线模式。启用后不会进行任何网络请求，索引和歌词只从本地缓存（或本地数据库副本）读取。
+    pub offline: bool,
+    #[serde(default)]
+    /// 预先克隆或下载的 amll-ttml-db 仓库目录。
+    ///
+    /// 设置后，索引从 `metadata/raw-lyrics-index.jsonl` 加载，歌词优先从 `raw-lyrics/` 读取。
+    pub local_database: Option<PathBuf>,
 }
 
 /// 从文件加载 AMLL 的配置。如果文件不存在，则返回默认配置。
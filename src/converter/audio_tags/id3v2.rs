@@ -0,0 +1,958 @@
+//! `ID3v2` 标签中的歌词帧
+//!
+//! 支持 `ID3v2.2`、`ID3v2.3` 和 `ID3v2.4`，包括反同步、扩展头部以及压缩帧。
+
+use std::{borrow::Cow, io::Read};
+
+use flate2::read::ZlibDecoder;
+use tracing::warn;
+
+use super::{
+    EmbeddedLyrics, EmbeddedLyricsContent, EmbeddedLyricsSource, LyricsToEmbed, SyncedText,
+    TagChange, TagChangeAction, decode_latin1,
+};
+use crate::converter::types::ConvertError;
+
+/// `ID3v2` 标签头部的长度。
+const HEADER_LEN: usize = 10;
+
+/// 标签头部标志：反同步。
+const TAG_FLAG_UNSYNCHRONISATION: u8 = 0x80;
+/// 标签头部标志：存在扩展头部。
+const TAG_FLAG_EXTENDED_HEADER: u8 = 0x40;
+/// 标签头部标志：存在尾部。
+const TAG_FLAG_FOOTER: u8 = 0x10;
+
+/// `SYLT` 时间戳格式：毫秒。
+const SYLT_TIMESTAMP_MS: u8 = 2;
+
+/// `ID3v2` 文本编码。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TextEncoding {
+    Latin1,
+    /// 带 BOM 的 UTF-16
+    Utf16,
+    Utf16Be,
+    Utf8,
+}
+
+impl TextEncoding {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Latin1),
+            1 => Some(Self::Utf16),
+            2 => Some(Self::Utf16Be),
+            3 => Some(Self::Utf8),
+            _ => None,
+        }
+    }
+
+    fn is_wide(self) -> bool {
+        matches!(self, Self::Utf16 | Self::Utf16Be)
+    }
+}
+
+/// 读取同步安全整数（每个字节只使用低 7 位）。
+pub(crate) fn read_synchsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take(4)
+        .fold(0, |acc, &b| (acc << 7) | usize::from(b & 0x7F))
+}
+
+/// 计算文件开头 `ID3v2` 标签的总长度（包括头部和尾部）。
+pub(crate) fn tag_size(data: &[u8]) -> Option<usize> {
+    if data.len() < HEADER_LEN || !data.starts_with(b"ID3") {
+        return None;
+    }
+    let footer_len = if data[5] & TAG_FLAG_FOOTER == 0 {
+        0
+    } else {
+        HEADER_LEN
+    };
+    Some(HEADER_LEN + read_synchsafe(&data[6..10]) + footer_len)
+}
+
+/// 移除反同步：将 `FF 00` 还原为 `FF`。
+pub(crate) fn remove_unsynchronisation(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len());
+    let mut previous_was_ff = false;
+    for &byte in data {
+        if previous_was_ff && byte == 0x00 {
+            previous_was_ff = false;
+            continue;
+        }
+        output.push(byte);
+        previous_was_ff = byte == 0xFF;
+    }
+    output
+}
+
+/// `ID3v2` 标签中的一个帧。
+#[derive(Debug)]
+pub(crate) struct Frame {
+    /// 帧 ID，`ID3v2.2` 中为三个字符
+    pub id: String,
+    /// 已经移除反同步并解压的帧内容
+    pub data: Vec<u8>,
+}
+
+/// 标签主体中一个帧的原始位置。
+#[derive(Debug)]
+struct RawFrame {
+    id: String,
+    flags: u8,
+    /// 帧头部在标签主体中的起始位置
+    start: usize,
+    /// 帧内容的长度（不含头部）
+    size: usize,
+}
+
+/// 解析后的 `ID3v2` 标签结构，写入时用于原样复制其他帧。
+#[derive(Debug)]
+struct TagLayout<'a> {
+    version: u8,
+    flags: u8,
+    /// 标签主体（版本低于 2.4 时已经移除了整体反同步）
+    body: Cow<'a, [u8]>,
+    /// 头部中记录的主体长度
+    body_size: usize,
+    /// 扩展头部之后、第一个帧的起始位置
+    frames_start: usize,
+    frames: Vec<RawFrame>,
+}
+
+impl TagLayout<'_> {
+    fn frame_header_len(&self) -> usize {
+        if self.version == 2 { 6 } else { 10 }
+    }
+
+    fn tag_unsynchronised(&self) -> bool {
+        self.flags & TAG_FLAG_UNSYNCHRONISATION != 0
+    }
+}
+
+/// 解析文件开头的 `ID3v2` 标签结构。
+fn parse_tag(data: &[u8]) -> Result<TagLayout<'_>, ConvertError> {
+    let invalid = |msg: &str| ConvertError::InvalidLyricFormat(format!("ID3v2 标签无效: {msg}"));
+
+    if tag_size(data).is_none() {
+        return Err(invalid("缺少 ID3 头部"));
+    }
+    let version = data[3];
+    if !(2..=4).contains(&version) {
+        return Err(invalid(&format!("不支持的版本 2.{version}")));
+    }
+    let flags = data[5];
+    let body_size = read_synchsafe(&data[6..10]);
+    let body = data
+        .get(HEADER_LEN..HEADER_LEN + body_size)
+        .ok_or_else(|| invalid("标签长度超出文件长度"))?;
+
+    // ID3v2.4 的反同步是按帧进行的，更早的版本则作用于整个标签
+    let body: Cow<'_, [u8]> = if flags & TAG_FLAG_UNSYNCHRONISATION != 0 && version < 4 {
+        Cow::Owned(remove_unsynchronisation(body))
+    } else {
+        Cow::Borrowed(body)
+    };
+
+    let mut pos = 0;
+    if flags & TAG_FLAG_EXTENDED_HEADER != 0 && version >= 3 {
+        let size_bytes = body.get(0..4).ok_or_else(|| invalid("扩展头部过短"))?;
+        pos = if version == 4 {
+            read_synchsafe(size_bytes)
+        } else {
+            u32_be(size_bytes) + 4
+        };
+    }
+    let frames_start = pos;
+
+    let (id_len, header_len) = if version == 2 { (3, 6) } else { (4, 10) };
+    let mut frames = Vec::new();
+
+    while pos + header_len <= body.len() {
+        let header = &body[pos..pos + header_len];
+        // 遇到填充区域
+        if header[0] == 0 {
+            break;
+        }
+        let id = String::from_utf8_lossy(&header[..id_len]).into_owned();
+        let size = match version {
+            2 => {
+                (usize::from(header[3]) << 16)
+                    | (usize::from(header[4]) << 8)
+                    | usize::from(header[5])
+            }
+            3 => u32_be(&header[4..8]),
+            _ => read_synchsafe(&header[4..8]),
+        };
+        let frame_flags = if version == 2 { 0 } else { header[9] };
+
+        if pos + header_len + size > body.len() {
+            warn!("[ID3v2] 帧 {id} 的长度超出标签范围，停止读取。");
+            break;
+        }
+        frames.push(RawFrame {
+            id,
+            flags: frame_flags,
+            start: pos,
+            size,
+        });
+        pos += header_len + size;
+    }
+
+    Ok(TagLayout {
+        version,
+        flags,
+        body,
+        body_size,
+        frames_start,
+        frames,
+    })
+}
+
+/// 读取文件开头 `ID3v2` 标签中的所有帧。
+///
+/// 加密的帧会被跳过。
+pub(crate) fn read_frames(data: &[u8]) -> Result<(u8, Vec<Frame>), ConvertError> {
+    let layout = parse_tag(data)?;
+    let header_len = layout.frame_header_len();
+
+    let frames = layout
+        .frames
+        .iter()
+        .filter_map(|frame| {
+            let raw = &layout.body[frame.start + header_len..frame.start + header_len + frame.size];
+            if let Some(data) = decode_frame_data(
+                layout.version,
+                frame.flags,
+                layout.tag_unsynchronised(),
+                raw,
+            ) {
+                Some(Frame {
+                    id: frame.id.clone(),
+                    data: data.into_owned(),
+                })
+            } else {
+                warn!("[ID3v2] 跳过无法解码的帧 {}。", frame.id);
+                None
+            }
+        })
+        .collect();
+
+    Ok((layout.version, frames))
+}
+
+/// 根据帧标志处理帧数据（分组、加密、压缩、反同步）。
+fn decode_frame_data(
+    version: u8,
+    frame_flags: u8,
+    tag_unsynchronised: bool,
+    raw: &[u8],
+) -> Option<Cow<'_, [u8]>> {
+    let mut data = raw;
+    let (compressed, encrypted, unsynchronised) = match version {
+        3 => {
+            let compressed = frame_flags & 0x80 != 0;
+            if compressed {
+                // 解压后的长度
+                data = data.get(4..)?;
+            }
+            if frame_flags & 0x40 != 0 {
+                data = data.get(1..)?;
+            }
+            if frame_flags & 0x20 != 0 {
+                data = data.get(1..)?;
+            }
+            (compressed, frame_flags & 0x40 != 0, false)
+        }
+        4 => {
+            if frame_flags & 0x40 != 0 {
+                data = data.get(1..)?;
+            }
+            if frame_flags & 0x04 != 0 {
+                data = data.get(1..)?;
+            }
+            if frame_flags & 0x01 != 0 {
+                data = data.get(4..)?;
+            }
+            (
+                frame_flags & 0x08 != 0,
+                frame_flags & 0x04 != 0,
+                tag_unsynchronised || frame_flags & 0x02 != 0,
+            )
+        }
+        _ => (false, false, false),
+    };
+
+    if encrypted {
+        return None;
+    }
+
+    let mut data: Cow<'_, [u8]> = if unsynchronised {
+        Cow::Owned(remove_unsynchronisation(data))
+    } else {
+        Cow::Borrowed(data)
+    };
+    if compressed {
+        let mut decompressed = Vec::new();
+        ZlibDecoder::new(data.as_ref())
+            .read_to_end(&mut decompressed)
+            .ok()?;
+        data = Cow::Owned(decompressed);
+    }
+    Some(data)
+}
+
+/// 读取 MP3 文件 `ID3v2` 标签中的 `USLT` 和 `SYLT` 帧。
+pub fn read_lyrics(data: &[u8]) -> Result<Vec<EmbeddedLyrics>, ConvertError> {
+    if tag_size(data).is_none() {
+        return Ok(Vec::new());
+    }
+    let (_, frames) = read_frames(data)?;
+    Ok(frames
+        .iter()
+        .filter_map(|frame| match frame.id.as_str() {
+            "USLT" | "ULT" => parse_uslt(&frame.data),
+            "SYLT" | "SLT" => parse_sylt(&frame.data),
+            _ => None,
+        })
+        .collect())
+}
+
+/// 解析 `USLT` 帧：编码、语言、描述、歌词文本。
+fn parse_uslt(data: &[u8]) -> Option<EmbeddedLyrics> {
+    let encoding = TextEncoding::from_byte(*data.first()?)?;
+    let language = decode_language(data.get(1..4)?);
+    let (description, text) = split_terminated(data.get(4..)?, encoding);
+
+    let mut wide_is_le = true;
+    let description = decode_text(description, encoding, &mut wide_is_le);
+    let text = decode_text(text, encoding, &mut wide_is_le);
+
+    Some(EmbeddedLyrics {
+        source: EmbeddedLyricsSource::Id3Uslt,
+        language,
+        description: Some(description).filter(|d| !d.is_empty()),
+        content: EmbeddedLyricsContent::Text(text),
+    })
+}
+
+/// 解析 `SYLT` 帧：编码、语言、时间戳格式、内容类型、描述，然后是若干“文本 + 时间戳”。
+fn parse_sylt(data: &[u8]) -> Option<EmbeddedLyrics> {
+    let encoding = TextEncoding::from_byte(*data.first()?)?;
+    let language = decode_language(data.get(1..4)?);
+    let timestamp_format = *data.get(4)?;
+    if timestamp_format != SYLT_TIMESTAMP_MS {
+        warn!("[ID3v2] 不支持以 MPEG 帧为单位的 SYLT 时间戳，已跳过。");
+        return None;
+    }
+    let (description, mut rest) = split_terminated(data.get(6..)?, encoding);
+
+    let mut wide_is_le = true;
+    let description = decode_text(description, encoding, &mut wide_is_le);
+
+    let mut synced = Vec::new();
+    while !rest.is_empty() {
+        let (text, after_text) = split_terminated(rest, encoding);
+        let Some(timestamp) = after_text.get(0..4) else {
+            break;
+        };
+        synced.push(SyncedText {
+            timestamp_ms: u32_be(timestamp) as u64,
+            text: decode_text(text, encoding, &mut wide_is_le),
+        });
+        rest = &after_text[4..];
+    }
+
+    Some(EmbeddedLyrics {
+        source: EmbeddedLyricsSource::Id3Sylt,
+        language,
+        description: Some(description).filter(|d| !d.is_empty()),
+        content: EmbeddedLyricsContent::Synced(synced),
+    })
+}
+
+/// 读取 `USLT` 或 `SYLT` 帧的语言代码和描述。
+///
+/// `SYLT` 帧在描述之前还有时间戳格式和内容类型两个字节。
+fn lyrics_frame_key(data: &[u8], is_sylt: bool) -> Option<([u8; 3], String)> {
+    let encoding = TextEncoding::from_byte(*data.first()?)?;
+    let language = data.get(1..4)?.try_into().ok()?;
+    let description_start = if is_sylt { 6 } else { 4 };
+    let (description, _) = split_terminated(data.get(description_start..)?, encoding);
+    let mut wide_is_le = true;
+    Some((
+        language,
+        decode_text(description, encoding, &mut wide_is_le),
+    ))
+}
+
+fn decode_language(bytes: &[u8]) -> Option<String> {
+    let language = decode_latin1(bytes)
+        .trim_matches(char::from(0))
+        .trim()
+        .to_string();
+    Some(language).filter(|l| !l.is_empty())
+}
+
+/// 在给定编码下，按字符串结束符把数据切分为两部分（结束符本身被丢弃）。
+pub(crate) fn split_terminated(data: &[u8], encoding: TextEncoding) -> (&[u8], &[u8]) {
+    if encoding.is_wide() {
+        let mut i = 0;
+        while i + 1 < data.len() {
+            if data[i] == 0 && data[i + 1] == 0 {
+                return (&data[..i], &data[i + 2..]);
+            }
+            i += 2;
+        }
+        (data, &[])
+    } else {
+        match data.iter().position(|&b| b == 0) {
+            Some(i) => (&data[..i], &data[i + 1..]),
+            None => (data, &[]),
+        }
+    }
+}
+
+/// 解码 `ID3v2` 文本。
+///
+/// 对于带 BOM 的 UTF-16，`wide_is_le` 记录最近一次看到的字节序，
+/// 以便处理只有第一个字符串带 BOM 的 `SYLT` 帧。
+pub(crate) fn decode_text(bytes: &[u8], encoding: TextEncoding, wide_is_le: &mut bool) -> String {
+    let text = match encoding {
+        TextEncoding::Latin1 => decode_latin1(bytes),
+        TextEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+        TextEncoding::Utf16 | TextEncoding::Utf16Be => {
+            let mut bytes = bytes;
+            if encoding == TextEncoding::Utf16Be {
+                *wide_is_le = false;
+            }
+            if bytes.starts_with(&[0xFF, 0xFE]) {
+                *wide_is_le = true;
+                bytes = &bytes[2..];
+            } else if bytes.starts_with(&[0xFE, 0xFF]) {
+                *wide_is_le = false;
+                bytes = &bytes[2..];
+            }
+            let units = bytes.chunks_exact(2).map(|pair| {
+                if *wide_is_le {
+                    u16::from_le_bytes([pair[0], pair[1]])
+                } else {
+                    u16::from_be_bytes([pair[0], pair[1]])
+                }
+            });
+            char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+    };
+    text.trim_end_matches(char::from(0)).to_string()
+}
+
+fn u32_be(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take(4)
+        .fold(0, |acc, &b| (acc << 8) | usize::from(b))
+}
+
+/// 标签已有足够空间时保留原长度；否则扩展标签时额外预留的填充长度。
+const DEFAULT_PADDING: usize = 1024;
+
+/// 写入的歌词帧类型：歌词。
+const SYLT_CONTENT_TYPE_LYRICS: u8 = 1;
+
+/// 写入 MP3 文件的 `USLT` 和 `SYLT` 帧，返回新的文件内容和变更列表。
+///
+/// 只替换语言和描述都与新歌词相同的 `USLT`、`SYLT` 帧，其他语言或描述的歌词帧、
+/// 其他帧和音频数据保持不变。
+/// 如果新的帧能放进原有的填充区域，标签长度也不会改变。
+/// 文件没有 `ID3v2` 标签时会新建一个 `ID3v2.3` 标签。
+pub(crate) fn write_lyrics(
+    data: &[u8],
+    lyrics: &LyricsToEmbed,
+) -> Result<(Vec<u8>, Vec<TagChange>), ConvertError> {
+    if tag_size(data).is_none() {
+        return write_new_tag(data, lyrics);
+    }
+
+    let layout = parse_tag(data)?;
+    let version = layout.version;
+    let header_len = layout.frame_header_len();
+    let (uslt_id, sylt_id) = lyrics_frame_ids(version);
+    // ID3v2.4 中，标签头部的反同步标志表示所有帧都经过了反同步
+    let unsync_frames = version == 4 && layout.tag_unsynchronised();
+
+    // 判断已有的歌词帧是否与新歌词的语言和描述相同，无法解码的帧不会被替换
+    let target_language = language_bytes(&lyrics.language);
+    let is_replaced = |frame: &RawFrame, is_sylt: bool| {
+        let raw = &layout.body[frame.start + header_len..frame.start + header_len + frame.size];
+        decode_frame_data(version, frame.flags, layout.tag_unsynchronised(), raw)
+            .and_then(|data| lyrics_frame_key(&data, is_sylt))
+            .is_some_and(|(language, description)| {
+                language.eq_ignore_ascii_case(&target_language) && description == lyrics.description
+            })
+    };
+
+    let mut body = layout.body[..layout.frames_start].to_vec();
+    let (mut old_uslt, mut old_sylt) = (0, 0);
+    for frame in &layout.frames {
+        if frame.id == uslt_id && is_replaced(frame, false) {
+            old_uslt += 1;
+        } else if frame.id == sylt_id && is_replaced(frame, true) {
+            old_sylt += 1;
+        } else {
+            body.extend_from_slice(
+                &layout.body[frame.start..frame.start + header_len + frame.size],
+            );
+        }
+    }
+
+    let changes = encode_lyrics_frames(
+        version,
+        unsync_frames,
+        lyrics,
+        old_uslt,
+        old_sylt,
+        &mut body,
+    )?;
+
+    let mut body = if layout.tag_unsynchronised() && version < 4 {
+        add_unsynchronisation(&body)
+    } else {
+        body
+    };
+
+    let has_footer = data[5] & TAG_FLAG_FOOTER != 0;
+    // 有尾部的标签不允许填充
+    if !has_footer {
+        let target_len = if body.len() <= layout.body_size {
+            layout.body_size
+        } else {
+            body.len() + DEFAULT_PADDING
+        };
+        body.resize(target_len, 0);
+    }
+    let size = write_synchsafe(body.len()).ok_or_else(tag_too_large)?;
+
+    let mut output = Vec::with_capacity(data.len() + body.len());
+    output.extend_from_slice(&data[..6]);
+    output.extend_from_slice(&size);
+    output.extend_from_slice(&body);
+    if has_footer {
+        output.extend_from_slice(b"3DI");
+        output.extend_from_slice(&data[3..6]);
+        output.extend_from_slice(&size);
+    }
+    output.extend_from_slice(&data[tag_size(data).unwrap_or(0)..]);
+    Ok((output, changes))
+}
+
+/// 为没有 `ID3v2` 标签的文件新建一个 `ID3v2.3` 标签。
+fn write_new_tag(
+    data: &[u8],
+    lyrics: &LyricsToEmbed,
+) -> Result<(Vec<u8>, Vec<TagChange>), ConvertError> {
+    let mut body = Vec::new();
+    let changes = encode_lyrics_frames(3, false, lyrics, 0, 0, &mut body)?;
+    body.resize(body.len() + DEFAULT_PADDING, 0);
+
+    let mut output = Vec::with_capacity(HEADER_LEN + body.len() + data.len());
+    output.extend_from_slice(b"ID3\x03\x00\x00");
+    output.extend_from_slice(&write_synchsafe(body.len()).ok_or_else(tag_too_large)?);
+    output.extend_from_slice(&body);
+    output.extend_from_slice(data);
+    Ok((output, changes))
+}
+
+fn tag_too_large() -> ConvertError {
+    ConvertError::InvalidLyricFormat("ID3v2 标签长度超出上限".to_string())
+}
+
+fn lyrics_frame_ids(version: u8) -> (&'static str, &'static str) {
+    if version == 2 {
+        ("ULT", "SLT")
+    } else {
+        ("USLT", "SYLT")
+    }
+}
+
+/// 编码新的歌词帧并追加到 `body`，同时根据被移除的旧帧数量生成变更列表。
+fn encode_lyrics_frames(
+    version: u8,
+    unsync_frames: bool,
+    lyrics: &LyricsToEmbed,
+    old_uslt: usize,
+    old_sylt: usize,
+    body: &mut Vec<u8>,
+) -> Result<Vec<TagChange>, ConvertError> {
+    let (uslt_id, sylt_id) = lyrics_frame_ids(version);
+    // ID3v2.4 才支持 UTF-8，更早的版本使用带 BOM 的 UTF-16
+    let encoding = if version == 4 {
+        TextEncoding::Utf8
+    } else {
+        TextEncoding::Utf16
+    };
+
+    let uslt = build_uslt(encoding, lyrics);
+    body.extend(encode_frame(version, uslt_id, &uslt, unsync_frames)?);
+    let mut changes = vec![TagChange::new(
+        EmbeddedLyricsSource::Id3Uslt,
+        old_uslt,
+        uslt.len(),
+    )];
+
+    if let Some(synced) = &lyrics.synced {
+        let sylt = build_sylt(encoding, lyrics, synced);
+        body.extend(encode_frame(version, sylt_id, &sylt, unsync_frames)?);
+        changes.push(TagChange::new(
+            EmbeddedLyricsSource::Id3Sylt,
+            old_sylt,
+            sylt.len(),
+        ));
+    } else if old_sylt > 0 {
+        changes.push(TagChange {
+            target: EmbeddedLyricsSource::Id3Sylt,
+            action: TagChangeAction::Removed,
+            new_len: 0,
+        });
+    }
+    Ok(changes)
+}
+
+/// 编码一个帧（头部 + 内容）。
+fn encode_frame(
+    version: u8,
+    id: &str,
+    data: &[u8],
+    unsynchronise: bool,
+) -> Result<Vec<u8>, ConvertError> {
+    let data: Cow<'_, [u8]> = if unsynchronise {
+        Cow::Owned(add_unsynchronisation(data))
+    } else {
+        Cow::Borrowed(data)
+    };
+    let mut frame = id.as_bytes().to_vec();
+    match version {
+        2 => {
+            let size = u32::try_from(data.len())
+                .ok()
+                .filter(|&size| size < 1 << 24)
+                .ok_or_else(tag_too_large)?;
+            frame.extend_from_slice(&size.to_be_bytes()[1..]);
+        }
+        3 => {
+            let size = u32::try_from(data.len()).map_err(|_| tag_too_large())?;
+            frame.extend_from_slice(&size.to_be_bytes());
+            frame.extend_from_slice(&[0, 0]);
+        }
+        _ => {
+            frame.extend_from_slice(&write_synchsafe(data.len()).ok_or_else(tag_too_large)?);
+            frame.extend_from_slice(&[0, if unsynchronise { 0x02 } else { 0 }]);
+        }
+    }
+    frame.extend_from_slice(&data);
+    Ok(frame)
+}
+
+/// 构建 `USLT` 帧内容：编码、语言、描述、歌词文本。
+fn build_uslt(encoding: TextEncoding, lyrics: &LyricsToEmbed) -> Vec<u8> {
+    let mut data = vec![encoding_byte(encoding)];
+    data.extend_from_slice(&language_bytes(&lyrics.language));
+    data.extend(encode_text(&lyrics.description, encoding));
+    data.extend_from_slice(terminator(encoding));
+    data.extend(encode_text(&lyrics.text, encoding));
+    data
+}
+
+/// 构建 `SYLT` 帧内容，时间戳使用毫秒。
+fn build_sylt(encoding: TextEncoding, lyrics: &LyricsToEmbed, synced: &[SyncedText]) -> Vec<u8> {
+    let mut data = vec![encoding_byte(encoding)];
+    data.extend_from_slice(&language_bytes(&lyrics.language));
+    data.extend_from_slice(&[SYLT_TIMESTAMP_MS, SYLT_CONTENT_TYPE_LYRICS]);
+    data.extend(encode_text(&lyrics.description, encoding));
+    data.extend_from_slice(terminator(encoding));
+    for segment in synced {
+        data.extend(encode_text(&segment.text, encoding));
+        data.extend_from_slice(terminator(encoding));
+        let timestamp = u32::try_from(segment.timestamp_ms).unwrap_or(u32::MAX);
+        data.extend_from_slice(&timestamp.to_be_bytes());
+    }
+    data
+}
+
+fn encoding_byte(encoding: TextEncoding) -> u8 {
+    match encoding {
+        TextEncoding::Latin1 => 0,
+        TextEncoding::Utf16 => 1,
+        TextEncoding::Utf16Be => 2,
+        TextEncoding::Utf8 => 3,
+    }
+}
+
+fn language_bytes(language: &str) -> [u8; 3] {
+    let mut bytes = *b"XXX";
+    for (slot, byte) in bytes.iter_mut().zip(language.bytes().filter(u8::is_ascii)) {
+        *slot = byte;
+    }
+    bytes
+}
+
+fn terminator(encoding: TextEncoding) -> &'static [u8] {
+    if encoding.is_wide() { &[0, 0] } else { &[0] }
+}
+
+/// 按指定编码编码文本（不含结束符）。带 BOM 的 UTF-16 使用小端序。
+fn encode_text(text: &str, encoding: TextEncoding) -> Vec<u8> {
+    match encoding {
+        TextEncoding::Latin1 => text
+            .chars()
+            .map(|c| u8::try_from(c).unwrap_or(b'?'))
+            .collect(),
+        TextEncoding::Utf8 => text.as_bytes().to_vec(),
+        TextEncoding::Utf16 => {
+            let mut bytes = vec![0xFF, 0xFE];
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            bytes
+        }
+        TextEncoding::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+    }
+}
+
+/// 编码同步安全整数，超出 28 位时返回 `None`。
+pub(crate) fn write_synchsafe(value: usize) -> Option<[u8; 4]> {
+    if value >= 1 << 28 {
+        return None;
+    }
+    let byte = |shift: usize| u8::try_from((value >> shift) & 0x7F).unwrap_or(0);
+    Some([byte(21), byte(14), byte(7), byte(0)])
+}
+
+/// 添加反同步：在 `FF` 后面紧跟 `00` 或不小于 `E0` 的字节时插入 `00`。
+pub(crate) fn add_unsynchronisation(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len());
+    for (i, &byte) in data.iter().enumerate() {
+        output.push(byte);
+        if byte == 0xFF
+            && data
+                .get(i + 1)
+                .is_none_or(|&next| next == 0 || next >= 0xE0)
+        {
+            output.push(0);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_tag(version: u8, frames: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (id, data) in frames {
+            body.extend_from_slice(id.as_bytes());
+            if version == 4 {
+                body.extend_from_slice(&write_synchsafe(data.len()).unwrap());
+            } else {
+                body.extend_from_slice(&u32::try_from(data.len()).unwrap().to_be_bytes());
+            }
+            body.extend_from_slice(&[0, 0]);
+            body.extend_from_slice(data);
+        }
+        // 填充
+        body.extend_from_slice(&[0; 16]);
+
+        let mut tag = b"ID3".to_vec();
+        tag.extend_from_slice(&[version, 0, 0]);
+        tag.extend_from_slice(&write_synchsafe(body.len()).unwrap());
+        tag.extend_from_slice(&body);
+        tag.extend_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+        tag
+    }
+
+    #[test]
+    fn test_read_uslt_utf16_and_sylt() {
+        let mut uslt = vec![1];
+        uslt.extend_from_slice(b"eng");
+        uslt.extend_from_slice(&[0xFF, 0xFE, 0, 0]);
+        uslt.extend_from_slice(&[0xFF, 0xFE]);
+        for unit in "[00:01.00]你好".encode_utf16() {
+            uslt.extend_from_slice(&unit.to_le_bytes());
+        }
+
+        let mut sylt = vec![3];
+        sylt.extend_from_slice(b"chi");
+        sylt.extend_from_slice(&[SYLT_TIMESTAMP_MS, 1]);
+        sylt.extend_from_slice(b"desc\0");
+        for (text, ts) in [("第一行", 1000u32), ("第二行", 2500)] {
+            sylt.extend_from_slice(text.as_bytes());
+            sylt.push(0);
+            sylt.extend_from_slice(&ts.to_be_bytes());
+        }
+
+        let data = build_tag(
+            3,
+            &[
+                ("TIT2", b"\0Title".to_vec()),
+                ("USLT", uslt),
+                ("SYLT", sylt),
+            ],
+        );
+        let lyrics = read_lyrics(&data).unwrap();
+
+        assert_eq!(lyrics.len(), 2);
+        assert_eq!(lyrics[0].source, EmbeddedLyricsSource::Id3Uslt);
+        assert_eq!(lyrics[0].language.as_deref(), Some("eng"));
+        assert_eq!(lyrics[0].description, None);
+        assert_eq!(
+            lyrics[0].content,
+            EmbeddedLyricsContent::Text("[00:01.00]你好".to_string())
+        );
+
+        assert_eq!(lyrics[1].description.as_deref(), Some("desc"));
+        assert_eq!(
+            lyrics[1].content,
+            EmbeddedLyricsContent::Synced(vec![
+                SyncedText {
+                    timestamp_ms: 1000,
+                    text: "第一行".to_string(),
+                },
+                SyncedText {
+                    timestamp_ms: 2500,
+                    text: "第二行".to_string(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_read_v24_unsynchronised_frame() {
+        let mut uslt = vec![0];
+        uslt.extend_from_slice(b"XXX\0");
+        uslt.extend_from_slice(&[b'a', 0xFF, 0x00, b'b']);
+        let data = build_tag(4, &[("USLT", uslt)]);
+        // 设置帧的反同步标志
+        let mut data = data;
+        data[HEADER_LEN + 9] = 0x02;
+
+        let lyrics = read_lyrics(&data).unwrap();
+        assert_eq!(
+            lyrics[0].content,
+            EmbeddedLyricsContent::Text("a\u{ff}b".to_string())
+        );
+    }
+
+    fn lyrics_to_embed(synced: Option<Vec<SyncedText>>) -> LyricsToEmbed {
+        LyricsToEmbed {
+            text: "[00:01.00]新的歌词".to_string(),
+            synced,
+            language: "chi".to_string(),
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_write_lyrics_keeps_other_frames_and_padding() {
+        let mut old_uslt = vec![0];
+        old_uslt.extend_from_slice(b"chi\0old");
+        let data = build_tag(3, &[("TIT2", b"\0Title".to_vec()), ("USLT", old_uslt)]);
+        // 扩大填充区域，使新的帧可以放进原标签
+        let tag_len = tag_size(&data).unwrap();
+        let mut body = data[HEADER_LEN..tag_len].to_vec();
+        body.resize(body.len() + 256, 0);
+        let mut data_with_padding = data[..6].to_vec();
+        data_with_padding.extend_from_slice(&write_synchsafe(body.len()).unwrap());
+        data_with_padding.extend_from_slice(&body);
+        data_with_padding.extend_from_slice(&data[tag_len..]);
+
+        let synced = vec![SyncedText {
+            timestamp_ms: 1000,
+            text: "新的歌词".to_string(),
+        }];
+        let (output, changes) =
+            write_lyrics(&data_with_padding, &lyrics_to_embed(Some(synced.clone()))).unwrap();
+
+        assert_eq!(output.len(), data_with_padding.len());
+        assert!(output.ends_with(&[0xFF, 0xFB, 0x90, 0x00]));
+        assert_eq!(changes[0].action, TagChangeAction::Replaced);
+        assert_eq!(changes[1].action, TagChangeAction::Added);
+
+        let (_, frames) = read_frames(&output).unwrap();
+        assert_eq!(frames[0].id, "TIT2");
+        assert_eq!(frames[0].data, b"\0Title");
+
+        let lyrics = read_lyrics(&output).unwrap();
+        assert_eq!(lyrics.len(), 2);
+        assert_eq!(lyrics[0].language.as_deref(), Some("chi"));
+        assert_eq!(
+            lyrics[0].content,
+            EmbeddedLyricsContent::Text("[00:01.00]新的歌词".to_string())
+        );
+        assert_eq!(lyrics[1].content, EmbeddedLyricsContent::Synced(synced));
+    }
+
+    #[test]
+    fn test_write_lyrics_keeps_lyrics_in_other_languages() {
+        let mut english = vec![0];
+        english.extend_from_slice(b"eng\0english");
+        let mut old_chinese = vec![0];
+        old_chinese.extend_from_slice(b"CHI\0old");
+        let mut described = vec![0];
+        described.extend_from_slice(b"chidesc\0other");
+        let data = build_tag(
+            3,
+            &[
+                ("USLT", english.clone()),
+                ("USLT", old_chinese),
+                ("USLT", described.clone()),
+            ],
+        );
+
+        let (output, changes) = write_lyrics(&data, &lyrics_to_embed(None)).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].target, EmbeddedLyricsSource::Id3Uslt);
+        assert_eq!(changes[0].action, TagChangeAction::Replaced);
+
+        let (_, frames) = read_frames(&output).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].data, english);
+        assert_eq!(frames[1].data, described);
+        let lyrics = read_lyrics(&output).unwrap();
+        assert_eq!(
+            lyrics[2].content,
+            EmbeddedLyricsContent::Text("[00:01.00]新的歌词".to_string())
+        );
+
+        // 只有其他语言的歌词时，新的歌词是新增的
+        let data = build_tag(3, &[("USLT", english)]);
+        let (output, changes) = write_lyrics(&data, &lyrics_to_embed(None)).unwrap();
+        assert_eq!(changes[0].action, TagChangeAction::Added);
+        assert_eq!(read_lyrics(&output).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_write_lyrics_creates_tag() {
+        let audio = [0xFF, 0xFB, 0x90, 0x00, 1, 2, 3];
+        let (output, changes) = write_lyrics(&audio, &lyrics_to_embed(None)).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].action, TagChangeAction::Added);
+        assert_eq!(&output[..4], b"ID3\x03");
+        assert_eq!(&output[tag_size(&output).unwrap()..], &audio);
+        assert_eq!(read_lyrics(&output).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_unsynchronisation_round_trip() {
+        let data = [0xFF, 0xE0, 0x01, 0xFF, 0x00, 0xFF];
+        let unsynchronised = add_unsynchronisation(&data);
+        assert_eq!(
+            unsynchronised,
+            [0xFF, 0x00, 0xE0, 0x01, 0xFF, 0x00, 0x00, 0xFF, 0x00]
+        );
+        assert_eq!(remove_unsynchronisation(&unsynchronised), data);
+    }
+}
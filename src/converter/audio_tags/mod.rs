@@ -0,0 +1,734 @@
+//! 音频文件内嵌歌词模块
+//!
+//! 从音频文件的标签中读取歌词，不依赖任何外部工具：
+//! - MP3：`ID3v2` 的 `USLT`（非同步歌词）和 `SYLT`（同步歌词）帧。
+//! - FLAC / Ogg：Vorbis 注释中的 `LYRICS` 和 `UNSYNCEDLYRICS` 字段。
+//! - MP4 / M4A：`moov/udta/meta/ilst` 中的 `©lyr` 原子。
+//!
+//! `SYLT` 帧自带时间戳，会被直接转换为 `LyricLine`；其他文本形式的歌词会先自动检测格式，
+//! 再交给对应的解析器处理。
+//!
+//! 也可以把转换结果写回这些标签。写入时只替换歌词相关的帧、字段或原子，
+//! 其余标签和音频数据按原样复制。
+
+pub mod id3v2;
+pub mod mp4;
+pub mod vorbis;
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::converter::{
+    generate_from_parsed, parse_input_file,
+    processors::metadata_processor::MetadataStore,
+    types::{
+        AnnotatedTrack, CanonicalMetadataKey, ContentType, ConversionOptions, ConvertError,
+        InputFile, LyricFormat, LyricLine, LyricSyllable, LyricTrack, ParsedSourceData,
+        TrackMetadataKey, Word,
+    },
+    utils::{detect_lyric_format, normalize_text_whitespace, process_syllable_text},
+};
+
+/// 同步歌词最后一行无法从下一行推断结束时间时，使用的默认时长（毫秒）。
+const LAST_SYNCED_LINE_DURATION_MS: u64 = 5000;
+
+/// ISO 639-1 两字母语言代码到 `ID3v2` 使用的 ISO 639-2 三字母代码的映射。
+const ISO_639_2_CODES: &[(&str, &str)] = &[
+    ("ar", "ara"),
+    ("de", "ger"),
+    ("en", "eng"),
+    ("es", "spa"),
+    ("fr", "fre"),
+    ("hi", "hin"),
+    ("id", "ind"),
+    ("it", "ita"),
+    ("ja", "jpn"),
+    ("ko", "kor"),
+    ("ms", "may"),
+    ("nl", "dut"),
+    ("pl", "pol"),
+    ("pt", "por"),
+    ("ru", "rus"),
+    ("sv", "swe"),
+    ("th", "tha"),
+    ("tl", "tgl"),
+    ("tr", "tur"),
+    ("uk", "ukr"),
+    ("vi", "vie"),
+    ("zh", "chi"),
+];
+
+/// 音频文件的容器格式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AudioContainer {
+    /// 以 `ID3v2` 标签开头的文件（通常是 MP3）。
+    Mp3,
+    /// FLAC 文件。
+    Flac,
+    /// Ogg 容器（Vorbis、Opus 等）。
+    Ogg,
+    /// MP4 / M4A 文件。
+    Mp4,
+}
+
+impl AudioContainer {
+    /// 根据文件开头的魔数识别容器格式。
+    #[must_use]
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"ID3") {
+            // FLAC 文件前面也可能带有 ID3v2 标签
+            let after_tag = id3v2::tag_size(data).and_then(|size| data.get(size..));
+            if after_tag.is_some_and(|rest| rest.starts_with(b"fLaC")) {
+                return Some(Self::Flac);
+            }
+            return Some(Self::Mp3);
+        }
+        if data.starts_with(b"fLaC") {
+            return Some(Self::Flac);
+        }
+        if data.starts_with(b"OggS") {
+            return Some(Self::Ogg);
+        }
+        if data.get(4..8) == Some(b"ftyp") {
+            return Some(Self::Mp4);
+        }
+        // 没有标签的 MPEG 音频，以帧同步字开头
+        if data.len() >= 2 && data[0] == 0xFF && data[1] & 0xE0 == 0xE0 {
+            return Some(Self::Mp3);
+        }
+        None
+    }
+}
+
+/// 歌词在音频标签中的来源。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmbeddedLyricsSource {
+    /// `ID3v2` 的 `USLT` 帧。
+    Id3Uslt,
+    /// `ID3v2` 的 `SYLT` 帧。
+    Id3Sylt,
+    /// Vorbis 注释字段，例如 `LYRICS`。
+    VorbisComment,
+    /// MP4 的 `©lyr` 原子。
+    Mp4Lyrics,
+}
+
+/// `SYLT` 帧中的一个带时间戳的文本片段。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncedText {
+    /// 片段开始时间（毫秒）。
+    pub timestamp_ms: u64,
+    /// 片段文本。以换行符开头表示新的一行。
+    pub text: String,
+}
+
+/// 内嵌歌词的内容。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmbeddedLyricsContent {
+    /// 文本形式的歌词，可能是纯文本，也可能是 LRC 等带时间戳的格式。
+    Text(String),
+    /// 同步歌词，即 `SYLT` 帧中的文本片段列表。
+    Synced(Vec<SyncedText>),
+}
+
+/// 从音频标签中读取到的一份歌词。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddedLyrics {
+    /// 歌词的来源。
+    pub source: EmbeddedLyricsSource,
+    /// 可选的语言代码（`ID3v2` 中为 ISO-639-2 三字母代码）。
+    pub language: Option<String>,
+    /// 可选的内容描述。
+    pub description: Option<String>,
+    /// 歌词内容。
+    pub content: EmbeddedLyricsContent,
+}
+
+/// 读取音频文件中的所有内嵌歌词。
+///
+/// # 返回
+/// 文件中找到的所有歌词，按在文件中出现的顺序排列；没有歌词时返回空列表。
+pub fn read_embedded_lyrics(data: &[u8]) -> Result<Vec<EmbeddedLyrics>, ConvertError> {
+    let container = AudioContainer::detect(data)
+        .ok_or_else(|| ConvertError::InvalidLyricFormat("无法识别的音频文件格式".to_string()))?;
+
+    match container {
+        AudioContainer::Mp3 => id3v2::read_lyrics(data),
+        AudioContainer::Flac => {
+            // 同时读取 FLAC 前面可能存在的 ID3v2 标签
+            let mut lyrics = if data.starts_with(b"ID3") {
+                id3v2::read_lyrics(data)?
+            } else {
+                Vec::new()
+            };
+            lyrics.extend(vorbis::read_flac_lyrics(data)?);
+            Ok(lyrics)
+        }
+        AudioContainer::Ogg => vorbis::read_ogg_lyrics(data),
+        AudioContainer::Mp4 => mp4::read_lyrics(data),
+    }
+}
+
+/// 从音频文件中提取歌词并解析为 `ParsedSourceData`。
+///
+/// 优先使用同步歌词（`SYLT`），其次是能识别出时间戳格式的文本歌词，
+/// 最后才是不带时间戳的纯文本歌词。
+pub fn parse_embedded_lyrics(
+    data: &[u8],
+    options: &ConversionOptions,
+) -> Result<ParsedSourceData, ConvertError> {
+    let lyrics = read_embedded_lyrics(data)?;
+    if lyrics.is_empty() {
+        return Err(ConvertError::InvalidLyricFormat(
+            "音频文件中没有找到内嵌歌词".to_string(),
+        ));
+    }
+
+    if let Some((synced, language)) = lyrics.iter().find_map(|l| match &l.content {
+        EmbeddedLyricsContent::Synced(synced) if !synced.is_empty() => {
+            Some((synced, l.language.as_deref()))
+        }
+        _ => None,
+    }) {
+        let mut parsed = parse_synced_text(synced);
+        insert_language(&mut parsed, language);
+        return Ok(parsed);
+    }
+
+    let texts: Vec<(&str, Option<&str>)> = lyrics
+        .iter()
+        .filter_map(|l| match &l.content {
+            EmbeddedLyricsContent::Text(text) if !text.trim().is_empty() => {
+                Some((text.as_str(), l.language.as_deref()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    if let Some((text, format, language)) = texts
+        .iter()
+        .find_map(|(text, language)| detect_lyric_format(text).map(|f| (text, f, language)))
+    {
+        let input = InputFile::new((*text).to_string(), format, None, None);
+        let mut parsed = parse_input_file(&input, options)?;
+        insert_language(&mut parsed, *language);
+        return Ok(parsed);
+    }
+
+    let Some((text, language)) = texts.first() else {
+        return Err(ConvertError::InvalidLyricFormat(
+            "音频文件中的内嵌歌词为空".to_string(),
+        ));
+    };
+    let mut parsed = parse_plain_text(text);
+    insert_language(&mut parsed, *language);
+    Ok(parsed)
+}
+
+/// 写入内嵌歌词的选项。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbedLyricsOptions {
+    /// 写入 `USLT`、Vorbis 注释和 `©lyr` 的文本格式。必须是文本格式。
+    pub text_format: LyricFormat,
+    /// 是否为 MP3 额外写入同步歌词（`SYLT`）。
+    pub write_synced: bool,
+    /// 覆盖 `ID3v2` 帧的语言代码。为 `None` 时从歌词元数据中推断。
+    pub language: Option<String>,
+    /// `ID3v2` 帧的内容描述。
+    pub description: String,
+    /// 只报告将要进行的修改，不生成新的文件内容。
+    pub dry_run: bool,
+}
+
+impl Default for EmbedLyricsOptions {
+    fn default() -> Self {
+        Self {
+            text_format: LyricFormat::Lrc,
+            write_synced: true,
+            language: None,
+            description: String::new(),
+            dry_run: false,
+        }
+    }
+}
+
+/// 对一处歌词标签进行的修改。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TagChangeAction {
+    /// 新增了歌词。
+    Added,
+    /// 替换了已有的歌词。
+    Replaced,
+    /// 移除了已有的歌词。
+    Removed,
+}
+
+/// 写入内嵌歌词时的一项修改。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagChange {
+    /// 被修改的歌词位置。
+    pub target: EmbeddedLyricsSource,
+    /// 修改类型。
+    pub action: TagChangeAction,
+    /// 写入内容的字节数，移除时为 0。
+    pub new_len: usize,
+}
+
+impl TagChange {
+    /// 根据已有歌词的数量决定是新增还是替换。
+    pub(crate) fn new(target: EmbeddedLyricsSource, existing: usize, new_len: usize) -> Self {
+        Self {
+            target,
+            action: if existing == 0 {
+                TagChangeAction::Added
+            } else {
+                TagChangeAction::Replaced
+            },
+            new_len,
+        }
+    }
+}
+
+/// 写入内嵌歌词的结果。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbedLyricsReport {
+    /// 音频文件的容器格式。
+    pub container: AudioContainer,
+    /// 进行的修改。
+    pub changes: Vec<TagChange>,
+    /// 原文件的字节数。
+    pub original_size: usize,
+    /// 新文件的字节数。
+    pub new_size: usize,
+    /// 新的文件内容。`dry_run` 时为 `None`。
+    pub output: Option<Vec<u8>>,
+}
+
+/// 准备写入标签的歌词。
+#[derive(Debug, Clone)]
+pub(crate) struct LyricsToEmbed {
+    /// 文本歌词
+    pub text: String,
+    /// `SYLT` 的文本片段，不写入同步歌词时为 `None`
+    pub synced: Option<Vec<SyncedText>>,
+    /// ISO 639-2 语言代码
+    pub language: String,
+    pub description: String,
+}
+
+/// 将歌词写入音频文件的标签。
+///
+/// - MP3：写入 `USLT`，并根据音节或行的时间写入 `SYLT`。
+/// - FLAC / Ogg：写入 Vorbis 注释的 `LYRICS` 字段。
+/// - MP4：写入 `©lyr` 原子。
+///
+/// 已有的歌词会被替换，其他标签和音频数据保持不变。MP3 只替换语言和描述都相同的
+/// `USLT`、`SYLT` 帧，其他语言的歌词会被保留。
+pub fn embed_lyrics(
+    data: &[u8],
+    source_data: &ParsedSourceData,
+    options: &EmbedLyricsOptions,
+    conversion_options: &ConversionOptions,
+) -> Result<EmbedLyricsReport, ConvertError> {
+    let container = AudioContainer::detect(data)
+        .ok_or_else(|| ConvertError::InvalidLyricFormat("无法识别的音频文件格式".to_string()))?;
+
+    let generated = generate_from_parsed(
+        source_data.clone(),
+        options.text_format,
+        conversion_options,
+        &None::<HashMap<String, Vec<String>>>,
+    )?;
+    if generated.output_bytes.is_some() {
+        return Err(ConvertError::InvalidLyricFormat(format!(
+            "{} 不是文本格式，无法写入音频标签",
+            options.text_format
+        )));
+    }
+
+    let lyrics = LyricsToEmbed {
+        text: generated.output_lyrics,
+        synced: options
+            .write_synced
+            .then(|| build_synced_text(&source_data.lines, source_data.is_line_timed_source))
+            .filter(|synced| !synced.is_empty()),
+        language: resolve_language(source_data, options.language.as_deref()),
+        description: options.description.clone(),
+    };
+
+    let (output, changes) = match container {
+        AudioContainer::Mp3 => id3v2::write_lyrics(data, &lyrics)?,
+        AudioContainer::Flac => vorbis::write_flac_lyrics(data, &lyrics.text)?,
+        AudioContainer::Ogg => vorbis::write_ogg_lyrics(data, &lyrics.text)?,
+        AudioContainer::Mp4 => mp4::write_lyrics(data, &lyrics.text)?,
+    };
+
+    Ok(EmbedLyricsReport {
+        container,
+        changes,
+        original_size: data.len(),
+        new_size: output.len(),
+        output: (!options.dry_run).then_some(output),
+    })
+}
+
+/// 将歌词行转换为 `SYLT` 的文本片段。
+///
+/// 逐字歌词的每个音节是一个片段，新的一行以换行符开头；逐行歌词的每一行是一个片段。
+fn build_synced_text(lines: &[LyricLine], is_line_timed: bool) -> Vec<SyncedText> {
+    let mut synced: Vec<SyncedText> = Vec::new();
+    for line in lines {
+        let Some(track) = line.main_track() else {
+            continue;
+        };
+        let syllables: Vec<&LyricSyllable> = track
+            .content
+            .words
+            .iter()
+            .flat_map(|w| &w.syllables)
+            .collect();
+
+        if is_line_timed || syllables.is_empty() {
+            let text = track.content.text();
+            if text.is_empty() {
+                continue;
+            }
+            synced.push(SyncedText {
+                timestamp_ms: line.start_ms,
+                text: if is_line_timed || synced.is_empty() {
+                    text
+                } else {
+                    format!("\n{text}")
+                },
+            });
+            continue;
+        }
+
+        for (i, syllable) in syllables.iter().enumerate() {
+            let prefix = if i == 0 && !synced.is_empty() {
+                "\n"
+            } else {
+                ""
+            };
+            let suffix = if syllable.ends_with_space { " " } else { "" };
+            synced.push(SyncedText {
+                timestamp_ms: syllable.start_ms,
+                text: format!("{prefix}{}{suffix}", syllable.text),
+            });
+        }
+    }
+    synced
+}
+
+/// 确定 `ID3v2` 帧的语言代码，无法确定时返回 `XXX`。
+fn resolve_language(source_data: &ParsedSourceData, language: Option<&str>) -> String {
+    let metadata_store = MetadataStore::from(source_data);
+    let track_language = source_data
+        .lines
+        .iter()
+        .filter_map(LyricLine::main_track)
+        .find_map(|t| t.content.metadata.get(&TrackMetadataKey::Language))
+        .cloned();
+
+    language
+        .map(str::to_string)
+        .or_else(|| {
+            metadata_store
+                .get_single_value(&CanonicalMetadataKey::Language)
+                .cloned()
+        })
+        .or(track_language)
+        .and_then(|l| to_iso_639_2(&l))
+        .unwrap_or_else(|| "XXX".to_string())
+}
+
+/// 将 BCP 47 或 ISO 639 语言代码转换为 ISO 639-2 三字母代码。
+fn to_iso_639_2(language: &str) -> Option<String> {
+    let primary = language
+        .split(['-', '_'])
+        .next()?
+        .trim()
+        .to_ascii_lowercase();
+    match primary.len() {
+        3 if primary.bytes().all(|b| b.is_ascii_lowercase()) => Some(primary),
+        2 => ISO_639_2_CODES
+            .iter()
+            .find(|(short, _)| *short == primary)
+            .map(|(_, long)| (*long).to_string()),
+        _ => None,
+    }
+}
+
+/// 记录标签中的语言代码。`ID3v2` 中的 `XXX` 表示未知语言，会被忽略。
+fn insert_language(parsed: &mut ParsedSourceData, language: Option<&str>) {
+    if let Some(language) = language.map(str::trim).filter(|l| {
+        !l.is_empty() && !l.eq_ignore_ascii_case("xxx") && !l.eq_ignore_ascii_case("und")
+    }) && !parsed.raw_metadata.contains_key("language")
+    {
+        parsed
+            .raw_metadata
+            .insert("language".to_string(), vec![language.to_string()]);
+    }
+}
+
+/// 将 `SYLT` 的文本片段转换为歌词行。
+///
+/// 如果有片段以换行符开头，则认为这是逐字同步的歌词：换行符表示新的一行，
+/// 每个片段是一个音节。否则每个片段就是一整行。
+fn parse_synced_text(synced: &[SyncedText]) -> ParsedSourceData {
+    let is_syllable_synced = synced
+        .iter()
+        .skip(1)
+        .any(|s| s.text.starts_with(['\n', '\r']));
+
+    let mut segments: Vec<&SyncedText> = synced.iter().collect();
+    segments.sort_by_key(|s| s.timestamp_ms);
+
+    let mut lines: Vec<LyricLine> = Vec::new();
+    if is_syllable_synced {
+        let mut current: Vec<LyricSyllable> = Vec::new();
+        for (i, segment) in segments.iter().enumerate() {
+            let starts_new_line = segment.text.starts_with(['\n', '\r']);
+            if starts_new_line && !current.is_empty() {
+                lines.push(build_line(std::mem::take(&mut current)));
+            }
+            let raw_text = segment.text.trim_start_matches(['\n', '\r']);
+            let Some((text, ends_with_space)) = process_syllable_text(raw_text, &mut current)
+            else {
+                continue;
+            };
+
+            let start_ms = segment.timestamp_ms;
+            let end_ms = segments
+                .get(i + 1)
+                .map_or(start_ms + LAST_SYNCED_LINE_DURATION_MS, |next| {
+                    next.timestamp_ms
+                });
+            current.push(LyricSyllable {
+                text,
+                start_ms,
+                end_ms,
+                duration_ms: Some(end_ms - start_ms),
+                ends_with_space,
+                ..Default::default()
+            });
+        }
+        if !current.is_empty() {
+            lines.push(build_line(current));
+        }
+    } else {
+        for (i, segment) in segments.iter().enumerate() {
+            let text = normalize_text_whitespace(&segment.text);
+            if text.is_empty() {
+                continue;
+            }
+            let start_ms = segment.timestamp_ms;
+            let end_ms = segments
+                .get(i + 1)
+                .map_or(start_ms + LAST_SYNCED_LINE_DURATION_MS, |next| {
+                    next.timestamp_ms
+                });
+            let mut line = LyricLine::new(start_ms, end_ms);
+            line.add_content_track(ContentType::Main, &text);
+            lines.push(line);
+        }
+    }
+
+    ParsedSourceData {
+        lines,
+        // SYLT 没有对应的文本格式，按照时间粒度选用最接近的格式
+        source_format: if is_syllable_synced {
+            LyricFormat::EnhancedLrc
+        } else {
+            LyricFormat::Lrc
+        },
+        is_line_timed_source: !is_syllable_synced,
+        ..Default::default()
+    }
+}
+
+/// 将不带时间戳的纯文本歌词转换为歌词行，所有行的时间都为 0。
+fn parse_plain_text(text: &str) -> ParsedSourceData {
+    let lines = text
+        .lines()
+        .map(normalize_text_whitespace)
+        .filter(|line| !line.is_empty())
+        .map(|text| {
+            let mut line = LyricLine::new(0, 0);
+            line.add_content_track(ContentType::Main, &text);
+            line
+        })
+        .collect();
+
+    ParsedSourceData {
+        lines,
+        source_format: LyricFormat::Lrc,
+        is_line_timed_source: true,
+        warnings: vec!["内嵌歌词没有时间信息，所有行的时间都被设为 0。".to_string()],
+        ..Default::default()
+    }
+}
+
+fn build_line(syllables: Vec<LyricSyllable>) -> LyricLine {
+    let start_ms = syllables.first().map_or(0, |s| s.start_ms);
+    let end_ms = syllables.last().map_or(start_ms, |s| s.end_ms);
+    LyricLine {
+        tracks: vec![AnnotatedTrack {
+            content_type: ContentType::Main,
+            content: LyricTrack {
+                words: vec![Word {
+                    syllables,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        }],
+        start_ms,
+        end_ms,
+        ..Default::default()
+    }
+}
+
+/// 按 ISO-8859-1 解码字节。
+pub(crate) fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| char::from(b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_synced_text_line_and_syllable_modes() {
+        let line_synced = vec![
+            SyncedText {
+                timestamp_ms: 1000,
+                text: "First line".to_string(),
+            },
+            SyncedText {
+                timestamp_ms: 3000,
+                text: "Second line".to_string(),
+            },
+        ];
+        let parsed = parse_synced_text(&line_synced);
+        assert!(parsed.is_line_timed_source);
+        assert_eq!(parsed.lines.len(), 2);
+        assert_eq!(parsed.lines[0].end_ms, 3000);
+        assert_eq!(parsed.lines[1].main_text(), Some("Second line".to_string()));
+
+        let syllable_synced = vec![
+            SyncedText {
+                timestamp_ms: 1000,
+                text: "Hel".to_string(),
+            },
+            SyncedText {
+                timestamp_ms: 1200,
+                text: "lo ".to_string(),
+            },
+            SyncedText {
+                timestamp_ms: 1500,
+                text: "world".to_string(),
+            },
+            SyncedText {
+                timestamp_ms: 2000,
+                text: "\nNext".to_string(),
+            },
+        ];
+        let parsed = parse_synced_text(&syllable_synced);
+        assert!(!parsed.is_line_timed_source);
+        assert_eq!(parsed.lines.len(), 2);
+        assert_eq!(parsed.lines[0].main_text(), Some("Hello world".to_string()));
+        assert_eq!(parsed.lines[0].end_ms, 2000);
+        assert_eq!(parsed.lines[1].start_ms, 2000);
+    }
+
+    #[test]
+    fn test_parse_embedded_lyrics_detects_text_format() {
+        let field = "LYRICS=[00:01.00]Hello\n[00:02.50]World";
+        let mut comment = Vec::new();
+        comment.extend_from_slice(&0u32.to_le_bytes());
+        comment.extend_from_slice(&1u32.to_le_bytes());
+        comment.extend_from_slice(&u32::try_from(field.len()).unwrap().to_le_bytes());
+        comment.extend_from_slice(field.as_bytes());
+
+        let mut data = b"fLaC".to_vec();
+        data.push(0x80 | vorbis::FLAC_BLOCK_VORBIS_COMMENT);
+        data.extend_from_slice(&u32::try_from(comment.len()).unwrap().to_be_bytes()[1..]);
+        data.extend_from_slice(&comment);
+
+        assert_eq!(AudioContainer::detect(&data), Some(AudioContainer::Flac));
+        let parsed = parse_embedded_lyrics(&data, &ConversionOptions::default()).unwrap();
+        assert_eq!(parsed.source_format, LyricFormat::Lrc);
+        assert_eq!(parsed.lines.len(), 2);
+        assert_eq!(parsed.lines[1].start_ms, 2500);
+        assert_eq!(parsed.lines[1].main_text(), Some("World".to_string()));
+    }
+
+    #[test]
+    fn test_parse_embedded_plain_text() {
+        let parsed = parse_plain_text("First\n\n  Second  line ");
+        assert_eq!(parsed.lines.len(), 2);
+        assert_eq!(parsed.lines[1].main_text(), Some("Second line".to_string()));
+        assert_eq!(parsed.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_embed_lyrics_dry_run_and_synced_text() {
+        let mut line = LyricLine::new(1000, 2000);
+        line.add_content_track(ContentType::Main, "Hello");
+        let mut second = LyricLine::new(2000, 3000);
+        second.add_content_track(ContentType::Main, "World");
+        let source_data = ParsedSourceData {
+            lines: vec![line, second],
+            is_line_timed_source: true,
+            raw_metadata: HashMap::from([("language".to_string(), vec!["ja-JP".to_string()])]),
+            ..Default::default()
+        };
+        let audio = [0xFF, 0xFB, 0x90, 0x00];
+
+        let options = EmbedLyricsOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let report = embed_lyrics(
+            &audio,
+            &source_data,
+            &options,
+            &ConversionOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(report.container, AudioContainer::Mp3);
+        assert!(report.output.is_none());
+        assert!(report.new_size > report.original_size);
+        assert_eq!(
+            report.changes.iter().map(|c| c.target).collect::<Vec<_>>(),
+            vec![EmbeddedLyricsSource::Id3Uslt, EmbeddedLyricsSource::Id3Sylt]
+        );
+
+        let report = embed_lyrics(
+            &audio,
+            &source_data,
+            &EmbedLyricsOptions::default(),
+            &ConversionOptions::default(),
+        )
+        .unwrap();
+        let lyrics = read_embedded_lyrics(&report.output.unwrap()).unwrap();
+        assert_eq!(lyrics[1].language.as_deref(), Some("jpn"));
+        assert_eq!(
+            lyrics[1].content,
+            EmbeddedLyricsContent::Synced(vec![
+                SyncedText {
+                    timestamp_ms: 1000,
+                    text: "Hello".to_string(),
+                },
+                SyncedText {
+                    timestamp_ms: 2000,
+                    text: "World".to_string(),
+                },
+            ])
+        );
+    }
+}
@@ -0,0 +1,473 @@
+//! MP4 元数据中的歌词原子
+//!
+//! iTunes 风格的元数据位于 `moov/udta/meta/ilst`，歌词保存在 `©lyr` 原子的 `data` 子原子中。
+
+use std::ops::Range;
+
+use super::{EmbeddedLyrics, EmbeddedLyricsContent, EmbeddedLyricsSource, TagChange};
+use crate::converter::types::ConvertError;
+
+/// 歌词原子的类型。
+pub(crate) const LYRICS_ATOM: [u8; 4] = *b"\xa9lyr";
+
+/// `data` 原子的类型标识：UTF-8 文本。
+const DATA_TYPE_UTF8: u32 = 1;
+/// `data` 原子的类型标识：UTF-16BE 文本。
+const DATA_TYPE_UTF16: u32 = 2;
+
+/// 一个 MP4 原子。
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Atom<'a> {
+    pub kind: [u8; 4],
+    /// 原子的内容（不含头部）
+    pub payload: &'a [u8],
+    /// 原子在所读取数据中的起始位置
+    pub offset: usize,
+    /// 头部长度（8 或 16）
+    pub header_len: usize,
+}
+
+impl Atom<'_> {
+    /// 原子的总长度（包括头部）。
+    fn len(&self) -> usize {
+        self.header_len + self.payload.len()
+    }
+}
+
+/// 读取一段数据中依次排列的所有原子。
+pub(crate) fn read_atoms(data: &[u8]) -> Result<Vec<Atom<'_>>, ConvertError> {
+    let invalid = |msg: &str| ConvertError::InvalidLyricFormat(format!("MP4 原子无效: {msg}"));
+
+    let mut atoms = Vec::new();
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let size32 = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        let mut kind = [0u8; 4];
+        kind.copy_from_slice(&data[pos + 4..pos + 8]);
+
+        let (header_len, size) = match size32 {
+            // 原子一直延伸到数据末尾
+            0 => (8, data.len() - pos),
+            // 64 位长度
+            1 => {
+                let bytes: [u8; 8] = data
+                    .get(pos + 8..pos + 16)
+                    .and_then(|b| b.try_into().ok())
+                    .ok_or_else(|| invalid("64 位长度不完整"))?;
+                let size = usize::try_from(u64::from_be_bytes(bytes))
+                    .map_err(|_| invalid("原子长度过大"))?;
+                (16, size)
+            }
+            size => (8, size as usize),
+        };
+        if size < header_len {
+            return Err(invalid("原子长度小于头部长度"));
+        }
+        let payload = data
+            .get(pos + header_len..pos + size)
+            .ok_or_else(|| invalid("原子长度超出数据范围"))?;
+
+        atoms.push(Atom {
+            kind,
+            payload,
+            offset: pos,
+            header_len,
+        });
+        pos += size;
+    }
+    Ok(atoms)
+}
+
+/// 在一组原子中查找指定类型的第一个原子。
+fn find_child(data: &[u8], kind: [u8; 4]) -> Result<Option<Atom<'_>>, ConvertError> {
+    Ok(read_atoms(data)?.into_iter().find(|a| a.kind == kind))
+}
+
+/// 返回 `meta` 原子的子原子区域。
+///
+/// iTunes 的 `meta` 是带有 4 字节版本和标志的完整原子，QuickTime 的则不是，
+/// 通过检查紧随其后的是否为 `hdlr` 来区分。
+pub(crate) fn meta_children(meta_payload: &[u8]) -> &[u8] {
+    if meta_payload.get(4..8) == Some(b"hdlr") {
+        meta_payload
+    } else {
+        meta_payload.get(4..).unwrap_or_default()
+    }
+}
+
+/// 读取 MP4 文件中的 `©lyr` 歌词。
+pub fn read_lyrics(data: &[u8]) -> Result<Vec<EmbeddedLyrics>, ConvertError> {
+    let Some(moov) = find_child(data, *b"moov")? else {
+        return Ok(Vec::new());
+    };
+
+    // 标准位置是 moov/udta/meta，少数文件直接把 meta 放在 moov 中
+    let meta = match find_child(moov.payload, *b"udta")? {
+        Some(udta) => find_child(udta.payload, *b"meta")?,
+        None => None,
+    };
+    let meta = match meta {
+        Some(meta) => meta,
+        None => match find_child(moov.payload, *b"meta")? {
+            Some(meta) => meta,
+            None => return Ok(Vec::new()),
+        },
+    };
+
+    let Some(ilst) = find_child(meta_children(meta.payload), *b"ilst")? else {
+        return Ok(Vec::new());
+    };
+
+    let mut lyrics = Vec::new();
+    for item in read_atoms(ilst.payload)?
+        .into_iter()
+        .filter(|a| a.kind == LYRICS_ATOM)
+    {
+        for data_atom in read_atoms(item.payload)?
+            .into_iter()
+            .filter(|a| &a.kind == b"data")
+        {
+            if let Some(text) = decode_data_atom(data_atom.payload) {
+                lyrics.push(EmbeddedLyrics {
+                    source: EmbeddedLyricsSource::Mp4Lyrics,
+                    language: None,
+                    description: None,
+                    content: EmbeddedLyricsContent::Text(text),
+                });
+            }
+        }
+    }
+    Ok(lyrics)
+}
+
+/// 解码 `data` 原子：4 字节类型标识、4 字节区域设置，然后是内容。
+fn decode_data_atom(payload: &[u8]) -> Option<String> {
+    let type_bytes: [u8; 4] = payload.get(0..4)?.try_into().ok()?;
+    let type_indicator = u32::from_be_bytes(type_bytes) & 0x00FF_FFFF;
+    let value = payload.get(8..)?;
+    match type_indicator {
+        DATA_TYPE_UTF8 => Some(String::from_utf8_lossy(value).into_owned()),
+        DATA_TYPE_UTF16 => {
+            let units = value
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+            Some(
+                char::decode_utf16(units)
+                    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// 写入 MP4 文件的 `©lyr` 歌词，返回新的文件内容和变更列表。
+///
+/// 缺少的 `udta`、`meta`、`ilst` 会被创建。长度变化优先由紧随 `ilst` 的 `free` 原子吸收；
+/// 无法吸收时会更新所有上级原子的长度，并修正 `stco` / `co64` 中位于修改位置之后的数据块偏移，
+/// 以保证音频数据仍然能被正确定位。
+pub(crate) fn write_lyrics(
+    data: &[u8],
+    text: &str,
+) -> Result<(Vec<u8>, Vec<TagChange>), ConvertError> {
+    let moov = find_child(data, *b"moov")?
+        .ok_or_else(|| ConvertError::InvalidLyricFormat("MP4 文件缺少 moov 原子".to_string()))?;
+    let moov_payload_start = moov.offset + moov.header_len;
+
+    let mut item_payload = DATA_TYPE_UTF8.to_be_bytes().to_vec();
+    item_payload.extend_from_slice(&[0; 4]);
+    item_payload.extend_from_slice(text.as_bytes());
+    let item = build_atom(LYRICS_ATOM, &build_atom(*b"data", &item_payload));
+
+    let mut ancestors = vec![moov.offset];
+    let udta = find_child(moov.payload, *b"udta")?;
+    let meta = match udta {
+        Some(udta) => find_child(udta.payload, *b"meta")?
+            .map(|meta| (meta, moov_payload_start + udta.offset + udta.header_len)),
+        None => None,
+    };
+    // 与读取时一致，udta 中没有 meta 时使用 moov 中的 meta
+    let meta = match meta {
+        Some(meta) => Some(meta),
+        None => find_child(moov.payload, *b"meta")?.map(|meta| (meta, moov_payload_start)),
+    };
+
+    let mut existing = 0;
+    let (mut range, mut replacement, free_sibling) = if let Some((meta, meta_base)) = meta {
+        let meta_start = meta_base + meta.offset;
+        if meta_base != moov_payload_start
+            && let Some(udta) = udta
+        {
+            ancestors.push(moov_payload_start + udta.offset);
+        }
+        ancestors.push(meta_start);
+
+        let children = meta_children(meta.payload);
+        let children_start = meta_start + meta.len() - children.len();
+        let atoms = read_atoms(children)?;
+        if let Some(index) = atoms.iter().position(|a| &a.kind == b"ilst") {
+            let ilst = atoms[index];
+            let mut payload = Vec::with_capacity(ilst.payload.len() + item.len());
+            for child in read_atoms(ilst.payload)? {
+                if child.kind == LYRICS_ATOM {
+                    existing += 1;
+                } else {
+                    payload
+                        .extend_from_slice(&ilst.payload[child.offset..child.offset + child.len()]);
+                }
+            }
+            payload.extend_from_slice(&item);
+            let start = children_start + ilst.offset;
+            let free_sibling = atoms
+                .get(index + 1)
+                .filter(|a| &a.kind == b"free")
+                .map(Atom::len);
+            (
+                start..start + ilst.len(),
+                build_atom(*b"ilst", &payload),
+                free_sibling,
+            )
+        } else {
+            let end = meta_start + meta.len();
+            (end..end, build_atom(*b"ilst", &item), None)
+        }
+    } else if let Some(udta) = udta {
+        let udta_start = moov_payload_start + udta.offset;
+        ancestors.push(udta_start);
+        let end = udta_start + udta.len();
+        (end..end, build_meta(&item), None)
+    } else {
+        let end = moov.offset + moov.len();
+        (end..end, build_atom(*b"udta", &build_meta(&item)), None)
+    };
+
+    let change = TagChange::new(EmbeddedLyricsSource::Mp4Lyrics, existing, text.len());
+
+    // 尽量保持文件长度不变：缩短时补一个 free 原子，增长时占用后面的 free 原子
+    let old_len = range.len();
+    if replacement.len() + 8 <= old_len {
+        let free_len = old_len - replacement.len();
+        replacement.extend(build_atom(*b"free", &vec![0; free_len - 8]));
+    } else if let Some(free_len) = free_sibling
+        && replacement.len() > old_len
+        && let Some(remaining) = (old_len + free_len).checked_sub(replacement.len())
+        && (remaining == 0 || remaining >= 8)
+    {
+        range.end += free_len;
+        if remaining > 0 {
+            replacement.extend(build_atom(*b"free", &vec![0; remaining - 8]));
+        }
+    }
+
+    let mut output = data.to_vec();
+    let growth = i64::try_from(replacement.len()).unwrap_or(i64::MAX)
+        - i64::try_from(range.len()).unwrap_or(i64::MAX);
+    if growth != 0 {
+        for &start in &ancestors {
+            adjust_atom_size(&mut output, start, growth)?;
+        }
+        adjust_chunk_offsets(&mut output, &moov, &range, growth)?;
+    }
+    output.splice(range, replacement);
+    Ok((output, vec![change]))
+}
+
+/// 构建一个原子（头部 + 内容）。
+fn build_atom(kind: [u8; 4], payload: &[u8]) -> Vec<u8> {
+    let size = u32::try_from(payload.len() + 8).unwrap_or(u32::MAX);
+    let mut atom = size.to_be_bytes().to_vec();
+    atom.extend_from_slice(&kind);
+    atom.extend_from_slice(payload);
+    atom
+}
+
+/// 构建包含 `hdlr` 和 `ilst` 的 iTunes 风格 `meta` 原子。
+fn build_meta(ilst_payload: &[u8]) -> Vec<u8> {
+    let mut hdlr = vec![0; 8];
+    hdlr.extend_from_slice(b"mdirappl");
+    hdlr.extend_from_slice(&[0; 9]);
+
+    let mut payload = vec![0; 4];
+    payload.extend(build_atom(*b"hdlr", &hdlr));
+    payload.extend(build_atom(*b"ilst", ilst_payload));
+    build_atom(*b"meta", &payload)
+}
+
+fn size_error() -> ConvertError {
+    ConvertError::InvalidLyricFormat("MP4 原子长度溢出".to_string())
+}
+
+/// 调整位于 `start` 的原子头部中记录的长度。
+fn adjust_atom_size(data: &mut [u8], start: usize, growth: i64) -> Result<(), ConvertError> {
+    let size32 = u32::from_be_bytes(
+        data[start..start + 4]
+            .try_into()
+            .map_err(|_| size_error())?,
+    );
+    match size32 {
+        // 延伸到文件末尾的原子不需要调整
+        0 => {}
+        1 => {
+            let field = &mut data[start + 8..start + 16];
+            let size = u64::from_be_bytes((&*field).try_into().map_err(|_| size_error())?);
+            let size = size.checked_add_signed(growth).ok_or_else(size_error)?;
+            field.copy_from_slice(&size.to_be_bytes());
+        }
+        size => {
+            let size = u32::try_from(i64::from(size) + growth).map_err(|_| size_error())?;
+            data[start..start + 4].copy_from_slice(&size.to_be_bytes());
+        }
+    }
+    Ok(())
+}
+
+/// 修正所有指向修改位置之后的数据块偏移。
+fn adjust_chunk_offsets(
+    data: &mut [u8],
+    moov: &Atom<'_>,
+    range: &Range<usize>,
+    growth: i64,
+) -> Result<(), ConvertError> {
+    let mut tables = Vec::new();
+    let moov_payload_start = moov.offset + moov.header_len;
+    for trak in read_atoms(moov.payload)?
+        .into_iter()
+        .filter(|a| &a.kind == b"trak")
+    {
+        let mut base = moov_payload_start + trak.offset + trak.header_len;
+        let mut current = trak;
+        let mut found = true;
+        for kind in [*b"mdia", *b"minf", *b"stbl"] {
+            if let Some(child) = find_child(current.payload, kind)? {
+                base += child.offset + child.header_len;
+                current = child;
+            } else {
+                found = false;
+                break;
+            }
+        }
+        if !found {
+            continue;
+        }
+        for table in read_atoms(current.payload)? {
+            let entry_size = match &table.kind {
+                b"stco" => 4,
+                b"co64" => 8,
+                _ => continue,
+            };
+            let count_bytes: [u8; 4] = table
+                .payload
+                .get(4..8)
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(size_error)?;
+            let count =
+                usize::try_from(u32::from_be_bytes(count_bytes)).map_err(|_| size_error())?;
+            let entries_start = base + table.offset + table.header_len + 8;
+            tables.push((entries_start, count, entry_size));
+        }
+    }
+
+    let threshold = u64::try_from(range.end).map_err(|_| size_error())?;
+    for (entries_start, count, entry_size) in tables {
+        for i in 0..count {
+            let pos = entries_start + i * entry_size;
+            let field = data.get_mut(pos..pos + entry_size).ok_or_else(size_error)?;
+            if entry_size == 4 {
+                let offset = u32::from_be_bytes((&*field).try_into().map_err(|_| size_error())?);
+                if u64::from(offset) >= threshold {
+                    let offset =
+                        u32::try_from(i64::from(offset) + growth).map_err(|_| size_error())?;
+                    field.copy_from_slice(&offset.to_be_bytes());
+                }
+            } else {
+                let offset = u64::from_be_bytes((&*field).try_into().map_err(|_| size_error())?);
+                if offset >= threshold {
+                    let offset = offset.checked_add_signed(growth).ok_or_else(size_error)?;
+                    field.copy_from_slice(&offset.to_be_bytes());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atom(kind: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut data = u32::try_from(payload.len() + 8)
+            .unwrap()
+            .to_be_bytes()
+            .to_vec();
+        data.extend_from_slice(kind);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn test_read_mp4_lyrics() {
+        let mut data_payload = DATA_TYPE_UTF8.to_be_bytes().to_vec();
+        data_payload.extend_from_slice(&[0; 4]);
+        data_payload.extend_from_slice("[00:01.00]歌词".as_bytes());
+
+        let lyr = atom(&LYRICS_ATOM, &atom(b"data", &data_payload));
+        let nam = atom(b"\xa9nam", &atom(b"data", &[0, 0, 0, 1, 0, 0, 0, 0, b'T']));
+        let ilst = atom(b"ilst", &[nam, lyr].concat());
+        let hdlr = atom(b"hdlr", &[0; 25]);
+        let mut meta_payload = vec![0; 4];
+        meta_payload.extend(hdlr);
+        meta_payload.extend(ilst);
+        let meta = atom(b"meta", &meta_payload);
+        let moov = atom(
+            b"moov",
+            &[atom(b"mvhd", &[0; 100]), atom(b"udta", &meta)].concat(),
+        );
+        let file = [
+            atom(b"ftyp", b"M4A \0\0\0\0"),
+            moov,
+            atom(b"mdat", &[1, 2, 3]),
+        ]
+        .concat();
+
+        let lyrics = read_lyrics(&file).unwrap();
+        assert_eq!(lyrics.len(), 1);
+        assert_eq!(
+            lyrics[0].content,
+            EmbeddedLyricsContent::Text("[00:01.00]歌词".to_string())
+        );
+    }
+
+    #[test]
+    fn test_write_mp4_lyrics_adjusts_chunk_offsets() {
+        let ftyp = atom(b"ftyp", b"M4A \0\0\0\0");
+        let build = |chunk_offset: u32| {
+            let mut stco = vec![0, 0, 0, 0, 0, 0, 0, 1];
+            stco.extend_from_slice(&chunk_offset.to_be_bytes());
+            let stbl = atom(b"stbl", &atom(b"stco", &stco));
+            let trak = atom(b"trak", &atom(b"mdia", &atom(b"minf", &stbl)));
+            atom(b"moov", &trak)
+        };
+        // mdat 的内容紧跟在 8 字节的头部之后
+        let chunk_offset = u32::try_from(ftyp.len() + build(0).len() + 8).unwrap();
+        let file = [ftyp.clone(), build(chunk_offset), atom(b"mdat", b"audio")].concat();
+
+        let (output, changes) = write_lyrics(&file, "歌词").unwrap();
+        assert_eq!(changes[0].action, super::super::TagChangeAction::Added);
+        assert!(output.ends_with(b"audio"));
+        assert_eq!(
+            read_lyrics(&output).unwrap()[0].content,
+            EmbeddedLyricsContent::Text("歌词".to_string())
+        );
+
+        let mut container = find_child(&output, *b"moov").unwrap().unwrap();
+        for kind in [*b"trak", *b"mdia", *b"minf", *b"stbl", *b"stco"] {
+            container = find_child(container.payload, kind).unwrap().unwrap();
+        }
+        let new_offset = usize::try_from(u32::from_be_bytes(
+            container.payload[8..12].try_into().unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(&output[new_offset..], b"audio");
+    }
+}
@@ -0,0 +1,695 @@
+//! Vorbis 注释中的歌词字段
+//!
+//! FLAC 把 Vorbis 注释保存在 `VORBIS_COMMENT` 元数据块中；
+//! Ogg Vorbis 和 Ogg Opus 则把它保存在逻辑流的第二个数据包中。
+
+use super::{EmbeddedLyrics, EmbeddedLyricsContent, EmbeddedLyricsSource, TagChange, id3v2};
+use crate::converter::types::ConvertError;
+
+/// 包含歌词的 Vorbis 注释字段名（不区分大小写）。
+pub const LYRICS_FIELDS: &[&str] = &["LYRICS", "UNSYNCEDLYRICS"];
+
+/// 写入歌词时使用的字段名。
+const LYRICS_FIELD: &str = "LYRICS";
+
+/// FLAC 元数据块类型：填充。
+const FLAC_BLOCK_PADDING: u8 = 1;
+/// FLAC 元数据块类型：Vorbis 注释。
+pub(crate) const FLAC_BLOCK_VORBIS_COMMENT: u8 = 4;
+
+/// Ogg 页面头部类型：延续上一页的数据包。
+const OGG_CONTINUED_PACKET: u8 = 0x01;
+/// 一个 Ogg 页面最多包含的段数。
+const OGG_MAX_SEGMENTS: usize = 255;
+
+/// 解析后的 Vorbis 注释。
+///
+/// 字段保存为原始字节，写回时未修改的字段可以原样输出。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct VorbisComment {
+    /// 编码器信息
+    pub vendor: Vec<u8>,
+    /// 按原始顺序排列的 `KEY=value` 字段
+    pub fields: Vec<Vec<u8>>,
+}
+
+impl VorbisComment {
+    /// 解析 Vorbis 注释结构（不含 Ogg Vorbis 的包类型前缀和结尾的帧标志位）。
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, ConvertError> {
+        Self::parse_prefix(data).map(|(comment, _)| comment)
+    }
+
+    /// 解析 Vorbis 注释结构，同时返回其占用的字节数。
+    fn parse_prefix(data: &[u8]) -> Result<(Self, usize), ConvertError> {
+        let invalid = || ConvertError::InvalidLyricFormat("Vorbis 注释数据无效".to_string());
+
+        let mut pos = 0;
+        let vendor = read_length_prefixed(data, &mut pos)
+            .ok_or_else(invalid)?
+            .to_vec();
+        let count = read_u32_le(data, &mut pos).ok_or_else(invalid)?;
+
+        let mut fields = Vec::new();
+        for _ in 0..count {
+            // 截断的注释中，已经读到的字段仍然有效
+            let Some(field) = read_length_prefixed(data, &mut pos) else {
+                break;
+            };
+            fields.push(field.to_vec());
+        }
+        Ok((Self { vendor, fields }, pos))
+    }
+
+    /// 编码为 Vorbis 注释结构。
+    fn to_bytes(&self) -> Result<Vec<u8>, ConvertError> {
+        let too_large = || ConvertError::InvalidLyricFormat("Vorbis 注释字段过长".to_string());
+        let mut data = Vec::new();
+        let write_length_prefixed = |bytes: &[u8], data: &mut Vec<u8>| {
+            let len = u32::try_from(bytes.len()).map_err(|_| too_large())?;
+            data.extend_from_slice(&len.to_le_bytes());
+            data.extend_from_slice(bytes);
+            Ok::<_, ConvertError>(())
+        };
+        write_length_prefixed(&self.vendor, &mut data)?;
+        let count = u32::try_from(self.fields.len()).map_err(|_| too_large())?;
+        data.extend_from_slice(&count.to_le_bytes());
+        for field in &self.fields {
+            write_length_prefixed(field, &mut data)?;
+        }
+        Ok(data)
+    }
+
+    /// 提取其中的歌词字段。
+    fn lyrics(&self) -> Vec<EmbeddedLyrics> {
+        self.fields
+            .iter()
+            .filter(|field| is_lyrics_field(field))
+            .filter_map(|field| {
+                let value = &field[field.iter().position(|&b| b == b'=')? + 1..];
+                Some(EmbeddedLyrics {
+                    source: EmbeddedLyricsSource::VorbisComment,
+                    language: None,
+                    description: None,
+                    content: EmbeddedLyricsContent::Text(
+                        String::from_utf8_lossy(value).into_owned(),
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// 用新的歌词替换所有歌词字段，返回被替换的字段数量。
+    fn replace_lyrics(&mut self, text: &str) -> usize {
+        let before = self.fields.len();
+        self.fields.retain(|field| !is_lyrics_field(field));
+        let removed = before - self.fields.len();
+        self.fields
+            .push(format!("{LYRICS_FIELD}={text}").into_bytes());
+        removed
+    }
+}
+
+fn is_lyrics_field(field: &[u8]) -> bool {
+    let Some(key) = field.split(|&b| b == b'=').next() else {
+        return false;
+    };
+    LYRICS_FIELDS
+        .iter()
+        .any(|f| key.eq_ignore_ascii_case(f.as_bytes()))
+        && field.contains(&b'=')
+}
+
+fn read_u32_le(data: &[u8], pos: &mut usize) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(*pos..*pos + 4)?.try_into().ok()?;
+    *pos += 4;
+    Some(u32::from_le_bytes(bytes))
+}
+
+fn read_length_prefixed<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    let start = *pos;
+    let len = usize::try_from(read_u32_le(data, pos)?).ok()?;
+    let Some(bytes) = data.get(*pos..pos.checked_add(len)?) else {
+        *pos = start;
+        return None;
+    };
+    *pos += len;
+    Some(bytes)
+}
+
+/// 构建替换歌词后的 Vorbis 注释结构。
+fn replace_comment_lyrics(
+    comment_data: &[u8],
+    text: &str,
+) -> Result<(Vec<u8>, TagChange), ConvertError> {
+    let (mut comment, consumed) = VorbisComment::parse_prefix(comment_data)?;
+    let removed = comment.replace_lyrics(text);
+    let mut output = comment.to_bytes()?;
+    // 保留注释结构之后的数据，例如 Vorbis 的帧标志位
+    output.extend_from_slice(&comment_data[consumed..]);
+    let change = TagChange::new(EmbeddedLyricsSource::VorbisComment, removed, text.len());
+    Ok((output, change))
+}
+
+/// FLAC 文件中的一个元数据块。
+#[derive(Debug)]
+pub(crate) struct FlacBlock<'a> {
+    pub block_type: u8,
+    pub data: &'a [u8],
+    /// 块头部在文件中的位置
+    pub offset: usize,
+}
+
+/// 读取 FLAC 文件的所有元数据块。文件开头可能带有 `ID3v2` 标签。
+pub(crate) fn read_flac_blocks(data: &[u8]) -> Result<Vec<FlacBlock<'_>>, ConvertError> {
+    let start = id3v2::tag_size(data).unwrap_or(0);
+    if data.get(start..start + 4) != Some(b"fLaC") {
+        return Err(ConvertError::InvalidLyricFormat(
+            "不是有效的 FLAC 文件".to_string(),
+        ));
+    }
+
+    let mut blocks = Vec::new();
+    let mut pos = start + 4;
+    loop {
+        let Some(header) = data.get(pos..pos + 4) else {
+            return Err(ConvertError::InvalidLyricFormat(
+                "FLAC 元数据块头部不完整".to_string(),
+            ));
+        };
+        let is_last = header[0] & 0x80 != 0;
+        let block_type = header[0] & 0x7F;
+        let len =
+            (usize::from(header[1]) << 16) | (usize::from(header[2]) << 8) | usize::from(header[3]);
+        let block_data = data.get(pos + 4..pos + 4 + len).ok_or_else(|| {
+            ConvertError::InvalidLyricFormat("FLAC 元数据块长度超出文件范围".to_string())
+        })?;
+        blocks.push(FlacBlock {
+            block_type,
+            data: block_data,
+            offset: pos,
+        });
+        pos += 4 + len;
+        if is_last {
+            break;
+        }
+    }
+    Ok(blocks)
+}
+
+/// 读取 FLAC 文件 Vorbis 注释中的歌词。
+pub fn read_flac_lyrics(data: &[u8]) -> Result<Vec<EmbeddedLyrics>, ConvertError> {
+    let mut lyrics = Vec::new();
+    for block in read_flac_blocks(data)? {
+        if block.block_type == FLAC_BLOCK_VORBIS_COMMENT {
+            lyrics.extend(VorbisComment::parse(block.data)?.lyrics());
+        }
+    }
+    Ok(lyrics)
+}
+
+/// 写入 FLAC 文件的 `LYRICS` 字段，返回新的文件内容和变更列表。
+///
+/// 注释块的长度变化会优先由填充块吸收，此时音频数据的位置不变。
+/// 文件没有 Vorbis 注释块时，会在 `STREAMINFO` 之后插入一个。
+pub(crate) fn write_flac_lyrics(
+    data: &[u8],
+    text: &str,
+) -> Result<(Vec<u8>, Vec<TagChange>), ConvertError> {
+    let blocks = read_flac_blocks(data)?;
+    let Some(last) = blocks.last() else {
+        return Err(ConvertError::InvalidLyricFormat(
+            "FLAC 文件缺少元数据块".to_string(),
+        ));
+    };
+    let metadata_start = blocks[0].offset;
+    let audio_start = last.offset + 4 + last.data.len();
+
+    let mut new_blocks: Vec<(u8, Vec<u8>)> = blocks
+        .iter()
+        .map(|b| (b.block_type, b.data.to_vec()))
+        .collect();
+
+    let (old_len, inserted, change) = if let Some(index) = new_blocks
+        .iter()
+        .position(|(t, _)| *t == FLAC_BLOCK_VORBIS_COMMENT)
+    {
+        let (comment, change) = replace_comment_lyrics(&new_blocks[index].1, text)?;
+        let old_len = new_blocks[index].1.len();
+        new_blocks[index].1 = comment;
+        (old_len, false, change)
+    } else {
+        let empty = VorbisComment {
+            vendor: b"lyrics_helper_rs".to_vec(),
+            fields: Vec::new(),
+        }
+        .to_bytes()?;
+        let (comment, change) = replace_comment_lyrics(&empty, text)?;
+        new_blocks.insert(1, (FLAC_BLOCK_VORBIS_COMMENT, comment));
+        (0, true, change)
+    };
+
+    // 用填充块吸收长度变化，新插入的块还需要额外的 4 字节头部
+    let new_len = new_blocks
+        .iter()
+        .find(|(t, _)| *t == FLAC_BLOCK_VORBIS_COMMENT)
+        .map_or(0, |(_, d)| d.len());
+    let needed = new_len + if inserted { 4 } else { 0 };
+    if let Some(padding) = new_blocks
+        .iter_mut()
+        .find(|(t, _)| *t == FLAC_BLOCK_PADDING)
+        && let Some(remaining) = (padding.1.len() + old_len).checked_sub(needed)
+    {
+        padding.1.resize(remaining, 0);
+    }
+
+    let mut output = data[..metadata_start].to_vec();
+    let block_count = new_blocks.len();
+    for (i, (block_type, block_data)) in new_blocks.iter().enumerate() {
+        let len = u32::try_from(block_data.len())
+            .ok()
+            .filter(|&len| len < 1 << 24)
+            .ok_or_else(|| ConvertError::InvalidLyricFormat("FLAC 元数据块过长".to_string()))?;
+        let last_flag = if i + 1 == block_count { 0x80 } else { 0 };
+        output.push(last_flag | block_type);
+        output.extend_from_slice(&len.to_be_bytes()[1..]);
+        output.extend_from_slice(block_data);
+    }
+    output.extend_from_slice(&data[audio_start..]);
+    Ok((output, vec![change]))
+}
+
+/// Ogg 页面。
+#[derive(Debug)]
+pub(crate) struct OggPage<'a> {
+    /// 页面在文件中的位置
+    pub offset: usize,
+    /// 页面的总长度
+    pub len: usize,
+    pub header_type: u8,
+    pub granule: u64,
+    pub serial: u32,
+    pub sequence: u32,
+    /// 段表
+    pub lacing: &'a [u8],
+    pub body: &'a [u8],
+}
+
+/// 读取文件中的所有 Ogg 页面。
+pub(crate) fn read_ogg_pages(data: &[u8]) -> Result<Vec<OggPage<'_>>, ConvertError> {
+    let invalid = || ConvertError::InvalidLyricFormat("Ogg 页面数据无效".to_string());
+
+    let mut pages = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let header = data.get(pos..pos + 27).ok_or_else(invalid)?;
+        if &header[0..4] != b"OggS" {
+            return Err(invalid());
+        }
+        let segment_count = usize::from(header[26]);
+        let lacing = data
+            .get(pos + 27..pos + 27 + segment_count)
+            .ok_or_else(invalid)?;
+        let body_len: usize = lacing.iter().map(|&l| usize::from(l)).sum();
+        let body_start = pos + 27 + segment_count;
+        let body = data
+            .get(body_start..body_start + body_len)
+            .ok_or_else(invalid)?;
+
+        pages.push(OggPage {
+            offset: pos,
+            len: 27 + segment_count + body_len,
+            header_type: header[5],
+            granule: u64::from_le_bytes(header[6..14].try_into().unwrap_or_default()),
+            serial: u32::from_le_bytes([header[14], header[15], header[16], header[17]]),
+            sequence: u32::from_le_bytes([header[18], header[19], header[20], header[21]]),
+            lacing,
+            body,
+        });
+        pos = body_start + body_len;
+    }
+    Ok(pages)
+}
+
+/// 从 Ogg 页面中重新组装第一个逻辑流的前 `max_packets` 个数据包。
+pub(crate) fn read_ogg_packets(pages: &[OggPage<'_>], max_packets: usize) -> Vec<Vec<u8>> {
+    let Some(serial) = pages.first().map(|p| p.serial) else {
+        return Vec::new();
+    };
+
+    let mut packets = Vec::new();
+    let mut current = Vec::new();
+    for page in pages.iter().filter(|p| p.serial == serial) {
+        let mut offset = 0;
+        for &lace in page.lacing {
+            let len = usize::from(lace);
+            current.extend_from_slice(&page.body[offset..offset + len]);
+            offset += len;
+            // 长度小于 255 的段表示数据包结束
+            if lace < 255 {
+                packets.push(std::mem::take(&mut current));
+                if packets.len() >= max_packets {
+                    return packets;
+                }
+            }
+        }
+    }
+    packets
+}
+
+/// 从 Ogg 注释数据包中取出 Vorbis 注释结构。
+///
+/// Vorbis 使用 `\x03vorbis` 前缀，Opus 使用 `OpusTags` 前缀。
+pub(crate) fn strip_comment_packet_prefix(packet: &[u8]) -> Option<&[u8]> {
+    packet
+        .strip_prefix(b"\x03vorbis")
+        .or_else(|| packet.strip_prefix(b"OpusTags"))
+}
+
+/// 读取 Ogg 文件 Vorbis 注释中的歌词。
+pub fn read_ogg_lyrics(data: &[u8]) -> Result<Vec<EmbeddedLyrics>, ConvertError> {
+    let pages = read_ogg_pages(data)?;
+    let packets = read_ogg_packets(&pages, 2);
+    let Some(comment_data) = packets.get(1).and_then(|p| strip_comment_packet_prefix(p)) else {
+        return Ok(Vec::new());
+    };
+    Ok(VorbisComment::parse(comment_data)?.lyrics())
+}
+
+/// 写入 Ogg 文件的 `LYRICS` 字段，返回新的文件内容和变更列表。
+///
+/// 注释包所在的页面（以及与它共享页面的其他头部数据包）会被重新分页，
+/// 之后属于同一逻辑流的页面只更新序号和校验和，数据包内容保持不变。
+pub(crate) fn write_ogg_lyrics(
+    data: &[u8],
+    text: &str,
+) -> Result<(Vec<u8>, Vec<TagChange>), ConvertError> {
+    let invalid = |msg: &str| ConvertError::InvalidLyricFormat(format!("Ogg 文件无效: {msg}"));
+
+    let pages = read_ogg_pages(data)?;
+    let serial = pages.first().ok_or_else(|| invalid("没有页面"))?.serial;
+    let stream_pages: Vec<usize> = pages
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.serial == serial)
+        .map(|(i, _)| i)
+        .collect();
+
+    // 第一个页面只包含识别头，注释包从第二个页面开始。
+    // 从这里开始读取，直到注释包结束并且页面恰好结束于数据包边界。
+    let first = *stream_pages.get(1).ok_or_else(|| invalid("缺少注释页面"))?;
+    if pages[first].header_type & OGG_CONTINUED_PACKET != 0 {
+        return Err(invalid("识别头跨越了多个页面"));
+    }
+    let mut packets: Vec<Vec<u8>> = Vec::new();
+    let mut current = Vec::new();
+    let mut last = None;
+    for &index in &stream_pages[1..] {
+        let page = &pages[index];
+        let mut offset = 0;
+        for &lace in page.lacing {
+            let len = usize::from(lace);
+            current.extend_from_slice(&page.body[offset..offset + len]);
+            offset += len;
+            if lace < 255 {
+                packets.push(std::mem::take(&mut current));
+            }
+        }
+        if !packets.is_empty() && current.is_empty() {
+            last = Some(index);
+            break;
+        }
+    }
+    let last = last.ok_or_else(|| invalid("注释包不完整"))?;
+
+    let comment_packet = &packets[0];
+    let (prefix_len, comment_data) = if let Some(rest) = comment_packet.strip_prefix(b"\x03vorbis")
+    {
+        (7, rest)
+    } else if let Some(rest) = comment_packet.strip_prefix(b"OpusTags") {
+        (8, rest)
+    } else {
+        return Err(invalid("第二个数据包不是注释包"));
+    };
+    let (comment, change) = replace_comment_lyrics(comment_data, text)?;
+    let mut new_packet = comment_packet[..prefix_len].to_vec();
+    new_packet.extend(comment);
+    packets[0] = new_packet;
+
+    let replaced: Vec<usize> = stream_pages
+        .iter()
+        .copied()
+        .filter(|&i| (first..=last).contains(&i))
+        .collect();
+    let new_pages = paginate(&packets, serial, pages[first].sequence, pages[last].granule);
+    let sequence_delta = i64::try_from(new_pages.len()).unwrap_or(i64::MAX)
+        - i64::try_from(replaced.len()).unwrap_or(i64::MAX);
+
+    let mut output = data[..pages[first].offset].to_vec();
+    for page in &new_pages {
+        output.extend_from_slice(page);
+    }
+    for (index, page) in pages.iter().enumerate().skip(first) {
+        if replaced.contains(&index) {
+            continue;
+        }
+        let raw = &data[page.offset..page.offset + page.len];
+        if page.serial == serial && sequence_delta != 0 {
+            let sequence = u32::try_from(i64::from(page.sequence) + sequence_delta)
+                .map_err(|_| invalid("页面序号溢出"))?;
+            let mut raw = raw.to_vec();
+            raw[18..22].copy_from_slice(&sequence.to_le_bytes());
+            update_page_checksum(&mut raw);
+            output.extend(raw);
+        } else {
+            output.extend_from_slice(raw);
+        }
+    }
+    Ok((output, vec![change]))
+}
+
+/// 将一组完整的数据包分页。最后一个页面使用 `final_granule`，
+/// 没有数据包在其中结束的页面使用 -1。
+fn paginate(
+    packets: &[Vec<u8>],
+    serial: u32,
+    first_sequence: u32,
+    final_granule: u64,
+) -> Vec<Vec<u8>> {
+    // (段长度, 所属数据包中的偏移, 所属数据包序号)
+    let mut segments: Vec<(u8, usize, usize)> = Vec::new();
+    for (packet_index, packet) in packets.iter().enumerate() {
+        let mut offset = 0;
+        loop {
+            let len = (packet.len() - offset).min(255);
+            segments.push((u8::try_from(len).unwrap_or(255), offset, packet_index));
+            offset += len;
+            if len < 255 {
+                break;
+            }
+        }
+    }
+
+    let chunks: Vec<&[(u8, usize, usize)]> = segments.chunks(OGG_MAX_SEGMENTS).collect();
+    let mut pages = Vec::with_capacity(chunks.len());
+    let mut continued = false;
+    for (i, chunk) in chunks.iter().enumerate() {
+        let packet_ends = chunk.iter().any(|&(len, _, _)| len < 255);
+        let granule = if i + 1 == chunks.len() {
+            final_granule
+        } else if packet_ends {
+            0
+        } else {
+            u64::MAX
+        };
+
+        let mut page = b"OggS\0".to_vec();
+        page.push(if continued { OGG_CONTINUED_PACKET } else { 0 });
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&serial.to_le_bytes());
+        let sequence = first_sequence.wrapping_add(u32::try_from(i).unwrap_or(u32::MAX));
+        page.extend_from_slice(&sequence.to_le_bytes());
+        page.extend_from_slice(&[0; 4]);
+        page.push(u8::try_from(chunk.len()).unwrap_or(u8::MAX));
+        page.extend(chunk.iter().map(|&(len, _, _)| len));
+        for &(len, offset, packet_index) in *chunk {
+            page.extend_from_slice(&packets[packet_index][offset..offset + usize::from(len)]);
+        }
+        update_page_checksum(&mut page);
+        pages.push(page);
+
+        continued = chunk.last().is_some_and(|&(len, _, _)| len == 255);
+    }
+    pages
+}
+
+/// 重新计算 Ogg 页面的 CRC 校验和。
+fn update_page_checksum(page: &mut [u8]) {
+    page[22..26].fill(0);
+    let crc = ogg_crc32(page);
+    page[22..26].copy_from_slice(&crc.to_le_bytes());
+}
+
+/// Ogg 使用的 CRC-32（多项式 `0x04C11DB7`，不反转，初始值为 0）。
+fn ogg_crc32(data: &[u8]) -> u32 {
+    data.iter().fold(0u32, |crc, &byte| {
+        let mut crc = crc ^ (u32::from(byte) << 24);
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ 0x04C1_1DB7
+            };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_comment(fields: &[&str]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let vendor = b"test vendor";
+        data.extend_from_slice(&u32::try_from(vendor.len()).unwrap().to_le_bytes());
+        data.extend_from_slice(vendor);
+        data.extend_from_slice(&u32::try_from(fields.len()).unwrap().to_le_bytes());
+        for field in fields {
+            data.extend_from_slice(&u32::try_from(field.len()).unwrap().to_le_bytes());
+            data.extend_from_slice(field.as_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_read_flac_lyrics() {
+        let comment = build_comment(&["TITLE=Song", "lyrics=[00:01.00]Hello"]);
+        let mut data = b"fLaC".to_vec();
+        // STREAMINFO
+        data.extend_from_slice(&[0, 0, 0, 34]);
+        data.extend_from_slice(&[0; 34]);
+        data.push(0x80 | FLAC_BLOCK_VORBIS_COMMENT);
+        data.extend_from_slice(&u32::try_from(comment.len()).unwrap().to_be_bytes()[1..]);
+        data.extend_from_slice(&comment);
+
+        let lyrics = read_flac_lyrics(&data).unwrap();
+        assert_eq!(lyrics.len(), 1);
+        assert_eq!(
+            lyrics[0].content,
+            EmbeddedLyricsContent::Text("[00:01.00]Hello".to_string())
+        );
+    }
+
+    #[test]
+    fn test_read_ogg_lyrics_across_pages() {
+        let mut comment = b"\x03vorbis".to_vec();
+        let long_lyrics = format!("UNSYNCEDLYRICS={}", "la ".repeat(200));
+        comment.extend_from_slice(&build_comment(&[&long_lyrics]));
+        comment.push(1);
+
+        let page = |sequence: u32, lacing: &[u8], body: &[u8]| {
+            let mut page = b"OggS".to_vec();
+            page.extend_from_slice(&[0, 0]);
+            page.extend_from_slice(&[0; 8]);
+            page.extend_from_slice(&7u32.to_le_bytes());
+            page.extend_from_slice(&sequence.to_le_bytes());
+            page.extend_from_slice(&[0; 4]);
+            page.push(u8::try_from(lacing.len()).unwrap());
+            page.extend_from_slice(lacing);
+            page.extend_from_slice(body);
+            page
+        };
+
+        let mut data = page(0, &[5], b"ident");
+        // 注释包被拆分到两个页面
+        let (first, second) = comment.split_at(255);
+        data.extend(page(1, &[255], first));
+        let second_lacing = [255, u8::try_from(second.len() - 255).unwrap()];
+        data.extend(page(2, &second_lacing, second));
+
+        let lyrics = read_ogg_lyrics(&data).unwrap();
+        assert_eq!(lyrics.len(), 1);
+        assert_eq!(
+            lyrics[0].content,
+            EmbeddedLyricsContent::Text("la ".repeat(200))
+        );
+    }
+
+    #[test]
+    fn test_write_flac_lyrics_uses_padding() {
+        let comment = build_comment(&["TITLE=Song", "LYRICS=old"]);
+        let mut data = b"fLaC".to_vec();
+        data.extend_from_slice(&[0, 0, 0, 34]);
+        data.extend_from_slice(&[7; 34]);
+        data.push(FLAC_BLOCK_VORBIS_COMMENT);
+        data.extend_from_slice(&u32::try_from(comment.len()).unwrap().to_be_bytes()[1..]);
+        data.extend_from_slice(&comment);
+        data.extend_from_slice(&[0x80 | FLAC_BLOCK_PADDING, 0, 1, 0]);
+        data.extend_from_slice(&[0; 256]);
+        data.extend_from_slice(b"audio frames");
+
+        let (output, changes) = write_flac_lyrics(&data, "[00:01.00]new").unwrap();
+        assert_eq!(output.len(), data.len());
+        assert!(output.ends_with(b"audio frames"));
+        assert_eq!(changes[0].action, super::super::TagChangeAction::Replaced);
+
+        let blocks = read_flac_blocks(&output).unwrap();
+        assert_eq!(blocks[0].data, &[7; 34]);
+        let comment = VorbisComment::parse(blocks[1].data).unwrap();
+        assert_eq!(comment.fields[0], b"TITLE=Song");
+        assert_eq!(
+            read_flac_lyrics(&output).unwrap()[0].content,
+            EmbeddedLyricsContent::Text("[00:01.00]new".to_string())
+        );
+    }
+
+    #[test]
+    fn test_write_ogg_lyrics_repaginates_header() {
+        let mut comment = b"\x03vorbis".to_vec();
+        comment.extend_from_slice(&build_comment(&["TITLE=Song"]));
+        comment.push(1);
+        let setup = b"\x05vorbis setup".to_vec();
+
+        let page = |sequence: u32, header_type: u8, lacing: &[u8], body: &[u8]| {
+            let mut page = b"OggS\0".to_vec();
+            page.push(header_type);
+            page.extend_from_slice(&[0; 8]);
+            page.extend_from_slice(&7u32.to_le_bytes());
+            page.extend_from_slice(&sequence.to_le_bytes());
+            page.extend_from_slice(&[0; 4]);
+            page.push(u8::try_from(lacing.len()).unwrap());
+            page.extend_from_slice(lacing);
+            page.extend_from_slice(body);
+            update_page_checksum(&mut page);
+            page
+        };
+
+        let mut data = page(0, 0x02, &[5], b"ident");
+        let lacing = [
+            u8::try_from(comment.len()).unwrap(),
+            u8::try_from(setup.len()).unwrap(),
+        ];
+        data.extend(page(1, 0, &lacing, &[comment, setup.clone()].concat()));
+        data.extend(page(2, 0x04, &[5], b"audio"));
+
+        // 超过一个页面的容量，注释包需要拆分到两个页面
+        let lyrics = "la ".repeat(22_000);
+        let (output, _) = write_ogg_lyrics(&data, &lyrics).unwrap();
+
+        let pages = read_ogg_pages(&output).unwrap();
+        let packets = read_ogg_packets(&pages, 4);
+        assert_eq!(packets[2], setup);
+        assert_eq!(packets[3], b"audio");
+        assert_eq!(pages.last().unwrap().sequence, 3);
+        assert!(pages.iter().all(|p| {
+            let mut raw = output[p.offset..p.offset + p.len].to_vec();
+            let stored = raw[22..26].to_vec();
+            update_page_checksum(&mut raw);
+            raw[22..26] == stored
+        }));
+        assert_eq!(
+            read_ogg_lyrics(&output).unwrap()[0].content,
+            EmbeddedLyricsContent::Text(lyrics)
+        );
+    }
+}
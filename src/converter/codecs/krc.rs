@@ -0,0 +1,126 @@
+//! 酷狗歌词解密工具模块。
+//! 该模块包含用于解密酷狗音乐歌词的函数。
+//!
+//! ## 致谢
+//!
+//! 本模块的解密逻辑（包括固定的16字节密钥和异或算法）源于
+//! `LyricDecoder` 项目。
+//!
+//! - Copyright (c) `SuJiKiNen` (`LyricDecoder` Project)
+//! - Licensed under the MIT License.
+//!
+//! <https://github.com/SuJiKiNen/LyricDecoder>
+
+use std::io::{Read, Write};
+
+use base64::{Engine as _, engine::general_purpose};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+
+use crate::error::{LyricsHelperError, Result};
+
+/// 酷狗 KRC 歌词解密所使用的固定16字节密钥。
+const KRC_DECRYPT_KEY: [u8; 16] = [
+    0x40, 0x47, 0x61, 0x77, 0x5E, 0x32, 0x74, 0x47, 0x51, 0x36, 0x31, 0x2D, 0xCE, 0xD2, 0x6E, 0x69,
+];
+
+/// 酷狗客户端 `.krc` 文件的4字节头部。
+pub const KRC_FILE_HEADER: &[u8; 4] = b"krc1";
+
+/// 解密酷狗音乐的 KRC 格式歌词。
+///
+/// # 参数
+///
+/// * `encrypted_krc_base64` - 一个经过 Base64 编码的加密 KRC 文本字符串。
+///
+/// # 返回
+///
+/// * `Result<String>` - 成功时返回解密后的 KRC 文本（通常是 KRC 格式的字符串）。
+///
+/// # 错误
+///
+/// * `LyricsHelperError::Base64Decode` - 如果输入的字符串不是有效的 Base64。
+/// * `LyricsHelperError::Decryption` - 如果输入的数据长度不足。
+/// * `LyricsHelperError::Io` - 如果 Zlib 解压缩失败。
+/// * `LyricsHelperError` (From `FromUtf8Error`) - 如果解密后的数据不是有效的 UTF-8 编码。
+pub fn decrypt_krc(encrypted_krc_base64: &str) -> Result<String> {
+    // Base64 解码
+    let data = general_purpose::STANDARD
+        .decode(encrypted_krc_base64.as_bytes())
+        .map_err(LyricsHelperError::Base64Decode)?;
+
+    decrypt_krc_bytes(&data)
+}
+
+/// 解密酷狗客户端 `.krc` 文件的原始字节。
+///
+/// # 错误
+///
+/// * `LyricsHelperError::Decryption` - 如果输入的数据长度不足。
+/// * `LyricsHelperError::Io` - 如果 Zlib 解压缩失败。
+/// * `LyricsHelperError` (From `FromUtf8Error`) - 如果解密后的数据不是有效的 UTF-8 编码。
+pub fn decrypt_krc_bytes(encrypted_krc: &[u8]) -> Result<String> {
+    if encrypted_krc.len() < KRC_FILE_HEADER.len() {
+        return Err(LyricsHelperError::Decryption(
+            "KRC 加密数据过短，至少需要4字节的头部。".into(),
+        ));
+    }
+
+    // 移除前4个字节的头部，然后异或
+    let data_to_decrypt: Vec<u8> = encrypted_krc[KRC_FILE_HEADER.len()..]
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| byte ^ KRC_DECRYPT_KEY[i % KRC_DECRYPT_KEY.len()])
+        .collect();
+
+    // Zlib 解压缩
+    let mut decoder = ZlibDecoder::new(data_to_decrypt.as_slice());
+    let mut decompressed_data = Vec::new();
+    decoder
+        .read_to_end(&mut decompressed_data)
+        .map_err(LyricsHelperError::Io)?;
+
+    // 转换为 UTF-8 字符串
+    String::from_utf8(decompressed_data).map_err(LyricsHelperError::from)
+}
+
+/// 将 KRC 文本加密为酷狗客户端可以加载的 `.krc` 文件字节。
+///
+/// 这是 [`decrypt_krc_bytes`] 的逆过程：Zlib 压缩、异或，然后加上 `krc1` 头部。
+///
+/// # 错误
+///
+/// * `LyricsHelperError::Io` - 如果 Zlib 压缩失败。
+pub fn encrypt_krc(krc_text: &str) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(krc_text.as_bytes())
+        .map_err(LyricsHelperError::Io)?;
+    let compressed_data = encoder.finish().map_err(LyricsHelperError::Io)?;
+
+    let mut output = Vec::with_capacity(KRC_FILE_HEADER.len() + compressed_data.len());
+    output.extend_from_slice(KRC_FILE_HEADER);
+    output.extend(
+        compressed_data
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| byte ^ KRC_DECRYPT_KEY[i % KRC_DECRYPT_KEY.len()]),
+    );
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_krc_encrypt_decrypt_round_trip() {
+        let krc_text = "[ti:测试]\n[1000,500]<0,250,0>你<250,250,0>好\n";
+
+        let encrypted = encrypt_krc(krc_text).unwrap();
+        assert!(encrypted.starts_with(KRC_FILE_HEADER));
+        assert_eq!(decrypt_krc_bytes(&encrypted).unwrap(), krc_text);
+
+        let encoded = general_purpose::STANDARD.encode(&encrypted);
+        assert_eq!(decrypt_krc(&encoded).unwrap(), krc_text);
+    }
+}
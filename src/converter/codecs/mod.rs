@@ -0,0 +1,7 @@
+//! 歌词文件编解码模块
+//!
+//! 包含酷狗 KRC 和 QQ 音乐 QRC 加密格式的字节级编解码，
+//! 供转换器读写本地文件，也供提供商解密在线歌词。
+
+pub mod krc;
+pub mod qrc;
@@ -15,8 +15,39 @@
 //!
 //! <https://github.com/SuJiKiNen/LyricDecoder>
 
+use std::sync::LazyLock;
+
+use fancy_regex::Regex;
+
 use crate::error::Result;
 
+static QRC_LYRIC_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"LyricContent="([^"]*)""#).unwrap());
+
+static AMP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&(?![a-zA-Z]{2,6};|#[0-9]{2,4};)").unwrap());
+
+static QUOT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?P<attr>\s+[\w:.-]+\s*=\s*")(?P<value>(?:[^"]|"(?!\s+[\w:.-]+\s*=\s*"|\s*(?:/?|\?)>))*)"#)
+        .unwrap()
+});
+
+/// QQ 音乐本地缓存 `.qrc` 文件头部的开头，完整的头部通常为 `[offset:0]\n`。
+pub const LOCAL_QRC_HEADER_PREFIX: &[u8] = b"[offset:";
+
+/// 本地缓存文件异或混淆使用的 128 字节密钥。
+#[rustfmt::skip]
+const LOCAL_QRC_MASK_KEY: [u8; 128] = [
+    0xC3, 0x4A, 0xD6, 0xCA, 0x90, 0x67, 0xF7, 0x52, 0xD8, 0xA1, 0x66, 0x62, 0x9F, 0x5B, 0x09, 0x00,
+    0xC3, 0x5E, 0x95, 0x23, 0x9F, 0x13, 0x11, 0x7E, 0xD8, 0x92, 0x3F, 0xBC, 0x90, 0xBB, 0x74, 0x0E,
+    0xC3, 0x47, 0x74, 0x3D, 0x90, 0xAA, 0x3F, 0x51, 0xD8, 0xF4, 0x11, 0x84, 0x9F, 0xDE, 0x95, 0x1D,
+    0xC3, 0xC6, 0x09, 0xD5, 0x9F, 0xFA, 0x66, 0xF9, 0xD8, 0xF0, 0xF7, 0xA0, 0x90, 0xA1, 0xD6, 0xF3,
+    0xC3, 0xF3, 0xD6, 0xA1, 0x90, 0xA0, 0xF7, 0xF0, 0xD8, 0xF9, 0x66, 0xFA, 0x9F, 0xD5, 0x09, 0xC6,
+    0xC3, 0x1D, 0x95, 0xDE, 0x9F, 0x84, 0x11, 0xF4, 0xD8, 0x51, 0x3F, 0xAA, 0x90, 0x3D, 0x74, 0x47,
+    0xC3, 0x0E, 0x74, 0xBB, 0x90, 0xBC, 0x3F, 0x92, 0xD8, 0x7E, 0x11, 0x13, 0x9F, 0x23, 0x95, 0x5E,
+    0xC3, 0x00, 0x09, 0x5B, 0x9F, 0x62, 0x66, 0xA1, 0xD8, 0x52, 0xF7, 0x67, 0x90, 0xCA, 0xD6, 0x4A,
+];
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// 对加密文本执行解密操作。
@@ -31,6 +62,114 @@ pub fn encrypt_qrc(plaintext: &str) -> Result<String> {
     Ok(encrypted_hex_string)
 }
 
+/// 对未经十六进制编码的加密字节执行解密操作。
+///
+/// 如果输入是 QQ 音乐客户端的本地缓存文件（见 [`is_local_qrc`]），
+/// 会先移除 `[offset:0]` 头部并去除异或混淆。
+pub fn decrypt_qrc_bytes(encrypted_bytes: &[u8]) -> Result<String> {
+    if is_local_qrc(encrypted_bytes)
+        && let Some(header_len) = local_header_len(encrypted_bytes)
+    {
+        let mut body = encrypted_bytes[header_len..].to_vec();
+        unmask_local_qrc(&mut body, header_len);
+        return qrc_logic::decrypt_lyrics_bytes(&body);
+    }
+    qrc_logic::decrypt_lyrics_bytes(encrypted_bytes)
+}
+
+/// 判断字节是否为 QQ 音乐客户端的本地缓存 `.qrc` 文件。
+///
+/// 本地缓存文件以明文的 `[offset:0]` 头部开头，其后是经过异或混淆的加密数据，
+/// 因此头部之后的内容不会是有效的 UTF-8 文本。
+#[must_use]
+pub fn is_local_qrc(bytes: &[u8]) -> bool {
+    local_header_len(bytes).is_some_and(|len| std::str::from_utf8(&bytes[len..]).is_err())
+}
+
+/// 返回本地缓存文件 `[offset:…]` 头部（包括其后的换行）的字节长度。
+fn local_header_len(bytes: &[u8]) -> Option<usize> {
+    if !bytes.starts_with(LOCAL_QRC_HEADER_PREFIX) {
+        return None;
+    }
+    let close = bytes.iter().take(32).position(|&b| b == b']')?;
+    let rest = &bytes[close + 1..];
+    let newline_len = if rest.starts_with(b"\r\n") {
+        2
+    } else {
+        usize::from(rest.starts_with(b"\n"))
+    };
+    Some(close + 1 + newline_len)
+}
+
+/// 去除本地缓存文件的异或混淆（与 QMC1 相同的算法）。
+///
+/// 密钥流的下标从文件开头算起，`offset` 是 `data` 在文件中的起始位置。
+fn unmask_local_qrc(data: &mut [u8], offset: usize) {
+    for (i, byte) in data.iter_mut().enumerate() {
+        let index = offset + i;
+        let key_index = if index > 0x7FFF {
+            (index % 0x7FFF) & 0x7F
+        } else {
+            index & 0x7F
+        };
+        *byte ^= LOCAL_QRC_MASK_KEY[key_index];
+    }
+}
+
+/// 对明文歌词执行加密操作，返回未经十六进制编码的加密字节。
+pub fn encrypt_qrc_bytes(plaintext: &str) -> Result<Vec<u8>> {
+    qrc_logic::encrypt_lyrics_bytes(plaintext)
+}
+
+/// 从解密后的 QRC 歌词文本中提取核心的 `LyricContent` 内容。
+///
+/// 这个函数会尝试修复文本中不规范的 XML 特殊字符。
+///
+/// # 参数
+/// * `decrypted_text` - 已经解密的歌词字符串。
+///
+/// # 返回
+/// * `String` - 提取出的 `LyricContent` 内容，或在某些情况下的原始文本。
+#[must_use]
+pub fn extract_from_qrc_wrapper(decrypted_text: &str) -> String {
+    if decrypted_text.is_empty() {
+        return String::new();
+    }
+
+    if !decrypted_text.starts_with("<?xml") {
+        return decrypted_text.to_string();
+    }
+
+    // 修复独立的 '&' 符号
+    let replaced_amp = AMP_RE.replace_all(decrypted_text, "&amp;");
+
+    // 修复未转义的双引号
+    let fixed_text = QUOT_RE.replace_all(&replaced_amp, |caps: &fancy_regex::Captures| {
+        let attr = &caps["attr"];
+        let value = &caps["value"];
+        format!("{}{}\"", attr, value.replace('"', "&quot;"))
+    });
+
+    if let Ok(Some(caps)) = QRC_LYRIC_RE.captures(&fixed_text)
+        && let Some(content) = caps.get(1)
+    {
+        return content.as_str().to_string();
+    }
+
+    decrypted_text.to_string()
+}
+
+/// 将 QRC 歌词文本包装为 QQ 音乐使用的 `QrcInfos` XML 结构。
+///
+/// 这是 [`extract_from_qrc_wrapper`] 的逆过程。与 QQ 音乐一致，`LyricContent`
+/// 中的内容不做转义。
+#[must_use]
+pub fn wrap_in_qrc_xml(qrc_content: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<QrcInfos>\n<QrcHeadInfo SaveTime=\"0\" Version=\"100\"/>\n<LyricInfo LyricCount=\"1\">\n<Lyric_1 LyricType=\"1\" LyricContent=\"{qrc_content}\"/>\n</LyricInfo>\n</QrcInfos>\n"
+    )
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// 内部模块，封装了所有解密逻辑。
@@ -129,6 +268,11 @@ mod qrc_logic {
         let encrypted_bytes = decode(encrypted_hex_str)
             .map_err(|e| LyricsHelperError::Decryption(format!("无效的十六进制字符串: {e}")))?;
 
+        decrypt_lyrics_bytes(&encrypted_bytes)
+    }
+
+    /// 解密未经十六进制编码的加密字节
+    pub(super) fn decrypt_lyrics_bytes(encrypted_bytes: &[u8]) -> Result<String> {
         if !encrypted_bytes.len().is_multiple_of(DES_BLOCK_SIZE) {
             return Err(LyricsHelperError::Decryption(format!(
                 "加密数据长度不是{DES_BLOCK_SIZE}的倍数",
@@ -152,6 +296,11 @@ mod qrc_logic {
 
     /// 加密 QQ 音乐歌词的主函数
     pub(super) fn encrypt_lyrics(plaintext: &str) -> Result<String> {
+        encrypt_lyrics_bytes(plaintext).map(encode)
+    }
+
+    /// 加密明文歌词，返回未经十六进制编码的加密字节
+    pub(super) fn encrypt_lyrics_bytes(plaintext: &str) -> Result<Vec<u8>> {
         let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
         encoder
             .write_all(plaintext.as_bytes())
@@ -171,7 +320,7 @@ mod qrc_logic {
                 CODEC.encrypt_block(chunk, out_slice);
             });
 
-        Ok(encode(encrypted_data))
+        Ok(encrypted_data)
     }
 
     /// 使用 Zlib 解压缩字节数据。
@@ -326,6 +475,7 @@ mod qrc_logic {
 
         /// 生成 S-P 盒合并查找表。
         #[allow(clippy::cast_possible_truncation)]
+        #[allow(clippy::needless_range_loop)]
         fn generate_sp_tables() -> [[u32; 64]; 8] {
             let mut sp_tables = [[0u32; 64]; 8];
 
@@ -535,6 +685,7 @@ mod qrc_logic {
         impl DesPermutationTables {
             /// 创建并填充所有查找表
             #[allow(clippy::cast_possible_truncation)]
+            #[allow(clippy::needless_range_loop)]
             fn new() -> Self {
                 /// 初始置换规则。
                 #[rustfmt::skip]
@@ -778,6 +929,71 @@ mod tests {
         println!("\n✅ 测试成功！初始明文与最终明文完全一致。");
     }
 
+    #[test]
+    fn test_converter_encrypted_qrc_round_trip() {
+        use crate::converter::{
+            self,
+            types::{ConversionInput, ConversionOptions, InputFile, LyricFormat},
+        };
+
+        let options = ConversionOptions::default();
+        let parse_encrypted = |input: InputFile| {
+            converter::parse_and_merge(
+                &ConversionInput {
+                    main_lyric: input,
+                    translations: Vec::new(),
+                    romanizations: Vec::new(),
+                    target_format: LyricFormat::Qrc,
+                    user_metadata_overrides: None,
+                },
+                &options,
+            )
+            .expect("解析加密 QRC 失败")
+        };
+
+        let from_hex = parse_encrypted(InputFile::new(
+            ENCRYPTED_HEX_STRING.to_string(),
+            LyricFormat::EncryptedQrc,
+            None,
+            None,
+        ));
+        assert!(!from_hex.lines.is_empty());
+
+        let raw_bytes = hex::decode(ENCRYPTED_HEX_STRING).unwrap();
+        let from_bytes = parse_encrypted(InputFile::from_bytes(
+            raw_bytes,
+            LyricFormat::EncryptedQrc,
+            None,
+            None,
+        ));
+        assert_eq!(from_hex.lines, from_bytes.lines);
+
+        let generated = converter::generate_from_parsed(
+            from_hex.clone(),
+            LyricFormat::EncryptedQrc,
+            &options,
+            &None::<std::collections::HashMap<String, Vec<String>>>,
+        )
+        .unwrap();
+        let output_bytes = generated.output_bytes.expect("加密格式应当输出字节");
+        assert_eq!(generated.output_lyrics, hex::encode(&output_bytes));
+
+        let decrypted = decrypt_qrc_bytes(&output_bytes).unwrap();
+        assert!(decrypted.starts_with("<?xml"));
+
+        let reparsed = parse_encrypted(InputFile::from_bytes(
+            output_bytes,
+            LyricFormat::EncryptedQrc,
+            None,
+            None,
+        ));
+        assert_eq!(reparsed.lines.len(), from_hex.lines.len());
+        assert_eq!(
+            reparsed.lines.first().map(|l| l.start_ms),
+            from_hex.lines.first().map(|l| l.start_ms)
+        );
+    }
+
     #[test]
     #[ignore]
     fn capture_key_schedule() {
@@ -844,4 +1060,17 @@ mod tests {
 
         println!("✅ key_schedule 验证成功！");
     }
+
+    #[test]
+    fn test_local_cache_file_decryption() {
+        let local_file = include_bytes!("../../../tests/test_data/local_cache.qrc");
+        assert!(local_file.starts_with(b"[offset:0]\n"));
+        assert!(is_local_qrc(local_file));
+
+        let decrypted = decrypt_qrc_bytes(local_file).expect("解密本地缓存文件失败");
+        assert_eq!(decrypted, decrypt_qrc(ENCRYPTED_HEX_STRING).unwrap());
+
+        // 以 `[offset:0]` 开头的明文 QRC 不应被识别为本地缓存文件
+        assert!(!is_local_qrc(b"[offset:0]\n[0,500]a(0,500)"));
+    }
 }
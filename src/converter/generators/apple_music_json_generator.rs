@@ -48,6 +48,7 @@ struct PlayParams {
 pub fn generate_apple_music_json(
     lines: &[LyricLine],
     metadata_store: &MetadataStore,
+    agent_store: &AgentStore,
     options: &ConversionOptions,
 ) -> Result<String, ConvertError> {
     let apple_ttml_options = TtmlGenerationOptions {
@@ -56,9 +57,8 @@ pub fn generate_apple_music_json(
         ..options.ttml.clone()
     };
 
-    let agent_store = AgentStore::from_metadata_store(metadata_store);
     let ttml_content =
-        ttml_generator::generate_ttml(lines, metadata_store, &agent_store, &apple_ttml_options)?;
+        ttml_generator::generate_ttml(lines, metadata_store, agent_store, &apple_ttml_options)?;
 
     let apple_music_id = metadata_store
         .get_single_value(&CanonicalMetadataKey::AppleMusicId)
@@ -34,6 +34,7 @@ pub fn generate_ass(
     metadata_store: &MetadataStore,
     is_line_timed: bool,
     options: &AssGenerationOptions,
+    include_credits: bool,
 ) -> Result<String, ConvertError> {
     let mut ass_content = String::with_capacity(lines.len() * 200 + 1024);
 
@@ -96,6 +97,9 @@ pub fn generate_ass(
     )?;
 
     for (key, values) in metadata_store.get_all_data() {
+        if key.is_credit() && !include_credits {
+            continue;
+        }
         for value in values {
             writeln!(
                 ass_content,
@@ -104,11 +108,14 @@ pub fn generate_ass(
         }
     }
 
-    for line in lines {
+    for (index, line) in lines.iter().enumerate() {
+        write_song_part_comment(&mut ass_content, lines, index)?;
+
         for annotated_track in &line.tracks {
             let is_bg = annotated_track.content_type == ContentType::Background;
             let style = if is_bg { "bg-main" } else { "Default" };
-            let mut actor_field = line.agent.clone().unwrap_or_default();
+            // ASS 没有音节级的演唱者，使用行的主要演唱者
+            let mut actor_field = line.primary_agent().unwrap_or_default().to_string();
             if is_bg {
                 actor_field = "x-bg".to_string();
             } else if let Some(part) = &line.song_part {
@@ -161,6 +168,35 @@ pub fn generate_ass(
     Ok(ass_content)
 }
 
+/// 在每个歌曲组成部分的第一行前写入一条注释，标记该部分的名称和时间范围。
+fn write_song_part_comment(
+    output: &mut String,
+    lines: &[LyricLine],
+    index: usize,
+) -> Result<(), ConvertError> {
+    let Some(part) = lines[index].song_part.as_deref().filter(|p| !p.is_empty()) else {
+        return Ok(());
+    };
+    if index > 0 && lines[index - 1].song_part.as_deref() == Some(part) {
+        return Ok(());
+    }
+
+    let section_end_ms = lines[index..]
+        .iter()
+        .take_while(|line| line.song_part.as_deref() == Some(part))
+        .map(|line| line.end_ms)
+        .max()
+        .unwrap_or(lines[index].end_ms);
+
+    writeln!(
+        output,
+        "Comment: 0,{},{},Default,,0,0,0,song-part,{part}",
+        format_ass_time(lines[index].start_ms),
+        format_ass_time(section_end_ms),
+    )?;
+    Ok(())
+}
+
 fn write_dialogue_line(
     output: &mut String,
     line: &LyricLine,
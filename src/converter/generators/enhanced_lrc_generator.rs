@@ -3,11 +3,11 @@
 use tracing::warn;
 
 use crate::converter::{
-    generators::lrc_generator::format_lrc_time_ms,
+    generators::lrc_generator::{AgentMarkers, format_lrc_time_ms},
     processors::metadata_processor::MetadataStore,
     types::{
-        ContentType, ConvertError, LrcGenerationOptions, LrcSubLinesOutputMode, LyricLine,
-        LyricSyllable,
+        AgentStore, ContentType, ConvertError, LrcAgentMarkerStyle, LrcGenerationOptions,
+        LrcSubLinesOutputMode, LyricLine, LyricSyllable,
     },
 };
 
@@ -15,12 +15,21 @@ use crate::converter::{
 pub fn generate_enhanced_lrc(
     lines: &[LyricLine],
     metadata_store: &MetadataStore,
+    agent_store: &AgentStore,
     options: &LrcGenerationOptions,
+    include_credits: bool,
 ) -> Result<String, ConvertError> {
-    let header = metadata_store.generate_lrc_header();
+    let header = metadata_store.generate_lrc_header_with_credits(include_credits);
     let mut lyric_lines = Vec::new();
 
+    let marker_style = match options.agent_marker_style {
+        LrcAgentMarkerStyle::Auto => LrcAgentMarkerStyle::Voice,
+        style => style,
+    };
+    let mut markers = AgentMarkers::new(lines, agent_store, marker_style);
+
     for line in lines {
+        let marker = markers.marker_for(line);
         if let Some(main_track) = line
             .tracks
             .iter()
@@ -39,7 +48,7 @@ pub fn generate_enhanced_lrc(
 
             if syllables.len() == 1 && syllables[0].end_ms <= syllables[0].start_ms {
                 lyric_lines.push(format!(
-                    "{}{}",
+                    "{}{marker}{}",
                     format_lrc_time_ms(line.start_ms),
                     syllables[0].text
                 ));
@@ -48,8 +57,10 @@ pub fn generate_enhanced_lrc(
                     &syllables.into_iter().cloned().collect::<Vec<_>>(),
                     line.start_ms,
                     line.end_ms,
+                    &marker,
                 ));
             }
+            markers.mark_emitted(line);
         }
 
         if let Some(bg_track) = line
@@ -84,6 +95,7 @@ pub fn generate_enhanced_lrc(
                             &syllables,
                             bg_start_ms,
                             bg_end_ms,
+                            "",
                         ));
                     }
                 }
@@ -103,9 +115,15 @@ pub fn generate_enhanced_lrc(
 }
 
 /// 辅助函数，根据音节列表构建单行增强型LRC歌词
-fn build_enhanced_lrc_line(syllables: &[LyricSyllable], start_ms: u64, end_ms: u64) -> String {
+fn build_enhanced_lrc_line(
+    syllables: &[LyricSyllable],
+    start_ms: u64,
+    end_ms: u64,
+    marker: &str,
+) -> String {
     let mut line_builder = String::new();
     line_builder.push_str(&format_lrc_time_ms(start_ms));
+    line_builder.push_str(marker);
 
     for syllable in syllables {
         line_builder.push_str(&format_word_time(syllable.start_ms));
@@ -0,0 +1,155 @@
+//! Karaoke MIDI (.kar) 格式生成器
+//!
+//! 生成只包含一条音轨的 SMF 格式 0 文件。文件把分辨率设为每四分音符 500 tick、
+//! 速度设为每四分音符 500000 微秒，这样 1 tick 恰好等于 1 毫秒。
+
+use crate::converter::{
+    processors::metadata_processor::MetadataStore,
+    types::{CanonicalMetadataKey, ContentType, ConvertError, LyricLine},
+};
+
+/// 每四分音符的 tick 数。
+const TICKS_PER_QUARTER: u16 = 500;
+
+/// 每四分音符的微秒数，与 `TICKS_PER_QUARTER` 配合使 1 tick = 1 毫秒。
+const TEMPO_US_PER_QUARTER: u32 = 500_000;
+
+/// Karaoke MIDI 生成的主入口函数。
+///
+/// 返回完整的 MIDI 文件字节。
+pub fn generate_kar(
+    lines: &[LyricLine],
+    metadata_store: &MetadataStore,
+) -> Result<Vec<u8>, ConvertError> {
+    let mut track = TrackWriter::default();
+
+    track.meta_event(0, 0x51, &TEMPO_US_PER_QUARTER.to_be_bytes()[1..]);
+    track.meta_event(0, 0x01, b"@KMIDI KARAOKE FILE");
+    track.meta_event(0, 0x01, b"@V0100");
+
+    // Soft Karaoke 约定：第一个 @T 是标题，第二个是艺术家
+    let title = metadata_store.get_single_value(&CanonicalMetadataKey::Title);
+    let artist = metadata_store
+        .get_multiple_values(&CanonicalMetadataKey::Artist)
+        .map(|artists| artists.join("/"));
+    if title.is_some() || artist.is_some() {
+        let title = title.map_or("", String::as_str);
+        track.meta_event(0, 0x01, format!("@T{title}").as_bytes());
+        if let Some(artist) = artist {
+            track.meta_event(0, 0x01, format!("@T{artist}").as_bytes());
+        }
+    }
+    if let Some(language) = metadata_store.get_single_value(&CanonicalMetadataKey::Language) {
+        track.meta_event(0, 0x01, format!("@L{language}").as_bytes());
+    }
+
+    let mut is_first_line = true;
+    let mut last_song_part: Option<&str> = None;
+    for line in lines {
+        // KAR 不支持背景人声
+        let Some(main_track) = line
+            .tracks
+            .iter()
+            .find(|t| t.content_type == ContentType::Main)
+        else {
+            continue;
+        };
+        let syllables: Vec<_> = main_track
+            .content
+            .words
+            .iter()
+            .flat_map(|w| &w.syllables)
+            .filter(|s| !s.text.is_empty())
+            .collect();
+        if syllables.is_empty() {
+            continue;
+        }
+
+        // `\` 表示新段落，`/` 表示新的一行
+        let song_part = line.song_part.as_deref();
+        let prefix = if is_first_line || (song_part.is_some() && song_part != last_song_part) {
+            "\\"
+        } else {
+            "/"
+        };
+        is_first_line = false;
+        last_song_part = song_part;
+
+        for (i, syl) in syllables.iter().enumerate() {
+            let mut text = String::with_capacity(syl.text.len() + 2);
+            if i == 0 {
+                text.push_str(prefix);
+            }
+            text.push_str(&syl.text);
+            if syl.ends_with_space && i + 1 < syllables.len() {
+                text.push(' ');
+            }
+            track.meta_event(syl.start_ms, 0x01, text.as_bytes());
+        }
+    }
+
+    let end_tick = lines.iter().map(|l| l.end_ms).max().unwrap_or(0);
+    track.meta_event(end_tick, 0x2F, &[]);
+
+    let track_data = track.finish();
+    let track_len = u32::try_from(track_data.len())
+        .map_err(|_| ConvertError::Internal("MIDI 音轨数据过大".to_string()))?;
+
+    let mut output = Vec::with_capacity(track_data.len() + 22);
+    output.extend_from_slice(b"MThd");
+    output.extend_from_slice(&6u32.to_be_bytes());
+    output.extend_from_slice(&0u16.to_be_bytes()); // 格式 0
+    output.extend_from_slice(&1u16.to_be_bytes()); // 一条音轨
+    output.extend_from_slice(&TICKS_PER_QUARTER.to_be_bytes());
+    output.extend_from_slice(b"MTrk");
+    output.extend_from_slice(&track_len.to_be_bytes());
+    output.extend_from_slice(&track_data);
+
+    Ok(output)
+}
+
+/// 按绝对 tick 写入事件，并自动计算增量时间。
+#[derive(Default)]
+struct TrackWriter {
+    data: Vec<u8>,
+    last_tick: u64,
+}
+
+impl TrackWriter {
+    fn meta_event(&mut self, tick: u64, meta_type: u8, payload: &[u8]) {
+        // 事件必须按时间排序，重叠的音节会被压到同一个 tick
+        let tick = tick.max(self.last_tick);
+        write_vlq(&mut self.data, tick - self.last_tick);
+        self.last_tick = tick;
+
+        self.data.push(0xFF);
+        self.data.push(meta_type);
+        write_vlq(&mut self.data, payload.len() as u64);
+        self.data.extend_from_slice(payload);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// 写入 MIDI 变长整数 (VLQ)。
+fn write_vlq(out: &mut Vec<u8>, value: u64) {
+    // VLQ 最多 4 字节，即 28 位
+    let value = value.min(0x0FFF_FFFF);
+    let mut groups = [0u8; 4];
+    let mut count = 0;
+    let mut rest = value;
+    loop {
+        groups[count] = (rest & 0x7F) as u8;
+        count += 1;
+        rest >>= 7;
+        if rest == 0 {
+            break;
+        }
+    }
+    for i in (0..count).rev() {
+        let continuation = if i == 0 { 0 } else { 0x80 };
+        out.push(groups[i] | continuation);
+    }
+}
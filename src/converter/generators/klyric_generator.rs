@@ -0,0 +1,63 @@
+//! 网易云 klyric 歌词格式生成器。
+
+use std::fmt::Write;
+
+use crate::converter::{
+    generators::yrc_generator::write_metadata_lines,
+    processors::metadata_processor::MetadataStore,
+    types::{ContentType, ConvertError, LyricLine},
+};
+
+/// klyric 生成的主入口函数。
+///
+/// 音节时间写为相对于行开始时间的偏移，即 `[start,duration](offset,duration)text`。
+pub fn generate_klyric(
+    lines: &[LyricLine],
+    metadata_store: &MetadataStore,
+) -> Result<String, ConvertError> {
+    let mut output = String::new();
+
+    write_metadata_lines(&mut output, lines, metadata_store)?;
+
+    for line in lines {
+        let syllables: Vec<_> = line
+            .tracks
+            .iter()
+            .filter(|t| t.content_type == ContentType::Main)
+            .flat_map(|t| &t.content.words)
+            .flat_map(|w| &w.syllables)
+            .collect();
+        if syllables.is_empty() {
+            continue;
+        }
+
+        let line_duration = line.end_ms.saturating_sub(line.start_ms);
+        write!(output, "[{},{}]", line.start_ms, line_duration)?;
+        for syl in syllables {
+            let offset = syl.start_ms.saturating_sub(line.start_ms);
+            let duration = syl.end_ms.saturating_sub(syl.start_ms);
+            let space = if syl.ends_with_space { " " } else { "" };
+            write!(output, "({offset},{duration}){}{space}", syl.text)?;
+        }
+        writeln!(output)?;
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::parsers::klyric_parser::parse_klyric;
+
+    #[test]
+    fn test_klyric_round_trip() {
+        let content = "[1000,600](0,200)He(200,400)llo\n[2000,500](0,500)World\n";
+        let parsed = parse_klyric(content).unwrap();
+        let metadata_store = MetadataStore::from(&parsed);
+
+        let generated = generate_klyric(&parsed.lines, &metadata_store).unwrap();
+
+        assert_eq!(generated, content);
+    }
+}
@@ -11,10 +11,15 @@ use crate::converter::{
 pub fn generate_krc(
     lines: &[LyricLine],
     metadata_store: &MetadataStore,
+    include_credits: bool,
 ) -> Result<String, ConvertError> {
     let mut krc_output = String::new();
 
-    writeln!(krc_output, "{}", metadata_store.generate_lrc_header())?;
+    writeln!(
+        krc_output,
+        "{}",
+        metadata_store.generate_lrc_header_with_credits(include_credits)
+    )?;
 
     for line in lines {
         // KRC 不支持背景人声
@@ -41,7 +46,13 @@ pub fn generate_krc(
                 let offset_ms = syl.start_ms.saturating_sub(line.start_ms);
                 let duration_ms = syl.end_ms.saturating_sub(syl.start_ms);
 
-                write!(krc_output, "<{},{},0>{}", offset_ms, duration_ms, syl.text)?;
+                let pitch = syl.pitch.unwrap_or(0);
+
+                write!(
+                    krc_output,
+                    "<{},{},{}>{}",
+                    offset_ms, duration_ms, pitch, syl.text
+                )?;
             }
 
             writeln!(krc_output)?;
@@ -6,8 +6,8 @@ use crate::converter::{
     generators,
     processors::metadata_processor::MetadataStore,
     types::{
-        AnnotatedTrack, ContentType, ConvertError, LqeGenerationOptions, LyricFormat, LyricLine,
-        TrackMetadataKey,
+        AgentStore, AnnotatedTrack, ContentType, ConvertError, LqeGenerationOptions, LyricFormat,
+        LyricLine, TrackMetadataKey,
     },
 };
 
@@ -16,13 +16,14 @@ pub fn generate_lqe(
     lines: &[LyricLine],
     metadata_store: &MetadataStore,
     options: &LqeGenerationOptions,
+    include_credits: bool,
 ) -> Result<String, ConvertError> {
     let mut writer = String::new();
 
     writer.push_str("[Lyricify Quick Export]\n");
     writer.push_str("[version:1.0]\n");
 
-    let lrc_header = metadata_store.generate_lrc_header();
+    let lrc_header = metadata_store.generate_lrc_header_with_credits(include_credits);
     if !lrc_header.is_empty() {
         writer.push_str(&lrc_header);
         writer.push('\n');
@@ -159,18 +160,26 @@ fn generate_sub_format(
     metadata_store: &MetadataStore,
     format: LyricFormat,
 ) -> Result<String, ConvertError> {
+    // 默认选项不输出演唱者标记，不需要演唱者信息；演职员信息只写入 LQE 头部
     let dummy_options = crate::converter::types::ConversionOptions::default();
+    let agent_store = AgentStore::default();
 
     match format {
-        LyricFormat::Lrc => {
-            generators::lrc_generator::generate_lrc(lines, metadata_store, &dummy_options.lrc)
-        }
+        LyricFormat::Lrc => generators::lrc_generator::generate_lrc(
+            lines,
+            metadata_store,
+            &agent_store,
+            &dummy_options.lrc,
+            false,
+        ),
         LyricFormat::EnhancedLrc => generators::enhanced_lrc_generator::generate_enhanced_lrc(
             lines,
             metadata_store,
+            &agent_store,
             &dummy_options.lrc,
+            false,
         ),
-        LyricFormat::Lys => generators::lys_generator::generate_lys(lines, metadata_store),
+        LyricFormat::Lys => generators::lys_generator::generate_lys(lines, metadata_store, false),
         _ => Err(ConvertError::Internal(format!(
             "LQE 生成器不支持将内部区块格式化为 '{format:?}'"
         ))),
@@ -1,12 +1,13 @@
 //! LRC 格式生成器
 
-use std::fmt::Write as FmtWrite;
+use std::{collections::HashSet, fmt::Write as FmtWrite};
 
 use crate::converter::{
+    parsers::lrc_parser::CHORUS_AGENT_ID,
     processors::metadata_processor::MetadataStore,
     types::{
-        ContentType, ConvertError, LrcEndTimeOutputMode, LrcGenerationOptions,
-        LrcSubLinesOutputMode, LyricLine, LyricTrack,
+        AgentStore, AgentType, ContentType, ConvertError, LrcAgentMarkerStyle,
+        LrcEndTimeOutputMode, LrcGenerationOptions, LrcSubLinesOutputMode, LyricLine, LyricTrack,
     },
 };
 
@@ -14,16 +15,25 @@ use crate::converter::{
 pub fn generate_lrc(
     lines: &[LyricLine],
     metadata_store: &MetadataStore,
+    agent_store: &AgentStore,
     options: &LrcGenerationOptions,
+    include_credits: bool,
 ) -> Result<String, ConvertError> {
     let mut lrc_output = String::with_capacity(lines.len() * 50);
 
-    let lrc_header = metadata_store.generate_lrc_header();
+    let lrc_header = metadata_store.generate_lrc_header_with_credits(include_credits);
     if !lrc_header.is_empty() {
         writeln!(lrc_output, "{}", lrc_header.trim_end_matches('\n'))?;
     }
 
+    let marker_style = match options.agent_marker_style {
+        LrcAgentMarkerStyle::Auto => LrcAgentMarkerStyle::Walaoke,
+        style => style,
+    };
+    let mut markers = AgentMarkers::new(lines, agent_store, marker_style);
+
     for (i, line) in lines.iter().enumerate() {
+        let marker = markers.marker_for(line);
         let main_annotated_track = line
             .tracks
             .iter()
@@ -33,24 +43,30 @@ pub fn generate_lrc(
             .iter()
             .find(|t| t.content_type == ContentType::Background);
 
-        match options.sub_lines_output_mode {
-            LrcSubLinesOutputMode::Ignore => {
-                if let Some(track) = main_annotated_track {
-                    write_track_as_line(&mut lrc_output, line.start_ms, &track.content)?;
+        let written = match options.sub_lines_output_mode {
+            LrcSubLinesOutputMode::Ignore => match main_annotated_track {
+                Some(track) => {
+                    write_track_as_line(&mut lrc_output, line.start_ms, &marker, &track.content)?
                 }
-            }
-            LrcSubLinesOutputMode::MergeWithParentheses => {
-                write_merged_line(
-                    &mut lrc_output,
-                    line.start_ms,
-                    main_annotated_track.map(|t| &t.content),
-                    bg_annotated_track.map(|t| &t.content),
-                )?;
-            }
+                None => false,
+            },
+            LrcSubLinesOutputMode::MergeWithParentheses => write_merged_line(
+                &mut lrc_output,
+                line.start_ms,
+                &marker,
+                main_annotated_track.map(|t| &t.content),
+                bg_annotated_track.map(|t| &t.content),
+            )?,
             LrcSubLinesOutputMode::SeparateLines => {
-                if let Some(track) = main_annotated_track {
-                    write_track_as_line(&mut lrc_output, line.start_ms, &track.content)?;
-                }
+                let written = match main_annotated_track {
+                    Some(track) => write_track_as_line(
+                        &mut lrc_output,
+                        line.start_ms,
+                        &marker,
+                        &track.content,
+                    )?,
+                    None => false,
+                };
                 if let Some(track) = bg_annotated_track {
                     let bg_start_ms = track
                         .content
@@ -60,9 +76,13 @@ pub fn generate_lrc(
                         .map(|s| s.start_ms)
                         .min()
                         .unwrap_or(line.start_ms);
-                    write_track_as_line(&mut lrc_output, bg_start_ms, &track.content)?;
+                    write_track_as_line(&mut lrc_output, bg_start_ms, "", &track.content)?;
                 }
+                written
             }
+        };
+        if written {
+            markers.mark_emitted(line);
         }
 
         let next_line_start_ms = lines.get(i + 1).map(|l| l.start_ms);
@@ -97,32 +117,35 @@ fn get_text_from_track(track: &LyricTrack) -> String {
     line_text.trim_end().to_string()
 }
 
-/// 将一个轨道作为简单的 LRC 行写入。
+/// 将一个轨道作为简单的 LRC 行写入。轨道没有文本时不写入，返回 `false`。
 fn write_track_as_line(
     output: &mut String,
     start_ms: u64,
+    marker: &str,
     track: &LyricTrack,
-) -> Result<(), std::fmt::Error> {
+) -> Result<bool, std::fmt::Error> {
     let text = get_text_from_track(track);
-    if !text.trim().is_empty() {
-        writeln!(output, "{}{}", format_lrc_time_ms(start_ms), text)?;
+    if text.trim().is_empty() {
+        return Ok(false);
     }
-    Ok(())
+    writeln!(output, "{}{marker}{text}", format_lrc_time_ms(start_ms))?;
+    Ok(true)
 }
 
-/// 将主轨道和背景轨道合并为一行写入。
+/// 将主轨道和背景轨道合并为一行写入。两者都没有文本时不写入，返回 `false`。
 fn write_merged_line(
     output: &mut String,
     line_start_ms: u64,
+    marker: &str,
     main_track: Option<&LyricTrack>,
     bg_track: Option<&LyricTrack>,
-) -> Result<(), std::fmt::Error> {
+) -> Result<bool, std::fmt::Error> {
     let main_text = main_track.map(get_text_from_track);
     let bg_text = bg_track.map(get_text_from_track);
 
     match (main_text, bg_text) {
         (Some(mt), Some(bt)) if !mt.trim().is_empty() && !bt.trim().is_empty() => {
-            let merged_text = format!("{} ({})", mt.trim(), bt.trim());
+            let merged_text = format!("{marker}{} ({})", mt.trim(), bt.trim());
             writeln!(
                 output,
                 "{}{}",
@@ -131,10 +154,10 @@ fn write_merged_line(
             )?;
         }
         (Some(mt), _) if !mt.trim().is_empty() => {
-            writeln!(output, "{}{}", format_lrc_time_ms(line_start_ms), mt)?;
+            writeln!(output, "{}{marker}{mt}", format_lrc_time_ms(line_start_ms))?;
         }
         (_, Some(bt)) if !bt.trim().is_empty() => {
-            let merged_text = format!("({})", bt.trim());
+            let merged_text = format!("{marker}({})", bt.trim());
             writeln!(
                 output,
                 "{}{}",
@@ -142,9 +165,91 @@ fn write_merged_line(
                 merged_text
             )?;
         }
-        _ => {}
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// 计算行首的演唱者标记。
+///
+/// 标记只在演唱者与上一个实际写出的行不同时输出，之后的行沿用该标记。
+/// Walaoke 样式中，`M:` 和 `F:` 只用于区分演唱者，并不表示性别：
+/// 编号最小的单人演唱者（如 `v1`）为 `M:`，其他单人演唱者为 `F:`，合唱为 `D:`。
+pub(crate) struct AgentMarkers {
+    style: LrcAgentMarkerStyle,
+    group_ids: HashSet<String>,
+    first_solo: Option<String>,
+    /// 上一个实际写出的行的演唱者
+    previous: Option<String>,
+}
+
+impl AgentMarkers {
+    /// `style` 不能是 `Auto`。
+    pub(crate) fn new(
+        lines: &[LyricLine],
+        agent_store: &AgentStore,
+        style: LrcAgentMarkerStyle,
+    ) -> Self {
+        let style = if lines.iter().all(|l| l.agent.is_none()) {
+            LrcAgentMarkerStyle::Disabled
+        } else {
+            style
+        };
+
+        let mut group_ids: HashSet<String> = agent_store
+            .agents_by_id
+            .values()
+            .filter(|a| a.agent_type == AgentType::Group)
+            .map(|a| a.id.clone())
+            .collect();
+        group_ids.insert(CHORUS_AGENT_ID.to_string());
+
+        let first_solo = lines
+            .iter()
+            .filter_map(|l| l.agent.as_deref())
+            .filter(|id| !group_ids.contains(*id))
+            .min_by_key(|id| {
+                (
+                    id.trim_start_matches('v')
+                        .parse::<u32>()
+                        .unwrap_or(u32::MAX),
+                    *id,
+                )
+            })
+            .map(str::to_string);
+
+        Self {
+            style,
+            group_ids,
+            first_solo,
+            previous: None,
+        }
+    }
+
+    /// 返回该行应使用的标记，不需要标记时返回空字符串。
+    pub(crate) fn marker_for(&self, line: &LyricLine) -> String {
+        let Some(agent) = line.agent.as_deref() else {
+            return String::new();
+        };
+        if self.style == LrcAgentMarkerStyle::Disabled || self.previous.as_deref() == Some(agent) {
+            return String::new();
+        }
+        match self.style {
+            LrcAgentMarkerStyle::Walaoke if self.group_ids.contains(agent) => "D:".to_string(),
+            LrcAgentMarkerStyle::Walaoke if self.first_solo.as_deref() == Some(agent) => {
+                "M:".to_string()
+            }
+            LrcAgentMarkerStyle::Walaoke => "F:".to_string(),
+            _ => format!("{agent}:"),
+        }
+    }
+
+    /// 记录该行已被写出，之后同一演唱者的行不再重复输出标记。
+    pub(crate) fn mark_emitted(&mut self, line: &LyricLine) {
+        if let Some(agent) = &line.agent {
+            self.previous = Some(agent.clone());
+        }
     }
-    Ok(())
 }
 
 /// 根据选项处理是否输出行结束时间戳。
@@ -191,3 +296,104 @@ pub fn format_lrc_time_ms(ms: u64) -> String {
     let milliseconds = ms % 1000;
     format!("[{minutes:02}:{seconds:02}.{milliseconds:03}]")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::{parsers::lrc_parser::parse_lrc, types::LrcParsingOptions};
+
+    #[test]
+    fn test_walaoke_markers_round_trip() {
+        let content = "[00:01.00]F:Second singer first\n[00:02.00]M:First\n[00:03.00]Still first\n[00:04.00]D:Together\n";
+        let parsed = parse_lrc(content, &LrcParsingOptions::default()).unwrap();
+        let metadata_store = MetadataStore::from(&parsed);
+
+        let options = LrcGenerationOptions {
+            agent_marker_style: LrcAgentMarkerStyle::Walaoke,
+            ..Default::default()
+        };
+        let output = generate_lrc(
+            &parsed.lines,
+            &metadata_store,
+            &parsed.agents,
+            &options,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "[00:01.000]F:Second singer first\n[00:02.000]M:First\n[00:03.000]Still first\n[00:04.000]D:Together\n"
+        );
+
+        // 默认不输出演唱者标记
+        let output = generate_lrc(
+            &parsed.lines,
+            &metadata_store,
+            &parsed.agents,
+            &LrcGenerationOptions::default(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "[00:01.000]Second singer first\n[00:02.000]First\n[00:03.000]Still first\n[00:04.000]Together\n"
+        );
+    }
+
+    #[test]
+    fn test_markers_skip_lines_that_are_not_written() {
+        let content = "[00:01.00]M:First\n[00:03.00]F:Second\n";
+        let mut parsed = parse_lrc(content, &LrcParsingOptions::default()).unwrap();
+        let metadata_store = MetadataStore::from(&parsed);
+        // 插入一行属于第二位演唱者、但没有文本的行，它不会被写出
+        let mut empty_line = parsed.lines[1].clone();
+        empty_line.start_ms = 2000;
+        empty_line.tracks.clear();
+        parsed.lines.insert(1, empty_line);
+
+        let options = LrcGenerationOptions {
+            agent_marker_style: LrcAgentMarkerStyle::Walaoke,
+            ..Default::default()
+        };
+        let output = generate_lrc(
+            &parsed.lines,
+            &metadata_store,
+            &parsed.agents,
+            &options,
+            false,
+        )
+        .unwrap();
+        assert_eq!(output, "[00:01.000]M:First\n[00:03.000]F:Second\n");
+    }
+
+    #[test]
+    fn test_credit_tags_are_opt_in() {
+        let mut metadata_store = MetadataStore::new();
+        metadata_store.add("ti", "Song").unwrap();
+        metadata_store.add("lyricist", "Writer").unwrap();
+        metadata_store.add("composer", "Composer").unwrap();
+
+        let output = generate_lrc(
+            &[],
+            &metadata_store,
+            &AgentStore::default(),
+            &LrcGenerationOptions::default(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(output, "[ti:Song]\n");
+
+        let output = generate_lrc(
+            &[],
+            &metadata_store,
+            &AgentStore::default(),
+            &LrcGenerationOptions::default(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "[ti:Song]\n[lyricist:Writer]\n[composer:Composer]\n"
+        );
+    }
+}
@@ -4,19 +4,30 @@ use std::fmt::Write;
 
 use crate::converter::{
     processors::metadata_processor::MetadataStore,
-    types::{ContentType, ConvertError, LyricLine, Word, lys_properties},
+    types::{ContentType, ConvertError, LyricLine, ViewSide, Word, lys_properties},
 };
 
 /// LYS 生成的主入口函数。
 pub fn generate_lys(
     lines: &[LyricLine],
     metadata_store: &MetadataStore,
+    include_credits: bool,
 ) -> Result<String, ConvertError> {
     let mut lys_output = String::with_capacity(lines.len() * 120);
 
-    writeln!(lys_output, "{}", metadata_store.generate_lrc_header())?;
+    writeln!(
+        lys_output,
+        "{}",
+        metadata_store.generate_lrc_header_with_credits(include_credits)
+    )?;
 
     for line in lines {
+        // 没有显式的显示位置时，主要演唱者为 "v2" 靠右，其他情况靠左
+        let view_side = line.view_side.unwrap_or(match line.primary_agent() {
+            Some("v2") => ViewSide::Right,
+            _ => ViewSide::Left,
+        });
+
         // 主歌词行
         if let Some(main_track) = line
             .tracks
@@ -24,9 +35,9 @@ pub fn generate_lys(
             .find(|t| t.content_type == ContentType::Main)
             && !main_track.content.words.is_empty()
         {
-            let property = match line.agent.as_deref() {
-                Some("v2") => lys_properties::MAIN_RIGHT,
-                _ => lys_properties::MAIN_LEFT,
+            let property = match view_side {
+                ViewSide::Left => lys_properties::MAIN_LEFT,
+                ViewSide::Right => lys_properties::MAIN_RIGHT,
             };
             write!(lys_output, "[{property}]")?;
             write_words_to_lys_string(&mut lys_output, &main_track.content.words, false)?;
@@ -40,11 +51,9 @@ pub fn generate_lys(
             .find(|t| t.content_type == ContentType::Background)
             && !bg_track.content.words.is_empty()
         {
-            let bg_property = match line.agent.as_deref() {
-                // agent "v2" -> 右。
-                Some("v2") => lys_properties::BG_RIGHT,
-                // 其他情况 -> 左。
-                _ => lys_properties::BG_LEFT,
+            let bg_property = match line.background_view_side.unwrap_or(view_side) {
+                ViewSide::Left => lys_properties::BG_LEFT,
+                ViewSide::Right => lys_properties::BG_RIGHT,
             };
             write!(lys_output, "[{bg_property}]")?;
             write_words_to_lys_string(&mut lys_output, &bg_track.content.words, true)?;
@@ -3,12 +3,16 @@
 pub mod apple_music_json_generator;
 pub mod ass_generator;
 pub mod enhanced_lrc_generator;
+pub mod kar_generator;
+pub mod klyric_generator;
 pub mod krc_generator;
 pub mod lqe_generator;
 pub mod lrc_generator;
 pub mod lyricify_lines_generator;
 pub mod lys_generator;
 pub mod qrc_generator;
+pub mod sami_generator;
 pub mod spl_generator;
 pub mod ttml_generator;
+pub mod ultrastar_generator;
 pub mod yrc_generator;
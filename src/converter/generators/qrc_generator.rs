@@ -11,10 +11,15 @@ use crate::converter::{
 pub fn generate_qrc(
     lines: &[LyricLine],
     metadata_store: &MetadataStore,
+    include_credits: bool,
 ) -> Result<String, ConvertError> {
     let mut qrc_output = String::new();
 
-    writeln!(qrc_output, "{}", metadata_store.generate_lrc_header())?;
+    writeln!(
+        qrc_output,
+        "{}",
+        metadata_store.generate_lrc_header_with_credits(include_credits)
+    )?;
 
     for line in lines {
         // 主歌词行
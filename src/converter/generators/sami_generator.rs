@@ -0,0 +1,308 @@
+//! SAMI (.smi) 字幕格式生成器。
+
+use std::fmt::Write;
+
+use quick_xml::escape::partial_escape;
+
+use crate::converter::{
+    parsers::sami_parser::SAMI_CLASS_LANGUAGES,
+    processors::metadata_processor::MetadataStore,
+    types::{
+        CanonicalMetadataKey, ConvertError, LyricLine, LyricTrack, SamiGenerationOptions,
+        TrackMetadataKey,
+    },
+};
+
+/// 一个语言类对应的输出轨道。
+struct SamiClass {
+    name: String,
+    language: String,
+    kind: SamiTrackKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SamiTrackKind {
+    Main,
+    Translation,
+    Romanization,
+}
+
+/// SAMI 生成的主入口函数。
+pub fn generate_sami(
+    lines: &[LyricLine],
+    metadata_store: &MetadataStore,
+    options: &SamiGenerationOptions,
+) -> Result<String, ConvertError> {
+    let classes = collect_classes(lines, metadata_store, options);
+    let mut output = String::new();
+
+    writeln!(output, "<SAMI>")?;
+    writeln!(output, "<HEAD>")?;
+    if let Some(title) = metadata_store.get_single_value(&CanonicalMetadataKey::Title) {
+        writeln!(output, "<TITLE>{}</TITLE>", partial_escape(title.as_str()))?;
+    }
+    writeln!(output, "<STYLE TYPE=\"text/css\">")?;
+    writeln!(output, "<!--")?;
+    writeln!(
+        output,
+        "P {{ margin-left:8pt; margin-right:8pt; margin-bottom:2pt; margin-top:2pt; \
+         text-align:center; font-size:20pt; font-family:Arial, sans-serif; \
+         font-weight:normal; color:white; }}"
+    )?;
+    for class in &classes {
+        writeln!(
+            output,
+            ".{} {{ Name:{}; lang:{}; SAMIType:CC; }}",
+            class.name, class.language, class.language
+        )?;
+    }
+    writeln!(output, "-->")?;
+    writeln!(output, "</STYLE>")?;
+    writeln!(output, "</HEAD>")?;
+    writeln!(output, "<BODY>")?;
+
+    // 当前仍显示在屏幕上的语言类，下一次 SYNC 时需要清屏
+    let mut active = vec![false; classes.len()];
+    for (i, line) in lines.iter().enumerate() {
+        let texts: Vec<Option<String>> = classes
+            .iter()
+            .map(|class| class_text(line, class, &classes[0].language, options))
+            .collect();
+        if texts.iter().all(Option::is_none) {
+            continue;
+        }
+
+        write_sync(&mut output, line.start_ms, &classes, &texts, &mut active)?;
+
+        let next_start = lines[i + 1..]
+            .iter()
+            .find(|l| l.main_track().is_some())
+            .map(|l| l.start_ms);
+        if next_start.is_none_or(|start| start > line.end_ms) {
+            let clears = vec![None; classes.len()];
+            write_sync(&mut output, line.end_ms, &classes, &clears, &mut active)?;
+        }
+    }
+
+    writeln!(output, "</BODY>")?;
+    writeln!(output, "</SAMI>")?;
+    Ok(output)
+}
+
+/// 写出一个 `<SYNC>` 块。`texts` 中为 `None` 的语言类只在仍处于显示状态时写入清屏标记。
+fn write_sync(
+    output: &mut String,
+    start_ms: u64,
+    classes: &[SamiClass],
+    texts: &[Option<String>],
+    active: &mut [bool],
+) -> Result<(), ConvertError> {
+    let mut paragraphs = Vec::new();
+    for (index, (class, text)) in classes.iter().zip(texts).enumerate() {
+        match text {
+            Some(text) => {
+                paragraphs.push(format!(
+                    "<P Class={}>{}",
+                    class.name,
+                    partial_escape(text.as_str())
+                ));
+                active[index] = true;
+            }
+            None if active[index] => {
+                paragraphs.push(format!("<P Class={}>&nbsp;", class.name));
+                active[index] = false;
+            }
+            None => {}
+        }
+    }
+    if paragraphs.is_empty() {
+        return Ok(());
+    }
+
+    write!(output, "<SYNC Start={start_ms}>")?;
+    for paragraph in paragraphs {
+        writeln!(output, "{paragraph}")?;
+    }
+    Ok(())
+}
+
+/// 确定输出的语言类：主歌词一个，之后按首次出现的顺序为每种翻译（和罗马音）语言各一个。
+fn collect_classes(
+    lines: &[LyricLine],
+    metadata_store: &MetadataStore,
+    options: &SamiGenerationOptions,
+) -> Vec<SamiClass> {
+    let main_language = metadata_store
+        .get_single_value(&CanonicalMetadataKey::Language)
+        .cloned()
+        .or_else(|| {
+            lines
+                .iter()
+                .filter_map(LyricLine::main_track)
+                .find_map(|t| t.content.metadata.get(&TrackMetadataKey::Language))
+                .cloned()
+        })
+        .unwrap_or_else(|| options.default_main_language.clone());
+
+    let mut classes = vec![SamiClass {
+        name: String::new(),
+        language: main_language.clone(),
+        kind: SamiTrackKind::Main,
+    }];
+
+    for track in lines.iter().filter_map(LyricLine::main_track) {
+        let mut auxiliary: Vec<(SamiTrackKind, &LyricTrack)> = track
+            .translations
+            .iter()
+            .map(|t| (SamiTrackKind::Translation, t))
+            .collect();
+        if options.include_romanizations {
+            auxiliary.extend(
+                track
+                    .romanizations
+                    .iter()
+                    .map(|t| (SamiTrackKind::Romanization, t)),
+            );
+        }
+        for (kind, aux) in auxiliary {
+            let language = auxiliary_language(aux, kind, &main_language, options);
+            if !classes
+                .iter()
+                .any(|c| c.kind == kind && c.language == language)
+            {
+                classes.push(SamiClass {
+                    name: String::new(),
+                    language,
+                    kind,
+                });
+            }
+        }
+    }
+
+    let mut used_names: Vec<String> = Vec::new();
+    for class in &mut classes {
+        let code = class_code(&class.language);
+        let mut name = format!("{code}CC");
+        let mut suffix = 2;
+        while used_names.contains(&name) {
+            name = format!("{code}{suffix}CC");
+            suffix += 1;
+        }
+        used_names.push(name.clone());
+        class.name = name;
+    }
+
+    classes
+}
+
+/// 取得翻译或罗马音轨道的语言代码。
+fn auxiliary_language(
+    track: &LyricTrack,
+    kind: SamiTrackKind,
+    main_language: &str,
+    options: &SamiGenerationOptions,
+) -> String {
+    if let Some(language) = track.metadata.get(&TrackMetadataKey::Language) {
+        return language.clone();
+    }
+    match kind {
+        SamiTrackKind::Romanization => {
+            let primary = main_language.split('-').next().unwrap_or(main_language);
+            format!("{primary}-Latn")
+        }
+        _ => options.default_translation_language.clone(),
+    }
+}
+
+/// 取得某一行在指定语言类中的文本。
+fn class_text(
+    line: &LyricLine,
+    class: &SamiClass,
+    main_language: &str,
+    options: &SamiGenerationOptions,
+) -> Option<String> {
+    let main = line.main_track()?;
+    let candidates = match class.kind {
+        SamiTrackKind::Main => return Some(main.content.text()).filter(|t| !t.trim().is_empty()),
+        SamiTrackKind::Translation => &main.translations,
+        SamiTrackKind::Romanization => &main.romanizations,
+    };
+    candidates
+        .iter()
+        .find(|t| auxiliary_language(t, class.kind, main_language, options) == class.language)
+        .map(LyricTrack::text)
+        .filter(|t| !t.trim().is_empty())
+}
+
+/// 由语言代码生成类名前缀，例如 `ko-KR` → `KR`，`en-US` → `EN`。
+fn class_code(language: &str) -> String {
+    if let Some((name, _)) = SAMI_CLASS_LANGUAGES
+        .iter()
+        .find(|(_, lang)| lang.eq_ignore_ascii_case(language))
+    {
+        return (*name).to_string();
+    }
+    let primary = language.split('-').next().unwrap_or(language);
+    SAMI_CLASS_LANGUAGES
+        .iter()
+        .find(|(_, lang)| {
+            lang.split('-')
+                .next()
+                .is_some_and(|p| p.eq_ignore_ascii_case(primary))
+        })
+        .map_or_else(
+            || primary.to_ascii_uppercase(),
+            |(name, _)| (*name).to_string(),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::{
+        parsers::sami_parser::parse_sami,
+        types::{ContentType, ParsedSourceData},
+    };
+
+    #[test]
+    fn test_sami_round_trip() {
+        let mut first = LyricLine::new(1000, 3000);
+        first.add_content_track(ContentType::Main, "안녕 & 하세요");
+        first.add_translation(ContentType::Main, "Hello", Some("en-US"));
+        let mut second = LyricLine::new(3000, 5000);
+        second.add_content_track(ContentType::Main, "노래");
+        let lines = vec![first, second];
+
+        let parsed = ParsedSourceData::default();
+        let metadata_store = MetadataStore::from(&parsed);
+        let generated =
+            generate_sami(&lines, &metadata_store, &SamiGenerationOptions::default()).unwrap();
+
+        assert!(generated.contains(".KRCC { Name:ko-KR; lang:ko-KR; SAMIType:CC; }"));
+        assert!(generated.contains(".ENCC { Name:en-US; lang:en-US; SAMIType:CC; }"));
+        assert!(generated.contains(
+            "<BODY>\n\
+             <SYNC Start=1000><P Class=KRCC>안녕 &amp; 하세요\n\
+             <P Class=ENCC>Hello\n\
+             <SYNC Start=3000><P Class=KRCC>노래\n\
+             <P Class=ENCC>&nbsp;\n\
+             <SYNC Start=5000><P Class=KRCC>&nbsp;\n\
+             </BODY>"
+        ));
+
+        let reparsed = parse_sami(&generated).unwrap();
+        assert_eq!(reparsed.lines.len(), 2);
+        assert_eq!(
+            reparsed.lines[0].main_text().as_deref(),
+            Some("안녕 & 하세요")
+        );
+        assert_eq!(
+            reparsed.lines[0].main_track().unwrap().translations[0].text(),
+            "Hello"
+        );
+        assert_eq!(
+            (reparsed.lines[1].start_ms, reparsed.lines[1].end_ms),
+            (3000, 5000)
+        );
+    }
+}
@@ -28,12 +28,19 @@ fn format_spl_timestamp(ms: u64, use_angle_brackets: bool) -> String {
 }
 
 /// SPL 生成的主入口函数。
+///
+/// SPL 不输出通用的元数据头部，只在 `include_credits` 为 `true` 时输出演职员标签。
 pub fn generate_spl(
     lines: &[LyricLine],
-    _metadata_store: &MetadataStore,
+    metadata_store: &MetadataStore,
+    include_credits: bool,
 ) -> Result<String, ConvertError> {
     let mut spl_output = String::new();
 
+    if include_credits {
+        spl_output.push_str(&metadata_store.generate_lrc_credit_tags());
+    }
+
     for (i, line) in lines.iter().enumerate() {
         write!(spl_output, "{}", format_spl_timestamp(line.start_ms, false))?;
 
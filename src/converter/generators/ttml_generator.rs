@@ -3,7 +3,7 @@
 //! 该解析器设计上仅用于生成 Apple Music 和 AMLL 使用的 TTML 歌词文件，
 //! 无法用于生成通用的 TTML 字幕文件。
 
-use std::{collections::HashMap, io::Cursor, sync::LazyLock};
+use std::{collections::BTreeMap, io::Cursor, sync::LazyLock};
 
 use hyphenation::{Hyphenator, Language, Load, Standard};
 use quick_xml::{
@@ -17,11 +17,40 @@ use crate::converter::{
     processors::metadata_processor::MetadataStore,
     types::{
         Agent, AgentStore, AgentType, AnnotatedTrack, CanonicalMetadataKey, ContentType,
-        ConvertError, LyricLine, LyricSyllable, LyricTrack, TtmlGenerationOptions, TtmlTimingMode,
+        ConvertError, LyricLine, LyricSyllable, LyricTrack, TtmlExtensions, TtmlGenerationOptions,
+        TtmlTimingMode, ViewSide, XmlElement, XmlNode,
     },
     utils::normalize_text_whitespace,
 };
 
+/// 写入 `<amll:meta>` 的元数据键，按输出顺序排列。
+pub(crate) const AMLL_META_KEYS: &[(&str, CanonicalMetadataKey)] = &[
+    ("musicName", CanonicalMetadataKey::Title),
+    ("artists", CanonicalMetadataKey::Artist),
+    ("album", CanonicalMetadataKey::Album),
+    ("isrc", CanonicalMetadataKey::Isrc),
+    ("appleMusicId", CanonicalMetadataKey::AppleMusicId),
+    ("ncmMusicId", CanonicalMetadataKey::NcmMusicId),
+    ("spotifyId", CanonicalMetadataKey::SpotifyId),
+    ("qqMusicId", CanonicalMetadataKey::QqMusicId),
+    ("ttmlAuthorGithub", CanonicalMetadataKey::TtmlAuthorGithub),
+    (
+        "ttmlAuthorGithubLogin",
+        CanonicalMetadataKey::TtmlAuthorGithubLogin,
+    ),
+];
+
+/// 显示位置对应的 `<style>` 和 `<region>` 的 `xml:id`。
+const VIEW_SIDE_STYLE_LEFT: &str = "s-left";
+const VIEW_SIDE_STYLE_RIGHT: &str = "s-right";
+const VIEW_SIDE_REGION_LEFT: &str = "r-left";
+const VIEW_SIDE_REGION_RIGHT: &str = "r-right";
+/// 背景人声显示位置对应的 `<style>` 的 `xml:id`，由 `<span ttm:role="x-bg">` 引用。
+const BACKGROUND_VIEW_SIDE_STYLE_LEFT: &str = "s-bg-left";
+const BACKGROUND_VIEW_SIDE_STYLE_RIGHT: &str = "s-bg-right";
+/// 演唱者颜色对应的 `<style>` 的 `xml:id` 前缀，后接演唱者 ID，由 `<p>` 引用。
+pub(crate) const AGENT_COLOR_STYLE_PREFIX: &str = "s-agent-";
+
 static ENGLISH_HYPHENATOR: LazyLock<Standard> = LazyLock::new(|| {
     // 从嵌入的资源中加载美式英语词典
     Standard::from_embedded(Language::EnglishUS)
@@ -45,19 +74,31 @@ fn format_ttml_time(ms: u64) -> String {
     }
 }
 
-fn write_timed_tracks_to_head<W: std::io::Write>(
-    writer: &mut Writer<W>,
-    lines: &[LyricLine],
-    p_key_counter_base: i32,
-    track_kind: &str, // "translation" 或 "romanization"
-    container_tag_name: &str,
-    item_tag_name: &str,
-) -> Result<(), ConvertError> {
-    // 按语言对轨道进行分组
-    let mut grouped_by_lang: HashMap<Option<String>, Vec<(i32, &LyricTrack)>> = HashMap::new();
+/// `<head>` 中一行歌词在某种语言下的辅助轨道。
+struct HeadAuxiliaryText<'a> {
+    key: String,
+    main: Vec<&'a LyricTrack>,
+    background: Vec<&'a LyricTrack>,
+}
+
+/// 按语言收集需要写入 `<iTunesMetadata>` 的翻译（`"translation"`）或音译（`"romanization"`）轨道。
+///
+/// 逐行模式下正文不输出背景人声，其辅助轨道也一并跳过。
+fn collect_head_auxiliary_tracks<'a>(
+    lines: &'a [LyricLine],
+    track_kind: &str,
+    options: &TtmlGenerationOptions,
+) -> BTreeMap<Option<String>, Vec<HeadAuxiliaryText<'a>>> {
+    let mut grouped_by_lang: BTreeMap<Option<String>, Vec<HeadAuxiliaryText<'a>>> = BTreeMap::new();
 
     for (line_idx, line) in lines.iter().enumerate() {
+        let key = format!("L{}", line_idx + 1);
         for annotated_track in &line.tracks {
+            let is_background = match annotated_track.content_type {
+                ContentType::Main => false,
+                ContentType::Background if options.timing_mode == TtmlTimingMode::Word => true,
+                ContentType::Background => continue,
+            };
             let tracks_to_check = match track_kind {
                 "translation" => &annotated_track.translations,
                 "romanization" => &annotated_track.romanizations,
@@ -65,23 +106,48 @@ fn write_timed_tracks_to_head<W: std::io::Write>(
             };
 
             for track in tracks_to_check {
-                if track
+                let has_text = track
                     .words
                     .iter()
-                    .any(|w| w.syllables.iter().any(|s| s.end_ms > s.start_ms))
-                {
-                    let lang = track.metadata.get(&TrackMetadataKey::Language).cloned();
-                    let line_key = line_idx.try_into().unwrap_or(i32::MAX - p_key_counter_base)
-                        + p_key_counter_base;
-                    grouped_by_lang
-                        .entry(lang)
-                        .or_default()
-                        .push((line_key, track));
+                    .flat_map(|w| &w.syllables)
+                    .any(|s| !s.text.trim().is_empty());
+                if !has_text {
+                    continue;
+                }
+
+                let lang = track.metadata.get(&TrackMetadataKey::Language).cloned();
+                let entries = grouped_by_lang.entry(lang).or_default();
+                if entries.last().is_none_or(|entry| entry.key != key) {
+                    entries.push(HeadAuxiliaryText {
+                        key: key.clone(),
+                        main: Vec::new(),
+                        background: Vec::new(),
+                    });
+                }
+                let entry = entries.last_mut().unwrap(); // 上面保证了至少有一个元素
+                if is_background {
+                    entry.background.push(track);
+                } else {
+                    entry.main.push(track);
                 }
             }
         }
     }
 
+    grouped_by_lang
+}
+
+/// 将收集到的辅助轨道写入 `<translations>` 或 `<transliterations>` 块。
+///
+/// 带时间的轨道写为逐字的 `<span>`，否则写为纯文本；背景人声的轨道放在
+/// `<span ttm:role="x-bg">` 中。
+fn write_auxiliary_tracks_to_head<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    grouped_by_lang: &BTreeMap<Option<String>, Vec<HeadAuxiliaryText<'_>>>,
+    container_tag_name: &str,
+    item_tag_name: &str,
+    options: &TtmlGenerationOptions,
+) -> Result<(), ConvertError> {
     if grouped_by_lang.is_empty() {
         return Ok(());
     }
@@ -89,35 +155,36 @@ fn write_timed_tracks_to_head<W: std::io::Write>(
     writer
         .create_element(container_tag_name)
         .write_inner_content(|writer| {
-            let mut sorted_groups: Vec<_> = grouped_by_lang.into_iter().collect();
-            sorted_groups.sort_by_key(|(lang, _)| lang.clone());
-
-            for (lang, entries) in sorted_groups {
+            for (lang, entries) in grouped_by_lang {
                 let mut item_builder = writer.create_element(item_tag_name);
+                if item_tag_name == "translation" {
+                    item_builder = item_builder.with_attribute(("type", "subtitle"));
+                }
                 if let Some(lang_code) = lang.as_ref().filter(|s| !s.is_empty()) {
                     item_builder = item_builder.with_attribute(("xml:lang", lang_code.as_str()));
                 }
 
                 item_builder.write_inner_content(|writer| {
-                    for (line_idx, track) in entries {
+                    for entry in entries {
                         writer
                             .create_element("text")
-                            .with_attribute(("for", format!("L{line_idx}").as_str()))
+                            .with_attribute(("for", entry.key.as_str()))
                             .write_inner_content(|writer| {
-                                for word in &track.words {
-                                    for syl in &word.syllables {
-                                        writer
-                                            .create_element("span")
-                                            .with_attribute((
-                                                "begin",
-                                                format_ttml_time(syl.start_ms).as_str(),
-                                            ))
-                                            .with_attribute((
-                                                "end",
-                                                format_ttml_time(syl.end_ms).as_str(),
-                                            ))
-                                            .write_text_content(BytesText::new(&syl.text))?;
-                                    }
+                                for track in &entry.main {
+                                    write_head_auxiliary_track(writer, track, options)
+                                        .map_err(std::io::Error::other)?;
+                                }
+                                if !entry.background.is_empty() {
+                                    writer
+                                        .create_element("span")
+                                        .with_attribute(("ttm:role", "x-bg"))
+                                        .write_inner_content(|writer| {
+                                            for track in &entry.background {
+                                                write_head_auxiliary_track(writer, track, options)
+                                                    .map_err(std::io::Error::other)?;
+                                            }
+                                            Ok(())
+                                        })?;
                                 }
                                 Ok(())
                             })?;
@@ -131,6 +198,28 @@ fn write_timed_tracks_to_head<W: std::io::Write>(
     Ok(())
 }
 
+/// 写入 `<text>` 中的单条辅助轨道。
+fn write_head_auxiliary_track<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    track: &LyricTrack,
+    options: &TtmlGenerationOptions,
+) -> Result<(), ConvertError> {
+    let all_syllables: Vec<_> = track.words.iter().flat_map(|w| &w.syllables).collect();
+    if all_syllables.iter().any(|s| s.end_ms > s.start_ms) {
+        return write_track_as_spans(writer, track, None, options);
+    }
+
+    let full_text = all_syllables
+        .iter()
+        .map(|s| s.text.clone())
+        .collect::<Vec<_>>()
+        .join(" ");
+    writer.write_event(Event::Text(BytesText::new(&normalize_text_whitespace(
+        &full_text,
+    ))))?;
+    Ok(())
+}
+
 /// TTML 生成的主入口函数。
 ///
 /// # 参数
@@ -146,6 +235,25 @@ pub fn generate_ttml(
     metadata_store: &MetadataStore,
     agent_store: &AgentStore,
     options: &TtmlGenerationOptions,
+) -> Result<String, ConvertError> {
+    generate_ttml_with_extensions(
+        lines,
+        metadata_store,
+        agent_store,
+        &TtmlExtensions::default(),
+        options,
+    )
+}
+
+/// 与 [`generate_ttml`] 相同，但会额外写回解析 TTML 时保留的文档级别未识别内容。
+///
+/// 行和音节上保留的属性与元素总是会被写回，不需要通过这个函数。
+pub fn generate_ttml_with_extensions(
+    lines: &[LyricLine],
+    metadata_store: &MetadataStore,
+    agent_store: &AgentStore,
+    extensions: &TtmlExtensions,
+    options: &TtmlGenerationOptions,
 ) -> Result<String, ConvertError> {
     let mut buffer = Vec::new();
     let indent_char = b' ';
@@ -155,10 +263,24 @@ pub fn generate_ttml(
     let result = if options.format {
         let mut writer =
             Writer::new_with_indent(Cursor::new(&mut buffer), indent_char, indent_size);
-        generate_ttml_inner(&mut writer, lines, metadata_store, agent_store, options)
+        generate_ttml_inner(
+            &mut writer,
+            lines,
+            metadata_store,
+            agent_store,
+            extensions,
+            options,
+        )
     } else {
         let mut writer = Writer::new(Cursor::new(&mut buffer));
-        generate_ttml_inner(&mut writer, lines, metadata_store, agent_store, options)
+        generate_ttml_inner(
+            &mut writer,
+            lines,
+            metadata_store,
+            agent_store,
+            extensions,
+            options,
+        )
     };
 
     result?;
@@ -172,6 +294,7 @@ fn generate_ttml_inner<W: std::io::Write>(
     lines: &[LyricLine],
     metadata_store: &MetadataStore,
     agent_store: &AgentStore,
+    extensions: &TtmlExtensions,
     options: &TtmlGenerationOptions,
 ) -> Result<(), ConvertError> {
     // 准备根元素的属性
@@ -198,18 +321,59 @@ fn generate_ttml_inner<W: std::io::Write>(
         CanonicalMetadataKey::TtmlAuthorGithub,
         CanonicalMetadataKey::TtmlAuthorGithubLogin,
     ];
-    if amll_keys_to_check_for_namespace
+    let has_preserved_amll_elements = extensions
+        .head_elements
         .iter()
-        .any(|key| metadata_store.get_multiple_values(key).is_some())
+        .chain(&extensions.metadata_elements)
+        .any(|e| e.name.starts_with("amll:"));
+    if has_preserved_amll_elements
+        || amll_keys_to_check_for_namespace
+            .iter()
+            .any(|key| metadata_store.get_multiple_values(key).is_some())
     {
         namespace_attrs.push(("xmlns:amll", "http://www.example.com/ns/amll".to_string()));
     }
 
+    // 保留的额外命名空间声明与其他命名空间一起排序，其余属性写在最后
+    let (preserved_namespaces, preserved_attrs): (Vec<_>, Vec<_>) = extensions
+        .root_attributes
+        .iter()
+        .partition(|(key, _)| key.starts_with("xmlns:"));
+
+    let view_sides = collect_view_sides(lines);
+    let background_view_sides = if options.timing_mode == TtmlTimingMode::Word {
+        collect_background_view_sides(lines)
+    } else {
+        Vec::new()
+    };
+    let agent_colors = collect_agent_colors(lines, agent_store);
+    if (!view_sides.is_empty() || !background_view_sides.is_empty() || !agent_colors.is_empty())
+        && !preserved_namespaces
+            .iter()
+            .any(|(key, _)| key == "xmlns:tts")
+    {
+        namespace_attrs.push(("xmlns:tts", "http://www.w3.org/ns/ttml#styling".to_string()));
+    }
+    let head_elements = merge_style_head_elements(
+        &extensions.head_elements,
+        &view_sides,
+        &background_view_sides,
+        &agent_colors,
+    );
+    for (key, value) in preserved_namespaces {
+        namespace_attrs.push((key.as_str(), value.clone()));
+    }
+
     // 设置主语言属性
     let lang_attr = options
         .main_language
         .as_ref()
         .or_else(|| metadata_store.get_single_value(&CanonicalMetadataKey::Language))
+        .or_else(|| {
+            metadata_store
+                .get_multiple_values_by_key("xml:lang_root")
+                .and_then(|values| values.first())
+        })
         .filter(|s| !s.is_empty())
         .map(|lang| ("xml:lang", lang.clone()));
 
@@ -243,9 +407,29 @@ fn generate_ttml_inner<W: std::io::Write>(
             .with_attribute((*key, value.as_str()));
     }
 
+    for (key, value) in preserved_attrs {
+        element_writer = element_writer
+            .new_line()
+            .with_attribute((key.as_str(), value.as_str()));
+    }
+
     element_writer.write_inner_content(|writer| {
-        write_ttml_head(writer, metadata_store, lines, agent_store, options)?;
-        write_ttml_body(writer, lines, options)?;
+        write_ttml_head(
+            writer,
+            metadata_store,
+            lines,
+            agent_store,
+            extensions,
+            &head_elements,
+            options,
+        )?;
+        write_ttml_body(
+            writer,
+            lines,
+            agent_store,
+            &extensions.body_attributes,
+            options,
+        )?;
         Ok(())
     })?;
 
@@ -257,6 +441,8 @@ fn write_ttml_head<W: std::io::Write>(
     metadata_store: &MetadataStore,
     lines: &[LyricLine],
     agent_store: &AgentStore,
+    extensions: &TtmlExtensions,
+    head_elements: &[XmlElement],
     options: &TtmlGenerationOptions,
 ) -> Result<(), ConvertError> {
     writer
@@ -273,6 +459,7 @@ fn write_ttml_head<W: std::io::Write>(
                             id: "v1".to_string(),
                             name: None,
                             agent_type: AgentType::Person,
+                            ..Default::default()
                         });
                     }
 
@@ -304,63 +491,37 @@ fn write_ttml_head<W: std::io::Write>(
                     }
 
                     if options.use_apple_format_rules {
-                        let mut translations_by_lang: HashMap<
-                            Option<String>,
-                            Vec<(String, String)>,
-                        > = HashMap::new();
-
-                        for (i, line) in lines.iter().enumerate() {
-                            let p_key = format!("L{}", i + 1);
-                            for at in &line.tracks {
-                                for track in &at.translations {
-                                    let all_syllables: Vec<_> =
-                                        track.words.iter().flat_map(|w| &w.syllables).collect();
-                                    let is_timed =
-                                        all_syllables.iter().any(|s| s.end_ms > s.start_ms);
-
-                                    if !is_timed || all_syllables.len() <= 1 {
-                                        let lang = track
-                                            .metadata
-                                            .get(&TrackMetadataKey::Language)
-                                            .cloned();
-                                        let full_text = all_syllables
-                                            .iter()
-                                            .map(|s| s.text.clone())
-                                            .collect::<Vec<_>>()
-                                            .join(" ");
-
-                                        if !full_text.trim().is_empty() {
-                                            translations_by_lang.entry(lang).or_default().push((
-                                                p_key.clone(),
-                                                normalize_text_whitespace(&full_text),
-                                            ));
-                                        }
-                                    }
+                        let translations =
+                            collect_head_auxiliary_tracks(lines, "translation", options);
+                        let transliterations =
+                            collect_head_auxiliary_tracks(lines, "romanization", options);
+
+                        // 作词、作曲者同样属于 songwriters，amll:meta 中没有对应的键。
+                        // 编曲和制作人既没有 iTunesMetadata 元素，也不是 AMLL 规范 §3.2
+                        // 定义的元数据，写成自定义 amll:meta 会使投稿文件带上规范外的键，因此不输出
+                        let mut valid_songwriters: Vec<&String> = Vec::new();
+                        for key in [
+                            CanonicalMetadataKey::Songwriter,
+                            CanonicalMetadataKey::Lyricist,
+                            CanonicalMetadataKey::Composer,
+                        ] {
+                            for name in metadata_store
+                                .get_multiple_values(&key)
+                                .into_iter()
+                                .flatten()
+                            {
+                                if !name.trim().is_empty()
+                                    && !valid_songwriters.iter().any(|s| s.trim() == name.trim())
+                                {
+                                    valid_songwriters.push(name);
                                 }
                             }
                         }
 
-                        let valid_songwriters: Vec<&String> = metadata_store
-                            .get_multiple_values(&CanonicalMetadataKey::Songwriter)
-                            .map(|vec| vec.iter().filter(|s| !s.trim().is_empty()).collect())
-                            .unwrap_or_default();
-
                         // 检查是否有任何内容来证明创建 <iTunesMetadata> 块是合理的
-                        let has_timed_translations = lines.iter().any(|l| {
-                            l.tracks.iter().any(|at| {
-                                at.translations
-                                    .iter()
-                                    .any(|t| t.words.iter().flat_map(|w| &w.syllables).count() > 1)
-                            })
-                        });
-                        let has_timed_romanizations = lines
-                            .iter()
-                            .any(|l| l.tracks.iter().any(|at| !at.romanizations.is_empty()));
-
-                        if !translations_by_lang.is_empty()
+                        if !translations.is_empty()
                             || !valid_songwriters.is_empty()
-                            || has_timed_translations
-                            || has_timed_romanizations
+                            || !transliterations.is_empty()
                         {
                             writer
                                 .create_element("iTunesMetadata")
@@ -369,43 +530,16 @@ fn write_ttml_head<W: std::io::Write>(
                                     "http://music.apple.com/lyric-ttml-internal",
                                 ))
                                 .write_inner_content(|writer| {
-                                    if !translations_by_lang.is_empty() {
-                                        writer.create_element("translations").write_inner_content(
-                                            |writer| {
-                                                for (lang, entries) in translations_by_lang {
-                                                    let mut trans_builder = writer
-                                                        .create_element("translation")
-                                                        .with_attribute(("type", "subtitle"));
-                                                    if let Some(lang_code) =
-                                                        lang.as_ref().filter(|s| !s.is_empty())
-                                                    {
-                                                        trans_builder = trans_builder
-                                                            .with_attribute((
-                                                                "xml:lang",
-                                                                lang_code.as_str(),
-                                                            ));
-                                                    }
-                                                    trans_builder.write_inner_content(
-                                                        |writer| {
-                                                            for (key, text) in entries {
-                                                                writer
-                                                                    .create_element("text")
-                                                                    .with_attribute((
-                                                                        "for",
-                                                                        key.as_str(),
-                                                                    ))
-                                                                    .write_text_content(
-                                                                        BytesText::new(&text),
-                                                                    )?;
-                                                            }
-                                                            Ok(())
-                                                        },
-                                                    )?;
-                                                }
-                                                Ok(())
-                                            },
-                                        )?;
-                                    }
+                                    let to_io_err = |e: ConvertError| std::io::Error::other(e);
+
+                                    write_auxiliary_tracks_to_head(
+                                        writer,
+                                        &translations,
+                                        "translations",
+                                        "translation",
+                                        options,
+                                    )
+                                    .map_err(to_io_err)?;
 
                                     if !valid_songwriters.is_empty() {
                                         writer.create_element("songwriters").write_inner_content(
@@ -422,25 +556,12 @@ fn write_ttml_head<W: std::io::Write>(
                                         )?;
                                     }
 
-                                    let to_io_err = |e: ConvertError| std::io::Error::other(e);
-
-                                    write_timed_tracks_to_head(
-                                        writer,
-                                        lines,
-                                        1,
-                                        "translation",
-                                        "translations",
-                                        "translation",
-                                    )
-                                    .map_err(to_io_err)?;
-
-                                    write_timed_tracks_to_head(
+                                    write_auxiliary_tracks_to_head(
                                         writer,
-                                        lines,
-                                        1,
-                                        "romanization",
+                                        &transliterations,
                                         "transliterations",
                                         "transliteration",
+                                        options,
                                     )
                                     .map_err(to_io_err)?;
 
@@ -449,38 +570,228 @@ fn write_ttml_head<W: std::io::Write>(
                         }
                     }
 
-                    let amll_meta_keys_to_check = [
-                        ("musicName", CanonicalMetadataKey::Title),
-                        ("artists", CanonicalMetadataKey::Artist),
-                        ("album", CanonicalMetadataKey::Album),
-                        ("isrc", CanonicalMetadataKey::Isrc),
-                        ("appleMusicId", CanonicalMetadataKey::AppleMusicId),
-                        ("ncmMusicId", CanonicalMetadataKey::NcmMusicId),
-                        ("spotifyId", CanonicalMetadataKey::SpotifyId),
-                        ("qqMusicId", CanonicalMetadataKey::QqMusicId),
-                        ("ttmlAuthorGithub", CanonicalMetadataKey::TtmlAuthorGithub),
-                        (
-                            "ttmlAuthorGithubLogin",
-                            CanonicalMetadataKey::TtmlAuthorGithubLogin,
-                        ),
-                    ];
-                    for (amll_key_name, canonical_key) in amll_meta_keys_to_check {
-                        if let Some(values) = metadata_store.get_multiple_values(&canonical_key) {
+                    for (amll_key_name, canonical_key) in AMLL_META_KEYS {
+                        if let Some(values) = metadata_store.get_multiple_values(canonical_key) {
                             for value_str in values {
                                 if !value_str.trim().is_empty() {
                                     writer
                                         .create_element("amll:meta")
-                                        .with_attribute(("key", amll_key_name))
+                                        .with_attribute(("key", *amll_key_name))
                                         .with_attribute(("value", value_str.trim()))
                                         .write_empty()?;
                                 }
                             }
                         }
                     }
+
+                    for element in &extensions.metadata_elements {
+                        write_xml_element(writer, element)?;
+                    }
                     Ok(())
                 })?;
+
+            for element in head_elements {
+                write_xml_element(writer, element)?;
+            }
+            Ok(())
+        })?;
+    Ok(())
+}
+
+/// 收集需要通过 `<region>` 写出的显示位置，按左、右的顺序排列。
+///
+/// 已经带有 `region` 属性的行沿用原有区域，不计入其中。
+fn collect_view_sides(lines: &[LyricLine]) -> Vec<ViewSide> {
+    [ViewSide::Left, ViewSide::Right]
+        .into_iter()
+        .filter(|side| {
+            lines
+                .iter()
+                .any(|line| view_side_region(line).is_some() && line.view_side == Some(*side))
+        })
+        .collect()
+}
+
+/// 收集背景人声需要通过 `<style>` 写出的显示位置，按左、右的顺序排列。
+fn collect_background_view_sides(lines: &[LyricLine]) -> Vec<ViewSide> {
+    [ViewSide::Left, ViewSide::Right]
+        .into_iter()
+        .filter(|side| {
+            lines.iter().any(|line| {
+                line.background_view_side == Some(*side)
+                    && line
+                        .tracks
+                        .iter()
+                        .any(|at| at.content_type == ContentType::Background)
+            })
+        })
+        .collect()
+}
+
+/// 背景人声显示位置对应的样式 ID。
+const fn background_view_side_style(side: ViewSide) -> &'static str {
+    match side {
+        ViewSide::Left => BACKGROUND_VIEW_SIDE_STYLE_LEFT,
+        ViewSide::Right => BACKGROUND_VIEW_SIDE_STYLE_RIGHT,
+    }
+}
+
+/// 收集行需要通过 `<style>` 写出的演唱者颜色，返回按样式 ID 排序的 `(样式 ID, 颜色)`。
+fn collect_agent_colors<'a>(
+    lines: &[LyricLine],
+    agent_store: &'a AgentStore,
+) -> Vec<(String, &'a str)> {
+    let mut colors: Vec<(String, &str)> = Vec::new();
+    for line in lines {
+        if let Some(style_id) = agent_color_style(line, agent_store)
+            && !colors.iter().any(|(id, _)| *id == style_id)
+            && let Some(color) = agent_store
+                .get(line.agent.as_deref().unwrap_or("v1"))
+                .and_then(|agent| agent.color.as_deref())
+        {
+            colors.push((style_id, color));
+        }
+    }
+    colors.sort();
+    colors
+}
+
+/// 行应写入的 `style` 属性值，即行的演唱者颜色对应的样式。
+///
+/// 已经带有 `style` 属性的行沿用原有样式。
+fn agent_color_style(line: &LyricLine, agent_store: &AgentStore) -> Option<String> {
+    if line.extra_attributes.iter().any(|(key, _)| key == "style") {
+        return None;
+    }
+    let agent_id = line.agent.as_deref().unwrap_or("v1");
+    agent_store
+        .get(agent_id)
+        .and_then(|agent| agent.color.as_ref())
+        .map(|_| format!("{AGENT_COLOR_STYLE_PREFIX}{agent_id}"))
+}
+
+/// 行应写入的 `region` 属性值。
+fn view_side_region(line: &LyricLine) -> Option<&'static str> {
+    if line.extra_attributes.iter().any(|(key, _)| key == "region") {
+        return None;
+    }
+    line.view_side.map(|side| match side {
+        ViewSide::Left => VIEW_SIDE_REGION_LEFT,
+        ViewSide::Right => VIEW_SIDE_REGION_RIGHT,
+    })
+}
+
+/// 将显示位置和演唱者颜色对应的 `<style>` 和 `<region>` 合并进 `<head>` 中保留的元素。
+///
+/// 背景人声的显示位置和演唱者颜色只需要 `<style>`。
+/// 已存在 `<styling>` 或 `<layout>` 时追加到其中（跳过 `xml:id` 已被占用的项），
+/// 否则新建：`<styling>` 放在最前，`<layout>` 放在最后。
+fn merge_style_head_elements(
+    preserved: &[XmlElement],
+    view_sides: &[ViewSide],
+    background_view_sides: &[ViewSide],
+    agent_colors: &[(String, &str)],
+) -> Vec<XmlElement> {
+    let mut head_elements = preserved.to_vec();
+    if view_sides.is_empty() && background_view_sides.is_empty() && agent_colors.is_empty() {
+        return head_elements;
+    }
+
+    let id_element = |name: &str, id: &str, attribute: (&str, &str)| XmlElement {
+        name: name.to_string(),
+        attributes: vec![
+            ("xml:id".to_string(), id.to_string()),
+            (attribute.0.to_string(), attribute.1.to_string()),
+        ],
+        children: Vec::new(),
+    };
+    let (mut styles, regions): (Vec<_>, Vec<_>) = view_sides
+        .iter()
+        .map(|side| {
+            let (style_id, region_id) = match side {
+                ViewSide::Left => (VIEW_SIDE_STYLE_LEFT, VIEW_SIDE_REGION_LEFT),
+                ViewSide::Right => (VIEW_SIDE_STYLE_RIGHT, VIEW_SIDE_REGION_RIGHT),
+            };
+            (
+                id_element("style", style_id, ("tts:textAlign", side.text_align())),
+                id_element("region", region_id, ("style", style_id)),
+            )
+        })
+        .unzip();
+    styles.extend(background_view_sides.iter().map(|side| {
+        id_element(
+            "style",
+            background_view_side_style(*side),
+            ("tts:textAlign", side.text_align()),
+        )
+    }));
+    styles.extend(
+        agent_colors
+            .iter()
+            .map(|(style_id, color)| id_element("style", style_id, ("tts:color", color))),
+    );
+
+    for (container, items, insert_first) in [("styling", styles, true), ("layout", regions, false)]
+    {
+        if items.is_empty() {
+            continue;
+        }
+        if let Some(existing) = head_elements
+            .iter_mut()
+            .find(|e| e.name.rsplit(':').next() == Some(container))
+        {
+            for item in items {
+                let id = &item.attributes[0].1;
+                let is_taken = existing.children.iter().any(|child| {
+                    matches!(child, XmlNode::Element(child)
+                        if child.attributes.iter().any(|(k, v)| k == "xml:id" && v == id))
+                });
+                if !is_taken {
+                    existing.children.push(XmlNode::Element(item));
+                }
+            }
+        } else {
+            let element = XmlElement {
+                name: container.to_string(),
+                attributes: Vec::new(),
+                children: items.into_iter().map(XmlNode::Element).collect(),
+            };
+            if insert_first {
+                head_elements.insert(0, element);
+            } else {
+                head_elements.push(element);
+            }
+        }
+    }
+    head_elements
+}
+
+/// 原样写出一个保留的 XML 元素。
+fn write_xml_element<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    element: &XmlElement,
+) -> std::io::Result<()> {
+    let builder = writer.create_element(&element.name).with_attributes(
+        element
+            .attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str())),
+    );
+    if element.children.is_empty() {
+        builder.write_empty()?;
+    } else {
+        builder.write_inner_content(|writer| {
+            for child in &element.children {
+                match child {
+                    XmlNode::Element(child) => write_xml_element(writer, child)?,
+                    XmlNode::Text(text) => {
+                        writer.write_event(Event::Text(BytesText::new(text)))?;
+                    }
+                }
+            }
             Ok(())
         })?;
+    }
     Ok(())
 }
 
@@ -488,6 +799,8 @@ fn write_ttml_head<W: std::io::Write>(
 fn write_ttml_body<W: std::io::Write>(
     writer: &mut Writer<W>,
     lines: &[LyricLine],
+    agent_store: &AgentStore,
+    extra_attributes: &[(String, String)],
     options: &TtmlGenerationOptions,
 ) -> Result<(), ConvertError> {
     let body_dur_ms = lines.iter().map(|line| line.end_ms).max().unwrap_or(0);
@@ -495,6 +808,11 @@ fn write_ttml_body<W: std::io::Write>(
     if body_dur_ms > 0 {
         body_builder = body_builder.with_attribute(("dur", format_ttml_time(body_dur_ms).as_str()));
     }
+    body_builder = body_builder.with_attributes(
+        extra_attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str())),
+    );
 
     if lines.is_empty() {
         body_builder.write_empty()?;
@@ -510,16 +828,28 @@ fn write_ttml_body<W: std::io::Write>(
             } else {
                 let prev_line = *current_div_lines.last().unwrap();
                 if prev_line.song_part != current_line.song_part {
-                    write_div(writer, &current_div_lines, options, &mut p_key_counter)
-                        .map_err(std::io::Error::other)?;
+                    write_div(
+                        writer,
+                        &current_div_lines,
+                        agent_store,
+                        options,
+                        &mut p_key_counter,
+                    )
+                    .map_err(std::io::Error::other)?;
                     current_div_lines.clear();
                 }
                 current_div_lines.push(current_line);
             }
         }
         if !current_div_lines.is_empty() {
-            write_div(writer, &current_div_lines, options, &mut p_key_counter)
-                .map_err(std::io::Error::other)?;
+            write_div(
+                writer,
+                &current_div_lines,
+                agent_store,
+                options,
+                &mut p_key_counter,
+            )
+            .map_err(std::io::Error::other)?;
         }
         Ok(())
     })?;
@@ -530,6 +860,7 @@ fn write_ttml_body<W: std::io::Write>(
 fn write_div<W: std::io::Write>(
     writer: &mut Writer<W>,
     part_lines: &[&LyricLine],
+    agent_store: &AgentStore,
     options: &TtmlGenerationOptions,
     p_key_counter: &mut i32,
 ) -> Result<(), ConvertError> {
@@ -559,6 +890,7 @@ fn write_div<W: std::io::Write>(
             *p_key_counter += 1;
 
             let agent_id_to_set = line.agent.as_deref().unwrap_or("v1");
+            let color_style = agent_color_style(line, agent_store);
 
             writer
                 .create_element("p")
@@ -566,8 +898,19 @@ fn write_div<W: std::io::Write>(
                 .with_attribute(("end", format_ttml_time(line.end_ms).as_str()))
                 .with_attribute(("itunes:key", format!("L{p_key_counter}").as_str()))
                 .with_attribute(("ttm:agent", agent_id_to_set))
+                .with_attributes(view_side_region(line).map(|region| ("region", region)))
+                .with_attributes(color_style.as_deref().map(|style| ("style", style)))
+                .with_attributes(
+                    line.extra_attributes
+                        .iter()
+                        .map(|(key, value)| (key.as_str(), value.as_str())),
+                )
                 .write_inner_content(|writer| {
-                    write_p_content(writer, line, options).map_err(std::io::Error::other)
+                    write_p_content(writer, line, options).map_err(std::io::Error::other)?;
+                    for element in &line.extra_elements {
+                        write_xml_element(writer, element)?;
+                    }
+                    Ok(())
                 })?;
         }
         Ok(())
@@ -579,6 +922,7 @@ fn write_div<W: std::io::Write>(
 fn write_syllable_with_optional_splitting<W: std::io::Write>(
     writer: &mut Writer<W>,
     syl: &LyricSyllable,
+    line_agent: Option<&str>,
     options: &TtmlGenerationOptions,
 ) -> Result<(), ConvertError> {
     if options.auto_word_splitting && syl.text.trim().chars().count() > 1 {
@@ -660,15 +1004,21 @@ fn write_syllable_with_optional_splitting<W: std::io::Write>(
                     .create_element("span")
                     .with_attribute(("begin", format_ttml_time(current_token_start_ms).as_str()))
                     .with_attribute(("end", format_ttml_time(token_end_ms).as_str()))
+                    .with_attributes(syllable_agent_attribute(syl, line_agent))
+                    .with_attributes(
+                        syl.extra_attributes
+                            .iter()
+                            .map(|(key, value)| (key.as_str(), value.as_str())),
+                    )
                     .write_text_content(BytesText::new(&text_to_write))?;
 
                 current_token_start_ms = token_end_ms;
             }
         } else {
-            write_single_syllable_span(writer, syl, options)?;
+            write_single_syllable_span(writer, syl, line_agent, options)?;
         }
     } else {
-        write_single_syllable_span(writer, syl, options)?;
+        write_single_syllable_span(writer, syl, line_agent, options)?;
     }
     Ok(())
 }
@@ -691,6 +1041,9 @@ fn write_p_content<W: std::io::Write>(
         .filter(|at| at.content_type == ContentType::Background)
         .collect();
 
+    // 与 <p> 上写出的演唱者相同的音节不需要单独标注
+    let line_agent = Some(line.agent.as_deref().unwrap_or("v1"));
+
     // 1. 处理主内容
     if options.timing_mode == TtmlTimingMode::Line {
         let line_text_to_write = main_content_tracks
@@ -707,7 +1060,7 @@ fn write_p_content<W: std::io::Write>(
         }
     } else {
         for at in &main_content_tracks {
-            write_track_as_spans(writer, &at.content, options)?;
+            write_track_as_spans(writer, &at.content, line_agent, options)?;
         }
     }
 
@@ -725,7 +1078,13 @@ fn write_p_content<W: std::io::Write>(
 
     // 3. 处理背景内容
     if options.timing_mode == TtmlTimingMode::Word && !background_annotated_tracks.is_empty() {
-        write_background_tracks(writer, &background_annotated_tracks, options)?;
+        write_background_tracks(
+            writer,
+            &background_annotated_tracks,
+            line.background_view_side,
+            line_agent,
+            options,
+        )?;
     }
 
     Ok(())
@@ -766,7 +1125,7 @@ fn write_inline_auxiliary_track<W: std::io::Write>(
             .with_attribute(("begin", format_ttml_time(start_ms).as_str()))
             .with_attribute(("end", format_ttml_time(end_ms).as_str()))
             .write_inner_content(|writer| {
-                write_track_as_spans(writer, track, options).map_err(std::io::Error::other)
+                write_track_as_spans(writer, track, None, options).map_err(std::io::Error::other)
             })?;
     } else {
         let full_text = all_syllables
@@ -787,11 +1146,12 @@ fn write_inline_auxiliary_track<W: std::io::Write>(
 fn write_track_as_spans<W: std::io::Write>(
     writer: &mut Writer<W>,
     track: &LyricTrack,
+    line_agent: Option<&str>,
     options: &TtmlGenerationOptions,
 ) -> Result<(), ConvertError> {
     let all_syllables: Vec<_> = track.words.iter().flat_map(|w| &w.syllables).collect();
     for (syl_idx, syl) in all_syllables.iter().enumerate() {
-        write_syllable_with_optional_splitting(writer, syl, options)?;
+        write_syllable_with_optional_splitting(writer, syl, line_agent, options)?;
 
         if syl.ends_with_space && syl_idx < all_syllables.len() - 1 && !options.format {
             writer.write_event(Event::Text(BytesText::new(" ")))?;
@@ -803,6 +1163,8 @@ fn write_track_as_spans<W: std::io::Write>(
 fn write_background_tracks<W: std::io::Write>(
     writer: &mut Writer<W>,
     bg_annotated_tracks: &[&AnnotatedTrack],
+    view_side: Option<ViewSide>,
+    line_agent: Option<&str>,
     options: &TtmlGenerationOptions,
 ) -> Result<(), ConvertError> {
     let all_syls: Vec<_> = bg_annotated_tracks
@@ -819,6 +1181,7 @@ fn write_background_tracks<W: std::io::Write>(
     writer
         .create_element("span")
         .with_attribute(("ttm:role", "x-bg"))
+        .with_attributes(view_side.map(|side| ("style", background_view_side_style(side))))
         .with_attribute(("begin", format_ttml_time(start_ms).as_str()))
         .with_attribute(("end", format_ttml_time(end_ms).as_str()))
         .write_inner_content(|writer| {
@@ -839,7 +1202,7 @@ fn write_background_tracks<W: std::io::Write>(
                     ..(*syl_bg).clone()
                 };
 
-                write_syllable_with_optional_splitting(writer, &temp_syl, options)
+                write_syllable_with_optional_splitting(writer, &temp_syl, line_agent, options)
                     .map_err(std::io::Error::other)?;
 
                 if syl_bg.ends_with_space && idx < num_syls - 1 && !options.format {
@@ -847,7 +1210,13 @@ fn write_background_tracks<W: std::io::Write>(
                 }
             }
 
-            for at in bg_annotated_tracks {
+            // Apple 格式下背景人声的辅助轨道写在 <head> 中
+            let inline_auxiliary_tracks = if options.use_apple_format_rules {
+                &[][..]
+            } else {
+                bg_annotated_tracks
+            };
+            for at in inline_auxiliary_tracks {
                 for track in &at.translations {
                     write_inline_auxiliary_track(writer, track, "x-translation", options)
                         .map_err(std::io::Error::other)?;
@@ -863,10 +1232,22 @@ fn write_background_tracks<W: std::io::Write>(
     Ok(())
 }
 
+/// 音节需要写出的 `ttm:agent` 属性，与所在行的演唱者相同时省略。
+fn syllable_agent_attribute<'a>(
+    syl: &'a LyricSyllable,
+    line_agent: Option<&str>,
+) -> Option<(&'static str, &'a str)> {
+    syl.agent
+        .as_deref()
+        .filter(|agent| Some(*agent) != line_agent)
+        .map(|agent| ("ttm:agent", agent))
+}
+
 /// 写入单个音节span
 fn write_single_syllable_span<W: std::io::Write>(
     writer: &mut Writer<W>,
     syl: &LyricSyllable,
+    line_agent: Option<&str>,
     options: &TtmlGenerationOptions,
 ) -> Result<(), ConvertError> {
     let text_to_write = if options.format && syl.ends_with_space {
@@ -882,6 +1263,12 @@ fn write_single_syllable_span<W: std::io::Write>(
             "end",
             format_ttml_time(syl.end_ms.max(syl.start_ms)).as_str(),
         ))
+        .with_attributes(syllable_agent_attribute(syl, line_agent))
+        .with_attributes(
+            syl.extra_attributes
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        )
         .write_text_content(BytesText::new(&text_to_write))?;
     Ok(())
 }
@@ -0,0 +1,263 @@
+//! `UltraStar` TXT 格式生成器
+
+use std::fmt::Write;
+
+use crate::converter::{
+    parsers::ultrastar_parser::parse_ultrastar_number,
+    processors::metadata_processor::MetadataStore,
+    types::{
+        AgentStore, AgentType, CanonicalMetadataKey, ContentType, ConvertError, LyricLine,
+        LyricSyllable, NoteType,
+    },
+};
+
+/// 没有 `bpm` 元数据时使用的 BPM。此时每拍为 10 毫秒，足以保留大部分时间精度。
+const DEFAULT_BPM: f64 = 1500.0;
+
+/// 原样写回的 `UltraStar` 头部（源文件解析时以小写键名存入元数据）。
+const PASSTHROUGH_HEADERS: &[&str] = &[
+    "MP3",
+    "AUDIO",
+    "COVER",
+    "BACKGROUND",
+    "VIDEO",
+    "VIDEOGAP",
+    "GENRE",
+    "EDITION",
+    "YEAR",
+    "CREATOR",
+    "VERSION",
+];
+
+/// `UltraStar` 生成的主入口函数。
+pub fn generate_ultrastar(
+    lines: &[LyricLine],
+    metadata_store: &MetadataStore,
+    agent_store: &AgentStore,
+) -> Result<String, ConvertError> {
+    let mut output = String::new();
+
+    let bpm_value = metadata_store
+        .get_multiple_values_by_key("bpm")
+        .and_then(|v| v.first())
+        .and_then(|v| parse_ultrastar_number(v))
+        .filter(|bpm| *bpm > 0.0);
+    let bpm = bpm_value.unwrap_or(DEFAULT_BPM);
+    let gap_ms = metadata_store
+        .get_multiple_values_by_key("gap")
+        .and_then(|v| v.first())
+        .and_then(|v| parse_ultrastar_number(v))
+        .unwrap_or(0.0);
+
+    if let Some(title) = metadata_store.get_single_value(&CanonicalMetadataKey::Title) {
+        writeln!(output, "#TITLE:{title}")?;
+    }
+    if let Some(artists) = metadata_store.get_multiple_values(&CanonicalMetadataKey::Artist) {
+        writeln!(output, "#ARTIST:{}", artists.join(", "))?;
+    }
+    if let Some(language) = metadata_store.get_single_value(&CanonicalMetadataKey::Language) {
+        writeln!(output, "#LANGUAGE:{language}")?;
+    }
+    for header in PASSTHROUGH_HEADERS {
+        if let Some(value) = metadata_store
+            .get_multiple_values_by_key(&header.to_lowercase())
+            .and_then(|v| v.first())
+        {
+            writeln!(output, "#{header}:{value}")?;
+        }
+    }
+    writeln!(output, "#BPM:{}", format_number(bpm))?;
+    writeln!(output, "#GAP:{}", format_number(gap_ms))?;
+
+    let players = assign_players(lines, agent_store);
+    let is_duet = players.iter().any(|(player, _)| *player != 1);
+
+    if is_duet {
+        for (player, agent_id) in [(1, first_agent(&players, 1)), (2, first_agent(&players, 2))] {
+            if let Some(name) = agent_id
+                .and_then(|id| agent_store.agents_by_id.get(id))
+                .and_then(|agent| agent.name.as_deref())
+            {
+                writeln!(output, "#P{player}:{name}")?;
+            }
+        }
+    }
+
+    let to_beat = |ms: u64| ms_to_beat(ms, bpm, gap_ms);
+
+    for player in 1..=3 {
+        let player_lines: Vec<&LyricLine> = lines
+            .iter()
+            .zip(&players)
+            .filter(|(_, (p, _))| *p == player)
+            .map(|(line, _)| line)
+            .collect();
+        if player_lines.is_empty() {
+            continue;
+        }
+        if is_duet {
+            writeln!(output, "P{player}")?;
+        }
+
+        let mut previous_end_beat: Option<i64> = None;
+        for line in player_lines {
+            let syllables: Vec<&LyricSyllable> = line
+                .tracks
+                .iter()
+                .filter(|t| t.content_type == ContentType::Main)
+                .flat_map(|t| t.content.words.iter())
+                .flat_map(|w| &w.syllables)
+                .filter(|s| !s.text.is_empty())
+                .collect();
+            let Some(first) = syllables.first() else {
+                continue;
+            };
+
+            let start_beat = to_beat(first.start_ms);
+            if let Some(end_beat) = previous_end_beat {
+                writeln!(output, "- {}", end_beat.min(start_beat))?;
+            }
+
+            for (i, syl) in syllables.iter().enumerate() {
+                let beat = to_beat(syl.start_ms);
+                let length = (to_beat(syl.end_ms) - beat).max(1);
+                let separator = if syl.ends_with_space && i + 1 < syllables.len() {
+                    " "
+                } else {
+                    ""
+                };
+                writeln!(
+                    output,
+                    "{} {} {} {} {}{}",
+                    note_type_marker(syl.note_type.unwrap_or_default()),
+                    beat,
+                    length,
+                    syl.pitch.unwrap_or(0),
+                    syl.text,
+                    separator
+                )?;
+                previous_end_beat = Some(beat + length);
+            }
+        }
+    }
+
+    writeln!(output, "E")?;
+    Ok(output)
+}
+
+/// 为每一行分配演唱者编号：1 和 2 为两位演唱者，3 为合唱。
+///
+/// 第一个出现的独唱 Agent（或没有 Agent 的行）为 1 号，其余独唱 Agent 都归为 2 号。
+fn assign_players<'a>(
+    lines: &'a [LyricLine],
+    agent_store: &AgentStore,
+) -> Vec<(u8, Option<&'a str>)> {
+    let mut first_solo_agent: Option<&str> = None;
+
+    lines
+        .iter()
+        .map(|line| {
+            let agent = line.agent.as_deref();
+            let is_group = agent.is_some_and(|id| {
+                id == "v1000"
+                    || agent_store
+                        .agents_by_id
+                        .get(id)
+                        .is_some_and(|a| a.agent_type == AgentType::Group)
+            });
+            let player = match agent {
+                _ if is_group => 3,
+                None => 1,
+                Some(id) => match first_solo_agent {
+                    None => {
+                        first_solo_agent = Some(id);
+                        1
+                    }
+                    Some(first) if first == id => 1,
+                    Some(_) => 2,
+                },
+            };
+            (player, agent)
+        })
+        .collect()
+}
+
+fn first_agent<'a>(players: &[(u8, Option<&'a str>)], player: u8) -> Option<&'a str> {
+    players
+        .iter()
+        .find(|(p, agent)| *p == player && agent.is_some())
+        .and_then(|(_, agent)| *agent)
+}
+
+fn note_type_marker(note_type: NoteType) -> char {
+    match note_type {
+        NoteType::Normal => ':',
+        NoteType::Golden => '*',
+        NoteType::Freestyle => 'F',
+        NoteType::Rap => 'R',
+        NoteType::RapGolden => 'G',
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+#[allow(clippy::cast_possible_truncation)]
+fn ms_to_beat(ms: u64, bpm: f64, gap_ms: f64) -> i64 {
+    ((ms as f64 - gap_ms) * bpm / 15_000.0).round() as i64
+}
+
+/// 格式化数字，整数不带小数部分。
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::parsers::ultrastar_parser::parse_ultrastar;
+
+    #[test]
+    fn test_ultrastar_round_trip() {
+        let content = "\
+#TITLE:Song
+#ARTIST:Singer
+#MP3:song.mp3
+#BPM:300
+#GAP:1000
+#P1:Alice
+#P2:Bob
+P1
+: 0 4 0 Hel
+* 4 4 2 lo
+F 8 2 -3 world
+- 12
+R 14 4 5 Next
+P2
+G 20 4 7 Other
+E
+";
+        let parsed = parse_ultrastar(content).unwrap();
+        let metadata_store = MetadataStore::from(&parsed);
+        let generated = generate_ultrastar(&parsed.lines, &metadata_store, &parsed.agents).unwrap();
+
+        assert!(generated.contains("#MP3:song.mp3\n"));
+        assert!(generated.contains("#BPM:300\n#GAP:1000\n"));
+        assert!(generated.ends_with(
+            "#P1:Alice
+#P2:Bob
+P1
+: 0 4 0 Hel
+* 4 4 2 lo
+F 8 2 -3 world
+- 10
+R 14 4 5 Next
+P2
+G 20 4 7 Other
+E
+"
+        ));
+    }
+}
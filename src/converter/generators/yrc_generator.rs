@@ -16,6 +16,62 @@ pub fn generate_yrc(
 ) -> Result<String, ConvertError> {
     let mut yrc_output = String::new();
 
+    write_metadata_lines(&mut yrc_output, lines, metadata_store)?;
+
+    for line in lines {
+        if let Some(main_track) = line
+            .tracks
+            .iter()
+            .find(|t| t.content_type == ContentType::Main)
+        {
+            let syllables: Vec<_> = main_track
+                .content
+                .words
+                .iter()
+                .flat_map(|w| &w.syllables)
+                .collect();
+            if syllables.is_empty() {
+                continue;
+            }
+
+            let line_duration = line.end_ms.saturating_sub(line.start_ms);
+            write!(yrc_output, "[{},{}]", line.start_ms, line_duration)?;
+
+            for syl in syllables {
+                let syl_duration = syl.end_ms.saturating_sub(syl.start_ms);
+                write!(
+                    yrc_output,
+                    "({},{},0){}",
+                    syl.start_ms, syl_duration, syl.text
+                )?;
+            }
+            writeln!(yrc_output)?;
+        }
+    }
+
+    Ok(yrc_output)
+}
+
+/// 将元数据写为网易云的 JSON 演职员信息行，YRC 和 klyric 共用。
+///
+/// 这些行均匀分布在第一行歌词之前的时间段内。
+pub(crate) fn write_metadata_lines(
+    output: &mut String,
+    lines: &[LyricLine],
+    metadata_store: &MetadataStore,
+) -> Result<(), ConvertError> {
+    // 如果有更具体的作词/作曲信息，就不再输出笼统的 Songwriter
+    let has_specific_credits = [
+        CanonicalMetadataKey::Lyricist,
+        CanonicalMetadataKey::Composer,
+    ]
+    .iter()
+    .any(|key| {
+        metadata_store
+            .get_multiple_values(key)
+            .is_some_and(|v| !v.is_empty())
+    });
+
     let mut metadata_to_generate: Vec<(String, &[String])> = Vec::new();
     for (key, values) in metadata_store.get_all_data() {
         if values.is_empty() {
@@ -25,8 +81,11 @@ pub fn generate_yrc(
             CanonicalMetadataKey::Title => "曲名",
             CanonicalMetadataKey::Artist => "歌手",
             CanonicalMetadataKey::Album => "专辑",
-            CanonicalMetadataKey::Songwriter => "作词",
-            CanonicalMetadataKey::Custom(custom_key) if custom_key == "composer" => "作曲",
+            CanonicalMetadataKey::Songwriter if !has_specific_credits => "作词",
+            CanonicalMetadataKey::Lyricist => "作词",
+            CanonicalMetadataKey::Composer => "作曲",
+            CanonicalMetadataKey::Arranger => "编曲",
+            CanonicalMetadataKey::Producer => "制作人",
             // 对于其他不适合显示的键（如 Offset, Language），返回空字符串来忽略它们
             _ => "",
         };
@@ -53,42 +112,11 @@ pub fn generate_yrc(
         for (i, (label, values)) in metadata_to_generate.iter().enumerate() {
             let metadata_time = (i as u64) * interval;
             let json_line = build_yrc_metadata_json(label, values, metadata_time);
-            writeln!(yrc_output, "{json_line}")?;
-        }
-    }
-
-    for line in lines {
-        if let Some(main_track) = line
-            .tracks
-            .iter()
-            .find(|t| t.content_type == ContentType::Main)
-        {
-            let syllables: Vec<_> = main_track
-                .content
-                .words
-                .iter()
-                .flat_map(|w| &w.syllables)
-                .collect();
-            if syllables.is_empty() {
-                continue;
-            }
-
-            let line_duration = line.end_ms.saturating_sub(line.start_ms);
-            write!(yrc_output, "[{},{}]", line.start_ms, line_duration)?;
-
-            for syl in syllables {
-                let syl_duration = syl.end_ms.saturating_sub(syl.start_ms);
-                write!(
-                    yrc_output,
-                    "({},{},0){}",
-                    syl.start_ms, syl_duration, syl.text
-                )?;
-            }
-            writeln!(yrc_output)?;
+            writeln!(output, "{json_line}")?;
         }
     }
 
-    Ok(yrc_output)
+    Ok(())
 }
 
 /// 辅助函数，构建单行 YRC 元数据 JSON 字符串。
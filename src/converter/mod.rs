@@ -1,5 +1,7 @@
 //! 歌词转换器核心模块
 
+pub mod audio_tags;
+pub mod codecs;
 pub mod generators;
 pub mod parsers;
 pub mod processors;
@@ -13,6 +15,7 @@ pub use types::{
 };
 
 use crate::converter::{
+    codecs::{krc, qrc},
     processors::{
         batch_processor, chinese_conversion_processor::ChineseConversionProcessor,
         metadata_processor::MetadataStore,
@@ -22,6 +25,7 @@ use crate::converter::{
         ConversionTask, ConvertError, FullConversionResult, InputFile, ParsedSourceData,
     },
 };
+use base64::{Engine as _, engine::general_purpose};
 use tracing::{debug, warn};
 
 // ==========================================================
@@ -107,12 +111,6 @@ pub fn generate_from_parsed<S: BuildHasher>(
 ) -> Result<FullConversionResult, ConvertError> {
     let mut metadata_store = MetadataStore::from(&source_data);
 
-    if let Some(agent_definitions) = source_data.raw_metadata.get("agent")
-        && !agent_definitions.is_empty()
-    {
-        metadata_store.set_multiple("internal::agents", agent_definitions.clone());
-    }
-
     if let Some(overrides) = user_metadata_overrides {
         for (key, values) in overrides {
             metadata_store.set_multiple(key, values.clone());
@@ -121,70 +119,132 @@ pub fn generate_from_parsed<S: BuildHasher>(
             .iter()
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect();
+
+        // 用户显式覆盖的 `agent` 定义优先于源文件中的定义
+        if let Some(definitions) = overrides.get("agent") {
+            source_data
+                .agents
+                .merge(AgentStore::from_definitions(definitions));
+        }
     }
 
     metadata_store.deduplicate_values();
 
+    source_data.agents.register_referenced(&source_data.lines);
+    source_data.agents.apply_view_sides(&mut source_data.lines);
+
     let output_lyrics = match target_format {
         LyricFormat::Lrc => generators::lrc_generator::generate_lrc(
             &source_data.lines,
             &metadata_store,
+            &source_data.agents,
             &options.lrc,
+            options.write_credits,
         ),
         LyricFormat::EnhancedLrc => generators::enhanced_lrc_generator::generate_enhanced_lrc(
             &source_data.lines,
             &metadata_store,
+            &source_data.agents,
             &options.lrc,
+            options.write_credits,
         ),
         LyricFormat::Ass => generators::ass_generator::generate_ass(
             &source_data.lines,
             &metadata_store,
             source_data.is_line_timed_source,
             &options.ass,
+            options.write_credits,
+        ),
+        LyricFormat::Ttml => generators::ttml_generator::generate_ttml_with_extensions(
+            &source_data.lines,
+            &metadata_store,
+            &source_data.agents,
+            &source_data.ttml_extensions,
+            &options.ttml,
         ),
-        LyricFormat::Ttml => {
-            let agent_store = AgentStore::from_metadata_store(&metadata_store);
-            generators::ttml_generator::generate_ttml(
-                &source_data.lines,
-                &metadata_store,
-                &agent_store,
-                &options.ttml,
-            )
-        }
         LyricFormat::AppleMusicJson => {
             generators::apple_music_json_generator::generate_apple_music_json(
                 &source_data.lines,
                 &metadata_store,
+                &source_data.agents,
                 options,
             )
         }
-        LyricFormat::Qrc => {
-            generators::qrc_generator::generate_qrc(&source_data.lines, &metadata_store)
-        }
+        LyricFormat::Qrc => generators::qrc_generator::generate_qrc(
+            &source_data.lines,
+            &metadata_store,
+            options.write_credits,
+        ),
+        LyricFormat::EncryptedQrc => generators::qrc_generator::generate_qrc(
+            &source_data.lines,
+            &metadata_store,
+            options.write_credits,
+        )
+        .map(|content| qrc::wrap_in_qrc_xml(&content)),
         LyricFormat::Lqe => generators::lqe_generator::generate_lqe(
             &source_data.lines,
             &metadata_store,
             &options.lqe,
+            options.write_credits,
+        ),
+        LyricFormat::Krc | LyricFormat::EncryptedKrc => generators::krc_generator::generate_krc(
+            &source_data.lines,
+            &metadata_store,
+            options.write_credits,
         ),
-        LyricFormat::Krc => {
-            generators::krc_generator::generate_krc(&source_data.lines, &metadata_store)
-        }
         LyricFormat::Yrc => {
             generators::yrc_generator::generate_yrc(&source_data.lines, &metadata_store)
         }
-        LyricFormat::Lys => {
-            generators::lys_generator::generate_lys(&source_data.lines, &metadata_store)
-        }
-        LyricFormat::Spl => {
-            generators::spl_generator::generate_spl(&source_data.lines, &metadata_store)
+        LyricFormat::Klyric => {
+            generators::klyric_generator::generate_klyric(&source_data.lines, &metadata_store)
         }
+        LyricFormat::Lys => generators::lys_generator::generate_lys(
+            &source_data.lines,
+            &metadata_store,
+            options.write_credits,
+        ),
+        LyricFormat::Spl => generators::spl_generator::generate_spl(
+            &source_data.lines,
+            &metadata_store,
+            options.write_credits,
+        ),
         LyricFormat::Lyl => {
             generators::lyricify_lines_generator::generate_lyl(&source_data.lines, &metadata_store)
         }
+        LyricFormat::UltraStar => generators::ultrastar_generator::generate_ultrastar(
+            &source_data.lines,
+            &metadata_store,
+            &source_data.agents,
+        ),
+        LyricFormat::Sami => generators::sami_generator::generate_sami(
+            &source_data.lines,
+            &metadata_store,
+            &options.sami,
+        ),
+        LyricFormat::YouTubeSrv3 | LyricFormat::YouTubeJson3 => {
+            Err(ConvertError::InvalidLyricFormat(format!(
+                "{target_format} 只支持导入，不能作为输出格式。"
+            )))
+        }
+        LyricFormat::Kar => {
+            let bytes =
+                generators::kar_generator::generate_kar(&source_data.lines, &metadata_store)?;
+            return Ok(FullConversionResult {
+                output_lyrics: general_purpose::STANDARD.encode(&bytes),
+                output_bytes: Some(bytes),
+                source_data,
+            });
+        }
     }?;
 
+    let (output_lyrics, output_bytes) = match encrypt_output(target_format, &output_lyrics)? {
+        Some((encoded, bytes)) => (encoded, Some(bytes)),
+        None => (output_lyrics, None),
+    };
+
     Ok(FullConversionResult {
         output_lyrics,
+        output_bytes,
         source_data,
     })
 }
@@ -239,12 +299,28 @@ pub fn parse_and_merge(
         options.matching_strategy,
     );
 
+    if options.timing_repair.enabled {
+        let issues =
+            processors::timing_linter::repair_timing(&mut main_new_lines, &options.timing_repair);
+        main_parsed_source
+            .warnings
+            .extend(issues.iter().map(ToString::to_string));
+    }
+
+    processors::text_normalizer::normalize_lines(&mut main_new_lines, &options.text_normalization);
+
     ChineseConversionProcessor::process(&mut main_new_lines, &options.chinese_conversion);
 
-    processors::metadata_stripper::strip_descriptive_metadata_lines(
+    let credits = processors::metadata_stripper::strip_descriptive_metadata_lines(
         &mut main_new_lines,
         &options.metadata_stripper,
     );
+    processors::metadata_stripper::merge_credits_into_metadata(
+        &credits,
+        &mut main_parsed_source.raw_metadata,
+    );
+
+    processors::song_structure::apply_song_structure(&mut main_new_lines, &options.song_structure);
 
     main_parsed_source.lines = main_new_lines;
 
@@ -367,16 +443,197 @@ fn parse_input_file(
         LyricFormat::Lrc => parsers::lrc_parser::parse_lrc(&file.content, &options.lrc_parsing),
         LyricFormat::EnhancedLrc => parsers::enhanced_lrc_parser::parse_enhanced_lrc(&file.content),
         LyricFormat::Krc => parsers::krc_parser::parse_krc(&file.content),
+        LyricFormat::EncryptedKrc => {
+            let decrypted = match &file.raw_bytes {
+                Some(bytes) => krc::decrypt_krc_bytes(bytes),
+                // 没有原始字节时，将文本内容视为 Base64 编码（酷狗 API 的返回形式）
+                None => krc::decrypt_krc(file.content.trim()),
+            }
+            .map_err(|e| ConvertError::Crypto(e.to_string()))?;
+            let mut parsed = parsers::krc_parser::parse_krc(&decrypted)?;
+            parsed.source_format = LyricFormat::EncryptedKrc;
+            Ok(parsed)
+        }
         LyricFormat::Ass => parsers::ass_parser::parse_ass(&file.content),
         LyricFormat::Ttml => parsers::ttml_parser::parse_ttml(&file.content, &options.ttml_parsing),
         LyricFormat::AppleMusicJson => {
             parsers::apple_music_json_parser::parse_apple_music_json(&file.content)
         }
         LyricFormat::Qrc => parsers::qrc_parser::parse_qrc(&file.content),
+        LyricFormat::EncryptedQrc => {
+            let bytes = file.raw_bytes.as_deref().unwrap_or(file.content.as_bytes());
+            let trimmed = bytes.trim_ascii();
+            // QQ 音乐的接口返回十六进制文本，本地缓存文件则可能直接保存加密字节
+            let decrypted = if !trimmed.is_empty() && trimmed.iter().all(u8::is_ascii_hexdigit) {
+                qrc::decrypt_qrc(&String::from_utf8_lossy(trimmed))
+            } else {
+                qrc::decrypt_qrc_bytes(bytes)
+            }
+            .map_err(|e| ConvertError::Crypto(e.to_string()))?;
+            let content = qrc::extract_from_qrc_wrapper(&decrypted);
+            let mut parsed = parsers::qrc_parser::parse_qrc(&content)?;
+            parsed.source_format = LyricFormat::EncryptedQrc;
+            Ok(parsed)
+        }
         LyricFormat::Yrc => parsers::yrc_parser::parse_yrc(&file.content),
+        LyricFormat::Klyric => parsers::klyric_parser::parse_klyric(&file.content),
         LyricFormat::Lys => parsers::lys_parser::parse_lys(&file.content),
         LyricFormat::Spl => parsers::spl_parser::parse_spl(&file.content),
         LyricFormat::Lqe => parsers::lqe_parser::parse_lqe(&file.content, options),
         LyricFormat::Lyl => parsers::lyricify_lines_parser::parse_lyl(&file.content),
+        LyricFormat::UltraStar => parsers::ultrastar_parser::parse_ultrastar(&file.content),
+        LyricFormat::Sami => match &file.raw_bytes {
+            Some(bytes) => parsers::sami_parser::parse_sami_bytes(bytes),
+            None => parsers::sami_parser::parse_sami(&file.content),
+        },
+        LyricFormat::YouTubeSrv3 => parsers::youtube_parser::parse_srv3(&file.content),
+        LyricFormat::YouTubeJson3 => parsers::youtube_parser::parse_json3(&file.content),
+        LyricFormat::Kar => parsers::kar_parser::parse_kar(
+            file.raw_bytes.as_deref().unwrap_or(file.content.as_bytes()),
+        ),
+    }
+}
+
+/// 对于加密格式，将生成的明文加密。
+///
+/// # 返回
+/// 加密格式返回加密字节的文本编码（KRC 为 Base64，QRC 为十六进制）和加密字节本身；
+/// 其他格式返回 `None`。
+fn encrypt_output(
+    target_format: LyricFormat,
+    plaintext: &str,
+) -> Result<Option<(String, Vec<u8>)>, ConvertError> {
+    let encrypted = match target_format {
+        LyricFormat::EncryptedKrc => krc::encrypt_krc(plaintext)
+            .map(|bytes| (general_purpose::STANDARD.encode(&bytes), bytes)),
+        LyricFormat::EncryptedQrc => {
+            qrc::encrypt_qrc_bytes(plaintext).map(|bytes| (hex::encode(&bytes), bytes))
+        }
+        _ => return Ok(None),
+    };
+    encrypted
+        .map(Some)
+        .map_err(|e| ConvertError::Crypto(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::types::InputFile;
+
+    const KRC_TEXT: &str = "[ti:测试]\n[1000,500]<0,250,0>你<250,250,0>好\n";
+
+    fn convert(main_lyric: InputFile, target_format: LyricFormat) -> FullConversionResult {
+        let input = ConversionInput {
+            main_lyric,
+            translations: vec![],
+            romanizations: vec![],
+            target_format,
+            user_metadata_overrides: None,
+        };
+        convert_single_lyric(&input, &ConversionOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_encrypted_krc_round_trip() {
+        let encrypted = krc::encrypt_krc(KRC_TEXT).unwrap();
+
+        // 本地 `.krc` 文件的原始字节
+        let from_bytes = convert(
+            InputFile::from_bytes(encrypted.clone(), LyricFormat::EncryptedKrc, None, None),
+            LyricFormat::Lrc,
+        );
+        let plain = convert(
+            InputFile::new(KRC_TEXT.to_string(), LyricFormat::Krc, None, None),
+            LyricFormat::Lrc,
+        );
+        assert!(plain.output_lyrics.contains("你好"));
+        assert_eq!(from_bytes.output_lyrics, plain.output_lyrics);
+        assert_eq!(
+            from_bytes.source_data.source_format,
+            LyricFormat::EncryptedKrc
+        );
+
+        // 酷狗接口返回的 Base64 文本
+        let from_base64 = convert(
+            InputFile::new(
+                general_purpose::STANDARD.encode(&encrypted),
+                LyricFormat::EncryptedKrc,
+                None,
+                None,
+            ),
+            LyricFormat::Lrc,
+        );
+        assert_eq!(from_base64.output_lyrics, from_bytes.output_lyrics);
+
+        let result = convert(
+            InputFile::new(KRC_TEXT.to_string(), LyricFormat::Krc, None, None),
+            LyricFormat::EncryptedKrc,
+        );
+        let output_bytes = result.output_bytes.unwrap();
+        assert!(output_bytes.starts_with(krc::KRC_FILE_HEADER));
+        assert_eq!(
+            general_purpose::STANDARD.encode(&output_bytes),
+            result.output_lyrics
+        );
+        let decrypted = krc::decrypt_krc_bytes(&output_bytes).unwrap();
+        assert!(decrypted.contains("<0,250,0>你<250,250,0>好"));
+    }
+
+    #[test]
+    fn test_credits_follow_write_credits_option() {
+        let content =
+            "[ti:测试]\n[lyricist:作词者]\n[arranger:编曲者]\n[00:01.00]你好\n[00:02.00]世界\n";
+        let convert_with = |target_format, write_credits| {
+            let input = ConversionInput {
+                main_lyric: InputFile::new(content.to_string(), LyricFormat::Lrc, None, None),
+                translations: vec![],
+                romanizations: vec![],
+                target_format,
+                user_metadata_overrides: None,
+            };
+            let options = ConversionOptions {
+                write_credits,
+                ..Default::default()
+            };
+            convert_single_lyric(&input, &options)
+                .unwrap()
+                .output_lyrics
+        };
+
+        for format in [
+            LyricFormat::Lrc,
+            LyricFormat::EnhancedLrc,
+            LyricFormat::Qrc,
+            LyricFormat::Krc,
+            LyricFormat::Lys,
+            LyricFormat::Lqe,
+            LyricFormat::Spl,
+            LyricFormat::Ass,
+        ] {
+            let output = convert_with(format, true);
+            assert!(output.contains("作词者"), "{format}: {output}");
+            assert!(output.contains("编曲者"), "{format}: {output}");
+
+            let output = convert_with(format, false);
+            assert!(!output.contains("作词者"), "{format}: {output}");
+            assert!(!output.contains("编曲者"), "{format}: {output}");
+        }
+
+        // SPL 中的演职员标签可以重新读入
+        let spl = convert_with(LyricFormat::Spl, true);
+        let parsed = parse_and_merge(
+            &ConversionInput {
+                main_lyric: InputFile::new(spl, LyricFormat::Spl, None, None),
+                translations: vec![],
+                romanizations: vec![],
+                target_format: LyricFormat::Lrc,
+                user_metadata_overrides: None,
+            },
+            &ConversionOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed.raw_metadata["arranger"], ["编曲者"]);
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
     }
 }
@@ -509,6 +509,7 @@ mod tests {
             end_ms: start_ms + duration_ms,
             duration_ms: Some(duration_ms),
             ends_with_space,
+            ..Default::default()
         }
     }
 
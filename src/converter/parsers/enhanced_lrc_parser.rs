@@ -5,12 +5,14 @@
 //! 2. `[line_time]<word_time>word<word_time>word...` (无末尾时间戳)
 //!
 //! 同时处理行起始时间与第一个词起始时间不一致的情况，并以后者为准。
+//! 行首的 `v1:`、`v2:` 或 Walaoke 的 `M:`、`F:`、`D:` 标记会被识别为演唱者。
 
 use regex::Regex;
 use std::collections::HashMap;
 use std::sync::LazyLock;
 
 use crate::converter::{
+    parsers::lrc_parser::{collect_marker_agents, split_agent_marker},
     types::{
         AnnotatedTrack, ContentType, ConvertError, LyricLine, LyricLineBuilder, LyricSyllable,
         LyricSyllableBuilder, LyricTrack, ParsedSourceData, Word,
@@ -35,6 +37,8 @@ pub fn parse_enhanced_lrc(content: &str) -> Result<ParsedSourceData, ConvertErro
     let mut lines: Vec<LyricLine> = Vec::new();
     let mut raw_metadata: HashMap<String, Vec<String>> = HashMap::new();
     let mut warnings: Vec<String> = Vec::new();
+    // 演唱者标记对之后的行持续生效，直到出现新的标记
+    let mut current_agent: Option<String> = None;
 
     for (line_num, line_str) in content.lines().enumerate() {
         let line_num_one_based = line_num + 1;
@@ -54,7 +58,11 @@ pub fn parse_enhanced_lrc(content: &str) -> Result<ParsedSourceData, ConvertErro
                 continue;
             };
 
-            let line_content = &line_str_trimmed[line_time_match.end()..];
+            let mut line_content = &line_str_trimmed[line_time_match.end()..];
+            if let Some((agent, rest)) = split_agent_marker(line_content) {
+                current_agent = Some(agent);
+                line_content = rest;
+            }
             let syllables = parse_syllables_from_line(
                 line_content,
                 line_start_ms,
@@ -74,6 +82,7 @@ pub fn parse_enhanced_lrc(content: &str) -> Result<ParsedSourceData, ConvertErro
                 };
                 LyricLineBuilder::default()
                     .start_ms(final_line_start_ms)
+                    .agent(current_agent.clone())
                     .track(AnnotatedTrack {
                         content_type: ContentType::Main,
                         content: main_track,
@@ -97,6 +106,7 @@ pub fn parse_enhanced_lrc(content: &str) -> Result<ParsedSourceData, ConvertErro
                 };
                 LyricLineBuilder::default()
                     .start_ms(line_start_ms)
+                    .agent(current_agent.clone())
                     .track(AnnotatedTrack {
                         content_type: ContentType::Main,
                         content: main_track,
@@ -113,11 +123,13 @@ pub fn parse_enhanced_lrc(content: &str) -> Result<ParsedSourceData, ConvertErro
 
     // 第二遍处理：填充行和音节的结束时间
     finalize_end_times(&mut lines, &mut warnings);
+    let agents = collect_marker_agents(&lines);
 
     Ok(ParsedSourceData {
         lines,
         raw_metadata,
         warnings,
+        agents,
         source_format: crate::converter::types::LyricFormat::EnhancedLrc,
         is_line_timed_source: false,
         ..Default::default()
@@ -289,4 +301,16 @@ mod tests {
         assert_eq!(syls1[1].start_ms, 10500);
         assert_eq!(line1.end_ms, 12500);
     }
+
+    #[test]
+    fn test_enhanced_lrc_voice_markers() {
+        let content = "[00:10.00]v1:<00:10.00>Hello <00:10.50>world\n[00:12.50]v2: <00:12.50>Reply\n[00:14.00]<00:14.00>Again";
+        let data = parse_enhanced_lrc(content).unwrap();
+
+        let agents: Vec<_> = data.lines.iter().map(|l| l.agent.as_deref()).collect();
+        assert_eq!(agents, vec![Some("v1"), Some("v2"), Some("v2")]);
+        assert_eq!(data.lines[1].main_text(), Some("Reply".to_string()));
+        assert!(!data.raw_metadata.contains_key("agent"));
+        assert_eq!(data.agents.agents_by_id.len(), 2);
+    }
 }
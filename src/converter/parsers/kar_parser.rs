@@ -0,0 +1,612 @@
+//! # Karaoke MIDI (.kar) 格式解析器
+//!
+//! 读取标准 MIDI 文件 (SMF) 中的歌词 (`FF 05`) 和文本 (`FF 01`) 元事件，
+//! 通过速度表把 tick 换算为毫秒，并按照 Soft Karaoke 的约定切分歌词行：
+//! 以 `/` 开头的文本表示新的一行，以 `\` 开头的文本表示新的段落。
+//! 以 `@` 开头的文本是文件信息（如 `@T` 标题），会被解析为元数据。
+//!
+//! 歌词只从歌词音轨中读取：优先使用名为 `Words` 的音轨，否则使用第一个包含
+//! `@` 文件信息或歌词事件的音轨，避免把其他音轨中的标记文本混入歌词。
+
+use std::collections::HashMap;
+
+use crate::converter::{
+    types::{
+        AnnotatedTrack, ContentType, ConvertError, LyricFormat, LyricLine, LyricSyllable,
+        LyricTrack, ParsedSourceData, Word,
+    },
+    utils::normalize_text_whitespace,
+};
+
+/// 没有显式速度事件时，MIDI 规定的默认速度（每四分音符的微秒数，即 120 BPM）。
+const DEFAULT_TEMPO_US_PER_QUARTER: u64 = 500_000;
+
+/// 行末音节无法从下一个音节推断结束时间时，允许的最长时长（毫秒）。
+const MAX_LAST_SYLLABLE_DURATION_MS: u64 = 1500;
+
+/// 一个带有绝对 tick 的文本类元事件。
+#[derive(Debug)]
+struct TextEvent {
+    tick: u64,
+    /// 元事件类型：`0x01` 为文本，`0x05` 为歌词
+    meta_type: u8,
+    text: String,
+}
+
+/// 一个音轨中的名称和文本类元事件。
+#[derive(Debug, Default)]
+struct MidiTrack {
+    /// 音轨名称 (`FF 03`)
+    name: Option<String>,
+    text_events: Vec<TextEvent>,
+}
+
+impl MidiTrack {
+    /// 判断该音轨是否包含 Soft Karaoke 文件信息或歌词事件。
+    fn has_karaoke_text(&self) -> bool {
+        self.text_events
+            .iter()
+            .any(|e| e.meta_type == 0x05 || e.text.starts_with('@'))
+    }
+}
+
+/// 从 SMF 中读取到的原始数据。
+#[derive(Debug, Default)]
+struct MidiData {
+    /// 每四分音符的 tick 数；如果为 `None`，则使用 SMPTE 时间
+    ticks_per_quarter: Option<u64>,
+    /// SMPTE 时间下，每秒的 tick 数
+    smpte_ticks_per_second: u64,
+    /// (tick, 每四分音符的微秒数)，速度事件对所有音轨生效
+    tempo_changes: Vec<(u64, u64)>,
+    tracks: Vec<MidiTrack>,
+}
+
+impl MidiData {
+    /// 选出歌词音轨。
+    ///
+    /// 依次尝试：名为 `Words` 的音轨、第一个包含 `@` 文件信息或歌词事件的音轨、
+    /// 第一个包含任何文本事件的音轨。
+    fn words_track(&self) -> Option<&MidiTrack> {
+        self.tracks
+            .iter()
+            .find(|t| {
+                t.name
+                    .as_deref()
+                    .is_some_and(|name| name.trim().eq_ignore_ascii_case("words"))
+            })
+            .or_else(|| self.tracks.iter().find(|t| t.has_karaoke_text()))
+            .or_else(|| self.tracks.iter().find(|t| !t.text_events.is_empty()))
+    }
+}
+
+/// 解析 Karaoke MIDI (.kar) 文件的原始字节到 `ParsedSourceData` 结构。
+pub fn parse_kar(data: &[u8]) -> Result<ParsedSourceData, ConvertError> {
+    let midi = read_smf(data)?;
+    let mut warnings = Vec::new();
+
+    let tick_to_ms = TempoMap::new(&midi);
+
+    let text_events: &[TextEvent] = midi
+        .words_track()
+        .map_or(&[], |track| track.text_events.as_slice());
+
+    let mut raw_metadata: HashMap<String, Vec<String>> = HashMap::new();
+    let mut title_count = 0;
+    for event in text_events
+        .iter()
+        .filter(|e| e.meta_type == 0x01 && e.text.starts_with('@'))
+    {
+        parse_info_text(&event.text, &mut title_count, &mut raw_metadata);
+    }
+
+    // 大多数 .kar 文件使用文本事件；如果文件使用了歌词事件，则以歌词事件为准
+    let has_lyric_events = text_events.iter().any(|e| e.meta_type == 0x05);
+    let lyric_type = if has_lyric_events { 0x05 } else { 0x01 };
+    let mut lyric_events: Vec<&TextEvent> = text_events
+        .iter()
+        .filter(|e| e.meta_type == lyric_type && !e.text.starts_with('@'))
+        .collect();
+    lyric_events.sort_by_key(|e| e.tick);
+
+    if lyric_events.is_empty() {
+        warnings.push("MIDI 文件中没有找到任何歌词事件。".to_string());
+    }
+
+    let lines = build_lines(&lyric_events, &tick_to_ms);
+
+    Ok(ParsedSourceData {
+        lines,
+        raw_metadata,
+        warnings,
+        source_format: LyricFormat::Kar,
+        is_line_timed_source: false,
+        ..Default::default()
+    })
+}
+
+/// 解析 `@` 开头的 Soft Karaoke 文件信息。
+///
+/// 第一个 `@T` 是标题，第二个是艺术家，其余的作为附加信息。
+fn parse_info_text(
+    text: &str,
+    title_count: &mut usize,
+    raw_metadata: &mut HashMap<String, Vec<String>>,
+) {
+    let mut chars = text.chars();
+    chars.next(); // '@'
+    let Some(tag) = chars.next() else {
+        return;
+    };
+    let value = chars.as_str().trim();
+    if value.is_empty() {
+        return;
+    }
+
+    let key = match tag {
+        'T' => {
+            *title_count += 1;
+            match *title_count {
+                1 => "title",
+                2 => "artist",
+                _ => "kar_info",
+            }
+        }
+        'L' => "language",
+        'I' => "kar_info",
+        _ => return,
+    };
+    raw_metadata
+        .entry(key.to_string())
+        .or_default()
+        .push(value.to_string());
+}
+
+/// 根据歌词事件构建歌词行。
+fn build_lines(events: &[&TextEvent], tempo_map: &TempoMap) -> Vec<LyricLine> {
+    let mut lines: Vec<Vec<LyricSyllable>> = Vec::new();
+    let mut current: Vec<LyricSyllable> = Vec::new();
+    let mut break_after_current = false;
+
+    for event in events {
+        let mut text = event.text.as_str();
+        let mut starts_new_line = break_after_current;
+        break_after_current = false;
+
+        if let Some(rest) = text.strip_prefix(['/', '\\']) {
+            starts_new_line = true;
+            text = rest;
+        }
+        // 有些文件用歌词事件末尾的换行符表示行结束
+        if let Some(rest) = text.strip_suffix(['\r', '\n']) {
+            break_after_current = true;
+            text = rest.trim_end_matches(['\r', '\n']);
+        }
+
+        if starts_new_line && !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+
+        if text.trim().is_empty() {
+            if !text.is_empty()
+                && let Some(last) = current.last_mut()
+            {
+                last.ends_with_space = true;
+            }
+            continue;
+        }
+
+        if text.starts_with(char::is_whitespace)
+            && let Some(last) = current.last_mut()
+        {
+            last.ends_with_space = true;
+        }
+
+        let start_ms = tempo_map.tick_to_ms(event.tick);
+        current.push(LyricSyllable {
+            text: normalize_text_whitespace(text),
+            start_ms,
+            end_ms: start_ms,
+            duration_ms: None,
+            ends_with_space: text.ends_with(char::is_whitespace),
+            ..Default::default()
+        });
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    // 用下一个音节的开始时间作为结束时间
+    let next_line_starts: Vec<Option<u64>> = lines
+        .iter()
+        .skip(1)
+        .map(|l| l.first().map(|s| s.start_ms))
+        .chain(std::iter::once(None))
+        .collect();
+
+    lines
+        .into_iter()
+        .zip(next_line_starts)
+        .map(|(mut syllables, next_line_start)| {
+            let count = syllables.len();
+            for i in 0..count {
+                let start_ms = syllables[i].start_ms;
+                let end_ms = if i + 1 < count {
+                    syllables[i + 1].start_ms
+                } else {
+                    let cap = start_ms + MAX_LAST_SYLLABLE_DURATION_MS;
+                    next_line_start.map_or(cap, |next| next.min(cap))
+                };
+                syllables[i].end_ms = end_ms.max(start_ms);
+                syllables[i].duration_ms = Some(syllables[i].end_ms - start_ms);
+            }
+            if let Some(last) = syllables.last_mut() {
+                last.ends_with_space = false;
+            }
+
+            let start_ms = syllables.first().map_or(0, |s| s.start_ms);
+            let end_ms = syllables.last().map_or(start_ms, |s| s.end_ms);
+            LyricLine {
+                tracks: vec![AnnotatedTrack {
+                    content_type: ContentType::Main,
+                    content: LyricTrack {
+                        words: vec![Word {
+                            syllables,
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }],
+                start_ms,
+                end_ms,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// 把 tick 换算为毫秒的速度表。
+struct TempoMap {
+    /// (起始 tick, 起始时间（微秒）, 每 tick 的微秒数 * `ticks_per_quarter`)
+    segments: Vec<(u64, u64, u64)>,
+    ticks_per_quarter: Option<u64>,
+    smpte_ticks_per_second: u64,
+}
+
+impl TempoMap {
+    fn new(midi: &MidiData) -> Self {
+        let mut tempo_changes = midi.tempo_changes.clone();
+        tempo_changes.sort_by_key(|(tick, _)| *tick);
+
+        let mut segments = vec![(0, 0, DEFAULT_TEMPO_US_PER_QUARTER)];
+        if let Some(tpq) = midi.ticks_per_quarter {
+            for (tick, tempo) in tempo_changes {
+                let &(last_tick, last_us, last_tempo) = segments.last().unwrap_or(&(0, 0, 0));
+                let us = last_us + (tick - last_tick) * last_tempo / tpq;
+                if tick == last_tick {
+                    segments.pop();
+                }
+                segments.push((tick, us, tempo));
+            }
+        }
+
+        Self {
+            segments,
+            ticks_per_quarter: midi.ticks_per_quarter,
+            smpte_ticks_per_second: midi.smpte_ticks_per_second,
+        }
+    }
+
+    fn tick_to_ms(&self, tick: u64) -> u64 {
+        let Some(tpq) = self.ticks_per_quarter else {
+            return tick * 1000 / self.smpte_ticks_per_second.max(1);
+        };
+        let index = self.segments.partition_point(|(t, _, _)| *t <= tick);
+        let (seg_tick, seg_us, tempo) = self.segments[index.saturating_sub(1)];
+        (seg_us + (tick - seg_tick) * tempo / tpq) / 1000
+    }
+}
+
+/// 一个简单的字节读取器。
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], ConvertError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| {
+                ConvertError::InvalidLyricFormat(format!("MIDI 数据在偏移 {} 处意外结束", self.pos))
+            })?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ConvertError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn peek_u8(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ConvertError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// 读取 MIDI 变长整数 (VLQ)。
+    fn read_vlq(&mut self) -> Result<u64, ConvertError> {
+        let mut value: u64 = 0;
+        for _ in 0..4 {
+            let byte = self.read_u8()?;
+            value = (value << 7) | u64::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ConvertError::InvalidLyricFormat(
+            "MIDI 变长整数超过 4 字节".to_string(),
+        ))
+    }
+}
+
+/// 解码 MIDI 文本。优先使用 UTF-8，失败时按 Latin-1 解码。
+fn decode_midi_text(bytes: &[u8]) -> String {
+    String::from_utf8(bytes.to_vec())
+        .unwrap_or_else(|_| bytes.iter().map(|&b| char::from(b)).collect())
+}
+
+/// 读取 SMF 文件头，以及所有音轨中的速度事件、音轨名称和文本事件。
+fn read_smf(data: &[u8]) -> Result<MidiData, ConvertError> {
+    let mut reader = ByteReader::new(data);
+    if reader.read_bytes(4)? != b"MThd" {
+        return Err(ConvertError::InvalidLyricFormat(
+            "不是有效的 MIDI 文件：缺少 MThd 头部".to_string(),
+        ));
+    }
+    let header_len = reader.read_u32()? as usize;
+    let header = reader.read_bytes(header_len)?;
+    if header.len() < 6 {
+        return Err(ConvertError::InvalidLyricFormat(
+            "MIDI 文件头过短".to_string(),
+        ));
+    }
+    let track_count = u16::from_be_bytes([header[2], header[3]]);
+    let [division_high, division_low] = [header[4], header[5]];
+
+    let mut midi = MidiData::default();
+    if division_high & 0x80 == 0 {
+        let ticks_per_quarter = u16::from_be_bytes([division_high, division_low]);
+        midi.ticks_per_quarter = Some(u64::from(ticks_per_quarter.max(1)));
+    } else {
+        // SMPTE：高字节是负的每秒帧数（补码），低字节是每帧的 tick 数
+        let frames_per_second = u64::from(division_high.wrapping_neg());
+        midi.smpte_ticks_per_second = frames_per_second * u64::from(division_low);
+    }
+
+    for _ in 0..track_count {
+        if reader.is_empty() {
+            break;
+        }
+        let chunk_id = reader.read_bytes(4)?;
+        let chunk_len = reader.read_u32()? as usize;
+        let chunk = reader.read_bytes(chunk_len)?;
+        if chunk_id == b"MTrk" {
+            read_track(chunk, &mut midi)?;
+        }
+    }
+
+    Ok(midi)
+}
+
+/// 读取一个 `MTrk` 块中的事件。
+fn read_track(chunk: &[u8], midi: &mut MidiData) -> Result<(), ConvertError> {
+    let mut reader = ByteReader::new(chunk);
+    let mut track = MidiTrack::default();
+    let mut tick: u64 = 0;
+    let mut running_status: Option<u8> = None;
+
+    while !reader.is_empty() {
+        tick += reader.read_vlq()?;
+
+        let status = match reader.peek_u8() {
+            Some(byte) if byte & 0x80 != 0 => {
+                reader.read_u8()?;
+                byte
+            }
+            // 运行状态：沿用上一个通道消息的状态字节
+            Some(_) => running_status.ok_or_else(|| {
+                ConvertError::InvalidLyricFormat("MIDI 音轨缺少状态字节".to_string())
+            })?,
+            None => break,
+        };
+
+        match status {
+            0xFF => {
+                let meta_type = reader.read_u8()?;
+                let len = usize::try_from(reader.read_vlq()?).unwrap_or(usize::MAX);
+                let payload = reader.read_bytes(len)?;
+                match meta_type {
+                    0x01 | 0x05 => track.text_events.push(TextEvent {
+                        tick,
+                        meta_type,
+                        text: decode_midi_text(payload),
+                    }),
+                    0x03 if track.name.is_none() => {
+                        track.name = Some(decode_midi_text(payload));
+                    }
+                    0x51 if payload.len() == 3 => {
+                        let tempo =
+                            u64::from(u32::from_be_bytes([0, payload[0], payload[1], payload[2]]));
+                        midi.tempo_changes.push((tick, tempo));
+                    }
+                    0x2F => break,
+                    _ => {}
+                }
+            }
+            0xF0 | 0xF7 => {
+                let len = usize::try_from(reader.read_vlq()?).unwrap_or(usize::MAX);
+                reader.read_bytes(len)?;
+            }
+            _ => {
+                running_status = Some(status);
+                let data_len = match status & 0xF0 {
+                    0xC0 | 0xD0 => 1,
+                    _ => 2,
+                };
+                reader.read_bytes(data_len)?;
+            }
+        }
+    }
+
+    midi.tracks.push(track);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::{
+        generators::kar_generator::generate_kar, processors::metadata_processor::MetadataStore,
+    };
+
+    fn text_event(delta: u8, meta_type: u8, text: &str) -> Vec<u8> {
+        let mut bytes = vec![delta, 0xFF, meta_type, u8::try_from(text.len()).unwrap()];
+        bytes.extend_from_slice(text.as_bytes());
+        bytes
+    }
+
+    /// 构建一个 96 tpq、速度为 120 BPM（每 tick 约 5.2ms）后变为 60 BPM 的最小 .kar 文件。
+    fn build_test_kar() -> Vec<u8> {
+        let mut track = Vec::new();
+        track.extend_from_slice(&text_event(0, 0x01, "@KMIDI KARAOKE FILE"));
+        track.extend_from_slice(&text_event(0, 0x01, "@TSong Title"));
+        track.extend_from_slice(&text_event(0, 0x01, "@TSome Artist"));
+        track.extend_from_slice(&text_event(0, 0x01, "\\Hel"));
+        // 一个音符事件，之后是运行状态
+        track.extend_from_slice(&[0, 0x90, 60, 100, 0, 62, 100]);
+        track.extend_from_slice(&text_event(96, 0x01, "lo "));
+        track.extend_from_slice(&text_event(96, 0x01, "world"));
+        // 在 tick 288 处将速度改为 60 BPM
+        track.extend_from_slice(&[96, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40]);
+        track.extend_from_slice(&text_event(0, 0x01, "/Next"));
+        track.extend_from_slice(&text_event(96, 0x01, " line"));
+        track.extend_from_slice(&[96, 0xFF, 0x2F, 0x00]);
+
+        build_smf(&[track])
+    }
+
+    /// 把若干音轨的事件数据组装为 96 tpq 的 SMF 文件。
+    fn build_smf(tracks: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"MThd");
+        data.extend_from_slice(&6u32.to_be_bytes());
+        let format: u8 = u8::from(tracks.len() > 1);
+        data.extend_from_slice(&[0, format, 0, u8::try_from(tracks.len()).unwrap(), 0, 96]);
+        for track in tracks {
+            data.extend_from_slice(b"MTrk");
+            data.extend_from_slice(&u32::try_from(track.len()).unwrap().to_be_bytes());
+            data.extend_from_slice(track);
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_kar_lines_and_tempo_map() {
+        let parsed = parse_kar(&build_test_kar()).unwrap();
+
+        assert_eq!(parsed.raw_metadata["title"], vec!["Song Title"]);
+        assert_eq!(parsed.raw_metadata["artist"], vec!["Some Artist"]);
+        assert_eq!(parsed.lines.len(), 2);
+
+        let first: Vec<(&str, u64, u64, bool)> = parsed.lines[0].tracks[0].content.words[0]
+            .syllables
+            .iter()
+            .map(|s| (s.text.as_str(), s.start_ms, s.end_ms, s.ends_with_space))
+            .collect();
+        assert_eq!(
+            first,
+            vec![
+                ("Hel", 0, 500, false),
+                ("lo", 500, 1000, true),
+                ("world", 1000, 1500, false)
+            ]
+        );
+
+        let second = &parsed.lines[1].tracks[0].content.words[0].syllables;
+        assert_eq!(second[0].text, "Next");
+        assert_eq!(second[0].start_ms, 1500);
+        assert!(second[0].ends_with_space);
+        // 速度变为 60 BPM 后，96 tick 为 1000ms
+        assert_eq!(second[1].start_ms, 2500);
+        assert_eq!(second[1].text, "line");
+    }
+
+    #[test]
+    fn test_generate_and_reparse_kar() {
+        let parsed = parse_kar(&build_test_kar()).unwrap();
+        let metadata_store = MetadataStore::from(&parsed);
+
+        let bytes = generate_kar(&parsed.lines, &metadata_store).unwrap();
+        let reparsed = parse_kar(&bytes).unwrap();
+
+        assert_eq!(reparsed.raw_metadata["title"], vec!["Song Title"]);
+        assert_eq!(reparsed.lines.len(), parsed.lines.len());
+        for (a, b) in reparsed.lines.iter().zip(&parsed.lines) {
+            assert_eq!(a.main_text(), b.main_text());
+            assert_eq!(a.start_ms, b.start_ms);
+        }
+    }
+
+    #[test]
+    fn test_only_words_track_is_read() {
+        let mut melody = Vec::new();
+        melody.extend_from_slice(&text_event(0, 0x03, "Melody"));
+        melody.extend_from_slice(&text_event(0, 0x01, "Verse 1"));
+        melody.extend_from_slice(&text_event(96, 0x05, "la"));
+        melody.extend_from_slice(&[0, 0xFF, 0x2F, 0x00]);
+
+        let mut words = Vec::new();
+        words.extend_from_slice(&text_event(0, 0x03, "Words"));
+        words.extend_from_slice(&text_event(0, 0x01, "@TSong Title"));
+        words.extend_from_slice(&text_event(0, 0x01, "\\Hel"));
+        words.extend_from_slice(&text_event(96, 0x01, "lo"));
+        words.extend_from_slice(&[0, 0xFF, 0x2F, 0x00]);
+
+        let parsed = parse_kar(&build_smf(&[melody, words])).unwrap();
+        assert_eq!(parsed.raw_metadata["title"], vec!["Song Title"]);
+        assert_eq!(parsed.lines.len(), 1);
+        assert_eq!(parsed.lines[0].main_text().as_deref(), Some("Hello"));
+    }
+
+    #[test]
+    fn test_unnamed_words_track_is_detected_by_content() {
+        let mut markers = Vec::new();
+        markers.extend_from_slice(&text_event(0, 0x01, "Intro"));
+        markers.extend_from_slice(&[0, 0xFF, 0x2F, 0x00]);
+
+        let mut lyrics = Vec::new();
+        lyrics.extend_from_slice(&text_event(0, 0x05, "Hi "));
+        lyrics.extend_from_slice(&text_event(96, 0x05, "there"));
+        lyrics.extend_from_slice(&[0, 0xFF, 0x2F, 0x00]);
+
+        let parsed = parse_kar(&build_smf(&[markers, lyrics])).unwrap();
+        assert_eq!(parsed.lines.len(), 1);
+        assert_eq!(parsed.lines[0].main_text().as_deref(), Some("Hi there"));
+    }
+}
@@ -0,0 +1,235 @@
+//! # 网易云 klyric 格式解析器
+//!
+//! klyric 是网易云音乐在 YRC 之前使用的逐字格式，每行形如
+//! `[行开始,行时长](音节时间,音节时长)文本...`。不同时期的文件中，音节时间有三种写法：
+//!
+//! - 全部为 `0`：音节按时长依次排列，从行开始时间累加；
+//! - 第一个音节时间不小于行开始时间：绝对时间；
+//! - 其他情况：相对于行开始时间的偏移。
+//!
+//! 部分文件使用 `<…>` 代替 `(…)`，或在时长后附带第三个数字，这些都会被兼容。
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::converter::{
+    parsers::yrc_parser::parse_json_credit_line,
+    types::{
+        AnnotatedTrack, ContentType, ConvertError, LyricFormat, LyricLine, LyricLineBuilder,
+        LyricSyllable, LyricSyllableBuilder, LyricTrack, ParsedSourceData, Word,
+    },
+    utils::{parse_and_store_metadata, process_syllable_text},
+};
+
+/// 匹配 klyric 行级时间戳 `[start,duration]`
+static KLYRIC_LINE_TIMESTAMP_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\[(?P<start>\d+),(?P<duration>\d+)]")
+        .expect("编译 KLYRIC_LINE_TIMESTAMP_REGEX 失败")
+});
+
+/// 匹配 klyric 音节时间戳 `(time,duration)`、`(time,duration,x)` 或 `<time,duration>`
+static KLYRIC_SYLLABLE_TIMESTAMP_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[(<](?P<time>\d+),(?P<duration>\d+)(?:,-?\d+)?[)>]")
+        .expect("编译 KLYRIC_SYLLABLE_TIMESTAMP_REGEX 失败")
+});
+
+/// 音节时间的解释方式，见模块文档。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SyllableTiming {
+    Sequential,
+    Absolute,
+    Offset,
+}
+
+/// 解析单行 klyric 歌词文本到 `LyricLine` 结构。
+fn parse_klyric_line(line_str: &str, line_num: usize) -> Result<LyricLine, ConvertError> {
+    let line_ts_cap = KLYRIC_LINE_TIMESTAMP_REGEX
+        .captures(line_str)
+        .ok_or_else(|| {
+            ConvertError::InvalidLyricFormat(format!(
+                "第 {line_num} 行: 行首缺少行时间戳标记 `[开始时间,总时长]`。",
+            ))
+        })?;
+
+    let line_start_ms: u64 = line_ts_cap["start"].parse()?;
+    let line_duration_ms: u64 = line_ts_cap["duration"].parse()?;
+    let content_after_line_ts = &line_str[line_ts_cap.get(0).map_or(0, |m| m.end())..];
+
+    let mut timestamps: Vec<(u64, u64, usize, usize)> = Vec::new();
+    for caps in KLYRIC_SYLLABLE_TIMESTAMP_REGEX.captures_iter(content_after_line_ts) {
+        let whole = caps.get(0).map_or(0..0, |m| m.range());
+        timestamps.push((
+            caps["time"].parse()?,
+            caps["duration"].parse()?,
+            whole.start,
+            whole.end,
+        ));
+    }
+
+    let timing = if timestamps.iter().all(|(time, ..)| *time == 0) {
+        SyllableTiming::Sequential
+    } else if timestamps
+        .first()
+        .is_some_and(|(time, ..)| *time >= line_start_ms)
+    {
+        SyllableTiming::Absolute
+    } else {
+        SyllableTiming::Offset
+    };
+
+    let mut syllables: Vec<LyricSyllable> = Vec::new();
+    let mut cursor_ms = line_start_ms;
+    for (i, &(time, duration, _, text_start)) in timestamps.iter().enumerate() {
+        let text_end = timestamps
+            .get(i + 1)
+            .map_or(content_after_line_ts.len(), |next| next.2);
+
+        let start_ms = match timing {
+            SyllableTiming::Sequential => cursor_ms,
+            SyllableTiming::Absolute => time,
+            SyllableTiming::Offset => line_start_ms + time,
+        };
+        cursor_ms = start_ms + duration;
+
+        if let Some((clean_text, ends_with_space)) =
+            process_syllable_text(&content_after_line_ts[text_start..text_end], &mut syllables)
+        {
+            let syllable = LyricSyllableBuilder::default()
+                .text(clean_text)
+                .start_ms(start_ms)
+                .end_ms(start_ms + duration)
+                .ends_with_space(ends_with_space)
+                .build()
+                .unwrap();
+            syllables.push(syllable);
+        }
+    }
+
+    let annotated_track = AnnotatedTrack {
+        content_type: ContentType::Main,
+        content: LyricTrack {
+            words: vec![Word {
+                syllables,
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    Ok(LyricLineBuilder::default()
+        .start_ms(line_start_ms)
+        .end_ms(line_start_ms + line_duration_ms)
+        .track(annotated_track)
+        .build()
+        .unwrap())
+}
+
+/// 解析 klyric 格式内容到 `ParsedSourceData` 结构。
+pub fn parse_klyric(content: &str) -> Result<ParsedSourceData, ConvertError> {
+    let mut lines: Vec<LyricLine> = Vec::new();
+    let mut raw_metadata: HashMap<String, Vec<String>> = HashMap::new();
+    let mut warnings: Vec<String> = Vec::new();
+
+    for (i, line_str_raw) in content.lines().enumerate() {
+        let line_num = i + 1;
+        let trimmed_line = line_str_raw.trim();
+
+        if trimmed_line.is_empty() {
+            continue;
+        }
+
+        if trimmed_line.starts_with('{') {
+            match parse_json_credit_line(trimmed_line) {
+                Some(credits) => {
+                    for (key, value) in credits {
+                        raw_metadata.entry(key).or_default().push(value);
+                    }
+                }
+                None => warnings.push(format!(
+                    "第 {line_num} 行: 看起来像 JSON 元数据但解析失败，已跳过。"
+                )),
+            }
+            continue;
+        }
+
+        if parse_and_store_metadata(trimmed_line, &mut raw_metadata) {
+            continue;
+        }
+
+        if KLYRIC_LINE_TIMESTAMP_REGEX.is_match(trimmed_line) {
+            match parse_klyric_line(trimmed_line, line_num) {
+                Ok(parsed_line) => lines.push(parsed_line),
+                Err(e) => {
+                    warnings.push(format!("第 {line_num} 行: 解析歌词行失败。错误: {e}"));
+                }
+            }
+        } else {
+            warnings.push(format!("第 {line_num} 行: 未能识别的行格式。"));
+        }
+    }
+
+    Ok(ParsedSourceData {
+        lines,
+        raw_metadata,
+        warnings,
+        source_format: LyricFormat::Klyric,
+        is_line_timed_source: false,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syllable_times(line: &LyricLine) -> Vec<(u64, u64, String)> {
+        line.tracks[0]
+            .content
+            .words
+            .iter()
+            .flat_map(|w| &w.syllables)
+            .map(|s| (s.start_ms, s.end_ms, s.text.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_klyric_timing_variants() {
+        let content = r#"[ti:Song]
+{"t":0,"c":[{"tx":"作曲: "},{"tx":"A"}]}
+[1000,600](0,200)He(0,400)llo
+[2000,600](0,200,0)Wo(200,400,0)rld
+[3000,600]<3000,200>Ab<3200,400>so
+"#;
+        let parsed = parse_klyric(content).unwrap();
+
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert_eq!(
+            parsed.raw_metadata.get("ti"),
+            Some(&vec!["Song".to_string()])
+        );
+        assert_eq!(
+            parsed.raw_metadata.get("composer"),
+            Some(&vec!["A".to_string()])
+        );
+        assert_eq!(parsed.lines.len(), 3);
+
+        let expected = |a: u64, b: u64, c: u64, first: &str, second: &str| {
+            vec![(a, b, first.to_string()), (b, c, second.to_string())]
+        };
+        assert_eq!(
+            syllable_times(&parsed.lines[0]),
+            expected(1000, 1200, 1600, "He", "llo")
+        );
+        assert_eq!(
+            syllable_times(&parsed.lines[1]),
+            expected(2000, 2200, 2600, "Wo", "rld")
+        );
+        assert_eq!(
+            syllable_times(&parsed.lines[2]),
+            expected(3000, 3200, 3600, "Ab", "so")
+        );
+    }
+}
@@ -22,7 +22,7 @@ static KRC_LINE_TIMESTAMP_REGEX: LazyLock<Regex> = LazyLock::new(|| {
 
 /// 匹配 KRC 音节级时间戳和文本 `<offset,duration,pitch>text`
 static KRC_SYLLABLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"<(?P<offset>\d+),(?P<duration>\d+),(?P<pitch>0)>(?P<text>[^<]*)")
+    Regex::new(r"<(?P<offset>\d+),(?P<duration>\d+),(?P<pitch>-?\d+)>(?P<text>[^<]*)")
         .expect("编译 KRC_SYLLABLE_REGEX 失败")
 });
 
@@ -66,15 +66,20 @@ pub fn parse_krc(content: &str) -> Result<ParsedSourceData, ConvertError> {
                     let duration_ms: u64 = syl_caps["duration"].parse()?;
                     let absolute_start_ms = line_start_ms + offset_ms;
 
-                    let syllable = LyricSyllableBuilder::default()
+                    let pitch: i32 = syl_caps["pitch"].parse()?;
+
+                    let mut builder = LyricSyllableBuilder::default();
+                    builder
                         .text(clean_text)
                         .start_ms(absolute_start_ms)
                         .end_ms(absolute_start_ms + duration_ms)
                         .duration_ms(duration_ms)
-                        .ends_with_space(ends_with_space)
-                        .build()
-                        .unwrap();
-                    syllables.push(syllable);
+                        .ends_with_space(ends_with_space);
+                    // 0 表示没有音高信息
+                    if pitch != 0 {
+                        builder.pitch(pitch);
+                    }
+                    syllables.push(builder.build().unwrap());
                 }
             }
 
@@ -315,4 +320,16 @@ mod tests {
             "最后一个音节的开始时间应匹配"
         );
     }
+
+    #[test]
+    fn test_krc_keeps_pitch_values() {
+        let content = "[1000,600]<0,200,0>你<200,200,62>好<400,200,-3>呀";
+        let parsed = parse_krc(content).unwrap();
+        let syllables = &parsed.lines[0].tracks[0].content.words[0].syllables;
+
+        assert_eq!(syllables.len(), 3);
+        assert_eq!(syllables[0].pitch, None);
+        assert_eq!(syllables[1].pitch, Some(62));
+        assert_eq!(syllables[2].pitch, Some(-3));
+    }
 }
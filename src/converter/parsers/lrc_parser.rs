@@ -6,8 +6,8 @@ use std::collections::HashMap;
 use std::sync::LazyLock;
 
 use crate::converter::types::{
-    LrcLineRole, LrcParsingOptions, LrcSameTimestampStrategy, LyricLineBuilder,
-    LyricSyllableBuilder,
+    Agent, AgentStore, AgentType, LrcLineRole, LrcParsingOptions, LrcSameTimestampStrategy,
+    LyricLineBuilder, LyricSyllableBuilder,
 };
 use crate::converter::{
     types::{
@@ -27,8 +27,16 @@ static LRC_TIMESTAMP_EXTRACT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"\[(\d{2,}):(\d{2})[.:](\d{2,3})]").expect("未能编译 LRC_TIMESTAMP_EXTRACT_REGEX")
 });
 
+/// 用于匹配歌词文本开头的演唱者标记：
+/// Walaoke 扩展的 `M:`、`F:`、`D:`，以及 `v1:` 这样直接使用 Agent ID 的标记
+static AGENT_MARKER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(M|F|D|v\d+):\s*").expect("未能编译 AGENT_MARKER_REGEX"));
+
 const DEFAULT_LAST_LINE_DURATION_MS: u64 = 10000;
 
+/// 合唱使用的 Agent ID
+pub(crate) const CHORUS_AGENT_ID: &str = "v1000";
+
 /// 解析 LRC 格式内容到 `ParsedSourceData` 结构。
 pub fn parse_lrc(
     content: &str,
@@ -37,12 +45,15 @@ pub fn parse_lrc(
     struct TempLrcEntry {
         timestamp_ms: u64,
         text: String,
+        agent: Option<String>,
     }
 
     let mut raw_metadata: HashMap<String, Vec<String>> = HashMap::new();
     let mut warnings: Vec<String> = Vec::new();
 
     let mut temp_entries: Vec<TempLrcEntry> = Vec::new();
+    // 演唱者标记对之后的行持续生效，直到出现新的标记
+    let mut current_agent: Option<String> = None;
 
     for (line_num, line_str) in content.lines().enumerate() {
         let line_str_trimmed = line_str.trim();
@@ -54,7 +65,11 @@ pub fn parse_lrc(
 
         if let Some(line_caps) = LRC_LINE_REGEX.captures(line_str_trimmed) {
             let all_timestamps_str = line_caps.get(1).map_or("", |m| m.as_str());
-            let raw_text_part = line_caps.get(2).map_or("", |m| m.as_str());
+            let mut raw_text_part = line_caps.get(2).map_or("", |m| m.as_str());
+            if let Some((agent, rest)) = split_agent_marker(raw_text_part) {
+                current_agent = Some(agent);
+                raw_text_part = rest;
+            }
             let text_part = normalize_text_whitespace(raw_text_part);
 
             for ts_cap in LRC_TIMESTAMP_EXTRACT_REGEX.captures_iter(all_timestamps_str) {
@@ -73,6 +88,7 @@ pub fn parse_lrc(
                         temp_entries.push(TempLrcEntry {
                             timestamp_ms: (minutes * 60 + seconds) * 1000 + ms,
                             text: text_part.clone(),
+                            agent: current_agent.clone(),
                         });
                     } else {
                         warnings.push(format!("LRC秒数无效 (行 {}): '{}'", line_num + 1, seconds));
@@ -244,10 +260,14 @@ pub fn parse_lrc(
         };
 
         if !tracks.is_empty() {
+            let agent = temp_entries[i..next_event_index]
+                .iter()
+                .find_map(|e| e.agent.clone());
             let line = LyricLineBuilder::default()
                 .tracks(tracks)
                 .start_ms(start_ms)
                 .end_ms(end_ms)
+                .agent(agent)
                 .build()
                 .unwrap();
             final_lyric_lines.push(line);
@@ -256,16 +276,55 @@ pub fn parse_lrc(
         i = next_event_index;
     }
 
+    let agents = collect_marker_agents(&final_lyric_lines);
+
     Ok(ParsedSourceData {
         lines: final_lyric_lines,
         raw_metadata,
         source_format: LyricFormat::Lrc,
         is_line_timed_source: true,
         warnings,
+        agents,
         ..Default::default()
     })
 }
 
+/// 移除歌词文本开头的演唱者标记，返回对应的 Agent ID 和剩余的文本。
+///
+/// Walaoke 的 `M:` 和 `F:` 分别映射为 `v1` 和 `v2`，`D:` 映射为合唱 `v1000`。
+pub(crate) fn split_agent_marker(text: &str) -> Option<(String, &str)> {
+    let caps = AGENT_MARKER_REGEX.captures(text)?;
+    let agent = match &caps[1] {
+        "M" => "v1",
+        "F" => "v2",
+        "D" => CHORUS_AGENT_ID,
+        id => id,
+    };
+    Some((agent.to_string(), &text[caps.get(0)?.end()..]))
+}
+
+/// 根据歌词行中出现的 Agent ID 生成演唱者定义。
+///
+/// `v1000` 为合唱，其余为单人演唱。
+pub(crate) fn collect_marker_agents(lines: &[LyricLine]) -> AgentStore {
+    let mut agents = AgentStore::default();
+    for id in lines.iter().filter_map(|l| l.agent.as_deref()) {
+        if agents.get(id).is_none() {
+            agents.insert(Agent {
+                id: id.to_string(),
+                name: None,
+                agent_type: if id == CHORUS_AGENT_ID {
+                    AgentType::Group
+                } else {
+                    AgentType::Person
+                },
+                ..Default::default()
+            });
+        }
+    }
+    agents
+}
+
 fn new_line_timed_track(text: String, start_ms: u64, end_ms: u64) -> LyricTrack {
     LyricTrack {
         words: vec![Word {
@@ -742,4 +801,32 @@ mod tests {
             Some("Arigatou".to_string())
         );
     }
+
+    #[test]
+    fn test_walaoke_markers_become_agents() {
+        let content =
+            "[00:01.00]M:First\n[00:02.00]Still male\n[00:03.00]F: Second\n[00:04.00]D:Together";
+        let parsed = parse_lrc(content, &LrcParsingOptions::default()).unwrap();
+
+        let agents: Vec<_> = parsed
+            .lines
+            .iter()
+            .map(|l| (l.main_text().unwrap(), l.agent.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            agents,
+            vec![
+                ("First".to_string(), "v1".to_string()),
+                ("Still male".to_string(), "v1".to_string()),
+                ("Second".to_string(), "v2".to_string()),
+                ("Together".to_string(), "v1000".to_string()),
+            ]
+        );
+        assert!(!parsed.raw_metadata.contains_key("agent"));
+        assert_eq!(parsed.agents.agents_by_id.len(), 3);
+        assert_eq!(
+            parsed.agents.agents_by_id["v1000"].agent_type,
+            AgentType::Group
+        );
+    }
 }
@@ -5,7 +5,8 @@ use std::collections::HashMap;
 use crate::converter::{
     types::{
         AnnotatedTrack, ContentType, ConvertError, LyricFormat, LyricLine, LyricLineBuilder,
-        LyricSyllable, LyricSyllableBuilder, LyricTrack, ParsedSourceData, Word, lys_properties,
+        LyricSyllable, LyricSyllableBuilder, LyricTrack, ParsedSourceData, ViewSide, Word,
+        lys_properties,
     },
     utils::{parse_and_store_metadata, process_syllable_text},
 };
@@ -101,6 +102,19 @@ fn parse_lys_line(line_str: &str, line_num: usize) -> Result<(u8, LyricLine), Co
     Ok((property, line))
 }
 
+/// 从 LYS 行属性中取出视图位置，未设置时返回 `None`。
+fn view_side_from_property(property: u8) -> Option<ViewSide> {
+    match property {
+        lys_properties::UNSET_LEFT | lys_properties::MAIN_LEFT | lys_properties::BG_LEFT => {
+            Some(ViewSide::Left)
+        }
+        lys_properties::UNSET_RIGHT | lys_properties::MAIN_RIGHT | lys_properties::BG_RIGHT => {
+            Some(ViewSide::Right)
+        }
+        _ => None,
+    }
+}
+
 /// 解析 LYS 格式内容到 `ParsedSourceData` 结构。
 pub fn parse_lys(content: &str) -> Result<ParsedSourceData, ConvertError> {
     let mut lines: Vec<LyricLine> = Vec::new();
@@ -140,16 +154,20 @@ pub fn parse_lys(content: &str) -> Result<ParsedSourceData, ConvertError> {
                                 "第 {line_num} 行: 连续的背景行，将提升为新的主歌词行。"
                             ));
                             parsed_line.agent.clone_from(&main_line.agent);
+                            parsed_line.view_side =
+                                view_side_from_property(property).or(main_line.view_side);
                             lines.push(parsed_line);
                         } else if let Some(mut bg_track) = parsed_line.tracks.pop() {
                             bg_track.content_type = ContentType::Background;
                             main_line.tracks.push(bg_track);
+                            main_line.background_view_side = view_side_from_property(property);
                         }
                     } else {
                         warnings.push(format!(
                             "第 {line_num} 行: 背景行出现在任何主歌词行之前，将提升为主歌词行。"
                         ));
                         parsed_line.agent = Some("v1".to_string());
+                        parsed_line.view_side = view_side_from_property(property);
                         lines.push(parsed_line);
                     }
                 } else {
@@ -169,6 +187,7 @@ pub fn parse_lys(content: &str) -> Result<ParsedSourceData, ConvertError> {
                         }
                     };
                     parsed_line.agent = agent;
+                    parsed_line.view_side = view_side_from_property(property);
                     lines.push(parsed_line);
                 }
             }
@@ -232,6 +251,7 @@ mod tests {
         assert_eq!(line1.start_ms, 100);
         assert_eq!(line1.end_ms, 600);
         assert_eq!(line1.agent, Some("v1".to_string()));
+        assert_eq!(line1.view_side, Some(ViewSide::Left));
         let main_track1 = &line1.tracks[0].content;
         assert_eq!(main_track1.words.len(), 1);
         assert_eq!(main_track1.words[0].syllables.len(), 2);
@@ -247,6 +267,17 @@ mod tests {
         let line2 = &result.lines[1];
         assert_eq!(line2.start_ms, 1000);
         assert_eq!(line2.agent, Some("v2".to_string()));
+        assert_eq!(line2.view_side, Some(ViewSide::Right));
+    }
+
+    #[test]
+    fn test_unset_view_side() {
+        let content = "[3]Main(100,200)\n[8](BG)(300,200)";
+        let result = parse_lys(content).unwrap();
+
+        assert_eq!(result.lines.len(), 1);
+        assert_eq!(result.lines[0].agent, Some("v1".to_string()));
+        assert_eq!(result.lines[0].view_side, None);
     }
 
     #[test]
@@ -265,6 +296,7 @@ mod tests {
             .unwrap();
         assert_eq!(bg_track.content.words[0].syllables[0].text, "(Background)");
         assert_eq!(bg_track.content.words[0].syllables[0].start_ms, 500);
+        assert_eq!(line.background_view_side, Some(ViewSide::Left));
     }
 
     #[test]
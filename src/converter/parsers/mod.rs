@@ -3,6 +3,8 @@
 pub mod apple_music_json_parser;
 pub mod ass_parser;
 pub mod enhanced_lrc_parser;
+pub mod kar_parser;
+pub mod klyric_parser;
 pub mod krc_parser;
 pub mod lqe_parser;
 pub mod lrc_parser;
@@ -10,6 +12,9 @@ pub mod lyricify_lines_parser;
 pub mod lys_parser;
 // pub mod musixmatch_parser;
 pub mod qrc_parser;
+pub mod sami_parser;
 pub mod spl_parser;
 pub mod ttml_parser;
+pub mod ultrastar_parser;
+pub mod youtube_parser;
 pub mod yrc_parser;
@@ -0,0 +1,446 @@
+//! # SAMI (.smi) 格式解析器
+//!
+//! SAMI 字幕由 `<STYLE>` 中声明的语言类（如 `.KRCC`、`.ENCC`）和一系列
+//! `<SYNC Start=毫秒><P Class=类名>文本` 块组成。文本为 `&nbsp;` 的块表示清屏。
+//!
+//! 第一个出现在正文中的语言类（按 `<STYLE>` 中的声明顺序）作为主歌词，
+//! 其余语言类作为主歌词的翻译。
+//!
+//! SAMI 文件常见 CP949 (EUC-KR) 编码，[`parse_sami_bytes`] 会根据 BOM 或
+//! `<meta charset>` 声明解码原始字节。
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use encoding_rs::{EUC_KR, Encoding, UTF_8};
+use regex::Regex;
+
+use crate::converter::types::{
+    ContentType, ConvertError, LyricFormat, LyricLine, ParsedSourceData, TrackMetadataKey,
+};
+
+static SYNC_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<SYNC\b[^>]*?\bStart\s*=\s*["']?(?P<start>\d+)["']?[^>]*>"#)
+        .expect("编译 SYNC_REGEX 失败")
+});
+
+static P_TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<P\b(?P<attrs>[^>]*)>").expect("编译 P_TAG_REGEX 失败"));
+
+static CLASS_ATTR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\bClass\s*=\s*["']?(?P<class>[\w-]+)"#).expect("编译 CLASS_ATTR_REGEX 失败")
+});
+
+static STYLE_BLOCK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<STYLE\b[^>]*>(?P<css>.*?)</STYLE>").expect("编译 STYLE_BLOCK_REGEX 失败")
+});
+
+static CSS_CLASS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)\.(?P<class>[\w-]+)\s*\{(?P<body>[^}]*)}").expect("编译 CSS_CLASS_REGEX 失败")
+});
+
+static CSS_LANG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\blang\s*:\s*(?P<lang>[^;\s]+)").expect("编译 CSS_LANG_REGEX 失败")
+});
+
+static TITLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<TITLE>(?P<title>.*?)</TITLE>").expect("编译 TITLE_REGEX 失败")
+});
+
+static BR_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<br\s*/?>").expect("编译 BR_REGEX 失败"));
+
+static TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<[^>]*>").expect("编译 TAG_REGEX 失败"));
+
+/// 匹配 `<meta charset="…">` 和 `<meta http-equiv="Content-Type" content="text/html; charset=…">`。
+static META_CHARSET_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)<meta\b[^>]*?\bcharset\s*=\s*["']?(?P<charset>[\w.:-]+)"#)
+        .expect("编译 META_CHARSET_REGEX 失败")
+});
+
+/// 常见 SAMI 语言类名（去掉 `CC` 后缀）与 BCP 47 语言代码的对应关系。
+pub(crate) const SAMI_CLASS_LANGUAGES: &[(&str, &str)] = &[
+    ("KR", "ko-KR"),
+    ("KO", "ko-KR"),
+    ("EN", "en-US"),
+    ("ENUS", "en-US"),
+    ("ENGB", "en-GB"),
+    ("JP", "ja-JP"),
+    ("JA", "ja-JP"),
+    ("CN", "zh-CN"),
+    ("ZH", "zh-CN"),
+    ("TW", "zh-TW"),
+];
+
+/// 一个 `<P>` 块。`text` 为 `None` 表示清屏。
+struct SamiEvent {
+    start_ms: u64,
+    class: String,
+    text: Option<String>,
+}
+
+/// 解析 SAMI 文件的原始字节到 `ParsedSourceData` 结构。
+///
+/// 编码按以下顺序确定：BOM、`<meta>` 中声明的 charset、UTF-8。
+/// 既没有声明、又不是有效 UTF-8 的文件按 CP949 (EUC-KR) 解码。
+pub fn parse_sami_bytes(data: &[u8]) -> Result<ParsedSourceData, ConvertError> {
+    let (content, _, _) = detect_encoding(data).decode(data);
+    parse_sami(&content)
+}
+
+/// 推断 SAMI 文件的编码。
+fn detect_encoding(data: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(data) {
+        return encoding;
+    }
+    // 声明本身总是 ASCII，可以直接在有损解码的文本中查找
+    let ascii_view = String::from_utf8_lossy(data);
+    if let Some(encoding) = META_CHARSET_REGEX
+        .captures(&ascii_view)
+        .and_then(|caps| Encoding::for_label(caps["charset"].as_bytes()))
+    {
+        return encoding;
+    }
+    if std::str::from_utf8(data).is_ok() {
+        UTF_8
+    } else {
+        EUC_KR
+    }
+}
+
+/// 解析 SAMI 格式内容到 `ParsedSourceData` 结构。
+pub fn parse_sami(content: &str) -> Result<ParsedSourceData, ConvertError> {
+    let mut raw_metadata: HashMap<String, Vec<String>> = HashMap::new();
+    let mut warnings: Vec<String> = Vec::new();
+
+    if let Some(title) = TITLE_REGEX
+        .captures(content)
+        .and_then(|caps| decode_text(&caps["title"]))
+    {
+        raw_metadata
+            .entry("title".to_string())
+            .or_default()
+            .push(title);
+    }
+
+    let style_classes = parse_style_classes(content);
+    let events = collect_events(content);
+    if events.is_empty() {
+        return Err(ConvertError::InvalidLyricFormat(
+            "SAMI 内容中没有找到任何 <SYNC> 块。".to_string(),
+        ));
+    }
+
+    let class_language = |class: &str| -> Option<String> {
+        style_classes
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(class))
+            .and_then(|(_, lang)| lang.clone())
+            .or_else(|| language_for_class(class).map(str::to_string))
+    };
+
+    let main_class = style_classes
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .find(|name| {
+            events
+                .iter()
+                .any(|e| e.text.is_some() && e.class.eq_ignore_ascii_case(name))
+        })
+        .or_else(|| {
+            events
+                .iter()
+                .find(|e| e.text.is_some())
+                .map(|e| e.class.as_str())
+        })
+        .unwrap_or_default()
+        .to_string();
+    let main_language = class_language(&main_class);
+    if let Some(lang) = &main_language {
+        raw_metadata
+            .entry("language".to_string())
+            .or_default()
+            .push(lang.clone());
+    }
+
+    let last_sync_ms = events.iter().map(|e| e.start_ms).max().unwrap_or(0);
+    // 每个块持续到同一语言类的下一个块；最后一个块持续到文件中最后一个 SYNC
+    let timed_events: Vec<(u64, u64, &SamiEvent)> = events
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.text.is_some())
+        .map(|(i, e)| {
+            let end_ms = events[i + 1..]
+                .iter()
+                .find(|next| {
+                    next.class.eq_ignore_ascii_case(&e.class) && next.start_ms > e.start_ms
+                })
+                .map_or(last_sync_ms, |next| next.start_ms);
+            (e.start_ms, end_ms.max(e.start_ms), e)
+        })
+        .collect();
+
+    let mut lines: Vec<LyricLine> = Vec::new();
+    for (start_ms, end_ms, event) in timed_events
+        .iter()
+        .filter(|(_, _, e)| e.class.eq_ignore_ascii_case(&main_class))
+    {
+        let mut line = LyricLine::new(*start_ms, *end_ms);
+        line.add_content_track(ContentType::Main, event.text.clone().unwrap_or_default());
+        if let (Some(lang), Some(track)) = (&main_language, line.tracks.first_mut()) {
+            track
+                .content
+                .metadata
+                .insert(TrackMetadataKey::Language, lang.clone());
+        }
+        lines.push(line);
+    }
+
+    for (start_ms, _, event) in timed_events
+        .iter()
+        .filter(|(_, _, e)| !e.class.eq_ignore_ascii_case(&main_class))
+    {
+        // 优先匹配开始时间相同的行，否则挂到时间范围覆盖该块的行
+        let target = lines
+            .iter()
+            .position(|l| l.start_ms == *start_ms)
+            .or_else(|| {
+                lines
+                    .iter()
+                    .position(|l| l.start_ms <= *start_ms && *start_ms < l.end_ms)
+            });
+        match target {
+            Some(index) => lines[index].add_translation(
+                ContentType::Main,
+                event.text.clone().unwrap_or_default(),
+                class_language(&event.class).as_deref(),
+            ),
+            None => warnings.push(format!(
+                "{}ms 处的 {} 类字幕没有对应的主歌词行，已跳过。",
+                start_ms, event.class
+            )),
+        }
+    }
+
+    Ok(ParsedSourceData {
+        lines,
+        raw_metadata,
+        warnings,
+        source_format: LyricFormat::Sami,
+        is_line_timed_source: true,
+        ..Default::default()
+    })
+}
+
+/// 根据常见的类名约定推断语言代码，例如 `KRCC` → `ko-KR`。
+fn language_for_class(class: &str) -> Option<&'static str> {
+    let upper = class.to_ascii_uppercase();
+    let code = upper.strip_suffix("CC").unwrap_or(&upper);
+    SAMI_CLASS_LANGUAGES
+        .iter()
+        .find(|(name, _)| *name == code)
+        .map(|(_, lang)| *lang)
+}
+
+/// 按声明顺序解析 `<STYLE>` 中的语言类及其 `lang` 属性。
+fn parse_style_classes(content: &str) -> Vec<(String, Option<String>)> {
+    let Some(css) = STYLE_BLOCK_REGEX.captures(content) else {
+        return Vec::new();
+    };
+    CSS_CLASS_REGEX
+        .captures_iter(&css["css"])
+        .map(|caps| {
+            let lang = CSS_LANG_REGEX
+                .captures(&caps["body"])
+                .map(|l| l["lang"].to_string());
+            (caps["class"].to_string(), lang)
+        })
+        .collect()
+}
+
+/// 将正文拆分为一个个 `<P>` 块。没有 `<P>` 标签的 SYNC 块视为类名为空的块。
+fn collect_events(content: &str) -> Vec<SamiEvent> {
+    let syncs: Vec<_> = SYNC_REGEX.captures_iter(content).collect();
+    let mut events = Vec::new();
+
+    for (i, caps) in syncs.iter().enumerate() {
+        let Ok(start_ms) = caps["start"].parse::<u64>() else {
+            continue;
+        };
+        let body_start = caps.get(0).map_or(0, |m| m.end());
+        let body_end = syncs
+            .get(i + 1)
+            .and_then(|next| next.get(0))
+            .map_or(content.len(), |m| m.start());
+        let body = &content[body_start..body_end];
+
+        let paragraphs: Vec<_> = P_TAG_REGEX.captures_iter(body).collect();
+        if paragraphs.is_empty() {
+            events.push(SamiEvent {
+                start_ms,
+                class: String::new(),
+                text: decode_text(body),
+            });
+            continue;
+        }
+        for (j, p) in paragraphs.iter().enumerate() {
+            let text_start = p.get(0).map_or(0, |m| m.end());
+            let text_end = paragraphs
+                .get(j + 1)
+                .and_then(|next| next.get(0))
+                .map_or(body.len(), |m| m.start());
+            let class = CLASS_ATTR_REGEX
+                .captures(&p["attrs"])
+                .map(|c| c["class"].to_string())
+                .unwrap_or_default();
+            events.push(SamiEvent {
+                start_ms,
+                class,
+                text: decode_text(&body[text_start..text_end]),
+            });
+        }
+    }
+
+    events
+}
+
+/// 去除标签、解码实体并规范化空白。结果为空（如 `&nbsp;`）时返回 `None`。
+fn decode_text(raw: &str) -> Option<String> {
+    let with_breaks = BR_REGEX.replace_all(raw, " ");
+    let without_tags = TAG_REGEX.replace_all(&with_breaks, "");
+    let decoded = decode_entities(&without_tags);
+    let text = decoded.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// 解码文本中的字符实体。无法识别的实体（以及没有分号的 `&`）原样保留。
+fn decode_entities(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        output.push_str(&rest[..amp]);
+        let after = &rest[amp + 1..];
+        let resolved = after
+            .find(';')
+            .and_then(|end| resolve_entity(&after[..end]).map(|c| (c, end)));
+        if let Some((c, end)) = resolved {
+            output.push(c);
+            rest = &after[end + 1..];
+        } else {
+            output.push('&');
+            rest = after;
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 解析一个实体名（不含 `&` 和 `;`），支持数字实体和字幕中常见的命名实体。
+fn resolve_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "hellip" => '…',
+        "middot" => '·',
+        "ndash" => '–',
+        "mdash" => '—',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        _ => return None,
+    };
+    Some(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sami_language_classes() {
+        let content = r#"<SAMI>
+<HEAD>
+<TITLE>Song &amp; Title</TITLE>
+<STYLE TYPE="text/css">
+<!--
+P { text-align:center; }
+.KRCC { Name:Korean; lang:ko-KR; SAMIType:CC; }
+.ENCC { Name:English; lang:en-US; SAMIType:CC; }
+-->
+</STYLE>
+</HEAD>
+<BODY>
+<SYNC Start=1000><P Class=KRCC>안녕<br>하세요
+<SYNC Start=1000><P Class=ENCC>Hello <font color="red">there</font>
+<SYNC Start=3000><P Class=KRCC>&nbsp;
+<P Class=ENCC>&nbsp;
+<SYNC Start=4000><P Class=KRCC>노래
+<SYNC Start=6000><P Class=KRCC>&nbsp;
+</BODY>
+</SAMI>"#;
+        let parsed = parse_sami(content).unwrap();
+
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert_eq!(
+            parsed.raw_metadata.get("title"),
+            Some(&vec!["Song & Title".to_string()])
+        );
+        assert_eq!(parsed.lines.len(), 2);
+
+        let first = &parsed.lines[0];
+        assert_eq!((first.start_ms, first.end_ms), (1000, 3000));
+        assert_eq!(first.main_text().as_deref(), Some("안녕 하세요"));
+        let main = first.main_track().unwrap();
+        assert_eq!(
+            main.content.metadata.get(&TrackMetadataKey::Language),
+            Some(&"ko-KR".to_string())
+        );
+        assert_eq!(main.translations[0].text(), "Hello there");
+        assert_eq!(
+            main.translations[0]
+                .metadata
+                .get(&TrackMetadataKey::Language),
+            Some(&"en-US".to_string())
+        );
+
+        let second = &parsed.lines[1];
+        assert_eq!((second.start_ms, second.end_ms), (4000, 6000));
+        assert!(second.main_track().unwrap().translations.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_entities_do_not_block_decoding() {
+        assert_eq!(
+            decode_text("Rock &amp; Roll &foo; &#44256;&#x1F3B5; A&B").as_deref(),
+            Some("Rock & Roll &foo; 고🎵 A&B")
+        );
+    }
+
+    #[test]
+    fn test_parse_sami_bytes_detects_charset() {
+        let content = "<SAMI>\n<HEAD>\n<META http-equiv=\"Content-Type\" content=\"text/html; charset=ks_c_5601-1987\">\n</HEAD>\n<BODY>\n<SYNC Start=1000><P Class=KRCC>안녕하세요\n<SYNC Start=2000><P Class=KRCC>&nbsp;\n</BODY>\n</SAMI>";
+        let (bytes, _, _) = EUC_KR.encode(content);
+        assert!(std::str::from_utf8(&bytes).is_err());
+
+        let parsed = parse_sami_bytes(&bytes).unwrap();
+        assert_eq!(parsed.lines[0].main_text().as_deref(), Some("안녕하세요"));
+
+        // 没有声明编码的 UTF-8 文件
+        let parsed =
+            parse_sami_bytes(content.replace("charset=ks_c_5601-1987", "").as_bytes()).unwrap();
+        assert_eq!(parsed.lines[0].main_text().as_deref(), Some("안녕하세요"));
+    }
+}
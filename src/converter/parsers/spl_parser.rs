@@ -7,7 +7,7 @@ use crate::converter::{
         AnnotatedTrack, ContentType, ConvertError, LyricFormat, LyricLine, LyricSyllable,
         LyricSyllableBuilder, LyricTrack, ParsedSourceData, Word,
     },
-    utils::process_syllable_text,
+    utils::{parse_and_store_metadata, process_syllable_text},
 };
 use regex::Regex;
 use std::sync::LazyLock;
@@ -121,6 +121,7 @@ pub fn parse_spl(content: &str) -> Result<ParsedSourceData, ConvertError> {
     let mut lines: Vec<LyricLine> = Vec::new();
     let mut warnings: Vec<String> = Vec::new();
     let mut spl_blocks: Vec<SplBlock> = Vec::new();
+    let mut raw_metadata: HashMap<String, Vec<String>> = HashMap::new();
 
     // 将原始文本行构建成逻辑块 (SplBlock)
     let mut line_iterator = content.lines().enumerate().peekable();
@@ -188,7 +189,8 @@ pub fn parse_spl(content: &str) -> Result<ParsedSourceData, ConvertError> {
                 }
             }
             spl_blocks.push(current_block);
-        } else {
+        } else if !parse_and_store_metadata(trimmed_line, &mut raw_metadata) {
+            // 演职员等 LRC 风格的元数据标签存入元数据，其他行跳过
             warnings.push(format!(
                 "第 {line_num} 行: 跳过无时间戳的孤立行 '{trimmed_line}'"
             ));
@@ -276,7 +278,7 @@ pub fn parse_spl(content: &str) -> Result<ParsedSourceData, ConvertError> {
 
     Ok(ParsedSourceData {
         lines,
-        raw_metadata: HashMap::new(),
+        raw_metadata,
         warnings,
         source_format: LyricFormat::Spl,
         is_line_timed_source: is_line_timed,
@@ -8,14 +8,18 @@ use std::{collections::HashMap, str};
 use quick_xml::{
     Reader,
     errors::Error as QuickXmlError,
-    events::{BytesStart, BytesText, Event},
+    escape::resolve_predefined_entity,
+    events::{BytesRef, BytesStart, BytesText, Event},
 };
 use tracing::error;
 
-use crate::converter::types::{
-    Agent, AgentStore, AgentType, AnnotatedTrack, ContentType, ConvertError, LyricFormat,
-    LyricLine, LyricSyllable, LyricTrack, ParsedSourceData, TrackMetadataKey, TtmlParsingOptions,
-    TtmlTimingMode, Word,
+use crate::converter::{
+    generators::ttml_generator::{AGENT_COLOR_STYLE_PREFIX, AMLL_META_KEYS},
+    types::{
+        Agent, AgentStore, AgentType, AnnotatedTrack, ContentType, ConvertError, LyricFormat,
+        LyricLine, LyricSyllable, LyricTrack, ParsedSourceData, TrackMetadataKey, TtmlExtensions,
+        TtmlParsingOptions, TtmlTimingMode, ViewSide, Word, XmlElement, XmlNode,
+    },
 };
 
 // =================================================================================
@@ -23,6 +27,7 @@ use crate::converter::types::{
 // =================================================================================
 
 const TAG_TT: &[u8] = b"tt";
+const TAG_HEAD: &[u8] = b"head";
 const TAG_METADATA: &[u8] = b"metadata";
 const TAG_BODY: &[u8] = b"body";
 const TAG_DIV: &[u8] = b"div";
@@ -59,6 +64,37 @@ const ATTR_XML_ID: &[u8] = b"xml:id";
 const ATTR_KEY: &[u8] = b"key";
 const ATTR_VALUE: &[u8] = b"value";
 const ATTR_FOR: &[u8] = b"for";
+const ATTR_DUR: &[u8] = b"dur";
+
+/// `<tt>` 上由生成器负责写出的属性，其余属性会被原样保留。
+const KNOWN_TT_ATTRS: &[&[u8]] = &[
+    b"xmlns",
+    b"xmlns:ttm",
+    b"xmlns:itunes",
+    b"xmlns:amll",
+    ATTR_ITUNES_TIMING,
+    ATTR_XML_LANG,
+];
+/// `<p>` 上由生成器负责写出的属性。
+const KNOWN_P_ATTRS: &[&[u8]] = &[
+    ATTR_BEGIN,
+    ATTR_END,
+    ATTR_AGENT,
+    ATTR_AGENT_ALIAS,
+    ATTR_ITUNES_SONG_PART,
+    ATTR_ITUNES_KEY,
+];
+/// `<span>` 上由生成器负责写出的属性。
+const KNOWN_SPAN_ATTRS: &[&[u8]] = &[
+    ATTR_BEGIN,
+    ATTR_END,
+    ATTR_ROLE,
+    ATTR_ROLE_ALIAS,
+    ATTR_XML_LANG,
+    ATTR_XML_SCHEME,
+    ATTR_AGENT,
+    ATTR_AGENT_ALIAS,
+];
 
 const ROLE_TRANSLATION: &[u8] = b"x-translation";
 const ROLE_ROMANIZATION: &[u8] = b"x-roman";
@@ -78,6 +114,7 @@ enum FormatDetection {
 
 /// 主解析器状态机，聚合了所有子状态和全局配置。
 #[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
 struct TtmlParserState {
     // --- 全局配置与状态 ---
     /// 是否为逐行计时模式。由 `<tt itunes:timing="line">` 或自动检测确定。
@@ -101,7 +138,11 @@ struct TtmlParserState {
     /// 文本处理缓冲区，用于优化字符串处理。
     text_processing_buffer: String,
 
+    /// 是否位于 `<head>` 内。
+    in_head: bool,
     in_metadata: bool,
+    /// 文档级别未识别的内容，会原样保留到 `ParsedSourceData` 中。
+    extensions: TtmlExtensions,
     /// 存储 `<metadata>` 区域解析状态的结构体。
     metadata_state: MetadataParseState,
     /// 存储 `<body>` 和 `<p>` 区域解析状态的结构体。
@@ -113,6 +154,8 @@ struct TtmlParserState {
     agent_counter: u32,
     /// 用于存储已为直接名称生成的 `ID` 映射 (`name` -> `id`)
     agent_name_to_id_map: HashMap<String, String>,
+    /// 背景人声 `<span>` 引用、并且能解析出显示位置的样式 ID。
+    background_styles: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -162,7 +205,7 @@ struct DetailedAuxiliaryTracks {
 /// 存储 `<metadata>` 区域解析状态的结构体。
 #[derive(Debug, Default)]
 struct MetadataParseState {
-    line_translation_map: HashMap<String, (LineTranslation, Option<String>)>,
+    line_translation_map: HashMap<String, Vec<LineTranslation>>,
     timed_track_map: HashMap<String, DetailedAuxiliaryTracks>,
 
     context: MetadataContext,
@@ -200,10 +243,16 @@ struct CurrentPElementData {
     agent: Option<String>,
     song_part: Option<String>, // 继承自 div 或 p 自身
     itunes_key: Option<String>,
+    /// `<p>` 上未识别的属性。
+    extra_attributes: Vec<(String, String)>,
+    /// `<p>` 中未识别的子元素。
+    extra_elements: Vec<XmlElement>,
     /// 用于在逐行模式下累积所有文本内容。
     line_text_accumulator: String,
     /// 累积所有带注解的轨道数据
     tracks_accumulator: Vec<AnnotatedTrack>,
+    /// 背景人声 `<span>` 所引用样式的显示位置。
+    background_view_side: Option<ViewSide>,
 }
 
 /// 代表当前 `<span>` 的上下文信息，用于处理嵌套和内容分类。
@@ -212,8 +261,11 @@ struct SpanContext {
     role: SpanRole,
     lang: Option<String>,   // xml:lang 属性
     scheme: Option<String>, // xml:scheme 属性
+    agent: Option<String>,  // ttm:agent 属性
     start_ms: Option<u64>,
     end_ms: Option<u64>,
+    /// 未识别的属性，会附加到由这个 span 生成的音节上。
+    extra_attributes: Vec<(String, String)>,
 }
 
 /// 定义 `<span>` 标签可能扮演的角色。
@@ -241,9 +293,11 @@ enum LastSyllableInfo {
     },
 }
 
-/// 用于存储从 `<head>` 中解析的逐行翻译。
-#[derive(Debug, Default, Clone)]
+/// 用于存储从 `<head>` 中解析的逐行翻译或音译。
+#[derive(Debug, Clone)]
 struct LineTranslation {
+    aux_type: AuxTrackType,
+    lang: Option<String>,
     main: Option<String>,
     background: Option<String>,
 }
@@ -339,7 +393,7 @@ pub fn parse_ttml(
                 &mut warnings,
             )?;
         } else if state.body_state.in_p {
-            handle_p_event(&event, &mut state, &reader, &mut lines, &mut warnings)?;
+            handle_p_event(&event, &mut state, &mut reader, &mut lines, &mut warnings)?;
         } else {
             if let Event::Eof = event {
                 break;
@@ -347,7 +401,7 @@ pub fn parse_ttml(
             handle_global_event(
                 &event,
                 &mut state,
-                &reader,
+                &mut reader,
                 &mut raw_metadata,
                 &mut warnings,
                 has_timed_span_tags,
@@ -358,6 +412,13 @@ pub fn parse_ttml(
         buf.clear();
     }
 
+    resolve_line_styles(
+        &mut lines,
+        &mut state.extensions,
+        &mut state.agent_store,
+        &state.background_styles,
+    );
+
     Ok(ParsedSourceData {
         lines,
         raw_metadata,
@@ -368,6 +429,7 @@ pub fn parse_ttml(
         warnings,
         raw_ttml_from_input: Some(content.to_string()),
         detected_formatted_ttml_input: Some(state.format_detection == FormatDetection::IsFormatted),
+        ttml_extensions: state.extensions,
         ..Default::default()
     })
 }
@@ -402,6 +464,7 @@ fn handle_metadata_event(
                         id: id.clone(),
                         name: None,
                         agent_type,
+                        ..Default::default()
                     };
                     state.agent_store.agents_by_id.insert(id.clone(), agent);
                     meta_state.context = MetadataContext::InAgent { id: Some(id) };
@@ -428,6 +491,13 @@ fn handle_metadata_event(
                 if let Some(key) = key_attr {
                     let value = value_attr.unwrap_or_else(|| text_content.into_owned());
                     if !key.is_empty() {
+                        // 生成器只会写出已知的键，其余的原样保留
+                        if !is_known_amll_meta_key(&key) {
+                            state
+                                .extensions
+                                .metadata_elements
+                                .push(amll_meta_element(e, &key, &value));
+                        }
                         raw_metadata.entry(key).or_default().push(value);
                     }
                 }
@@ -436,6 +506,7 @@ fn handle_metadata_event(
             TAG_SONGWRITER => {
                 if matches!(meta_state.context, MetadataContext::InITunesMetadata) {
                     meta_state.context = MetadataContext::InSongwriter;
+                    meta_state.text_buffer.clear();
                 }
             }
             TAG_TRANSLATIONS => {
@@ -500,31 +571,57 @@ fn handle_metadata_event(
                         end_ms,
                         lang: None,
                         scheme: None,
+                        agent: None,
+                        extra_attributes: Vec::new(),
                     });
                 }
             }
+            name if matches!(meta_state.context, MetadataContext::None) => {
+                if let Some(local_name) = name.strip_prefix(b"ttm:") {
+                    // 规范 §3.1：`<ttm:title>` 视为 musicName，其他 `<ttm:*>` 转换为自定义 AMLL 元数据
+                    let key = if local_name == b"title" {
+                        "musicName".to_string()
+                    } else {
+                        String::from_utf8_lossy(local_name).into_owned()
+                    };
+                    let value = reader.read_text(e.name())?.trim().to_string();
+                    if !value.is_empty() {
+                        if !is_known_amll_meta_key(&key) {
+                            state
+                                .extensions
+                                .metadata_elements
+                                .push(amll_meta_element(e, &key, &value));
+                        }
+                        raw_metadata.entry(key).or_default().push(value);
+                    }
+                } else {
+                    let element = read_xml_element(e, reader)?;
+                    state.extensions.metadata_elements.push(element);
+                }
+            }
             _ => {}
         },
-        Event::Text(e) => {
-            if !meta_state.span_stack.is_empty() {
-                // 处理在 span 内部的文本
-                meta_state.text_buffer.push_str(&e.xml_content()?);
-            } else if matches!(meta_state.context, MetadataContext::InAuxiliaryText { .. }) {
-                // 处理 `<text>` 标签直接子节点中的文本（即主翻译）
-                meta_state
-                    .current_main_plain_text
-                    .push_str(&e.xml_content()?);
-            } else if matches!(meta_state.context, MetadataContext::InSongwriter) {
-                raw_metadata
-                    .entry("songwriters".to_string())
-                    .or_default()
-                    .push(e.xml_content()?.into_owned());
+        Event::Text(e) => push_metadata_text(meta_state, &e.xml_content()?),
+        Event::GeneralRef(e) => {
+            if let Some(text) = resolve_general_ref(e)? {
+                push_metadata_text(meta_state, &text);
             }
         }
         Event::End(e) => match e.name().as_ref() {
             TAG_METADATA => state.in_metadata = false,
             TAG_ITUNES_METADATA => meta_state.context = MetadataContext::None,
-            TAG_SONGWRITER => meta_state.context = MetadataContext::InITunesMetadata,
+            TAG_SONGWRITER => {
+                let songwriter = std::mem::take(&mut meta_state.text_buffer);
+                if matches!(meta_state.context, MetadataContext::InSongwriter)
+                    && !songwriter.trim().is_empty()
+                {
+                    raw_metadata
+                        .entry("songwriters".to_string())
+                        .or_default()
+                        .push(songwriter.trim().to_string());
+                }
+                meta_state.context = MetadataContext::InITunesMetadata;
+            }
             TAG_AGENT | TAG_AGENT_TTM => {
                 meta_state.context = MetadataContext::None;
             }
@@ -595,36 +692,30 @@ fn handle_metadata_event(
                 {
                     let main_plain_text = meta_state.current_main_plain_text.trim();
                     let bg_plain_text = meta_state.current_bg_plain_text.trim();
-                    let has_plain_text = !main_plain_text.is_empty() || !bg_plain_text.is_empty();
 
                     let has_main_syllables = !meta_state.current_main_syllables.is_empty();
                     let has_bg_syllables = !meta_state.current_bg_syllables.is_empty();
 
-                    // 判断是逐行翻译，还是带时间的辅助轨道
-                    if !has_main_syllables
-                        && !has_bg_syllables
-                        && has_plain_text
-                        && matches!(aux_type, AuxTrackType::Translation)
-                    {
-                        // 是逐行翻译，存入 line_translation_map
-                        let line_translation = LineTranslation {
-                            main: if main_plain_text.is_empty() {
-                                None
-                            } else {
-                                Some(main_plain_text.to_string())
-                            },
-                            background: if bg_plain_text.is_empty() {
-                                None
-                            } else {
-                                Some(bg_plain_text.to_string())
-                            },
-                        };
-
+                    // 没有逐字音节的部分作为逐行翻译或音译，存入 line_translation_map
+                    let line_main = (!has_main_syllables && !main_plain_text.is_empty())
+                        .then(|| main_plain_text.to_string());
+                    let line_background = (!has_bg_syllables && !bg_plain_text.is_empty())
+                        .then(|| bg_plain_text.to_string());
+                    if line_main.is_some() || line_background.is_some() {
                         meta_state
                             .line_translation_map
-                            .insert(text_key.clone(), (line_translation, lang.clone()));
-                    } else if has_main_syllables || has_bg_syllables {
-                        // 是带时间戳的辅助轨道，存入 timed_track_map。
+                            .entry(text_key.clone())
+                            .or_default()
+                            .push(LineTranslation {
+                                aux_type: *aux_type,
+                                lang: lang.clone(),
+                                main: line_main,
+                                background: line_background,
+                            });
+                    }
+
+                    // 带时间戳的部分存入 timed_track_map
+                    if has_main_syllables || has_bg_syllables {
                         let entry = meta_state
                             .timed_track_map
                             .entry(text_key.clone())
@@ -685,12 +776,58 @@ fn handle_metadata_event(
     Ok(())
 }
 
+/// 处理 `<metadata>` 中的文本（包括解析后的实体引用）。
+fn push_metadata_text(meta_state: &mut MetadataParseState, text: &str) {
+    if matches!(meta_state.context, MetadataContext::InAuxiliaryText { .. })
+        && meta_state
+            .span_stack
+            .last()
+            .is_none_or(|s| s.start_ms.is_none())
+        // 两个逐字 span 之间不含换行的空白，属于前一个音节
+        && !text.is_empty()
+        && text.trim().is_empty()
+        && !text.contains('\n')
+    {
+        let in_background = meta_state
+            .span_stack
+            .iter()
+            .any(|s| s.role == SpanRole::Background);
+        let target_syllables = if in_background {
+            &mut meta_state.current_bg_syllables
+        } else {
+            &mut meta_state.current_main_syllables
+        };
+        if let Some(last_syllable) = target_syllables.last_mut() {
+            last_syllable.ends_with_space = true;
+            return;
+        }
+    }
+
+    if !meta_state.span_stack.is_empty() {
+        // 处理在 span 内部的文本
+        meta_state.text_buffer.push_str(text);
+    } else if matches!(meta_state.context, MetadataContext::InAuxiliaryText { .. }) {
+        // 处理 `<text>` 标签直接子节点中的文本（即主翻译）
+        meta_state.current_main_plain_text.push_str(text);
+    } else if matches!(meta_state.context, MetadataContext::InSongwriter) {
+        meta_state.text_buffer.push_str(text);
+    }
+}
+
+/// 解析实体引用，无法识别的实体返回 `None`。
+fn resolve_general_ref(e: &BytesRef) -> Result<Option<String>, ConvertError> {
+    Ok(match e.resolve_char_ref()? {
+        Some(c) => Some(c.to_string()),
+        None => resolve_predefined_entity(&e.decode()?).map(str::to_string),
+    })
+}
+
 /// 处理全局事件（在 `<p>` 或 `<metadata>` 之外的事件）。
 /// 主要负责识别文档的根元素、body、div 和 p 的开始，并相应地更新状态。
 fn handle_global_event(
     event: &Event<'_>,
     state: &mut TtmlParserState,
-    reader: &Reader<&[u8]>,
+    reader: &mut Reader<&[u8]>,
     raw_metadata: &mut HashMap<String, Vec<String>>,
     warnings: &mut Vec<String>,
     has_timed_span_tags: bool,
@@ -707,9 +844,18 @@ fn handle_global_event(
                 warnings,
                 options,
             )?,
+            TAG_HEAD => state.in_head = true,
             TAG_METADATA => state.in_metadata = true,
+            _ if state.in_head => {
+                let element = read_xml_element(e, reader)?;
+                state.extensions.head_elements.push(element);
+            }
 
-            TAG_BODY => state.body_state.in_body = true,
+            TAG_BODY => {
+                state.body_state.in_body = true;
+                state.extensions.body_attributes =
+                    collect_unknown_attributes(e, reader, &[ATTR_DUR])?;
+            }
             TAG_DIV if state.body_state.in_body => {
                 state.body_state.in_div = true;
                 // 获取 song-part
@@ -753,6 +899,7 @@ fn handle_global_event(
                                 id: new_id.clone(),
                                 name: Some(val),
                                 agent_type: AgentType::Person, // 默认为 Person
+                                ..Default::default()
                             };
                             state
                                 .agent_store
@@ -767,6 +914,7 @@ fn handle_global_event(
                 let song_part = get_string_attribute(e, reader, &[ATTR_ITUNES_SONG_PART])?
                     .or(state.body_state.current_div_song_part.clone());
                 let itunes_key = get_string_attribute(e, reader, &[ATTR_ITUNES_KEY])?;
+                let extra_attributes = collect_unknown_attributes(e, reader, KNOWN_P_ATTRS)?;
 
                 state.body_state.current_p_element_data = Some(CurrentPElementData {
                     start_ms,
@@ -774,6 +922,7 @@ fn handle_global_event(
                     agent: final_agent_id,
                     song_part,
                     itunes_key,
+                    extra_attributes,
                     ..Default::default()
                 });
 
@@ -783,6 +932,7 @@ fn handle_global_event(
             _ => {}
         },
         Event::End(e) => match e.local_name().as_ref() {
+            TAG_HEAD => state.in_head = false,
             TAG_DIV if state.body_state.in_div => {
                 state.body_state.in_div = false;
                 state.body_state.current_div_song_part = None; // 离开 div 时清除
@@ -803,52 +953,10 @@ fn handle_p_end(
 ) {
     if let Some(mut p_data) = state.body_state.current_p_element_data.take() {
         if let Some(key) = &p_data.itunes_key {
-            // 回填逐行翻译
-            if let Some((line_translation, lang)) =
-                state.metadata_state.line_translation_map.get(key)
-            {
-                // 处理主音轨翻译
-                if let Some(main_text) = &line_translation.main
-                    && let Some(main_annotated_track) = p_data
-                        .tracks_accumulator
-                        .iter_mut()
-                        .find(|at| at.content_type == ContentType::Main)
-                {
-                    // 检查是否已存在具有相同文本的翻译轨道
-                    let translation_exists =
-                        main_annotated_track.translations.iter().any(|track| {
-                            track
-                                .words
-                                .iter()
-                                .flat_map(|w| &w.syllables)
-                                .any(|s| s.text == *main_text)
-                        });
-
-                    if !translation_exists {
-                        let translation_track =
-                            create_simple_translation_track(main_text, lang.as_ref());
-                        main_annotated_track.translations.push(translation_track);
-                    }
-                }
-
-                // 处理背景人声音轨的翻译
-                if let Some(bg_text) = &line_translation.background {
-                    let bg_annotated_track =
-                        get_or_create_target_annotated_track(&mut p_data, ContentType::Background);
-
-                    let translation_exists = bg_annotated_track.translations.iter().any(|track| {
-                        track
-                            .words
-                            .iter()
-                            .flat_map(|w| &w.syllables)
-                            .any(|s| s.text == *bg_text)
-                    });
-
-                    if !translation_exists {
-                        let translation_track =
-                            create_simple_translation_track(bg_text, lang.as_ref());
-                        bg_annotated_track.translations.push(translation_track);
-                    }
+            // 回填逐行翻译和音译
+            if let Some(line_translations) = state.metadata_state.line_translation_map.get(key) {
+                for line_translation in line_translations {
+                    backfill_line_translation(&mut p_data, line_translation);
                 }
             }
         }
@@ -864,7 +972,7 @@ fn handle_p_end(
 fn handle_p_event(
     event: &Event<'_>,
     state: &mut TtmlParserState,
-    reader: &Reader<&[u8]>,
+    reader: &mut Reader<&[u8]>,
     lines: &mut Vec<LyricLine>,
     warnings: &mut Vec<String>,
 ) -> Result<(), ConvertError> {
@@ -872,6 +980,12 @@ fn handle_p_event(
         Event::Start(e) if e.local_name().as_ref() == TAG_SPAN => {
             process_span_start(e, state, reader, warnings)?;
         }
+        Event::Start(e) if e.local_name().as_ref() != TAG_BR => {
+            let element = read_xml_element(e, reader)?;
+            if let Some(p_data) = state.body_state.current_p_element_data.as_mut() {
+                p_data.extra_elements.push(element);
+            }
+        }
         Event::Text(e) => process_text_event(e, state)?,
         Event::GeneralRef(e) => {
             let entity_name = str::from_utf8(e.as_ref())
@@ -963,7 +1077,7 @@ fn process_tt_start(
     } else {
         let timing_attr = e.try_get_attribute(ATTR_ITUNES_TIMING)?;
         if let Some(attr) = timing_attr {
-            if attr.value.as_ref() == b"line" {
+            if attr.value.eq_ignore_ascii_case(b"line") {
                 state.is_line_timing_mode = true;
             }
         } else if !has_timed_span_tags {
@@ -976,6 +1090,8 @@ fn process_tt_start(
         }
     }
 
+    state.extensions.root_attributes = collect_unknown_attributes(e, reader, KNOWN_TT_ATTRS)?;
+
     // 获取 xml:lang 属性
     if let Some(attr) = e.try_get_attribute(ATTR_XML_LANG)? {
         let lang_val = attr.decode_and_unescape_value(reader.decoder())?;
@@ -1018,16 +1134,34 @@ fn process_span_start(
 
     let lang = get_string_attribute(e, reader, &[ATTR_XML_LANG])?;
     let scheme = get_string_attribute(e, reader, &[ATTR_XML_SCHEME])?;
+    let agent = get_string_attribute(e, reader, &[ATTR_AGENT, ATTR_AGENT_ALIAS])?;
     let start_ms = get_time_attribute(e, reader, &[ATTR_BEGIN], warnings)?;
     let end_ms = get_time_attribute(e, reader, &[ATTR_END], warnings)?;
+    let extra_attributes = collect_unknown_attributes(e, reader, KNOWN_SPAN_ATTRS)?;
+
+    // 背景人声容器通过 `style` 引用的样式决定其显示位置
+    if role == SpanRole::Background
+        && let Some((_, style_ids)) = extra_attributes.iter().find(|(k, _)| k == "style")
+        && let Some(side) = style_view_side(&state.extensions.head_elements, style_ids)
+        && let Some(p_data) = state.body_state.current_p_element_data.as_mut()
+    {
+        p_data.background_view_side = Some(side);
+        for style_id in style_ids.split_whitespace() {
+            if !state.background_styles.iter().any(|s| s == style_id) {
+                state.background_styles.push(style_id.to_string());
+            }
+        }
+    }
 
     // 将解析出的上下文压入堆栈，以支持嵌套 span
     state.body_state.span_stack.push(SpanContext {
         role,
         lang,
         scheme,
+        agent,
         start_ms,
         end_ms,
+        extra_attributes,
     });
 
     // 如果是背景人声容器的开始，则初始化背景数据累加器
@@ -1178,6 +1312,21 @@ fn handle_generic_span_end(
             .iter()
             .any(|s| s.role == SpanRole::Background);
 
+        // 音节的演唱者继承自最近的带有 `ttm:agent` 的 span，与行的演唱者相同时不单独记录
+        let syllable_agent = ctx
+            .agent
+            .as_ref()
+            .or_else(|| {
+                state
+                    .body_state
+                    .span_stack
+                    .iter()
+                    .rev()
+                    .find_map(|s| s.agent.as_ref())
+            })
+            .filter(|agent| p_data.agent.as_ref() != Some(*agent))
+            .cloned();
+
         let target_content_type = if was_within_bg {
             ContentType::Background
         } else {
@@ -1193,6 +1342,7 @@ fn handle_generic_span_end(
         }
         let target_word = target_content_track.words.first_mut().unwrap();
 
+        let syllable_count = target_word.syllables.len();
         process_syllable(
             start_ms,
             end_ms.max(start_ms),
@@ -1201,6 +1351,12 @@ fn handle_generic_span_end(
             &mut state.text_processing_buffer,
             &mut target_word.syllables,
         );
+        if target_word.syllables.len() > syllable_count
+            && let Some(syllable) = target_word.syllables.last_mut()
+        {
+            syllable.extra_attributes.clone_from(&ctx.extra_attributes);
+            syllable.agent = syllable_agent;
+        }
 
         if !target_word.syllables.is_empty() {
             state.body_state.last_syllable_info = LastSyllableInfo::EndedSyllable {
@@ -1267,6 +1423,7 @@ fn process_syllable(
         end_ms,
         duration_ms: Some(end_ms.saturating_sub(start_ms)),
         ends_with_space: raw_text.ends_with(char::is_whitespace),
+        ..Default::default()
     };
 
     syllable_accumulator.push(new_syllable);
@@ -1399,6 +1556,7 @@ fn handle_background_span_end(
                         end_ms: end_ms.max(start_ms),
                         duration_ms: Some(end_ms.saturating_sub(start_ms)),
                         ends_with_space: !text.is_empty() && text.ends_with(char::is_whitespace),
+                        ..Default::default()
                     };
 
                     if bg_content_track.words.is_empty() {
@@ -1522,6 +1680,10 @@ fn finalize_p_element(
         song_part: p_data.song_part,
         tracks: p_data.tracks_accumulator,
         itunes_key: p_data.itunes_key.clone(),
+        view_side: None,
+        background_view_side: p_data.background_view_side,
+        extra_attributes: p_data.extra_attributes,
+        extra_elements: p_data.extra_elements,
     };
 
     // 重新计算行的结束时间，应为所有轨道中所有音节的最大结束时间
@@ -1559,12 +1721,219 @@ fn finalize_p_element(
     }
 }
 
+/// 元素上指定属性的值。
+fn attribute<'a>(element: &'a XmlElement, key: &str) -> Option<&'a str> {
+    element
+        .attributes
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+/// `<head>` 中某个容器（如 `<styling>`）下指定名称的子元素。
+fn children_named<'a>(
+    head_elements: &'a [XmlElement],
+    container: &'a str,
+    name: &'a str,
+) -> impl Iterator<Item = &'a XmlElement> {
+    head_elements
+        .iter()
+        .filter(move |e| e.name.rsplit(':').next() == Some(container))
+        .flat_map(|e| &e.children)
+        .filter_map(move |child| match child {
+            XmlNode::Element(child) if child.name.rsplit(':').next() == Some(name) => Some(child),
+            _ => None,
+        })
+}
+
+/// 元素是否没有子节点，并且只带有 `allowed` 中的属性。
+fn only_has_attributes(element: &XmlElement, allowed: &[&str]) -> bool {
+    element.children.is_empty()
+        && element
+            .attributes
+            .iter()
+            .all(|(k, _)| allowed.contains(&k.as_str()))
+}
+
+/// 从 `style` 属性引用的 `<style>` 中取出显示位置，多个样式时后面的优先。
+fn style_view_side(head_elements: &[XmlElement], style_ids: &str) -> Option<ViewSide> {
+    style_ids.split_whitespace().rev().find_map(|style_id| {
+        children_named(head_elements, "styling", "style")
+            .find(|style| attribute(style, "xml:id") == Some(style_id))
+            .and_then(|style| attribute(style, "tts:textAlign"))
+            .and_then(ViewSide::from_text_align)
+    })
+}
+
+/// 根据 `<p>` 的 `region` 和 `style` 属性解析行的显示位置和演唱者的颜色。
+///
+/// 区域的对齐方式取自它自身的 `tts:textAlign`，或者它通过 `style` 引用的 `<style>`。
+/// 只描述对齐方式的区域和样式会从保留的 `<head>` 内容中移除，行上的 `region` 属性也一并去掉，
+/// 生成 TTML 时再根据 `view_side` 重新写出；带有其他样式或布局信息的区域则原样保留。
+/// 背景人声 `<span>` 引用的样式（`background_styles`）按同样的规则处理。
+///
+/// `<p>` 通过 `style` 引用的、只设置 `tts:color` 的样式视为该行演唱者的颜色。
+/// 其中由本库生成的样式（`s-agent-<ID>`）同样会被移除，生成 TTML 时再根据 `Agent::color`
+/// 重新写出；其他样式则原样保留。
+fn resolve_line_styles(
+    lines: &mut [LyricLine],
+    extensions: &mut TtmlExtensions,
+    agent_store: &mut AgentStore,
+    background_styles: &[String],
+) {
+    let head = &extensions.head_elements;
+    let styles: HashMap<&str, &XmlElement> = children_named(head, "styling", "style")
+        .filter_map(|style| Some((attribute(style, "xml:id")?, style)))
+        .collect();
+
+    // 区域 ID -> (显示位置, 是否只描述对齐方式)
+    let mut regions: HashMap<String, (ViewSide, bool)> = HashMap::new();
+    for region in children_named(head, "layout", "region") {
+        let Some(id) = attribute(region, "xml:id") else {
+            continue;
+        };
+        let referenced_styles: Vec<&XmlElement> = attribute(region, "style")
+            .into_iter()
+            .flat_map(str::split_whitespace)
+            .filter_map(|style_id| styles.get(style_id).copied())
+            .collect();
+        let side = attribute(region, "tts:textAlign")
+            .and_then(ViewSide::from_text_align)
+            .or_else(|| {
+                referenced_styles.iter().rev().find_map(|style| {
+                    attribute(style, "tts:textAlign").and_then(ViewSide::from_text_align)
+                })
+            });
+        let style_count = attribute(region, "style").map_or(0, |v| v.split_whitespace().count());
+        let is_alignment_only = only_has_attributes(region, &["xml:id", "tts:textAlign", "style"])
+            && referenced_styles.len() == style_count
+            && referenced_styles
+                .iter()
+                .all(|style| only_has_attributes(style, &["xml:id", "tts:textAlign"]));
+        if let Some(side) = side {
+            regions.insert(id.to_string(), (side, is_alignment_only));
+        }
+    }
+
+    let mut removed_regions: Vec<String> = Vec::new();
+    for line in lines.iter_mut() {
+        let Some(index) = line
+            .extra_attributes
+            .iter()
+            .position(|(k, _)| k == "region")
+        else {
+            continue;
+        };
+        let Some(&(side, is_alignment_only)) = regions.get(&line.extra_attributes[index].1) else {
+            continue;
+        };
+        line.view_side = Some(side);
+        if is_alignment_only {
+            let (_, region) = line.extra_attributes.remove(index);
+            if !removed_regions.contains(&region) {
+                removed_regions.push(region);
+            }
+        }
+    }
+
+    let mut color_styles: Vec<String> = Vec::new();
+    for line in lines.iter_mut() {
+        let (Some(agent_id), Some(index)) = (
+            line.agent.as_deref(),
+            line.extra_attributes.iter().position(|(k, _)| k == "style"),
+        ) else {
+            continue;
+        };
+        let style_id = line.extra_attributes[index].1.trim().to_string();
+        let Some(color) = styles
+            .get(style_id.as_str())
+            .filter(|style| only_has_attributes(style, &["xml:id", "tts:color"]))
+            .and_then(|style| attribute(style, "tts:color"))
+        else {
+            continue;
+        };
+        let agent = agent_store
+            .agents_by_id
+            .entry(agent_id.to_string())
+            .or_insert_with(|| Agent::new(agent_id));
+        // 同一演唱者的行引用了不同颜色时，保留后出现的 `style` 属性
+        if agent.color.as_deref().is_some_and(|c| c != color) {
+            continue;
+        }
+        agent.color = Some(color.to_string());
+        if style_id == format!("{AGENT_COLOR_STYLE_PREFIX}{agent_id}") {
+            line.extra_attributes.remove(index);
+            if !color_styles.contains(&style_id) {
+                color_styles.push(style_id);
+            }
+        }
+    }
+
+    if removed_regions.is_empty() && background_styles.is_empty() && color_styles.is_empty() {
+        return;
+    }
+
+    let is_removed_region = |e: &XmlElement| {
+        e.name.rsplit(':').next() == Some("region")
+            && attribute(e, "xml:id").is_some_and(|id| removed_regions.iter().any(|r| r == id))
+    };
+
+    // 被移除的区域引用、且没有被其他内容引用的样式也一并移除
+    let mut removable_styles: Vec<String> = children_named(head, "layout", "region")
+        .filter(|region| is_removed_region(region))
+        .filter_map(|region| attribute(region, "style"))
+        .flat_map(str::split_whitespace)
+        .map(str::to_string)
+        .collect();
+    removable_styles.extend(
+        background_styles
+            .iter()
+            .filter(|id| {
+                styles
+                    .get(id.as_str())
+                    .is_some_and(|style| only_has_attributes(style, &["xml:id", "tts:textAlign"]))
+            })
+            .cloned(),
+    );
+    removable_styles.extend(color_styles);
+    let still_referenced: Vec<&str> = children_named(head, "layout", "region")
+        .filter(|region| !is_removed_region(region))
+        .filter_map(|region| attribute(region, "style"))
+        .chain(
+            lines
+                .iter()
+                .flat_map(|line| &line.extra_attributes)
+                .filter(|(k, _)| k == "style")
+                .map(|(_, v)| v.as_str()),
+        )
+        .flat_map(str::split_whitespace)
+        .collect();
+    removable_styles.retain(|id| !still_referenced.contains(&id.as_str()));
+
+    for container in &mut extensions.head_elements {
+        match container.name.rsplit(':').next() {
+            Some("layout") => container.children.retain(
+                |node| !matches!(node, XmlNode::Element(e) if is_removed_region(e)),
+            ),
+            Some("styling") => container.children.retain(|node| {
+                !matches!(node, XmlNode::Element(e)
+                    if attribute(e, "xml:id").is_some_and(|id| removable_styles.iter().any(|s| s == id)))
+            }),
+            _ => {}
+        }
+    }
+    extensions.head_elements.retain(|e| {
+        !matches!(e.name.rsplit(':').next(), Some("layout" | "styling"))
+            || e.children.iter().any(|c| matches!(c, XmlNode::Element(_)))
+    });
+}
+
 // =================================================================================
 // 7. 工具函数
 // =================================================================================
 
 /// 解析 TTML 时间字符串到毫秒。
-fn parse_ttml_time_to_ms(time_str: &str) -> Result<u64, ConvertError> {
+pub(crate) fn parse_ttml_time_to_ms(time_str: &str) -> Result<u64, ConvertError> {
     // 解析毫秒部分（.1, .12, .123）
     fn parse_decimal_ms_part(ms_str: &str, original_time_str: &str) -> Result<u64, ConvertError> {
         if ms_str.is_empty() || ms_str.len() > 3 || ms_str.chars().any(|c| !c.is_ascii_digit()) {
@@ -1704,6 +2073,118 @@ fn clean_parentheses_from_bg_text_into(text: &str, output: &mut String) {
     output.push_str(trimmed);
 }
 
+/// 判断一个 `<amll:meta>` 键是否会由生成器根据元数据写出。
+fn is_known_amll_meta_key(key: &str) -> bool {
+    AMLL_META_KEYS.iter().any(|(name, _)| *name == key)
+}
+
+/// 构造一个需要原样保留的 `<amll:meta>` 元素。
+///
+/// 元素名沿用原文件中的写法（`meta` 或 `amll:meta`），`<ttm:*>` 则统一转换为 `amll:meta`。
+fn amll_meta_element(e: &BytesStart, key: &str, value: &str) -> XmlElement {
+    let name = if e.name().as_ref() == TAG_META {
+        "meta"
+    } else {
+        "amll:meta"
+    };
+    XmlElement {
+        name: name.to_string(),
+        attributes: vec![
+            ("key".to_string(), key.to_string()),
+            ("value".to_string(), value.to_string()),
+        ],
+        children: Vec::new(),
+    }
+}
+
+/// 收集元素上不在 `known_attrs` 中的属性，保持原始顺序。
+fn collect_unknown_attributes(
+    e: &BytesStart,
+    reader: &Reader<&[u8]>,
+    known_attrs: &[&[u8]],
+) -> Result<Vec<(String, String)>, ConvertError> {
+    let mut attributes = Vec::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        if known_attrs.contains(&attr.key.as_ref()) {
+            continue;
+        }
+        let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+        let value = attr
+            .decode_and_unescape_value(reader.decoder())?
+            .into_owned();
+        attributes.push((key, value));
+    }
+    Ok(attributes)
+}
+
+/// 读取一个完整的元素子树，用于原样保留未识别的元素。调用时 `start` 对应的开始事件已被读取。
+///
+/// 只包含换行的空白文本被视为格式化缩进，不会被保留。
+fn read_xml_element(
+    start: &BytesStart,
+    reader: &mut Reader<&[u8]>,
+) -> Result<XmlElement, ConvertError> {
+    fn push_text(element: &mut XmlElement, text: &str) {
+        if let Some(XmlNode::Text(last)) = element.children.last_mut() {
+            last.push_str(text);
+        } else {
+            element.children.push(XmlNode::Text(text.to_string()));
+        }
+    }
+
+    let new_element =
+        |e: &BytesStart, reader: &Reader<&[u8]>| -> Result<XmlElement, ConvertError> {
+            Ok(XmlElement {
+                name: String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+                attributes: collect_unknown_attributes(e, reader, &[])?,
+                children: Vec::new(),
+            })
+        };
+
+    let mut stack = vec![new_element(start, reader)?];
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                let element = new_element(&e, reader)?;
+                stack.push(element);
+            }
+            Event::End(_) => {
+                let mut finished = stack.pop().unwrap_or_default();
+                finished.children.retain(|node| {
+                    !matches!(node, XmlNode::Text(text) if text.contains('\n') && text.trim().is_empty())
+                });
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(XmlNode::Element(finished)),
+                    None => return Ok(finished),
+                }
+            }
+            Event::Text(e) => {
+                if let Some(current) = stack.last_mut() {
+                    push_text(current, &e.xml_content()?);
+                }
+            }
+            Event::CData(e) => {
+                if let Some(current) = stack.last_mut() {
+                    push_text(current, &String::from_utf8_lossy(&e));
+                }
+            }
+            Event::GeneralRef(e) => {
+                if let (Some(text), Some(current)) = (resolve_general_ref(&e)?, stack.last_mut()) {
+                    push_text(current, &text);
+                }
+            }
+            Event::Eof => {
+                return Err(ConvertError::Internal(format!(
+                    "元素 <{}> 未闭合",
+                    stack.first().map_or("", |e| e.name.as_str())
+                )));
+            }
+            _ => {}
+        }
+    }
+}
+
 /// 从给定的属性名列表中获取第一个找到的属性，并将其转换为目标类型。
 ///
 /// # 参数
@@ -1834,6 +2315,70 @@ fn get_or_create_target_annotated_track(
     }
 }
 
+/// 将 `<head>` 中的一条逐行翻译或音译回填到当前行，已存在相同文本的轨道时跳过。
+fn backfill_line_translation(p_data: &mut CurrentPElementData, line_translation: &LineTranslation) {
+    fn push_if_absent(
+        annotated_track: &mut AnnotatedTrack,
+        aux_type: AuxTrackType,
+        text: &str,
+        lang: Option<&String>,
+    ) {
+        let tracks = match aux_type {
+            AuxTrackType::Translation => &mut annotated_track.translations,
+            AuxTrackType::Romanization => &mut annotated_track.romanizations,
+        };
+        let exists = tracks.iter().any(|track| {
+            track
+                .words
+                .iter()
+                .flat_map(|w| &w.syllables)
+                .any(|s| s.text == text)
+        });
+        if !exists {
+            tracks.push(create_simple_translation_track(text, lang));
+        }
+    }
+
+    let lang = line_translation.lang.as_ref();
+
+    if let Some(main_text) = &line_translation.main {
+        // 逐行模式下主音轨要到 finalize_p_element 才会创建，这里先插入一个空的主音轨
+        if !p_data.line_text_accumulator.trim().is_empty()
+            && !p_data
+                .tracks_accumulator
+                .iter()
+                .any(|at| at.content_type == ContentType::Main)
+        {
+            p_data.tracks_accumulator.insert(
+                0,
+                AnnotatedTrack {
+                    content_type: ContentType::Main,
+                    ..Default::default()
+                },
+            );
+        }
+
+        if let Some(main_annotated_track) = p_data
+            .tracks_accumulator
+            .iter_mut()
+            .find(|at| at.content_type == ContentType::Main)
+        {
+            push_if_absent(
+                main_annotated_track,
+                line_translation.aux_type,
+                main_text,
+                lang,
+            );
+        }
+    }
+
+    if let Some(bg_text) = &line_translation.background {
+        let bg_annotated_track =
+            get_or_create_target_annotated_track(p_data, ContentType::Background);
+        push_if_absent(bg_annotated_track, line_translation.aux_type, bg_text, lang);
+    }
+}
+
 fn create_simple_translation_track(text: &str, lang: Option<&String>) -> LyricTrack {
     let syllable = LyricSyllable {
         text: text.to_string(),
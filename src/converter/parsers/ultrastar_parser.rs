@@ -0,0 +1,363 @@
+//! # `UltraStar` TXT 格式解析器
+//!
+//! `UltraStar` 是 K 歌游戏使用的纯文本格式。文件由 `#KEY:VALUE` 头部和音符行组成：
+//! `<类型> <开始拍> <拍数> <音高> <文本>`。`-` 行表示换行，`E` 表示文件结束。
+//! 合唱文件使用 `P1`、`P2`、`P3` 切换演唱者，分别映射为 `v1`、`v2` 和合唱 `v1000`。
+
+use std::collections::HashMap;
+
+use crate::converter::types::{
+    Agent, AgentStore, AgentType, AnnotatedTrack, ContentType, ConvertError, LyricFormat,
+    LyricLine, LyricSyllable, LyricTrack, NoteType, ParsedSourceData, Word,
+};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// 匹配音符行 `<类型> <开始拍> <拍数> <音高> <文本>`。
+/// 音高后只有一个分隔空格，文本可以以空格开头（表示与前一个音符之间有词间空格）。
+static ULTRASTAR_NOTE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<type>[:*FRG])\s+(?P<start>-?\d+)\s+(?P<length>\d+)\s+(?P<pitch>-?\d+)(?: (?P<text>.*))?$",
+    )
+    .expect("编译 ULTRASTAR_NOTE_REGEX 失败")
+});
+
+/// 匹配换行行 `- <拍> [<拍>]`。
+static ULTRASTAR_BREAK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^-\s*(?P<first>-?\d+)(?:\s+(?P<second>-?\d+))?")
+        .expect("编译 ULTRASTAR_BREAK_REGEX 失败")
+});
+
+/// 合唱时两位演唱者同时演唱的 Agent ID。
+const DUET_BOTH_AGENT_ID: &str = "v1000";
+
+/// 将 `UltraStar` 的音符类型标记转换为 `NoteType`。
+fn parse_note_type(marker: &str) -> NoteType {
+    match marker {
+        "*" => NoteType::Golden,
+        "F" => NoteType::Freestyle,
+        "R" => NoteType::Rap,
+        "G" => NoteType::RapGolden,
+        _ => NoteType::Normal,
+    }
+}
+
+/// 解析 `UltraStar` 中的小数。部分文件使用逗号作为小数点，例如 `#BPM:300,5`。
+pub(crate) fn parse_ultrastar_number(value: &str) -> Option<f64> {
+    value.trim().replace(',', ".").parse().ok()
+}
+
+/// 解析时的节拍换算参数。
+struct BeatClock {
+    /// `UltraStar` 的 BPM（每拍为四分之一个“四分音符”）
+    bpm: f64,
+    /// 第一拍相对于音频开始的偏移（毫秒）
+    gap_ms: f64,
+}
+
+impl BeatClock {
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    fn beat_to_ms(&self, beat: i64) -> u64 {
+        let ms = self.gap_ms + beat as f64 * 15_000.0 / self.bpm;
+        ms.round().max(0.0) as u64
+    }
+}
+
+/// 解析 `UltraStar` TXT 格式内容到 `ParsedSourceData` 结构。
+pub fn parse_ultrastar(content: &str) -> Result<ParsedSourceData, ConvertError> {
+    let mut raw_metadata: HashMap<String, Vec<String>> = HashMap::new();
+    let mut warnings: Vec<String> = Vec::new();
+    let mut lines: Vec<LyricLine> = Vec::new();
+    let mut clock: Option<BeatClock> = None;
+    let mut gap_ms = 0.0;
+    let mut relative = false;
+    let mut relative_offset: i64 = 0;
+    let mut player_names: [Option<String>; 2] = [None, None];
+    let mut current_agent: Option<&'static str> = None;
+    let mut is_duet = false;
+    let mut current_syllables: Vec<LyricSyllable> = Vec::new();
+
+    let mut flush_line = |syllables: &mut Vec<LyricSyllable>, agent: Option<&str>| {
+        if syllables.is_empty() {
+            return;
+        }
+        if let Some(last) = syllables.last_mut() {
+            last.ends_with_space = false;
+        }
+        let syllables = std::mem::take(syllables);
+        let start_ms = syllables.first().map_or(0, |s| s.start_ms);
+        let end_ms = syllables.iter().map(|s| s.end_ms).max().unwrap_or(start_ms);
+        lines.push(LyricLine {
+            tracks: vec![AnnotatedTrack {
+                content_type: ContentType::Main,
+                content: LyricTrack {
+                    words: vec![Word {
+                        syllables,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            start_ms,
+            end_ms,
+            agent: agent.map(str::to_string),
+            ..Default::default()
+        });
+    };
+
+    for (i, line_str) in content.lines().enumerate() {
+        let line_num = i + 1;
+        let line = line_str.trim_end_matches(['\r', '\n']);
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = trimmed.strip_prefix('#') {
+            let Some((key, value)) = header.split_once(':') else {
+                warnings.push(format!("第 {line_num} 行: 无法识别的头部 '{trimmed}'"));
+                continue;
+            };
+            let key = key.trim().to_uppercase();
+            let value = value.trim();
+            match key.as_str() {
+                "BPM" => {
+                    let Some(bpm) = parse_ultrastar_number(value).filter(|b| *b > 0.0) else {
+                        return Err(ConvertError::InvalidLyricFormat(format!(
+                            "第 {line_num} 行: 无效的 BPM '{value}'"
+                        )));
+                    };
+                    clock = Some(BeatClock { bpm, gap_ms });
+                }
+                "GAP" => {
+                    gap_ms = parse_ultrastar_number(value).unwrap_or_else(|| {
+                        warnings.push(format!("第 {line_num} 行: 无效的 GAP '{value}'"));
+                        0.0
+                    });
+                    if let Some(clock) = clock.as_mut() {
+                        clock.gap_ms = gap_ms;
+                    }
+                }
+                "RELATIVE" => relative = value.eq_ignore_ascii_case("yes"),
+                "P1" | "DUETSINGERP1" => player_names[0] = Some(value.to_string()),
+                "P2" | "DUETSINGERP2" => player_names[1] = Some(value.to_string()),
+                _ => {}
+            }
+            let metadata_key = match key.as_str() {
+                "TITLE" => "title".to_string(),
+                "ARTIST" => "artist".to_string(),
+                "LANGUAGE" => "language".to_string(),
+                "P1" | "P2" | "DUETSINGERP1" | "DUETSINGERP2" | "RELATIVE" => continue,
+                _ => key.to_lowercase(),
+            };
+            if !value.is_empty() {
+                raw_metadata
+                    .entry(metadata_key)
+                    .or_default()
+                    .push(value.to_string());
+            }
+            continue;
+        }
+
+        if trimmed == "E" {
+            break;
+        }
+
+        if let Some(player) = trimmed.strip_prefix('P')
+            && let Ok(player) = player.trim().parse::<u8>()
+        {
+            flush_line(&mut current_syllables, current_agent);
+            is_duet = true;
+            relative_offset = 0;
+            current_agent = match player {
+                1 => Some("v1"),
+                2 => Some("v2"),
+                _ => Some(DUET_BOTH_AGENT_ID),
+            };
+            continue;
+        }
+
+        let Some(clock) = clock.as_ref() else {
+            return Err(ConvertError::InvalidLyricFormat(
+                "UltraStar 文件在音符之前缺少 #BPM 头部".to_string(),
+            ));
+        };
+
+        if let Some(caps) = ULTRASTAR_BREAK_REGEX.captures(trimmed) {
+            flush_line(&mut current_syllables, current_agent);
+            if relative {
+                // 相对模式下，换行行的最后一个数字是下一行的起始偏移
+                let shift = caps
+                    .name("second")
+                    .or_else(|| caps.name("first"))
+                    .map_or(Ok(0), |m| m.as_str().parse::<i64>())?;
+                relative_offset += shift;
+            }
+            continue;
+        }
+
+        if let Some(caps) = ULTRASTAR_NOTE_REGEX.captures(line.trim_start()) {
+            let start_beat: i64 = caps["start"].parse::<i64>()? + relative_offset;
+            let length: i64 = caps["length"].parse()?;
+            let pitch: i32 = caps["pitch"].parse()?;
+            let raw_text = caps.name("text").map_or("", |m| m.as_str());
+
+            if raw_text.starts_with(' ')
+                && let Some(last) = current_syllables.last_mut()
+            {
+                last.ends_with_space = true;
+            }
+            let text = raw_text.trim();
+            if text.is_empty() {
+                continue;
+            }
+
+            let start_ms = clock.beat_to_ms(start_beat);
+            let end_ms = clock.beat_to_ms(start_beat + length).max(start_ms);
+            current_syllables.push(LyricSyllable {
+                text: text.to_string(),
+                start_ms,
+                end_ms,
+                duration_ms: Some(end_ms - start_ms),
+                ends_with_space: raw_text.ends_with(' '),
+                pitch: Some(pitch),
+                note_type: Some(parse_note_type(&caps["type"])),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        warnings.push(format!("第 {line_num} 行: 无法识别的内容 '{trimmed}'"));
+    }
+    flush_line(&mut current_syllables, current_agent);
+
+    // 合唱文件中两位演唱者的行是分开书写的，需要按时间重新排序
+    lines.sort_by_key(|l| l.start_ms);
+
+    let mut agents = AgentStore::default();
+    if is_duet {
+        for (id, name) in ["v1", "v2"].into_iter().zip(player_names) {
+            agents.agents_by_id.insert(
+                id.to_string(),
+                Agent {
+                    id: id.to_string(),
+                    name,
+                    agent_type: AgentType::Person,
+                    ..Default::default()
+                },
+            );
+        }
+        if lines
+            .iter()
+            .any(|l| l.agent.as_deref() == Some(DUET_BOTH_AGENT_ID))
+        {
+            agents.agents_by_id.insert(
+                DUET_BOTH_AGENT_ID.to_string(),
+                Agent {
+                    id: DUET_BOTH_AGENT_ID.to_string(),
+                    name: None,
+                    agent_type: AgentType::Group,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    Ok(ParsedSourceData {
+        lines,
+        raw_metadata,
+        warnings,
+        agents,
+        source_format: LyricFormat::UltraStar,
+        is_line_timed_source: false,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ultrastar_notes_and_headers() {
+        let content = "\
+#TITLE:Song
+#ARTIST:Singer
+#BPM:300
+#GAP:1000
+: 0 4 0 Hel
+* 4 4 2 lo
+F 8 2 -3  world
+- 12
+R 14 4 5 Next
+E
+: 100 4 0 ignored";
+        let parsed = parse_ultrastar(content).unwrap();
+
+        assert_eq!(parsed.raw_metadata["title"], vec!["Song"]);
+        assert_eq!(parsed.raw_metadata["bpm"], vec!["300"]);
+        assert_eq!(parsed.lines.len(), 2);
+
+        // BPM 300 时每拍为 50ms
+        let syllables = &parsed.lines[0].tracks[0].content.words[0].syllables;
+        assert_eq!(syllables.len(), 3);
+        assert_eq!(syllables[0].start_ms, 1000);
+        assert_eq!(syllables[0].end_ms, 1200);
+        assert_eq!(syllables[1].note_type, Some(NoteType::Golden));
+        assert!(syllables[1].ends_with_space);
+        assert_eq!(syllables[2].text, "world");
+        assert_eq!(syllables[2].pitch, Some(-3));
+        assert_eq!(syllables[2].note_type, Some(NoteType::Freestyle));
+        assert_eq!(parsed.lines[0].main_text(), Some("Hello world".to_string()));
+
+        let next = &parsed.lines[1].tracks[0].content.words[0].syllables[0];
+        assert_eq!(next.start_ms, 1700);
+        assert_eq!(next.note_type, Some(NoteType::Rap));
+    }
+
+    #[test]
+    fn test_parse_ultrastar_duet_and_relative() {
+        let content = "\
+#BPM:300
+#RELATIVE:yes
+#P1:Alice
+#P2:Bob
+P1
+: 0 4 0 A
+- 6 10
+: 0 4 0 B
+P2
+: 4 4 0 C
+P3
+: 20 2 0 D
+E";
+        let parsed = parse_ultrastar(content).unwrap();
+        let summary: Vec<(String, u64, Option<String>)> = parsed
+            .lines
+            .iter()
+            .map(|l| (l.main_text().unwrap(), l.start_ms, l.agent.clone()))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("A".to_string(), 0, Some("v1".to_string())),
+                ("C".to_string(), 200, Some("v2".to_string())),
+                ("B".to_string(), 500, Some("v1".to_string())),
+                ("D".to_string(), 1000, Some("v1000".to_string())),
+            ]
+        );
+        assert!(!parsed.raw_metadata.contains_key("agent"));
+        assert_eq!(
+            parsed.agents.agents_by_id["v2"].name.as_deref(),
+            Some("Bob")
+        );
+        assert_eq!(
+            parsed.agents.agents_by_id["v1000"].agent_type,
+            AgentType::Group
+        );
+    }
+}
@@ -0,0 +1,420 @@
+//! # `YouTube` 字幕格式解析器
+//!
+//! 支持 `YouTube` timed-text 接口的 `srv3`（XML）和 `json3`（JSON）两种格式。
+//! 两者结构相同：每个事件（`<p>` / `events[]`）有开始时间和时长，
+//! 事件内的片段（`<s>` / `segs[]`）通过 `t` / `tOffsetMs` 给出相对于事件开始的偏移，
+//! 这些片段被解析为音节。
+//!
+//! 事件在组合成行时会做以下合并：
+//! - 追加事件（`a="1"` / `aAppend`）并入上一行；
+//! - 与上一行重叠且文本相同的事件只延长上一行；
+//! - 与上一行重叠且以上一行文本开头的事件（逐词显示的卡拉 OK 字幕）只追加新增的部分。
+
+use std::collections::HashMap;
+
+use quick_xml::{Reader, events::Event};
+use serde::Deserialize;
+
+use crate::converter::{
+    types::{
+        AnnotatedTrack, ContentType, ConvertError, LyricFormat, LyricLine, LyricSyllable,
+        LyricSyllableBuilder, LyricTrack, ParsedSourceData, Word,
+    },
+    utils::process_syllable_text,
+};
+
+/// 一个字幕事件。
+#[derive(Debug, Default)]
+struct CaptionEvent {
+    start_ms: u64,
+    duration_ms: u64,
+    append: bool,
+    segments: Vec<CaptionSegment>,
+}
+
+/// 事件内的一个片段，`offset_ms` 相对于事件开始时间。
+#[derive(Debug, Default)]
+struct CaptionSegment {
+    offset_ms: u64,
+    text: String,
+}
+
+impl CaptionEvent {
+    fn end_ms(&self) -> u64 {
+        self.start_ms + self.duration_ms
+    }
+
+    fn text(&self) -> String {
+        let joined: String = self.segments.iter().map(|s| s.text.as_str()).collect();
+        joined.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Json3Document {
+    #[serde(default)]
+    events: Vec<Json3Event>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Json3Event {
+    #[serde(default)]
+    t_start_ms: u64,
+    #[serde(default)]
+    d_duration_ms: u64,
+    #[serde(default)]
+    a_append: u8,
+    #[serde(default)]
+    segs: Vec<Json3Segment>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Json3Segment {
+    #[serde(default)]
+    utf8: String,
+    #[serde(default)]
+    t_offset_ms: u64,
+}
+
+/// 解析 `YouTube` `json3` 字幕到 `ParsedSourceData` 结构。
+pub fn parse_json3(content: &str) -> Result<ParsedSourceData, ConvertError> {
+    let document: Json3Document = serde_json::from_str(content)
+        .map_err(|e| ConvertError::json_parse(e, "YouTube json3 字幕".to_string()))?;
+
+    let events = document
+        .events
+        .into_iter()
+        .map(|event| CaptionEvent {
+            start_ms: event.t_start_ms,
+            duration_ms: event.d_duration_ms,
+            append: event.a_append != 0,
+            segments: event
+                .segs
+                .into_iter()
+                .map(|seg| CaptionSegment {
+                    offset_ms: seg.t_offset_ms,
+                    text: seg.utf8,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(build_source_data(events, LyricFormat::YouTubeJson3))
+}
+
+/// 解析 `YouTube` `srv3` 字幕到 `ParsedSourceData` 结构。
+pub fn parse_srv3(content: &str) -> Result<ParsedSourceData, ConvertError> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(false);
+
+    let mut events: Vec<CaptionEvent> = Vec::new();
+    let mut current: Option<CaptionEvent> = None;
+    let mut in_segment = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.local_name().as_ref() == b"p" => {
+                let mut event = CaptionEvent::default();
+                for attr in e.attributes() {
+                    let attr = attr?;
+                    let value = attr.unescape_value()?;
+                    match attr.key.as_ref() {
+                        b"t" => event.start_ms = value.parse()?,
+                        b"d" => event.duration_ms = value.parse()?,
+                        b"a" => event.append = value.as_ref() != "0",
+                        _ => {}
+                    }
+                }
+                current = Some(event);
+            }
+            Event::Empty(e) if e.local_name().as_ref() == b"p" => {}
+            Event::Start(e) if e.local_name().as_ref() == b"s" => {
+                if let Some(event) = current.as_mut() {
+                    let mut offset_ms = 0;
+                    for attr in e.attributes() {
+                        let attr = attr?;
+                        if attr.key.as_ref() == b"t" {
+                            offset_ms = attr.unescape_value()?.parse()?;
+                        }
+                    }
+                    event.segments.push(CaptionSegment {
+                        offset_ms,
+                        text: String::new(),
+                    });
+                    in_segment = true;
+                }
+            }
+            Event::End(e) if e.local_name().as_ref() == b"s" => in_segment = false,
+            Event::End(e) if e.local_name().as_ref() == b"p" => {
+                events.extend(current.take());
+                in_segment = false;
+            }
+            Event::Text(e) => {
+                if let Some(event) = current.as_mut() {
+                    push_segment_text(event, in_segment, &e.decode()?);
+                }
+            }
+            Event::GeneralRef(e) => {
+                if let Some(event) = current.as_mut() {
+                    let resolved = match e.resolve_char_ref()? {
+                        Some(ch) => Some(ch.to_string()),
+                        None => quick_xml::escape::resolve_predefined_entity(&e.decode()?)
+                            .map(str::to_string),
+                    };
+                    if let Some(text) = resolved {
+                        push_segment_text(event, in_segment, &text);
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(build_source_data(events, LyricFormat::YouTubeSrv3))
+}
+
+/// 将 `<p>` 中的文本追加到当前片段。不在 `<s>` 中的文本追加到最后一个片段，没有片段时新建一个。
+fn push_segment_text(event: &mut CaptionEvent, in_segment: bool, text: &str) {
+    match event.segments.last_mut() {
+        Some(segment) if in_segment || !segment.text.is_empty() => segment.text.push_str(text),
+        _ => event.segments.push(CaptionSegment {
+            offset_ms: 0,
+            text: text.to_string(),
+        }),
+    }
+}
+
+/// 将字幕事件合并为歌词行。
+fn build_source_data(events: Vec<CaptionEvent>, format: LyricFormat) -> ParsedSourceData {
+    let has_word_timing = events
+        .iter()
+        .any(|e| e.segments.iter().any(|s| s.offset_ms > 0));
+    let mut lines: Vec<LyricLine> = Vec::new();
+
+    for event in events {
+        let text = event.text();
+        if text.is_empty() {
+            continue;
+        }
+        let end_ms = event.end_ms();
+
+        if let Some(last) = lines.last_mut() {
+            if event.append {
+                let separated = event
+                    .segments
+                    .iter()
+                    .find(|s| !s.text.is_empty())
+                    .is_some_and(|s| s.text.starts_with(char::is_whitespace));
+                append_syllables(last, event_syllables(&event), separated);
+                last.end_ms = last.end_ms.max(end_ms);
+                continue;
+            }
+
+            let overlaps = event.start_ms < last.end_ms;
+            let last_text = last.main_text().unwrap_or_default();
+            if overlaps && text == last_text {
+                last.end_ms = last.end_ms.max(end_ms);
+                extend_last_syllable(last, end_ms);
+                continue;
+            }
+            if overlaps && text.len() > last_text.len() && text.starts_with(&last_text) {
+                let suffix_text = &text[last_text.len()..];
+                let suffix = CaptionEvent {
+                    start_ms: event.start_ms,
+                    duration_ms: event.duration_ms,
+                    append: true,
+                    segments: vec![CaptionSegment {
+                        offset_ms: 0,
+                        text: suffix_text.to_string(),
+                    }],
+                };
+                let separated = suffix_text.starts_with(char::is_whitespace);
+                append_syllables(last, event_syllables(&suffix), separated);
+                last.end_ms = last.end_ms.max(end_ms);
+                continue;
+            }
+        }
+
+        let mut line = LyricLine::new(event.start_ms, end_ms);
+        line.add_track(AnnotatedTrack {
+            content_type: ContentType::Main,
+            content: LyricTrack {
+                words: vec![Word {
+                    syllables: event_syllables(&event),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        lines.push(line);
+    }
+
+    ParsedSourceData {
+        lines,
+        raw_metadata: HashMap::new(),
+        source_format: format,
+        is_line_timed_source: !has_word_timing,
+        ..Default::default()
+    }
+}
+
+/// 将事件的片段转换为音节，每个片段持续到下一个片段开始或事件结束。
+fn event_syllables(event: &CaptionEvent) -> Vec<LyricSyllable> {
+    let mut syllables: Vec<LyricSyllable> = Vec::new();
+    for (i, segment) in event.segments.iter().enumerate() {
+        let start_ms = event.start_ms + segment.offset_ms;
+        let end_ms = event
+            .segments
+            .get(i + 1)
+            .map_or(event.end_ms(), |next| event.start_ms + next.offset_ms)
+            .max(start_ms);
+        let raw_text = segment.text.replace('\n', " ");
+        if let Some((text, ends_with_space)) = process_syllable_text(&raw_text, &mut syllables) {
+            syllables.push(
+                LyricSyllableBuilder::default()
+                    .text(text)
+                    .start_ms(start_ms)
+                    .end_ms(end_ms)
+                    .ends_with_space(ends_with_space)
+                    .build()
+                    .unwrap(),
+            );
+        }
+    }
+    syllables
+}
+
+/// 将音节追加到行的主轨道末尾，并把原来最后一个音节的结束时间截到新音节开始处。
+///
+/// `separated` 表示追加的文本与原有文本之间是否有空白。
+fn append_syllables(line: &mut LyricLine, new_syllables: Vec<LyricSyllable>, separated: bool) {
+    let Some(word) = line
+        .tracks
+        .first_mut()
+        .and_then(|t| t.content.words.last_mut())
+    else {
+        return;
+    };
+    if let (Some(last), Some(first_new)) = (word.syllables.last_mut(), new_syllables.first()) {
+        last.end_ms = last.end_ms.min(first_new.start_ms).max(last.start_ms);
+        last.ends_with_space |= separated;
+    }
+    word.syllables.extend(new_syllables);
+}
+
+fn extend_last_syllable(line: &mut LyricLine, end_ms: u64) {
+    if let Some(last) = line
+        .tracks
+        .first_mut()
+        .and_then(|t| t.content.words.last_mut())
+        .and_then(|w| w.syllables.last_mut())
+    {
+        last.end_ms = last.end_ms.max(end_ms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syllables(line: &LyricLine) -> Vec<(u64, u64, &str)> {
+        line.tracks[0].content.words[0]
+            .syllables
+            .iter()
+            .map(|s| (s.start_ms, s.end_ms, s.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_srv3_word_timing_and_append() {
+        let content = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+<body>
+<p t="1000" d="2000" w="1"><s ac="0">Hello</s><s t="500" ac="0"> world</s></p>
+<p t="2990" d="10" a="1" w="1">
+</p>
+<p t="3000" d="1000" a="1"><s> again</s></p>
+<p t="5000" d="1000">Tom &amp; Jerry</p>
+</body>
+</timedtext>"#;
+        let parsed = parse_srv3(content).unwrap();
+
+        assert!(!parsed.is_line_timed_source);
+        assert_eq!(parsed.lines.len(), 2);
+        assert_eq!(
+            parsed.lines[0].main_text().as_deref(),
+            Some("Hello world again")
+        );
+        assert_eq!(
+            (parsed.lines[0].start_ms, parsed.lines[0].end_ms),
+            (1000, 4000)
+        );
+        assert_eq!(
+            syllables(&parsed.lines[0]),
+            vec![
+                (1000, 1500, "Hello"),
+                (1500, 3000, "world"),
+                (3000, 4000, "again")
+            ]
+        );
+        assert_eq!(parsed.lines[1].main_text().as_deref(), Some("Tom & Jerry"));
+    }
+
+    #[test]
+    fn test_parse_json3_merges_overlapping_events() {
+        let content = r#"{"wireMagic":"pb3","events":[
+{"tStartMs":0,"dDurationMs":9000,"id":1,"wpWinPosId":1},
+{"tStartMs":1000,"dDurationMs":1000,"wWinId":1,"segs":[{"utf8":"Hello"}]},
+{"tStartMs":1500,"dDurationMs":1000,"wWinId":1,"segs":[{"utf8":"Hello world"}]},
+{"tStartMs":2200,"dDurationMs":1000,"wWinId":1,"segs":[{"utf8":"Hello world"}]},
+{"tStartMs":4000,"dDurationMs":1000,"segs":[{"utf8":"Next"},{"utf8":" line","tOffsetMs":400}]}
+]}"#;
+        let parsed = parse_json3(content).unwrap();
+
+        assert_eq!(parsed.lines.len(), 2);
+        assert_eq!(
+            (parsed.lines[0].start_ms, parsed.lines[0].end_ms),
+            (1000, 3200)
+        );
+        assert_eq!(
+            syllables(&parsed.lines[0]),
+            vec![(1000, 1500, "Hello"), (1500, 3200, "world")]
+        );
+        assert_eq!(
+            syllables(&parsed.lines[1]),
+            vec![(4000, 4400, "Next"), (4400, 5000, "line")]
+        );
+    }
+
+    #[test]
+    fn test_json3_translation_merges_into_srv3_lines() {
+        let main = parse_srv3(
+            r#"<timedtext format="3"><body><p t="1000" d="2000">안녕하세요</p></body></timedtext>"#,
+        )
+        .unwrap();
+        let translation = parse_json3(
+            r#"{"events":[{"tStartMs":1000,"dDurationMs":2000,"segs":[{"utf8":"Hello"}]}]}"#,
+        )
+        .unwrap();
+
+        let mut lines = main.lines.clone();
+        crate::converter::merge_tracks(
+            &mut lines,
+            &[(
+                translation.lines.clone(),
+                translation,
+                Some("en".to_string()),
+            )],
+            &[],
+            crate::converter::types::AuxiliaryLineMatchingStrategy::SortedSync { tolerance_ms: 20 },
+        );
+
+        assert_eq!(
+            lines[0].main_track().unwrap().translations[0].text(),
+            "Hello"
+        );
+    }
+}
@@ -95,6 +95,67 @@ fn parse_yrc_line(line_str: &str, line_num: usize) -> Result<LyricLine, ConvertE
         .unwrap())
 }
 
+/// 将网易云演职员标签映射为规范的元数据键名，未知标签保持原样。
+fn credit_label_to_key(label: &str) -> String {
+    match label {
+        "曲名" | "歌名" => "title",
+        "歌手" | "演唱" => "artist",
+        "专辑" => "album",
+        "作词" | "词" => "lyricist",
+        "作曲" | "曲" => "composer",
+        "编曲" => "arranger",
+        "制作人" | "监制" => "producer",
+        other => other,
+    }
+    .to_string()
+}
+
+/// 解析网易云 YRC / klyric 中的 JSON 演职员信息行，例如
+/// `{"t":0,"c":[{"tx":"作词: "},{"tx":"A"},{"tx":"/"},{"tx":"B"}]}`。
+///
+/// 第一个 `tx` 是标签，其后的每个 `tx`（跳过 `/` 分隔符）都是一个独立的值。
+/// 只有一个元素的行（如 `{"tx":"作词：A"}`）会按冒号拆分。
+///
+/// # 返回
+/// 成功解析时返回 `(键, 值)` 列表，不含演职员信息的行返回空列表；
+/// 不是合法 JSON 时返回 `None`。
+pub(crate) fn parse_json_credit_line(line: &str) -> Option<Vec<(String, String)>> {
+    let json_value: Value = serde_json::from_str(line).ok()?;
+    let texts: Vec<&str> = json_value
+        .get("c")
+        .and_then(Value::as_array)
+        .map(|c| {
+            c.iter()
+                .filter_map(|item| item.get("tx").and_then(Value::as_str))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let Some((first, rest)) = texts.split_first() else {
+        return Some(Vec::new());
+    };
+
+    let is_separator = |c: char| c == ':' || c == '：';
+    let (label, mut values) = match first.split_once(is_separator) {
+        Some((label, value)) => (label.trim(), vec![value.trim()]),
+        None => (first.trim(), Vec::new()),
+    };
+    values.extend(rest.iter().map(|s| s.trim()));
+
+    let key = credit_label_to_key(label);
+    if key.is_empty() {
+        return Some(Vec::new());
+    }
+
+    Some(
+        values
+            .into_iter()
+            .filter(|v| !v.is_empty() && *v != "/")
+            .map(|v| (key.clone(), v.to_string()))
+            .collect(),
+    )
+}
+
 /// 解析 YRC 格式内容到 `ParsedSourceData` 结构。
 pub fn parse_yrc(content: &str) -> Result<ParsedSourceData, ConvertError> {
     let mut lines: Vec<LyricLine> = Vec::new();
@@ -109,40 +170,17 @@ pub fn parse_yrc(content: &str) -> Result<ParsedSourceData, ConvertError> {
             continue;
         }
 
-        // 解析 JSON 元数据行
-        if trimmed_line.starts_with("{\"t\":") {
-            if let Ok(json_value) = serde_json::from_str::<Value>(trimmed_line) {
-                if let Some(c_array) = json_value.get("c").and_then(|v| v.as_array()) {
-                    if c_array.is_empty() {
-                        continue;
-                    }
-                    // 第一个 "tx" 元素通常是键，例如 "作词: "
-                    let key_part = c_array[0]
-                        .get("tx")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .trim();
-                    let key = key_part
-                        .trim_end_matches(|c: char| c == ':' || c == '：' || c.is_whitespace())
-                        .to_string();
-
-                    // 后续的 "tx" 元素是值
-                    let value = c_array
-                        .iter()
-                        .skip(1) // 跳过作为键的第一个元素
-                        .filter_map(|item| item.get("tx").and_then(|v| v.as_str()))
-                        .filter(|s| !s.trim().is_empty() && *s != "/") // 过滤掉空字符串和分隔符
-                        .collect::<Vec<_>>()
-                        .join(", "); // 用逗号连接多个作者
-
-                    if !key.is_empty() && !value.is_empty() {
+        // 解析 JSON 演职员信息行
+        if trimmed_line.starts_with('{') {
+            match parse_json_credit_line(trimmed_line) {
+                Some(credits) => {
+                    for (key, value) in credits {
                         raw_metadata.entry(key).or_default().push(value);
                     }
                 }
-            } else {
-                warnings.push(format!(
+                None => warnings.push(format!(
                     "第 {line_num} 行: 看起来像 JSON 元数据但解析失败，已跳过。"
-                ));
+                )),
             }
             continue;
         }
@@ -171,3 +209,34 @@ pub fn parse_yrc(content: &str) -> Result<ParsedSourceData, ConvertError> {
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_credit_lines_become_metadata() {
+        let content = r#"{"t":0,"c":[{"tx":"作词: ","li":null},{"tx":"A"},{"tx":"/"},{"tx":"B"}]}
+{"t":1000,"c":[{"tx":"作曲：C"}]}
+{"t":2000,"c":[{"tx":"和声: "},{"tx":"D"}]}
+{"t":3000,"c":[]}
+[5000,1000](5000,500,0)Hel(5500,500,0)lo
+"#;
+        let parsed = parse_yrc(content).unwrap();
+
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert_eq!(parsed.lines.len(), 1);
+        assert_eq!(
+            parsed.raw_metadata.get("lyricist"),
+            Some(&vec!["A".to_string(), "B".to_string()])
+        );
+        assert_eq!(
+            parsed.raw_metadata.get("composer"),
+            Some(&vec!["C".to_string()])
+        );
+        assert_eq!(
+            parsed.raw_metadata.get("和声"),
+            Some(&vec!["D".to_string()])
+        );
+    }
+}
@@ -8,11 +8,13 @@ use std::{
 };
 
 use crate::converter::{
+    codecs::{krc, qrc},
     convert_single_lyric,
     types::{
         BatchConversionConfig, BatchEntryStatus, BatchFileId, BatchLoadedFile, ConversionInput,
         ConversionOptions, ConvertError, InputFile, LyricFormat,
     },
+    utils::detect_lyric_format,
 };
 
 /// 表示一组相关联的歌词文件（主歌词、翻译、罗马音）。
@@ -175,21 +177,55 @@ pub fn execute_batch_conversion<S: BuildHasher>(
                 ConvertError::Internal(format!("文件ID {file_id:?} 未在查找表中找到"))
             })?;
 
-            let content = fs::read_to_string(&loaded_file.path)?;
-            let format = get_format_from_path(&loaded_file.path).ok_or_else(|| {
-                ConvertError::InvalidLyricFormat(loaded_file.path.to_string_lossy().to_string())
-            })?;
+            let bytes = fs::read(&loaded_file.path)?;
+            let format = get_format_from_path(&loaded_file.path)
+                .or_else(|| detect_txt_format(&loaded_file.path, &bytes))
+                .ok_or_else(|| {
+                    ConvertError::InvalidLyricFormat(loaded_file.path.to_string_lossy().to_string())
+                })?;
+
+            // 酷狗客户端的 .krc 文件是加密的，通过头部识别
+            if format == LyricFormat::Krc && bytes.starts_with(krc::KRC_FILE_HEADER) {
+                return Ok(InputFile::from_bytes(
+                    bytes,
+                    LyricFormat::EncryptedKrc,
+                    None,
+                    Some(loaded_file.filename.clone()),
+                ));
+            }
+
+            // Karaoke MIDI 是二进制格式，SAMI 文件则常使用 CP949 编码，由解析器自行解码
+            if matches!(format, LyricFormat::Kar | LyricFormat::Sami) {
+                return Ok(InputFile::from_bytes(
+                    bytes,
+                    format,
+                    None,
+                    Some(loaded_file.filename.clone()),
+                ));
+            }
+
+            // QQ 音乐的本地 .qrc 文件是加密的：客户端缓存以 `[offset:0]` 头部开头，
+            // 其他加密文件则不是合法的 UTF-8 文本
+            if format == LyricFormat::Qrc && is_encrypted_qrc(&bytes) {
+                return Ok(InputFile::from_bytes(
+                    bytes,
+                    LyricFormat::EncryptedQrc,
+                    None,
+                    Some(loaded_file.filename.clone()),
+                ));
+            }
 
             Ok(InputFile {
-                content,
+                content: String::from_utf8(strip_utf8_bom(&bytes).to_vec())?,
                 format,
                 // TODO: 从路径获取语言和文件名
                 language: None,
                 filename: Some(loaded_file.filename.clone()),
+                raw_bytes: None,
             })
         };
 
-        let conversion_result = (|| -> Result<String, ConvertError> {
+        let conversion_result = (|| -> Result<Vec<u8>, ConvertError> {
             // 读取主歌词文件
             let main_lyric = read_and_build_input(&task.main_lyric_id)?;
 
@@ -217,14 +253,18 @@ pub fn execute_batch_conversion<S: BuildHasher>(
             };
 
             // 调用核心转换函数
-            convert_single_lyric(&conversion_input, options)
-                .map(|full_result| full_result.output_lyrics)
+            // 加密格式直接写入加密字节
+            convert_single_lyric(&conversion_input, options).map(|full_result| {
+                full_result
+                    .output_bytes
+                    .unwrap_or_else(|| full_result.output_lyrics.into_bytes())
+            })
         })();
 
         match conversion_result {
-            Ok(result_string) => {
+            Ok(output_content) => {
                 let output_path = output_dir.join(&task.output_filename_preview);
-                match fs::write(&output_path, result_string) {
+                match fs::write(&output_path, output_content) {
                     Ok(()) => {
                         task.status = BatchEntryStatus::Completed {
                             output_path,
@@ -251,3 +291,80 @@ fn get_format_from_path(path: &Path) -> Option<LyricFormat> {
         .and_then(|s| s.to_str())
         .and_then(LyricFormat::from_string)
 }
+
+/// 去掉 UTF-8 BOM（`EF BB BF`）。
+fn strip_utf8_bom(bytes: &[u8]) -> &[u8] {
+    bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes)
+}
+
+/// 判断 `.qrc` 文件是否为加密文件。
+///
+/// 明文 QRC 或 LRC（包括带 BOM 的文件和空文件）都是合法的 UTF-8 文本，
+/// 加密文件则是本地缓存头部加密文数据，或是无法解码为 UTF-8 的字节。
+fn is_encrypted_qrc(bytes: &[u8]) -> bool {
+    qrc::is_local_qrc(bytes) || std::str::from_utf8(strip_utf8_bom(bytes)).is_err()
+}
+
+/// 根据内容推断 `.txt` 文件的歌词格式。
+///
+/// `UltraStar` 文件通过 `#TITLE:`/`#BPM:` 头部识别，其他 `.txt` 文件（例如保存为
+/// `.txt` 的 LRC）按内容特征识别；无法识别的纯文本返回 `None`。
+fn detect_txt_format(path: &Path, bytes: &[u8]) -> Option<LyricFormat> {
+    let is_txt = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"));
+    if !is_txt {
+        return None;
+    }
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(detect_lyric_format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_encrypted_qrc() {
+        assert!(!is_encrypted_qrc(b""));
+        assert!(!is_encrypted_qrc(b"\xEF\xBB\xBF[ti:test]\n[0,500]a(0,500)"));
+        assert!(!is_encrypted_qrc(b"[offset:0]\n[0,500]a(0,500)"));
+        assert!(is_encrypted_qrc(&[
+            0x98, 0x25, 0xB0, 0xAC, 0xE3, 0x02, 0x83, 0x68
+        ]));
+    }
+
+    #[test]
+    fn test_batch_conversion_reads_plaintext_qrc_with_bom() {
+        let dir = std::env::temp_dir().join(format!("batch-qrc-bom-{}", std::process::id()));
+        let input_dir = dir.join("input");
+        let output_dir = dir.join("output");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::write(
+            input_dir.join("song.qrc"),
+            "\u{feff}[ti:测试]\n[1000,1000]你(1000,500)好(1500,500)\n",
+        )
+        .unwrap();
+
+        let groups = discover_and_pair_files(&input_dir).unwrap();
+        let (mut tasks, file_lookup) = create_batch_tasks(groups, LyricFormat::Lrc);
+        execute_batch_conversion(
+            &mut tasks,
+            &file_lookup,
+            &output_dir,
+            &ConversionOptions::default(),
+        )
+        .unwrap();
+
+        let BatchEntryStatus::Completed { output_path, .. } = &tasks[0].status else {
+            panic!("转换失败: {:?}", tasks[0].status);
+        };
+        let output = fs::read_to_string(output_path).unwrap();
+        assert!(output.contains("你好"), "{output}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
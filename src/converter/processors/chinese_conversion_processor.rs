@@ -1,6 +1,6 @@
 //! 简繁中文转换器。
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::converter::{
     LyricLine, LyricSyllable, LyricTrack, TrackMetadataKey, Word,
@@ -76,6 +76,86 @@ fn pinyin_is_same(original: &str, converted: &str) -> bool {
     original_pinyins == converted_pinyins
 }
 
+/// 转换结果中的一段文本。
+struct ConvertedSegment {
+    original: String,
+    converted: String,
+    /// 该段是否来自用户自定义词组
+    from_phrase: bool,
+}
+
+/// 将自定义词组表整理为按长度降序排列的列表，以便优先匹配较长的词组。
+fn prepare_phrases(custom_phrases: &HashMap<String, String>) -> Vec<(&str, &str)> {
+    let mut phrases: Vec<(&str, &str)> = custom_phrases
+        .iter()
+        .filter(|(from, _)| !from.is_empty())
+        .map(|(from, to)| (from.as_str(), to.as_str()))
+        .collect();
+    phrases.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(b.0)));
+    phrases
+}
+
+/// 先应用自定义词组，再用 `OpenCC` 转换剩余的文本，返回分段的转换结果。
+fn convert_segments(
+    text: &str,
+    config: BuiltinConfig,
+    phrases: &[(&str, &str)],
+) -> Vec<ConvertedSegment> {
+    let mut segments = Vec::new();
+    let mut pending_start = 0;
+    let mut pos = 0;
+
+    let flush = |segments: &mut Vec<ConvertedSegment>, pending: &str| {
+        if !pending.is_empty() {
+            segments.push(ConvertedSegment {
+                original: pending.to_string(),
+                converted: convert(pending, config),
+                from_phrase: false,
+            });
+        }
+    };
+
+    while pos < text.len() {
+        let rest = &text[pos..];
+        if let Some(&(from, to)) = phrases.iter().find(|(from, _)| rest.starts_with(from)) {
+            flush(&mut segments, &text[pending_start..pos]);
+            segments.push(ConvertedSegment {
+                original: from.to_string(),
+                converted: to.to_string(),
+                from_phrase: true,
+            });
+            pos += from.len();
+            pending_start = pos;
+        } else {
+            pos += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    flush(&mut segments, &text[pending_start..]);
+
+    segments
+}
+
+/// 先应用自定义词组，再用 `OpenCC` 转换剩余的文本。
+fn convert_text(text: &str, config: BuiltinConfig, phrases: &[(&str, &str)]) -> String {
+    convert_segments(text, config, phrases)
+        .into_iter()
+        .map(|segment| segment.converted)
+        .collect()
+}
+
+/// 检查转换是否保留了每个字的读音，从而可以安全地按字数分配回各个音节。
+///
+/// 自定义词组被视为可信的替换，只要求其字数不变。
+fn segments_preserve_reading(segments: &[ConvertedSegment]) -> bool {
+    segments.iter().all(|segment| {
+        if segment.from_phrase {
+            segment.original.chars().count() == segment.converted.chars().count()
+        } else {
+            pinyin_is_same(&segment.original, &segment.converted)
+        }
+    })
+}
+
 /// 一个用于执行简繁中文转换的处理器。
 #[derive(Debug, Default)]
 pub struct ChineseConversionProcessor;
@@ -89,20 +169,33 @@ impl ChineseConversionProcessor {
 
     /// 对一组歌词行应用简繁转换。
     ///
+    /// 主歌词、背景人声和翻译分别使用 `options` 中各自的配置。
+    ///
     /// # 参数
     /// * `lines` - 一个可变的歌词行切片，转换结果将直接写入其中。
     /// * `options` - 简繁转换的配置选项，决定是否执行以及执行何种模式的转换。
     pub fn process(lines: &mut [LyricLine], options: &ChineseConversionOptions) {
+        let phrases = prepare_phrases(&options.custom_phrases);
+
+        // 先转换已有的翻译，避免再次转换下面以翻译形式添加的主歌词
+        if let Some(config) = options.translation_config {
+            Self::convert_translations(lines, config, &phrases);
+        }
+
+        if let Some(config) = options.background_config {
+            Self::replace(lines, ContentType::Background, config, &phrases);
+        }
+
         let Some(config) = options.config else {
             return;
         };
 
         match options.mode {
             ChineseConversionMode::AddAsTranslation => {
-                Self::add_as_translation(lines, config, options);
+                Self::add_as_translation(lines, config, options, &phrases);
             }
             ChineseConversionMode::Replace => {
-                Self::replace(lines, config);
+                Self::replace(lines, ContentType::Main, config, &phrases);
             }
         }
     }
@@ -111,6 +204,7 @@ impl ChineseConversionProcessor {
         lines: &mut [LyricLine],
         config: BuiltinConfig,
         options: &ChineseConversionOptions,
+        phrases: &[(&str, &str)],
     ) {
         let lang_tag = options
             .target_lang_tag
@@ -151,9 +245,9 @@ impl ChineseConversionProcessor {
                     .collect();
 
                 if !main_text.is_empty() {
-                    let converted_text = convert(&main_text, config);
+                    let converted_text = convert_text(&main_text, config, phrases);
 
-                    let mut metadata = std::collections::HashMap::new();
+                    let mut metadata = HashMap::new();
                     metadata.insert(TrackMetadataKey::Language, target_lang_tag.to_string());
 
                     let translation_track = LyricTrack {
@@ -172,67 +266,108 @@ impl ChineseConversionProcessor {
         }
     }
 
-    fn replace(lines: &mut [LyricLine], config: BuiltinConfig) {
+    /// 转换所有语言标签为空或为中文的翻译轨道，并更新其语言标签。
+    fn convert_translations(
+        lines: &mut [LyricLine],
+        config: BuiltinConfig,
+        phrases: &[(&str, &str)],
+    ) {
+        let new_lang_tag = config.deduce_lang_tag();
+
         for line in lines.iter_mut() {
             for at in &mut line.tracks {
-                if at.content_type == ContentType::Main {
-                    let main_track = &mut at.content;
-                    for word in &mut main_track.words {
-                        let original_syllable_texts: Vec<String> =
-                            word.syllables.iter().map(|s| s.text.clone()).collect();
-                        let full_word_text = original_syllable_texts.join("");
-
-                        if full_word_text.is_empty() {
-                            continue;
-                        }
+                for translation in &mut at.translations {
+                    let lang = translation.metadata.get(&TrackMetadataKey::Language);
+                    if lang.is_some_and(|l| !l.to_lowercase().starts_with("zh")) {
+                        continue;
+                    }
+                    let has_lang = lang.is_some();
+
+                    Self::replace_track(translation, config, phrases);
+
+                    if has_lang && let Some(tag) = new_lang_tag {
+                        translation
+                            .metadata
+                            .insert(TrackMetadataKey::Language, tag.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    fn replace(
+        lines: &mut [LyricLine],
+        content_type: ContentType,
+        config: BuiltinConfig,
+        phrases: &[(&str, &str)],
+    ) {
+        for line in lines.iter_mut() {
+            for at in &mut line.tracks {
+                if at.content_type == content_type {
+                    Self::replace_track(&mut at.content, config, phrases);
+                }
+            }
+        }
+    }
+
+    /// 原地转换一个轨道，尽量保持音节的划分不变。
+    fn replace_track(track: &mut LyricTrack, config: BuiltinConfig, phrases: &[(&str, &str)]) {
+        for word in &mut track.words {
+            let original_syllable_texts: Vec<String> =
+                word.syllables.iter().map(|s| s.text.clone()).collect();
+            let full_word_text = original_syllable_texts.join("");
+
+            if full_word_text.is_empty() {
+                continue;
+            }
+
+            let segments = convert_segments(&full_word_text, config, phrases);
+            let converted_full_text: String =
+                segments.iter().map(|s| s.converted.as_str()).collect();
+
+            if segments_preserve_reading(&segments) {
+                let mut converted_chars = converted_full_text.chars();
+                for (i, original_text) in original_syllable_texts.iter().enumerate() {
+                    let char_count = original_text.chars().count();
+                    let new_syllable_text: String =
+                        converted_chars.by_ref().take(char_count).collect();
+                    if let Some(syllable) = word.syllables.get_mut(i) {
+                        syllable.text = new_syllable_text;
+                    }
+                }
+            } else {
+                warn!(
+                    "词组 '{}' 转换后读音或长度改变 ('{}')，回退到逐音节转换。",
+                    full_word_text, converted_full_text,
+                );
+
+                for syllable in &mut word.syllables {
+                    if syllable.text.is_empty() {
+                        continue;
+                    }
 
-                        let converted_full_text = convert(&full_word_text, config);
-
-                        if pinyin_is_same(&full_word_text, &converted_full_text) {
-                            let mut converted_chars = converted_full_text.chars();
-                            for (i, original_text) in original_syllable_texts.iter().enumerate() {
-                                let char_count = original_text.chars().count();
-                                let new_syllable_text: String =
-                                    converted_chars.by_ref().take(char_count).collect();
-                                if let Some(syllable) = word.syllables.get_mut(i) {
-                                    syllable.text = new_syllable_text;
-                                }
-                            }
+                    let original_text = &syllable.text;
+                    let syllable_segments = convert_segments(original_text, config, phrases);
+
+                    if segments_preserve_reading(&syllable_segments) {
+                        syllable.text =
+                            syllable_segments.into_iter().map(|s| s.converted).collect();
+                    } else {
+                        let char_by_char_converted: String = original_text
+                            .chars()
+                            .map(|c| {
+                                let mut char_str = [0u8; 4];
+                                convert_text(c.encode_utf8(&mut char_str), config, phrases)
+                            })
+                            .collect();
+
+                        if pinyin_is_same(original_text, &char_by_char_converted) {
+                            syllable.text = char_by_char_converted;
                         } else {
                             warn!(
-                                "词组 '{}' 转换后读音或长度改变 ('{}')，回退到逐音节转换。",
-                                full_word_text, converted_full_text,
+                                "音节 '{}' 转换后读音改变，逐字转换也无效。保留原文。",
+                                original_text
                             );
-
-                            for syllable in &mut word.syllables {
-                                if syllable.text.is_empty() {
-                                    continue;
-                                }
-
-                                let original_text = &syllable.text;
-                                let converted_text_syllable = convert(original_text, config);
-
-                                if pinyin_is_same(original_text, &converted_text_syllable) {
-                                    syllable.text = converted_text_syllable;
-                                } else {
-                                    let char_by_char_converted: String = original_text
-                                        .chars()
-                                        .map(|c| {
-                                            let mut char_str = [0u8; 4];
-                                            convert(c.encode_utf8(&mut char_str), config)
-                                        })
-                                        .collect();
-
-                                    if pinyin_is_same(original_text, &char_by_char_converted) {
-                                        syllable.text = char_by_char_converted;
-                                    } else {
-                                        warn!(
-                                            "音节 '{}' 转换后读音改变，逐字转换也无效。保留原文。",
-                                            original_text
-                                        );
-                                    }
-                                }
-                            }
                         }
                     }
                 }
@@ -363,7 +498,7 @@ mod tests {
         let options = ChineseConversionOptions {
             config: Some(BuiltinConfig::S2twp),
             mode: ChineseConversionMode::AddAsTranslation,
-            target_lang_tag: None,
+            ..Default::default()
         };
 
         ChineseConversionProcessor::process(&mut lines, &options);
@@ -405,6 +540,7 @@ mod tests {
             config: Some(BuiltinConfig::S2t),
             mode: ChineseConversionMode::AddAsTranslation,
             target_lang_tag: Some("zh-Hant".to_string()),
+            ..Default::default()
         };
         ChineseConversionProcessor::process(&mut lines, &options);
         assert_eq!(lines[0].tracks[0].translations.len(), 1);
@@ -421,9 +557,56 @@ mod tests {
         let options = ChineseConversionOptions {
             config: None,
             mode: ChineseConversionMode::AddAsTranslation,
-            target_lang_tag: None,
+            ..Default::default()
         };
         ChineseConversionProcessor::process(&mut lines, &options);
         assert_eq!(lines[0].tracks[0].translations.len(), 0);
     }
+
+    #[test]
+    fn test_custom_phrases_applied_before_opencc() {
+        let mut lines = vec![new_syllable_track_line(vec!["鼠", "标", "和", "键盘"])];
+        let options = ChineseConversionOptions {
+            config: Some(BuiltinConfig::S2t),
+            custom_phrases: HashMap::from([("鼠标".to_string(), "滑鼠".to_string())]),
+            ..Default::default()
+        };
+        ChineseConversionProcessor::process(&mut lines, &options);
+
+        let syllables: Vec<String> = lines[0].tracks[0].content.words[0]
+            .syllables
+            .iter()
+            .map(|s| s.text.clone())
+            .collect();
+        assert_eq!(syllables, vec!["滑", "鼠", "和", "鍵盤"]);
+    }
+
+    #[test]
+    fn test_per_track_configs() {
+        let mut line = new_track_line("简体");
+        line.add_content_track(ContentType::Background, "简体");
+        line.add_translation(ContentType::Main, "繁體", Some("zh-Hant"));
+        line.add_translation(ContentType::Main, "English", Some("en"));
+        let mut lines = vec![line];
+
+        let options = ChineseConversionOptions {
+            config: None,
+            background_config: Some(BuiltinConfig::S2t),
+            translation_config: Some(BuiltinConfig::T2s),
+            ..Default::default()
+        };
+        ChineseConversionProcessor::process(&mut lines, &options);
+
+        let line = &lines[0];
+        assert_eq!(line.main_text().as_deref(), Some("简体"));
+        assert_eq!(line.background_text().as_deref(), Some("簡體"));
+
+        let translations = &line.tracks[0].translations;
+        assert_eq!(translations[0].text(), "繁体");
+        assert_eq!(
+            translations[0].metadata.get(&TrackMetadataKey::Language),
+            Some(&"zh-Hans".to_string())
+        );
+        assert_eq!(translations[1].text(), "English");
+    }
 }
@@ -1,14 +1,20 @@
 //! 元数据处理器。
 
-use std::{
-    collections::{HashMap, HashSet},
-    fmt::Write as FmtWrite,
-};
+use std::{collections::HashMap, fmt::Write as FmtWrite};
 
 use crate::converter::types::{
     CanonicalMetadataKey, ParseCanonicalMetadataKeyError, ParsedSourceData,
 };
 
+/// LRC 头部中演职员信息的标签，按输出顺序排列。
+const LRC_CREDIT_TAGS: &[(CanonicalMetadataKey, &str)] = &[
+    (CanonicalMetadataKey::Songwriter, "au"),
+    (CanonicalMetadataKey::Lyricist, "lyricist"),
+    (CanonicalMetadataKey::Composer, "composer"),
+    (CanonicalMetadataKey::Arranger, "arranger"),
+    (CanonicalMetadataKey::Producer, "producer"),
+];
+
 /// 一个用于存储、管理和规范化歌词元数据的中央容器。
 #[derive(Debug, Clone, Default)]
 pub struct MetadataStore {
@@ -189,20 +195,53 @@ impl MetadataStore {
     /// 有多个值的，使用 "/" 连接（offset 除外）。
     #[must_use]
     pub fn generate_lrc_header(&self) -> String {
+        self.generate_lrc_header_with_credits(false)
+    }
+
+    /// 生成LRC元数据头部字符串，可选择是否输出演职员标签。
+    ///
+    /// `include_credits` 为 `true` 时，会在 `by` 之后额外输出
+    /// `au`、`lyricist`、`composer`、`arranger`、`producer`。
+    /// 这些标签并不属于通用的 LRC 标签，部分播放器可能无法识别。
+    #[must_use]
+    pub fn generate_lrc_header_with_credits(&self, include_credits: bool) -> String {
+        let credit_tags: &[_] = if include_credits {
+            LRC_CREDIT_TAGS
+        } else {
+            &[]
+        };
+        self.write_lrc_tags(
+            [
+                (CanonicalMetadataKey::Title, "ti"),
+                (CanonicalMetadataKey::Artist, "ar"),
+                (CanonicalMetadataKey::Album, "al"),
+                (CanonicalMetadataKey::TtmlAuthorGithubLogin, "by"),
+            ]
+            .iter()
+            .chain(credit_tags)
+            .chain(&[
+                (CanonicalMetadataKey::Language, "language"),
+                (CanonicalMetadataKey::Offset, "offset"),
+            ]),
+        )
+    }
+
+    /// 只生成演职员标签（`au`、`lyricist`、`composer`、`arranger`、`producer`）。
+    ///
+    /// 用于本身没有 LRC 元数据头部的格式，例如 SPL。
+    #[must_use]
+    pub fn generate_lrc_credit_tags(&self) -> String {
+        self.write_lrc_tags(LRC_CREDIT_TAGS)
+    }
+
+    /// 按顺序将元数据写为 `[标签:值]` 行。
+    fn write_lrc_tags<'a>(
+        &self,
+        lrc_tags_to_write: impl IntoIterator<Item = &'a (CanonicalMetadataKey, &'a str)>,
+    ) -> String {
         let mut output = String::new();
-        let mut written_keys: HashSet<&CanonicalMetadataKey> = HashSet::new();
-
-        // 定义LRC标签和对应的CanonicalMetadataKey的映射
-        let lrc_tags_to_write: Vec<(CanonicalMetadataKey, &str)> = vec![
-            (CanonicalMetadataKey::Title, "ti"),
-            (CanonicalMetadataKey::Artist, "ar"),
-            (CanonicalMetadataKey::Album, "al"),
-            (CanonicalMetadataKey::TtmlAuthorGithubLogin, "by"),
-            (CanonicalMetadataKey::Language, "language"),
-            (CanonicalMetadataKey::Offset, "offset"),
-        ];
-
-        for (key_type, lrc_tag_name) in &lrc_tags_to_write {
+
+        for (key_type, lrc_tag_name) in lrc_tags_to_write {
             if let Some(values) = self.data.get(key_type) {
                 if values.is_empty() {
                     continue;
@@ -219,7 +258,6 @@ impl MetadataStore {
 
                 if !value_to_write.trim().is_empty() || *lrc_tag_name == "offset" {
                     let _ = writeln!(output, "[{}:{}]", lrc_tag_name, value_to_write.trim());
-                    written_keys.insert(key_type);
                 }
             }
         }
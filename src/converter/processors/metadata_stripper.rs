@@ -2,7 +2,8 @@
 
 use std::{
     borrow::Cow,
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
+    hash::BuildHasher,
     sync::{Mutex, OnceLock},
 };
 
@@ -11,9 +12,81 @@ use tracing::{debug, trace, warn};
 
 use crate::converter::{
     LyricLine,
-    types::{ContentType, MetadataStripperFlags, MetadataStripperOptions},
+    types::{CanonicalMetadataKey, ContentType, MetadataStripperFlags, MetadataStripperOptions},
 };
 
+/// 从演职员信息行中提取出的一条记录，例如 `作词：A / B` 中的 `作词` 和 `A`、`B`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreditEntry {
+    /// 原始的角色名，例如 "作词"、"Composed by"。
+    pub role: String,
+    /// 该角色对应的名字列表。
+    pub names: Vec<String>,
+}
+
+impl CreditEntry {
+    /// 获取该角色对应的规范化元数据键。
+    ///
+    /// 无法识别的角色会以原始角色名作为自定义键。
+    #[must_use]
+    pub fn metadata_keys(&self) -> Vec<CanonicalMetadataKey> {
+        match self.role.trim().to_lowercase().as_str() {
+            "作词" | "词" | "作詞" | "詞" | "lyrics" | "lyrics by" | "lyricist" | "written by"
+            | "词lyrics" | "词lyricist" => vec![CanonicalMetadataKey::Lyricist],
+            "作曲" | "曲" | "composer" | "composed by" | "music by" | "曲composer" => {
+                vec![CanonicalMetadataKey::Composer]
+            }
+            "词曲" | "詞曲" | "作词作曲" | "作詞作曲" | "words and music by" => {
+                vec![
+                    CanonicalMetadataKey::Lyricist,
+                    CanonicalMetadataKey::Composer,
+                ]
+            }
+            "编曲" | "編曲" | "arranger" | "arranged by" | "arrangement" | "编曲arranged by"
+            | "编曲arranger" | "编曲arrangement" => vec![CanonicalMetadataKey::Arranger],
+            "制作人"
+            | "製作人"
+            | "producer"
+            | "produced by"
+            | "record producer"
+            | "music producer"
+            | "制作人producer"
+            | "制作人record producer"
+            | "制作人music producer" => vec![CanonicalMetadataKey::Producer],
+            _ => vec![CanonicalMetadataKey::Custom(self.role.trim().to_string())],
+        }
+    }
+}
+
+/// 将提取出的演职员信息合并到原始元数据中。
+///
+/// 作词和作曲者还会被同时加入 `Songwriter`，以便写入 TTML 的 `<songwriters>`。
+pub fn merge_credits_into_metadata<S: BuildHasher>(
+    credits: &[CreditEntry],
+    raw_metadata: &mut HashMap<String, Vec<String>, S>,
+) {
+    let mut push_names = |key: &CanonicalMetadataKey, names: &[String]| {
+        let values = raw_metadata.entry(key.to_string()).or_default();
+        for name in names {
+            if !values.contains(name) {
+                values.push(name.clone());
+            }
+        }
+    };
+
+    for credit in credits {
+        for key in credit.metadata_keys() {
+            push_names(&key, &credit.names);
+            if matches!(
+                key,
+                CanonicalMetadataKey::Lyricist | CanonicalMetadataKey::Composer
+            ) {
+                push_names(&CanonicalMetadataKey::Songwriter, &credit.names);
+            }
+        }
+    }
+}
+
 type RegexCacheKey = (String, bool); // (pattern, case_sensitive)
 type RegexCacheMap = BTreeMap<RegexCacheKey, Regex>;
 
@@ -66,14 +139,88 @@ fn get_plain_text_from_new_lyric_line(line: &LyricLine) -> String {
     String::new()
 }
 
+/// 跳过行首的 `[...]` 或 `(...)` 前缀。
+fn skip_bracketed_prefix(text: &str) -> &str {
+    let mut text_after_prefix = text.trim_start();
+    if text_after_prefix.starts_with('[') {
+        if let Some(end_bracket_idx) = text_after_prefix.find(']') {
+            text_after_prefix = text_after_prefix[end_bracket_idx + 1..].trim_start();
+        }
+    } else if text_after_prefix.starts_with('(')
+        && let Some(end_paren_idx) = text_after_prefix.find(')')
+    {
+        text_after_prefix = text_after_prefix[end_paren_idx + 1..].trim_start();
+    }
+    text_after_prefix
+}
+
+/// 构建用于在一行中查找所有 "角色：" 标记的正则表达式。
+fn build_credit_regex(keywords: &[String], case_sensitive: bool) -> Option<Regex> {
+    let mut sorted_keywords: Vec<&String> =
+        keywords.iter().filter(|k| !k.trim().is_empty()).collect();
+    // 较长的关键词优先匹配，例如 "编曲Arranged By" 优先于 "编曲"
+    sorted_keywords.sort_by_key(|k| std::cmp::Reverse(k.chars().count()));
+    if sorted_keywords.is_empty() {
+        return None;
+    }
+
+    let alternation = sorted_keywords
+        .iter()
+        .map(|k| regex::escape(k))
+        .collect::<Vec<_>>()
+        .join("|");
+    let pattern = format!(r"(?:^|[\s/|｜,，;；])(?P<role>{alternation})\s*[:：]");
+    get_cached_regex(&pattern, case_sensitive)
+}
+
+/// 将一行演职员信息解析为若干条记录。
+///
+/// 一行中可以包含多个角色，例如 "作词：A / 作曲：B、C"。
+/// 名字之间可以用 `/`、`、` 或 `&` 分隔。
+fn parse_credit_line(text: &str, credit_regex: &Regex) -> Vec<CreditEntry> {
+    let text = skip_bracketed_prefix(text);
+    let markers: Vec<(&str, usize, usize)> = credit_regex
+        .captures_iter(text)
+        .filter_map(|caps| {
+            let role = caps.name("role")?;
+            let whole = caps.get(0)?;
+            Some((role.as_str(), whole.start(), whole.end()))
+        })
+        .collect();
+
+    markers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &(role, _, names_start))| {
+            let names_end = markers.get(i + 1).map_or(text.len(), |next| next.1);
+            let names: Vec<String> = text[names_start..names_end]
+                .split(['/', '／', '、', '&', '＆'])
+                .map(|name| name.trim_matches(|c: char| c.is_whitespace() || c == '|' || c == '｜'))
+                .filter(|name| !name.is_empty())
+                .map(ToString::to_string)
+                .collect();
+            (!names.is_empty()).then(|| CreditEntry {
+                role: role.trim().to_string(),
+                names,
+            })
+        })
+        .collect()
+}
+
 /// 从 `LyricLine` 列表中移除元数据行。
+///
+/// # 返回
+/// 如果启用了 `MetadataStripperFlags::EXTRACT_CREDITS`，返回从被移除的演职员信息行中
+/// 解析出的记录；否则返回空列表。
 pub fn strip_descriptive_metadata_lines(
     lines: &mut Vec<LyricLine>,
     options: &MetadataStripperOptions,
-) {
+) -> Vec<CreditEntry> {
+    let mut credits = Vec::new();
+
     if !options.flags.contains(MetadataStripperFlags::ENABLED) {
         trace!("[MetadataStripper] 功能被禁用，跳过处理。");
-        return;
+        return credits;
     }
 
     let default_keywords: &[&str] = &[
@@ -349,13 +496,27 @@ pub fn strip_descriptive_metadata_lines(
                 .contains(MetadataStripperFlags::ENABLE_REGEX_STRIPPING)
                 || regex_to_use.is_empty()))
     {
-        return;
+        return credits;
     }
 
     let original_count = lines.len();
 
     // 基于关键词的移除
     if !keywords_to_use.is_empty() {
+        let credit_regex = if options
+            .flags
+            .contains(MetadataStripperFlags::EXTRACT_CREDITS)
+        {
+            build_credit_regex(
+                &keywords_to_use,
+                options
+                    .flags
+                    .contains(MetadataStripperFlags::KEYWORD_CASE_SENSITIVE),
+            )
+        } else {
+            None
+        };
+
         let prepared_keywords: Cow<'_, [String]> = if options
             .flags
             .contains(MetadataStripperFlags::KEYWORD_CASE_SENSITIVE)
@@ -366,16 +527,7 @@ pub fn strip_descriptive_metadata_lines(
         };
 
         let line_matches_keyword_rule = |line_to_check: &str| -> bool {
-            let mut text_after_prefix = line_to_check.trim_start();
-            if text_after_prefix.starts_with('[') {
-                if let Some(end_bracket_idx) = text_after_prefix.find(']') {
-                    text_after_prefix = text_after_prefix[end_bracket_idx + 1..].trim_start();
-                }
-            } else if text_after_prefix.starts_with('(')
-                && let Some(end_paren_idx) = text_after_prefix.find(')')
-            {
-                text_after_prefix = text_after_prefix[end_paren_idx + 1..].trim_start();
-            }
+            let text_after_prefix = skip_bracketed_prefix(line_to_check);
 
             // 决定是否忽略大小写
             let prepared_line: Cow<str> = if options
@@ -428,6 +580,19 @@ pub fn strip_descriptive_metadata_lines(
             last_lyric_line_exclusive_index = first_lyric_line_index;
         }
 
+        // 在移除前，从将被移除的演职员信息行中提取结构化数据
+        if let Some(credit_regex) = &credit_regex {
+            for (i, line_item) in lines.iter().enumerate() {
+                if i >= first_lyric_line_index && i < last_lyric_line_exclusive_index {
+                    continue;
+                }
+                let line_text = get_plain_text_from_new_lyric_line(line_item);
+                if line_matches_keyword_rule(&line_text) {
+                    credits.extend(parse_credit_line(&line_text, credit_regex));
+                }
+            }
+        }
+
         if first_lyric_line_index < last_lyric_line_exclusive_index {
             // 先移除尾部，这样不会影响头部的索引
             lines.drain(last_lyric_line_exclusive_index..);
@@ -486,4 +651,103 @@ pub fn strip_descriptive_metadata_lines(
             lines.len()
         );
     }
+
+    credits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_credits_before_stripping() {
+        let mut lines: Vec<LyricLine> = [
+            "作词：A / B",
+            "作曲 : C、D 编曲：E",
+            "Produced by: F & G",
+            "第一句歌词",
+            "第二句歌词",
+        ]
+        .into_iter()
+        .map(|text| {
+            let mut line = LyricLine::default();
+            line.add_content_track(ContentType::Main, text);
+            line
+        })
+        .collect();
+        let options = MetadataStripperOptions {
+            flags: MetadataStripperFlags::default() | MetadataStripperFlags::EXTRACT_CREDITS,
+            keywords: Some(vec![
+                "作词".to_string(),
+                "作曲".to_string(),
+                "编曲".to_string(),
+                "Produced by".to_string(),
+            ]),
+            ..Default::default()
+        };
+
+        let credits = strip_descriptive_metadata_lines(&mut lines, &options);
+
+        assert_eq!(lines.len(), 2);
+        let as_pairs: Vec<(&str, Vec<&str>)> = credits
+            .iter()
+            .map(|c| {
+                (
+                    c.role.as_str(),
+                    c.names.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            as_pairs,
+            vec![
+                ("作词", vec!["A", "B"]),
+                ("作曲", vec!["C", "D"]),
+                ("编曲", vec!["E"]),
+                ("Produced by", vec!["F", "G"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_credits_into_metadata() {
+        let credits = vec![
+            CreditEntry {
+                role: "词曲".to_string(),
+                names: vec!["A".to_string()],
+            },
+            CreditEntry {
+                role: "Arranged By".to_string(),
+                names: vec!["B".to_string()],
+            },
+            CreditEntry {
+                role: "和声".to_string(),
+                names: vec!["C".to_string()],
+            },
+        ];
+        let mut raw_metadata = HashMap::new();
+        merge_credits_into_metadata(&credits, &mut raw_metadata);
+
+        assert_eq!(raw_metadata["Lyricist"], vec!["A"]);
+        assert_eq!(raw_metadata["Composer"], vec!["A"]);
+        assert_eq!(raw_metadata["Songwriter"], vec!["A"]);
+        assert_eq!(raw_metadata["Arranger"], vec!["B"]);
+        assert_eq!(raw_metadata["和声"], vec!["C"]);
+    }
+
+    #[test]
+    fn test_credits_not_extracted_without_flag() {
+        let mut lines: Vec<LyricLine> = ["作词：A", "歌词", "另一句歌词"]
+            .into_iter()
+            .map(|text| {
+                let mut line = LyricLine::default();
+                line.add_content_track(ContentType::Main, text);
+                line
+            })
+            .collect();
+        let credits =
+            strip_descriptive_metadata_lines(&mut lines, &MetadataStripperOptions::default());
+        assert!(credits.is_empty());
+        assert_eq!(lines.len(), 2);
+    }
 }
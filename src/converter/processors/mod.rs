@@ -5,4 +5,9 @@ pub mod batch_processor;
 pub mod chinese_conversion_processor;
 pub mod metadata_processor;
 pub mod metadata_stripper;
+pub mod song_structure;
 pub mod syllable_smoothing;
+pub mod text_normalizer;
+pub mod timing_linter;
+pub mod ttml_canonicalizer;
+pub mod ttml_validator;
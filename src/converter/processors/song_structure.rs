@@ -0,0 +1,346 @@
+//! 歌曲结构识别器。
+//!
+//! 通过规范化文本的相似度和行间的时间间隔找出重复的段落，
+//! 并据此为歌词行标记 Intro、Verse、PreChorus、Chorus、Bridge、Outro 等组成部分。
+
+use std::{collections::BTreeSet, ops::Range};
+
+use tracing::debug;
+
+use crate::converter::types::{LyricLine, SongPart, SongStructureOptions};
+
+/// 识别歌曲结构并填充 `LyricLine::song_part`，然后应用用户指定的覆盖标记。
+///
+/// 如果 `options.enabled` 为 `false`，只会应用 `options.overrides`。
+/// 如果源文件已经带有标记且未设置 `overwrite_existing`，则跳过自动识别。
+/// 如果歌词中找不到任何重复段落，自动识别不会写入任何标记。
+pub fn apply_song_structure(lines: &mut [LyricLine], options: &SongStructureOptions) {
+    if options.enabled
+        && (options.overwrite_existing || lines.iter().all(|line| line.song_part.is_none()))
+    {
+        if let Some(parts) = detect_song_structure(lines, options) {
+            for (line, part) in lines.iter_mut().zip(parts) {
+                line.song_part = Some(part.as_str().to_string());
+            }
+        } else {
+            debug!("[SongStructure] 未找到重复段落，跳过结构标记。");
+        }
+    }
+
+    for line in lines.iter_mut() {
+        if let Some(song_override) = options
+            .overrides
+            .iter()
+            .rev()
+            .find(|o| (o.start_ms..o.end_ms).contains(&line.start_ms))
+        {
+            line.song_part = Some(song_override.part.clone());
+        }
+    }
+}
+
+/// 为每一行推断所属的歌曲组成部分。
+///
+/// # 返回
+/// 与 `lines` 一一对应的标记列表。如果找不到可作为副歌的重复段落，返回 `None`。
+#[must_use]
+pub fn detect_song_structure(
+    lines: &[LyricLine],
+    options: &SongStructureOptions,
+) -> Option<Vec<SongPart>> {
+    let texts: Vec<String> = lines
+        .iter()
+        .map(|line| normalize_for_comparison(&line.main_text().unwrap_or_default()))
+        .collect();
+    let line_count = texts.len();
+
+    let similar: Vec<Vec<bool>> = texts
+        .iter()
+        .map(|a| {
+            texts
+                .iter()
+                .map(|b| {
+                    !a.is_empty()
+                        && !b.is_empty()
+                        && strsim::normalized_levenshtein(a, b) >= options.similarity_threshold
+                })
+                .collect()
+        })
+        .collect();
+
+    let segments = find_segments(lines, &similar, options);
+    let clusters = cluster_segments(&segments, &similar);
+
+    let cluster_count = clusters.iter().max().map_or(0, |max| max + 1);
+    let mut cluster_sizes = vec![0usize; cluster_count];
+    let mut cluster_lines = vec![0usize; cluster_count];
+    for (segment, &cluster) in segments.iter().zip(&clusters) {
+        cluster_sizes[cluster] += 1;
+        cluster_lines[cluster] += segment.len();
+    }
+
+    // 重复次数最多（其次是总行数最多）的段落被视为副歌
+    let chorus_cluster = (0..cluster_count)
+        .filter(|&c| cluster_sizes[c] >= 2)
+        .max_by_key(|&c| (cluster_sizes[c], cluster_lines[c]))?;
+    let chorus_len = cluster_lines[chorus_cluster] / cluster_sizes[chorus_cluster];
+
+    let is_chorus = |index: usize| clusters.get(index) == Some(&chorus_cluster);
+    let first_chorus = (0..segments.len()).find(|&i| is_chorus(i))?;
+    let last_chorus = (0..segments.len()).rfind(|&i| is_chorus(i))?;
+
+    let mut segment_parts = Vec::with_capacity(segments.len());
+    for (index, segment) in segments.iter().enumerate() {
+        let cluster = clusters[index];
+        let part = if cluster == chorus_cluster {
+            SongPart::Chorus
+        } else if cluster_sizes[cluster] >= 2 {
+            let occurrences: Vec<usize> = (0..segments.len())
+                .filter(|&i| clusters[i] == cluster)
+                .collect();
+            let before_chorus = occurrences.iter().filter(|&&i| is_chorus(i + 1)).count();
+            let after_chorus = occurrences
+                .iter()
+                .filter(|&&i| i > 0 && is_chorus(i - 1))
+                .count();
+            if before_chorus * 2 >= occurrences.len() {
+                SongPart::PreChorus
+            } else if after_chorus * 2 >= occurrences.len() {
+                // 紧跟在副歌后面的重复段落通常是副歌的延伸
+                SongPart::Chorus
+            } else {
+                SongPart::Verse
+            }
+        } else if index == 0
+            && index < first_chorus
+            && segment.len() <= 2
+            && segment.len() < chorus_len
+        {
+            SongPart::Intro
+        } else if index == segments.len() - 1 && index > last_chorus && segment.len() <= chorus_len
+        {
+            SongPart::Outro
+        } else if index > first_chorus
+            && index < last_chorus
+            && (first_chorus..index).filter(|&i| is_chorus(i)).count() >= 2
+        {
+            SongPart::Bridge
+        } else {
+            SongPart::Verse
+        };
+        segment_parts.push(part);
+    }
+
+    let mut parts = vec![SongPart::Verse; line_count];
+    for (segment, part) in segments.iter().zip(segment_parts) {
+        for line_part in &mut parts[segment.clone()] {
+            *line_part = part;
+        }
+    }
+    Some(parts)
+}
+
+/// 根据时间间隔和连续重复的行，把歌词切分为若干段落。
+fn find_segments(
+    lines: &[LyricLine],
+    similar: &[Vec<bool>],
+    options: &SongStructureOptions,
+) -> Vec<Range<usize>> {
+    let line_count = lines.len();
+    let mut boundaries: BTreeSet<usize> = BTreeSet::from([0, line_count]);
+
+    for i in 1..line_count {
+        let gap = lines[i].start_ms.saturating_sub(lines[i - 1].end_ms);
+        if gap >= options.section_gap_ms {
+            boundaries.insert(i);
+        }
+    }
+
+    let min_repeat_lines = options.min_repeat_lines.max(1);
+    for i in 0..line_count {
+        for j in i + 1..line_count {
+            // 只从一段连续重复的起点开始计算
+            if !similar[i][j] || (i > 0 && similar[i - 1][j - 1]) {
+                continue;
+            }
+            let mut run = 0;
+            while i + run < j && j + run < line_count && similar[i + run][j + run] {
+                run += 1;
+            }
+            if run >= min_repeat_lines {
+                boundaries.extend([i, i + run, j, j + run]);
+            }
+        }
+    }
+
+    let boundaries: Vec<usize> = boundaries.into_iter().collect();
+    boundaries
+        .windows(2)
+        .map(|w| w[0]..w[1])
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+/// 将文本相似的段落归入同一组，返回每个段落所属的组编号。
+fn cluster_segments(segments: &[Range<usize>], similar: &[Vec<bool>]) -> Vec<usize> {
+    let mut representatives: Vec<usize> = Vec::new();
+    let mut clusters = Vec::with_capacity(segments.len());
+
+    for (index, segment) in segments.iter().enumerate() {
+        let cluster = representatives
+            .iter()
+            .position(|&rep| segments_are_similar(&segments[rep], segment, similar));
+        if let Some(cluster) = cluster {
+            clusters.push(cluster);
+        } else {
+            clusters.push(representatives.len());
+            representatives.push(index);
+        }
+    }
+    clusters
+}
+
+/// 判断两个段落是否重复：行数最多相差一行，且逐行对齐后大部分行都相似。
+#[allow(clippy::cast_precision_loss)]
+fn segments_are_similar(a: &Range<usize>, b: &Range<usize>, similar: &[Vec<bool>]) -> bool {
+    if a.len().abs_diff(b.len()) > 1 {
+        return false;
+    }
+    let compared = a.len().min(b.len());
+    if compared == 0 {
+        return false;
+    }
+    let matched = a
+        .clone()
+        .zip(b.clone())
+        .filter(|&(i, j)| similar[i][j])
+        .count();
+    matched as f64 / compared as f64 >= 0.75
+}
+
+/// 规范化用于比较的文本：折叠全角字符、转为小写，并只保留字母和数字。
+fn normalize_for_comparison(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::types::{ContentType, SongPartOverride};
+
+    fn build_lines(texts: &[&str]) -> Vec<LyricLine> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let start_ms = i as u64 * 3000;
+                let mut line = LyricLine::new(start_ms, start_ms + 2500);
+                line.add_content_track(ContentType::Main, *text);
+                line
+            })
+            .collect()
+    }
+
+    fn enabled_options() -> SongStructureOptions {
+        SongStructureOptions {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_detect_verse_chorus_bridge() {
+        let lines = build_lines(&[
+            "Walking down the empty street",
+            "Nobody here but me",
+            "Hold on, hold on to the light",
+            "We will be alright tonight",
+            "Morning comes without a sound",
+            "Feet are barely on the ground",
+            "Hold on, hold on to the light",
+            "We will be alright tonight",
+            "Every road leads back to you",
+            "Hold on, hold on to the light",
+            "We will be alright tonight",
+        ]);
+
+        let parts = detect_song_structure(&lines, &enabled_options()).unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                SongPart::Verse,
+                SongPart::Verse,
+                SongPart::Chorus,
+                SongPart::Chorus,
+                SongPart::Verse,
+                SongPart::Verse,
+                SongPart::Chorus,
+                SongPart::Chorus,
+                SongPart::Bridge,
+                SongPart::Chorus,
+                SongPart::Chorus,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_pre_chorus() {
+        let lines = build_lines(&[
+            "清晨的阳光照进窗",
+            "风吹过安静的街道",
+            "就快要到了",
+            "你听见了吗",
+            "副歌在这里",
+            "副歌第二句",
+            "夜晚的星星在闪烁",
+            "我们走在回家路上",
+            "就快要到了",
+            "你听见了吗",
+            "副歌在这里",
+            "副歌第二句",
+            "副歌在这里",
+            "副歌第二句",
+        ]);
+
+        let parts = detect_song_structure(&lines, &enabled_options()).unwrap();
+        assert_eq!(parts[2], SongPart::PreChorus);
+        assert_eq!(parts[4], SongPart::Chorus);
+        assert_eq!(parts[6], SongPart::Verse);
+        assert_eq!(parts[13], SongPart::Chorus);
+    }
+
+    #[test]
+    fn test_no_repetition_leaves_lines_untouched() {
+        let mut lines = build_lines(&["一", "二", "三"]);
+        apply_song_structure(&mut lines, &enabled_options());
+        assert!(lines.iter().all(|line| line.song_part.is_none()));
+    }
+
+    #[test]
+    fn test_existing_labels_and_overrides() {
+        let mut lines = build_lines(&["A line", "Another line", "A line", "Another line"]);
+        lines[0].song_part = Some("Verse".to_string());
+
+        let options = SongStructureOptions {
+            overrides: vec![SongPartOverride {
+                start_ms: 3000,
+                end_ms: 6001,
+                part: "Hook".to_string(),
+            }],
+            ..enabled_options()
+        };
+        apply_song_structure(&mut lines, &options);
+
+        assert_eq!(lines[0].song_part.as_deref(), Some("Verse"));
+        assert_eq!(lines[1].song_part.as_deref(), Some("Hook"));
+        assert_eq!(lines[2].song_part.as_deref(), Some("Hook"));
+        assert_eq!(lines[3].song_part, None);
+    }
+}
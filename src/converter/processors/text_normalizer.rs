@@ -0,0 +1,368 @@
+//! 文本规范化处理器。
+//!
+//! 统一不同来源歌词中混用的全角/半角字符、引号、零宽字符和空白，
+//! 并可以在中日韩文字与拉丁字母之间插入空格。
+//!
+//! 所有处理都在音节内部或音节边界（`ends_with_space`）上进行，不会修改任何时间戳。
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::converter::{
+    types::{
+        LyricLine, LyricSyllable, LyricTrack, QuoteStyle, TextNormalizationFlags,
+        TextNormalizationOptions, WidthFolding,
+    },
+    utils::is_cjk,
+};
+
+/// 对所有歌词行的所有轨道（主歌词、背景人声、翻译、罗马音）进行文本规范化。
+///
+/// 如果未设置 `TextNormalizationFlags::ENABLED`，不做任何处理。
+pub fn normalize_lines(lines: &mut [LyricLine], options: &TextNormalizationOptions) {
+    if !options.flags.contains(TextNormalizationFlags::ENABLED) {
+        return;
+    }
+
+    for line in lines.iter_mut() {
+        for annotated_track in &mut line.tracks {
+            normalize_track(&mut annotated_track.content, options);
+            for track in annotated_track
+                .translations
+                .iter_mut()
+                .chain(annotated_track.romanizations.iter_mut())
+            {
+                normalize_track(track, options);
+            }
+        }
+    }
+}
+
+/// 规范化单个轨道。
+pub fn normalize_track(track: &mut LyricTrack, options: &TextNormalizationOptions) {
+    let mut quote_state = QuoteState::default();
+    for syllable in track.words.iter_mut().flat_map(|w| &mut w.syllables) {
+        syllable.text = normalize_text(&syllable.text, options, &mut quote_state);
+    }
+
+    if options
+        .flags
+        .contains(TextNormalizationFlags::COLLAPSE_WHITESPACE)
+    {
+        move_whitespace_to_boundaries(track);
+    }
+
+    if options
+        .flags
+        .contains(TextNormalizationFlags::PANGU_SPACING)
+    {
+        apply_pangu_spacing_between_syllables(track);
+    }
+}
+
+/// 规范化一段文本中不涉及音节边界的部分。
+fn normalize_text(
+    text: &str,
+    options: &TextNormalizationOptions,
+    quote_state: &mut QuoteState,
+) -> String {
+    let mut chars: Vec<char> = text.chars().collect();
+
+    if options
+        .flags
+        .contains(TextNormalizationFlags::REMOVE_ZERO_WIDTH)
+    {
+        chars.retain(|&c| !is_zero_width(c));
+    }
+    if options.flags.contains(TextNormalizationFlags::UNICODE_NFC) {
+        chars = chars.into_iter().nfc().collect();
+    }
+    if options.width_folding != WidthFolding::Keep {
+        for c in &mut chars {
+            *c = fold_width(*c, options.width_folding);
+        }
+    }
+    if options.quote_style != QuoteStyle::Keep {
+        convert_quotes(&mut chars, options.quote_style, quote_state);
+    }
+    if options
+        .flags
+        .contains(TextNormalizationFlags::COLLAPSE_WHITESPACE)
+    {
+        chars = collapse_inner_whitespace(&chars);
+    }
+    if options
+        .flags
+        .contains(TextNormalizationFlags::PANGU_SPACING)
+    {
+        chars = insert_pangu_spaces(&chars);
+    }
+
+    chars.into_iter().collect()
+}
+
+/// 判断是否为需要移除的零宽字符。
+///
+/// 零宽连接符（U+200D）和零宽非连接符（U+200C）会影响表情符号序列和
+/// 部分文字的字形，因此予以保留。
+fn is_zero_width(c: char) -> bool {
+    matches!(c, '\u{200B}' | '\u{2060}' | '\u{FEFF}')
+}
+
+fn fold_width(c: char, folding: WidthFolding) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => {
+            let folded = char::from_u32(c as u32 - 0xFEE0).unwrap_or(c);
+            if folding == WidthFolding::All || folded.is_ascii_alphanumeric() {
+                folded
+            } else {
+                c
+            }
+        }
+        _ => c,
+    }
+}
+
+/// 记录弯引号的开闭状态，在同一轨道的音节之间共享。
+#[derive(Debug, Default)]
+struct QuoteState {
+    double_open: bool,
+    single_open: bool,
+}
+
+fn convert_quotes(chars: &mut [char], style: QuoteStyle, state: &mut QuoteState) {
+    for i in 0..chars.len() {
+        let c = chars[i];
+        chars[i] = match style {
+            QuoteStyle::Keep => c,
+            QuoteStyle::Straight => match c {
+                '“' | '”' | '„' | '‟' | '＂' => '"',
+                '‘' | '’' | '‚' | '‛' | '＇' => '\'',
+                _ => c,
+            },
+            QuoteStyle::Curly => match c {
+                '"' | '＂' => {
+                    state.double_open = !state.double_open;
+                    if state.double_open { '“' } else { '”' }
+                }
+                '\'' | '＇' => {
+                    let prev_is_letter = i > 0 && chars[i - 1].is_alphanumeric();
+                    let next_is_letter = chars.get(i + 1).is_some_and(|n| n.is_alphanumeric());
+                    if prev_is_letter && (next_is_letter || !state.single_open) {
+                        // 词中的撇号，例如 don't、rock 'n' roll 的结尾
+                        '’'
+                    } else {
+                        state.single_open = !state.single_open;
+                        if state.single_open { '‘' } else { '’' }
+                    }
+                }
+                _ => c,
+            },
+        };
+    }
+}
+
+/// 将连续的空白合并为一个普通空格。首尾空白保留，由 `move_whitespace_to_boundaries` 处理。
+fn collapse_inner_whitespace(chars: &[char]) -> Vec<char> {
+    let mut result = Vec::with_capacity(chars.len());
+    for &c in chars {
+        if c.is_whitespace() {
+            if result.last() != Some(&' ') {
+                result.push(' ');
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// 把音节首尾的空白转移到 `ends_with_space` 上，移除只含空白的音节，
+/// 并去除整个轨道的首尾空白。
+fn move_whitespace_to_boundaries(track: &mut LyricTrack) {
+    let mut previous: Option<&mut LyricSyllable> = None;
+    for syllable in track.words.iter_mut().flat_map(|w| &mut w.syllables) {
+        if syllable.text.starts_with(char::is_whitespace)
+            && let Some(prev) = previous.as_deref_mut()
+        {
+            prev.ends_with_space = true;
+        }
+        if syllable.text.ends_with(char::is_whitespace) {
+            syllable.ends_with_space = true;
+        }
+        let trimmed = syllable.text.trim();
+        if trimmed.len() != syllable.text.len() {
+            syllable.text = trimmed.to_string();
+        }
+
+        if syllable.text.is_empty() {
+            // 只含空白的音节会被移除，把它的空格记到前一个音节上
+            if let Some(prev) = previous.as_deref_mut() {
+                prev.ends_with_space |= syllable.ends_with_space;
+            }
+        } else {
+            previous = Some(syllable);
+        }
+    }
+
+    for word in &mut track.words {
+        word.syllables.retain(|s| !s.text.is_empty());
+    }
+    track.words.retain(|w| !w.syllables.is_empty());
+
+    if let Some(last) = track.words.last_mut().and_then(|w| w.syllables.last_mut()) {
+        last.ends_with_space = false;
+    }
+}
+
+fn is_pangu_latin(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '\u{00C0}'..='\u{024F}')
+}
+
+fn needs_pangu_space(left: char, right: char) -> bool {
+    (is_cjk(left) && is_pangu_latin(right)) || (is_pangu_latin(left) && is_cjk(right))
+}
+
+fn insert_pangu_spaces(chars: &[char]) -> Vec<char> {
+    let mut result = Vec::with_capacity(chars.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if i > 0 && needs_pangu_space(chars[i - 1], c) {
+            result.push(' ');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// 在相邻音节的边界上应用 pangu 空格，通过 `ends_with_space` 表示。
+fn apply_pangu_spacing_between_syllables(track: &mut LyricTrack) {
+    let mut syllables: Vec<&mut LyricSyllable> = track
+        .words
+        .iter_mut()
+        .flat_map(|w| &mut w.syllables)
+        .collect();
+
+    for i in 1..syllables.len() {
+        let (left_part, right_part) = syllables.split_at_mut(i);
+        let left = &mut *left_part[i - 1];
+        let right = &right_part[0];
+        if left.ends_with_space {
+            continue;
+        }
+        if let (Some(l), Some(r)) = (left.text.chars().last(), right.text.chars().next())
+            && needs_pangu_space(l, r)
+        {
+            left.ends_with_space = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::types::Word;
+
+    fn track_from(parts: &[&str]) -> LyricTrack {
+        LyricTrack {
+            words: vec![Word {
+                syllables: parts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, text)| LyricSyllable {
+                        text: (*text).to_string(),
+                        start_ms: i as u64 * 100,
+                        end_ms: i as u64 * 100 + 100,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn enabled_options() -> TextNormalizationOptions {
+        TextNormalizationOptions {
+            flags: TextNormalizationFlags::default() | TextNormalizationFlags::ENABLED,
+            ..Default::default()
+        }
+    }
+
+    fn texts_and_spaces(track: &LyricTrack) -> Vec<(String, bool)> {
+        track
+            .words
+            .iter()
+            .flat_map(|w| &w.syllables)
+            .map(|s| (s.text.clone(), s.ends_with_space))
+            .collect()
+    }
+
+    #[test]
+    fn test_whitespace_moves_to_ends_with_space_without_touching_timing() {
+        let mut track = track_from(&["  Hello ", " ", "\u{200B}wor", "ld\u{3000} "]);
+        normalize_track(&mut track, &enabled_options());
+
+        assert_eq!(
+            texts_and_spaces(&track),
+            vec![
+                ("Hello".to_string(), true),
+                ("wor".to_string(), false),
+                ("ld".to_string(), false),
+            ]
+        );
+        let starts: Vec<u64> = track.words[0]
+            .syllables
+            .iter()
+            .map(|s| s.start_ms)
+            .collect();
+        assert_eq!(starts, vec![0, 200, 300]);
+    }
+
+    #[test]
+    fn test_width_quotes_and_pangu() {
+        let options = TextNormalizationOptions {
+            width_folding: WidthFolding::Alphanumeric,
+            quote_style: QuoteStyle::Curly,
+            flags: enabled_options().flags | TextNormalizationFlags::PANGU_SPACING,
+        };
+        let mut track = track_from(&["我爱ＲＯＣＫ", "，", "\"don't", "stop\"", "你"]);
+        normalize_track(&mut track, &options);
+
+        assert_eq!(
+            texts_and_spaces(&track),
+            vec![
+                ("我爱 ROCK".to_string(), false),
+                ("，".to_string(), false),
+                ("“don’t".to_string(), false),
+                ("stop”".to_string(), false),
+                ("你".to_string(), false),
+            ]
+        );
+
+        let mut track = track_from(&["周杰伦", "Jay"]);
+        normalize_track(&mut track, &options);
+        assert!(track.words[0].syllables[0].ends_with_space);
+    }
+
+    #[test]
+    fn test_nfc_composition() {
+        let mut track = track_from(&["Cafe\u{0301}", "か\u{3099}", "e\u{0323}\u{0302}"]);
+        normalize_track(&mut track, &enabled_options());
+        let texts: Vec<String> = texts_and_spaces(&track)
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        assert_eq!(texts, vec!["Café", "が", "ệ"]);
+    }
+
+    #[test]
+    fn test_zero_width_joiners_are_kept() {
+        let mut track = track_from(&["\u{FEFF}a\u{200B}b\u{2060}", "👨\u{200D}👩", "क\u{200C}्"]);
+        normalize_track(&mut track, &enabled_options());
+        let texts: Vec<String> = texts_and_spaces(&track)
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        assert_eq!(texts, vec!["ab", "👨\u{200D}👩", "क\u{200C}्"]);
+    }
+}
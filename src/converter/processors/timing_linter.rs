@@ -0,0 +1,487 @@
+//! 时间轴检查与修复器。
+//!
+//! 各提供商返回的歌词经常带有行重叠、音节超出行范围、零时长或结束时间早于开始时间等问题。
+//! 本模块提供只读的检查函数 [`lint_timing`]，以及可选的修复函数 [`repair_timing`]。
+
+use std::fmt;
+
+use tracing::debug;
+
+use crate::converter::types::{
+    LyricLine, LyricSyllable, LyricTrack, OverlapRepairStrategy, TimingRepairOptions,
+};
+
+/// 时间轴问题所在的位置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingIssueTarget {
+    /// 整行。
+    Line,
+    /// 行内某个内容轨道（主歌词或背景人声）中的一个音节。
+    Syllable {
+        /// 轨道在 `LyricLine::tracks` 中的索引。
+        track_index: usize,
+        /// 词组在轨道中的索引。
+        word_index: usize,
+        /// 音节在词组中的索引。
+        syllable_index: usize,
+    },
+}
+
+/// 时间轴问题的类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingIssueKind {
+    /// 结束时间早于开始时间。
+    EndBeforeStart,
+    /// 时长为零。
+    ZeroDuration,
+    /// 音节超出了所在行的时间范围。
+    SyllableOutsideLine,
+    /// 与后面某一行的时间重叠。
+    LineOverlap {
+        /// 发生重叠的另一行的索引。
+        next_line_index: usize,
+        /// 重叠的时长（毫秒）。
+        overlap_ms: u64,
+    },
+    /// 行的开始时间早于上一行，即歌词行未按时间排序。
+    OutOfOrder,
+}
+
+/// 一条时间轴检查结果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimingIssue {
+    /// 问题所在行的索引。
+    pub line_index: usize,
+    /// 问题所在的具体位置。
+    pub target: TimingIssueTarget,
+    /// 问题类型。
+    pub kind: TimingIssueKind,
+    /// 出问题的行或音节的开始时间（毫秒）。
+    pub start_ms: u64,
+    /// 出问题的行或音节的结束时间（毫秒）。
+    pub end_ms: u64,
+}
+
+impl fmt::Display for TimingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "第 {} 行", self.line_index + 1)?;
+        if let TimingIssueTarget::Syllable {
+            track_index,
+            word_index,
+            syllable_index,
+        } = self.target
+        {
+            write!(
+                f,
+                " (轨道 {track_index}, 词组 {word_index}, 音节 {syllable_index})"
+            )?;
+        }
+        write!(f, " [{}ms - {}ms]: ", self.start_ms, self.end_ms)?;
+        match self.kind {
+            TimingIssueKind::EndBeforeStart => write!(f, "结束时间早于开始时间"),
+            TimingIssueKind::ZeroDuration => write!(f, "时长为零"),
+            TimingIssueKind::SyllableOutsideLine => write!(f, "音节超出了行的时间范围"),
+            TimingIssueKind::LineOverlap {
+                next_line_index,
+                overlap_ms,
+            } => write!(f, "与第 {} 行重叠了 {overlap_ms}ms", next_line_index + 1),
+            TimingIssueKind::OutOfOrder => write!(f, "开始时间早于上一行"),
+        }
+    }
+}
+
+/// 检查歌词行的时间轴，返回发现的所有问题。
+///
+/// 只检查内容轨道（主歌词和背景人声）的音节，翻译和罗马音轨道通常没有独立的时间信息。
+///
+/// # 参数
+/// * `lines` - 要检查的歌词行。
+/// * `options` - 检查选项，主要用于决定是否允许不同演唱者的行互相重叠。
+#[must_use]
+pub fn lint_timing(lines: &[LyricLine], options: &TimingRepairOptions) -> Vec<TimingIssue> {
+    let mut issues = Vec::new();
+
+    for (line_index, line) in lines.iter().enumerate() {
+        let line_issue = |kind| TimingIssue {
+            line_index,
+            target: TimingIssueTarget::Line,
+            kind,
+            start_ms: line.start_ms,
+            end_ms: line.end_ms,
+        };
+
+        if line_index > 0 && line.start_ms < lines[line_index - 1].start_ms {
+            issues.push(line_issue(TimingIssueKind::OutOfOrder));
+        }
+
+        let line_bounds_valid = match line.end_ms.cmp(&line.start_ms) {
+            std::cmp::Ordering::Less => {
+                issues.push(line_issue(TimingIssueKind::EndBeforeStart));
+                false
+            }
+            std::cmp::Ordering::Equal => {
+                issues.push(line_issue(TimingIssueKind::ZeroDuration));
+                false
+            }
+            std::cmp::Ordering::Greater => true,
+        };
+
+        if let Some(next_line_index) = find_overlap_partner(lines, line_index, options) {
+            let next_line = &lines[next_line_index];
+            if line_bounds_valid && next_line.start_ms < line.end_ms {
+                issues.push(line_issue(TimingIssueKind::LineOverlap {
+                    next_line_index,
+                    overlap_ms: line.end_ms - next_line.start_ms,
+                }));
+            }
+        }
+
+        for (track_index, track) in line.tracks.iter().enumerate() {
+            for (word_index, word) in track.content.words.iter().enumerate() {
+                for (syllable_index, syllable) in word.syllables.iter().enumerate() {
+                    let syllable_issue = |kind| TimingIssue {
+                        line_index,
+                        target: TimingIssueTarget::Syllable {
+                            track_index,
+                            word_index,
+                            syllable_index,
+                        },
+                        kind,
+                        start_ms: syllable.start_ms,
+                        end_ms: syllable.end_ms,
+                    };
+
+                    if syllable.end_ms < syllable.start_ms {
+                        issues.push(syllable_issue(TimingIssueKind::EndBeforeStart));
+                    } else if syllable.end_ms == syllable.start_ms {
+                        issues.push(syllable_issue(TimingIssueKind::ZeroDuration));
+                    }
+
+                    if line_bounds_valid
+                        && (syllable.start_ms < line.start_ms || syllable.end_ms > line.end_ms)
+                    {
+                        issues.push(syllable_issue(TimingIssueKind::SyllableOutsideLine));
+                    }
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+/// 检查并修复歌词行的时间轴问题。
+///
+/// 修复按以下顺序进行：
+/// 1. 按开始时间对歌词行进行稳定排序。
+/// 2. 为零时长或负时长的音节重新分配结束时间。
+/// 3. 为缺失结束时间的行，使用下一行的开始时间填充。
+/// 4. 按 `options.overlap_strategy` 截断或分割互相重叠的行。
+/// 5. 将音节限制在所在行的范围内，并根据音节重新计算行的起止时间。
+///
+/// # 返回
+/// 修复前检查到的所有问题。如果没有问题，歌词行不会被修改。
+pub fn repair_timing(lines: &mut [LyricLine], options: &TimingRepairOptions) -> Vec<TimingIssue> {
+    let issues = lint_timing(lines, options);
+    if issues.is_empty() {
+        return issues;
+    }
+
+    lines.sort_by_key(|line| line.start_ms);
+
+    for line in lines.iter_mut() {
+        for track in &mut line.tracks {
+            repair_syllable_durations(&mut track.content, options.min_syllable_duration_ms);
+        }
+    }
+
+    for i in 0..lines.len() {
+        if lines[i].end_ms > lines[i].start_ms {
+            continue;
+        }
+        let start_ms = lines[i].start_ms;
+        let next_start = lines[i + 1..]
+            .iter()
+            .map(|line| line.start_ms)
+            .find(|&next_start| next_start > start_ms);
+        let syllable_end = content_span(&lines[i])
+            .map(|(_, end)| end)
+            .filter(|&end| end > start_ms);
+
+        lines[i].end_ms = next_start
+            .or(syllable_end)
+            .unwrap_or_else(|| start_ms.saturating_add(options.default_line_duration_ms));
+    }
+
+    for i in 0..lines.len() {
+        let Some(j) = find_overlap_partner(lines, i, options) else {
+            continue;
+        };
+        let (current_end, next_start, next_end) =
+            (lines[i].end_ms, lines[j].start_ms, lines[j].end_ms);
+        if next_start >= current_end || next_start <= lines[i].start_ms {
+            continue;
+        }
+
+        match options.overlap_strategy {
+            OverlapRepairStrategy::Split if next_end > current_end => {
+                let midpoint = next_start + (current_end - next_start) / 2;
+                lines[i].end_ms = midpoint;
+                lines[j].start_ms = midpoint;
+            }
+            _ => lines[i].end_ms = next_start,
+        }
+    }
+
+    for line in lines.iter_mut() {
+        clamp_syllables_to_line(line);
+        if let Some((start_ms, end_ms)) = content_span(line)
+            && end_ms > start_ms
+        {
+            line.start_ms = start_ms;
+            line.end_ms = end_ms;
+        }
+    }
+
+    debug!("[TimingLinter] 修复了 {} 个时间轴问题。", issues.len());
+
+    issues
+}
+
+/// 找到需要与第 `line_index` 行比较重叠的下一行。
+///
+/// 如果允许不同演唱者互相重叠，则找同一演唱者的下一行；否则直接取下一行。
+fn find_overlap_partner(
+    lines: &[LyricLine],
+    line_index: usize,
+    options: &TimingRepairOptions,
+) -> Option<usize> {
+    let agent = &lines[line_index].agent;
+    if options.allow_cross_agent_overlap {
+        lines[line_index + 1..]
+            .iter()
+            .position(|line| &line.agent == agent)
+            .map(|offset| line_index + 1 + offset)
+    } else {
+        (line_index + 1 < lines.len()).then_some(line_index + 1)
+    }
+}
+
+/// 计算一行中所有内容轨道音节覆盖的时间范围。
+fn content_span(line: &LyricLine) -> Option<(u64, u64)> {
+    line.tracks
+        .iter()
+        .flat_map(|track| &track.content.words)
+        .flat_map(|word| &word.syllables)
+        .fold(None, |span, syllable| match span {
+            None => Some((syllable.start_ms, syllable.end_ms)),
+            Some((start, end)) => Some((start.min(syllable.start_ms), end.max(syllable.end_ms))),
+        })
+}
+
+/// 为零时长或负时长的音节重新分配结束时间。
+///
+/// 新的结束时间为 `start_ms + min_duration_ms`，但不会超过下一个音节的开始时间。
+fn repair_syllable_durations(track: &mut LyricTrack, min_duration_ms: u64) {
+    let mut syllables: Vec<&mut LyricSyllable> = track
+        .words
+        .iter_mut()
+        .flat_map(|word| &mut word.syllables)
+        .collect();
+
+    for i in 0..syllables.len() {
+        if syllables[i].end_ms > syllables[i].start_ms {
+            continue;
+        }
+        let start_ms = syllables[i].start_ms;
+        let mut end_ms = start_ms.saturating_add(min_duration_ms);
+        if let Some(next) = syllables.get(i + 1)
+            && next.start_ms > start_ms
+        {
+            end_ms = end_ms.min(next.start_ms);
+        }
+        set_syllable_end(syllables[i], end_ms);
+    }
+}
+
+/// 将一行中所有内容轨道的音节限制在行的时间范围内。
+fn clamp_syllables_to_line(line: &mut LyricLine) {
+    let (line_start, line_end) = (line.start_ms, line.end_ms);
+    if line_end <= line_start {
+        return;
+    }
+
+    for syllable in line
+        .tracks
+        .iter_mut()
+        .flat_map(|track| &mut track.content.words)
+        .flat_map(|word| &mut word.syllables)
+    {
+        syllable.start_ms = syllable.start_ms.clamp(line_start, line_end);
+        let end_ms = syllable.end_ms.clamp(syllable.start_ms, line_end);
+        set_syllable_end(syllable, end_ms);
+    }
+}
+
+/// 设置音节的结束时间，并同步更新可选的 `duration_ms`。
+fn set_syllable_end(syllable: &mut LyricSyllable, end_ms: u64) {
+    syllable.end_ms = end_ms;
+    if syllable.duration_ms.is_some() {
+        syllable.duration_ms = Some(end_ms.saturating_sub(syllable.start_ms));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::types::{AnnotatedTrack, ContentType, Word};
+
+    fn syllable(text: &str, start_ms: u64, end_ms: u64) -> LyricSyllable {
+        LyricSyllable {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            ..Default::default()
+        }
+    }
+
+    fn new_line(start_ms: u64, end_ms: u64, syllables: Vec<LyricSyllable>) -> LyricLine {
+        LyricLine {
+            tracks: vec![AnnotatedTrack {
+                content_type: ContentType::Main,
+                content: LyricTrack {
+                    words: vec![Word {
+                        syllables,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            start_ms,
+            end_ms,
+            ..Default::default()
+        }
+    }
+
+    fn syllables_of(line: &LyricLine) -> Vec<(u64, u64)> {
+        line.tracks[0].content.words[0]
+            .syllables
+            .iter()
+            .map(|s| (s.start_ms, s.end_ms))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_clean_lines() {
+        let lines = vec![
+            new_line(
+                0,
+                1000,
+                vec![syllable("a", 0, 500), syllable("b", 500, 1000)],
+            ),
+            new_line(1000, 2000, vec![syllable("c", 1000, 2000)]),
+        ];
+        assert!(lint_timing(&lines, &TimingRepairOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_lint_detects_all_issue_kinds() {
+        let lines = vec![
+            new_line(
+                1000,
+                3000,
+                vec![syllable("a", 900, 1500), syllable("b", 2000, 1800)],
+            ),
+            new_line(2500, 2500, vec![syllable("c", 2500, 2500)]),
+            new_line(2000, 4000, vec![syllable("d", 2000, 4000)]),
+        ];
+        let kinds: Vec<_> = lint_timing(&lines, &TimingRepairOptions::default())
+            .into_iter()
+            .map(|issue| (issue.line_index, issue.kind))
+            .collect();
+
+        assert!(kinds.contains(&(0, TimingIssueKind::SyllableOutsideLine)));
+        assert!(kinds.contains(&(0, TimingIssueKind::EndBeforeStart)));
+        assert!(kinds.contains(&(
+            0,
+            TimingIssueKind::LineOverlap {
+                next_line_index: 1,
+                overlap_ms: 500
+            }
+        )));
+        assert!(kinds.contains(&(1, TimingIssueKind::ZeroDuration)));
+        assert!(kinds.contains(&(2, TimingIssueKind::OutOfOrder)));
+    }
+
+    #[test]
+    fn test_cross_agent_overlap_is_allowed_by_default() {
+        let mut first = new_line(0, 2000, vec![syllable("a", 0, 2000)]);
+        first.agent = Some("v1".to_string());
+        let mut second = new_line(1000, 3000, vec![syllable("b", 1000, 3000)]);
+        second.agent = Some("v2".to_string());
+        let lines = vec![first, second];
+
+        assert!(lint_timing(&lines, &TimingRepairOptions::default()).is_empty());
+
+        let strict = TimingRepairOptions {
+            allow_cross_agent_overlap: false,
+            ..Default::default()
+        };
+        assert_eq!(lint_timing(&lines, &strict).len(), 1);
+    }
+
+    #[test]
+    fn test_repair_trims_overlap_and_clamps_syllables() {
+        let mut lines = vec![
+            new_line(
+                0,
+                2000,
+                vec![syllable("a", 0, 800), syllable("b", 800, 2000)],
+            ),
+            new_line(1500, 3000, vec![syllable("c", 1500, 3000)]),
+        ];
+        let issues = repair_timing(&mut lines, &TimingRepairOptions::default());
+
+        assert!(!issues.is_empty());
+        assert_eq!((lines[0].start_ms, lines[0].end_ms), (0, 1500));
+        assert_eq!(syllables_of(&lines[0]), vec![(0, 800), (800, 1500)]);
+        assert_eq!((lines[1].start_ms, lines[1].end_ms), (1500, 3000));
+        assert!(lint_timing(&lines, &TimingRepairOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_repair_splits_overlap() {
+        let mut lines = vec![
+            new_line(0, 2000, vec![syllable("a", 0, 2000)]),
+            new_line(1000, 3000, vec![syllable("b", 1000, 3000)]),
+        ];
+        let options = TimingRepairOptions {
+            overlap_strategy: OverlapRepairStrategy::Split,
+            ..Default::default()
+        };
+        repair_timing(&mut lines, &options);
+
+        assert_eq!((lines[0].start_ms, lines[0].end_ms), (0, 1500));
+        assert_eq!((lines[1].start_ms, lines[1].end_ms), (1500, 3000));
+        assert_eq!(syllables_of(&lines[1]), vec![(1500, 3000)]);
+    }
+
+    #[test]
+    fn test_repair_fills_missing_end_and_bad_syllables() {
+        let mut lines = vec![
+            new_line(
+                1000,
+                0,
+                vec![syllable("a", 1000, 1000), syllable("b", 1020, 1500)],
+            ),
+            new_line(4000, 4000, vec![syllable("c", 4000, 3000)]),
+        ];
+        repair_timing(&mut lines, &TimingRepairOptions::default());
+
+        assert_eq!(syllables_of(&lines[0]), vec![(1000, 1020), (1020, 1500)]);
+        assert_eq!((lines[0].start_ms, lines[0].end_ms), (1000, 1500));
+        assert_eq!(syllables_of(&lines[1]), vec![(4000, 4050)]);
+        assert_eq!((lines[1].start_ms, lines[1].end_ms), (4000, 4050));
+    }
+}
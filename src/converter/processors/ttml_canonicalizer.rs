@@ -0,0 +1,511 @@
+//! TTML 规范化排版器。
+//!
+//! 在格式化（缩进）和压缩两种排版之间转换 TTML 文本，并统一属性顺序、命名空间前缀、
+//! 实体转义和空元素写法，使数据库不同修订版之间的 diff 只反映真正的内容变化。
+//!
+//! 转换不会改变歌词语义。按照规范 §6 的约定，只由空白组成且包含换行的文本节点
+//! 是排版用的空白，会被丢弃；其余文本（包括 `<span>` 之间不含换行的空格）原样保留。
+//!
+//! 格式化输出时，`<p>`、`<span>`、`<text>` 以及任何含有文本的元素都写在同一行内，
+//! 因此不会引入新的空白，输出在两种排版之间来回转换后保持不变。
+
+use std::{collections::HashMap, fmt::Write};
+
+use quick_xml::{
+    Reader,
+    events::{BytesDecl, BytesStart, Event},
+};
+
+use crate::converter::types::{ConvertError, TtmlCanonicalizeOptions, TtmlLayout};
+
+/// 已知命名空间 URI 及其规范前缀。
+const CANONICAL_PREFIXES: &[(&str, &str)] = &[
+    ("http://www.w3.org/ns/ttml#metadata", "ttm"),
+    ("http://www.w3.org/ns/ttml#styling", "tts"),
+    ("http://www.w3.org/ns/ttml#parameter", "ttp"),
+    ("http://music.apple.com/lyric-ttml-internal", "itunes"),
+    ("http://www.example.com/ns/amll", "amll"),
+];
+
+/// 格式化输出时总是写在同一行内的元素（按本地名匹配）。
+const INLINE_ELEMENTS: &[&str] = &["p", "span", "text"];
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+    Comment(String),
+    /// XML 声明、处理指令和 DOCTYPE，已序列化为最终文本。
+    Raw(String),
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn local_name(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or(&self.name)
+    }
+
+    fn is_inline(&self) -> bool {
+        INLINE_ELEMENTS.contains(&self.local_name())
+            || self.children.iter().any(|c| matches!(c, Node::Text(_)))
+    }
+}
+
+/// 一个元素作用域内的前缀绑定：原前缀 -> (URI, 输出前缀)。
+type PrefixScope = HashMap<String, (String, String)>;
+
+/// 将 TTML 文本规范化为指定的排版方式。
+///
+/// # 参数
+/// * `content` - TTML 文本。
+/// * `options` - 排版选项。
+///
+/// # 返回
+/// 规范化后的 TTML 文本；输入不是合法的 XML 时返回错误。
+pub fn canonicalize_ttml(
+    content: &str,
+    options: &TtmlCanonicalizeOptions,
+) -> Result<String, ConvertError> {
+    let mut nodes = parse_nodes(content.trim_start_matches('\u{feff}'))?;
+
+    strip_formatting_whitespace(&mut nodes);
+    // 根元素之外的文本没有意义
+    nodes.retain(|n| !matches!(n, Node::Text(_)));
+
+    let mut output = String::with_capacity(content.len());
+    match options.layout {
+        TtmlLayout::Formatted => {
+            for node in &nodes {
+                write_formatted(&mut output, node, 0, options.indent_size);
+                output.push('\n');
+            }
+        }
+        TtmlLayout::Minified => {
+            for node in &nodes {
+                write_inline(&mut output, node);
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// 将 TTML 文本规范化为默认缩进的格式化排版。
+pub fn format_ttml(content: &str) -> Result<String, ConvertError> {
+    canonicalize_ttml(content, &TtmlCanonicalizeOptions::default())
+}
+
+/// 将 TTML 文本规范化为压缩排版。
+pub fn minify_ttml(content: &str) -> Result<String, ConvertError> {
+    canonicalize_ttml(
+        content,
+        &TtmlCanonicalizeOptions {
+            layout: TtmlLayout::Minified,
+            ..Default::default()
+        },
+    )
+}
+
+// =============================================================================
+// 解析
+// =============================================================================
+
+/// 将 XML 文本解析为节点树，同时解码实体并统一命名空间前缀。
+fn parse_nodes(content: &str) -> Result<Vec<Node>, ConvertError> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().expand_empty_elements = false;
+
+    let mut root_nodes: Vec<Node> = Vec::new();
+    let mut stack: Vec<(Element, PrefixScope)> = Vec::new();
+    let mut pending_text = String::new();
+
+    loop {
+        let event = reader.read_event()?;
+
+        if !matches!(
+            event,
+            Event::Text(_) | Event::GeneralRef(_) | Event::CData(_)
+        ) && !pending_text.is_empty()
+        {
+            let text = Node::Text(std::mem::take(&mut pending_text));
+            push_node(&mut stack, &mut root_nodes, text);
+        }
+
+        match event {
+            Event::Start(e) => {
+                let scope = stack.last().map(|(_, s)| s.clone()).unwrap_or_default();
+                stack.push(read_element(&e, scope)?);
+            }
+            Event::Empty(e) => {
+                let scope = stack.last().map(|(_, s)| s.clone()).unwrap_or_default();
+                let (element, _) = read_element(&e, scope)?;
+                push_node(&mut stack, &mut root_nodes, Node::Element(element));
+            }
+            Event::End(_) => {
+                if let Some((element, _)) = stack.pop() {
+                    push_node(&mut stack, &mut root_nodes, Node::Element(element));
+                }
+            }
+            Event::Text(e) => pending_text.push_str(&e.xml_content()?),
+            Event::CData(e) => pending_text.push_str(&e.xml_content()?),
+            Event::GeneralRef(e) => {
+                let resolved = if let Some(c) = e.resolve_char_ref()? {
+                    c.to_string()
+                } else {
+                    let name = e.decode()?;
+                    quick_xml::escape::resolve_predefined_entity(&name)
+                        .ok_or_else(|| {
+                            ConvertError::Internal(format!("无法解析的实体引用: &{name};"))
+                        })?
+                        .to_string()
+                };
+                pending_text.push_str(&resolved);
+            }
+            Event::Comment(e) => {
+                let comment = Node::Comment(e.decode()?.into_owned());
+                push_node(&mut stack, &mut root_nodes, comment);
+            }
+            Event::Decl(e) => {
+                push_node(&mut stack, &mut root_nodes, Node::Raw(format_decl(&e)?));
+            }
+            Event::PI(e) => {
+                let pi = Node::Raw(format!("<?{}?>", String::from_utf8_lossy(&e)));
+                push_node(&mut stack, &mut root_nodes, pi);
+            }
+            Event::DocType(e) => {
+                let doctype = Node::Raw(format!("<!DOCTYPE {}>", e.decode()?));
+                push_node(&mut stack, &mut root_nodes, doctype);
+            }
+            Event::Eof => break,
+        }
+    }
+
+    if let Some((element, _)) = stack.last() {
+        return Err(ConvertError::Internal(format!(
+            "元素 <{}> 没有闭合",
+            element.name
+        )));
+    }
+
+    Ok(root_nodes)
+}
+
+fn push_node(stack: &mut [(Element, PrefixScope)], root_nodes: &mut Vec<Node>, node: Node) {
+    match stack.last_mut() {
+        Some((parent, _)) => parent.children.push(node),
+        None => root_nodes.push(node),
+    }
+}
+
+/// 以规范的属性顺序重写 XML 声明。
+fn format_decl(decl: &BytesDecl) -> Result<String, ConvertError> {
+    let mut output = format!(
+        r#"<?xml version="{}""#,
+        String::from_utf8_lossy(&decl.version()?)
+    );
+    if let Some(encoding) = decl.encoding() {
+        let _ = write!(
+            output,
+            r#" encoding="{}""#,
+            String::from_utf8_lossy(&encoding?)
+        );
+    }
+    if let Some(standalone) = decl.standalone() {
+        let _ = write!(
+            output,
+            r#" standalone="{}""#,
+            String::from_utf8_lossy(&standalone?)
+        );
+    }
+    output.push_str("?>");
+    Ok(output)
+}
+
+/// 读取一个元素的名称和属性，处理它声明的命名空间前缀，并对属性排序。
+///
+/// 返回元素本身以及供其子元素继承的前缀作用域。
+fn read_element(
+    e: &BytesStart,
+    mut scope: PrefixScope,
+) -> Result<(Element, PrefixScope), ConvertError> {
+    let mut declarations: Vec<(String, String)> = Vec::new();
+    let mut default_namespace: Option<String> = None;
+    let mut attributes: Vec<(String, String)> = Vec::new();
+
+    for attr in e.attributes() {
+        let attr = attr?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+        let value = attr.unescape_value()?.into_owned();
+        if key == "xmlns" {
+            default_namespace = Some(value);
+        } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+            declarations.push((prefix.to_string(), value));
+        } else {
+            attributes.push((key, value));
+        }
+    }
+
+    // 先确定本元素声明的每个前缀应当输出为什么
+    let inherited = scope.clone();
+    let mut declared_outputs: Vec<(String, String)> = Vec::new();
+    for (prefix, uri) in &declarations {
+        let output_prefix = canonical_prefix(uri)
+            .filter(|candidate| {
+                let clashes_in_scope = inherited.iter().any(|(old, (u, out))| {
+                    out == candidate && u != uri && !declarations.iter().any(|(p, _)| p == old)
+                });
+                let clashes_here = declarations.iter().any(|(p, u)| p == candidate && u != uri);
+                !clashes_in_scope && !clashes_here
+            })
+            .unwrap_or(prefix)
+            .to_string();
+
+        // 与继承的绑定完全相同的声明是多余的
+        let redundant = inherited
+            .get(prefix)
+            .is_some_and(|(u, out)| u == uri && *out == output_prefix);
+        if !redundant && !declared_outputs.iter().any(|(p, _)| *p == output_prefix) {
+            declared_outputs.push((output_prefix.clone(), uri.clone()));
+        }
+        scope.insert(prefix.clone(), (uri.clone(), output_prefix));
+    }
+
+    let name = rename_qname(&String::from_utf8_lossy(e.name().as_ref()), &scope);
+    let mut renamed: Vec<(String, String)> = attributes
+        .into_iter()
+        .map(|(key, value)| (rename_qname(&key, &scope), value))
+        .collect();
+    renamed.sort_by(|(a, _), (b, _)| attribute_rank(a).cmp(&attribute_rank(b)).then(a.cmp(b)));
+    declared_outputs.sort();
+
+    let mut all_attributes = Vec::with_capacity(renamed.len() + declared_outputs.len() + 1);
+    if let Some(uri) = default_namespace {
+        all_attributes.push(("xmlns".to_string(), uri));
+    }
+    all_attributes.extend(
+        declared_outputs
+            .into_iter()
+            .map(|(prefix, uri)| (format!("xmlns:{prefix}"), uri)),
+    );
+    all_attributes.extend(renamed);
+
+    Ok((
+        Element {
+            name,
+            attributes: all_attributes,
+            children: Vec::new(),
+        },
+        scope,
+    ))
+}
+
+fn canonical_prefix(uri: &str) -> Option<&'static str> {
+    CANONICAL_PREFIXES
+        .iter()
+        .find(|(u, _)| *u == uri)
+        .map(|(_, prefix)| *prefix)
+}
+
+/// 将带前缀的名称替换为作用域中的输出前缀。未声明的前缀和 `xml:` 保持不变。
+fn rename_qname(qname: &str, scope: &PrefixScope) -> String {
+    match qname.split_once(':') {
+        Some((prefix, local)) if prefix != "xml" => match scope.get(prefix) {
+            Some((_, output)) => format!("{output}:{local}"),
+            None => qname.to_string(),
+        },
+        _ => qname.to_string(),
+    }
+}
+
+/// 时间属性排在最前，其余属性按名称排序。
+fn attribute_rank(name: &str) -> u8 {
+    match name {
+        "begin" => 0,
+        "end" => 1,
+        "dur" => 2,
+        _ => 3,
+    }
+}
+
+// =============================================================================
+// 空白处理
+// =============================================================================
+
+/// 递归丢弃只由空白组成且包含换行的文本节点。
+fn strip_formatting_whitespace(nodes: &mut Vec<Node>) {
+    nodes.retain_mut(|node| match node {
+        Node::Text(text) => !(text.contains('\n') && text.chars().all(char::is_whitespace)),
+        Node::Element(element) => {
+            strip_formatting_whitespace(&mut element.children);
+            true
+        }
+        _ => true,
+    });
+}
+
+// =============================================================================
+// 输出
+// =============================================================================
+
+fn write_formatted(output: &mut String, node: &Node, depth: usize, indent_size: usize) {
+    output.push_str(&" ".repeat(depth * indent_size));
+
+    let Node::Element(element) = node else {
+        write_inline(output, node);
+        return;
+    };
+    if element.children.is_empty() || element.is_inline() {
+        write_inline(output, node);
+        return;
+    }
+
+    write_start_tag(output, element, false);
+    output.push('\n');
+    for child in &element.children {
+        write_formatted(output, child, depth + 1, indent_size);
+        output.push('\n');
+    }
+    output.push_str(&" ".repeat(depth * indent_size));
+    write_end_tag(output, element);
+}
+
+fn write_inline(output: &mut String, node: &Node) {
+    match node {
+        Node::Element(element) if element.children.is_empty() => {
+            write_start_tag(output, element, true);
+        }
+        Node::Element(element) => {
+            write_start_tag(output, element, false);
+            for child in &element.children {
+                write_inline(output, child);
+            }
+            write_end_tag(output, element);
+        }
+        Node::Text(text) => escape_into(output, text, false),
+        Node::Comment(comment) => {
+            output.push_str("<!--");
+            output.push_str(comment);
+            output.push_str("-->");
+        }
+        Node::Raw(raw) => output.push_str(raw),
+    }
+}
+
+fn write_start_tag(output: &mut String, element: &Element, self_closing: bool) {
+    output.push('<');
+    output.push_str(&element.name);
+    for (key, value) in &element.attributes {
+        output.push(' ');
+        output.push_str(key);
+        output.push_str("=\"");
+        escape_into(output, value, true);
+        output.push('"');
+    }
+    output.push_str(if self_closing { "/>" } else { ">" });
+}
+
+fn write_end_tag(output: &mut String, element: &Element) {
+    output.push_str("</");
+    output.push_str(&element.name);
+    output.push('>');
+}
+
+/// 以固定的方式转义文本：文本只转义 `&`、`<`、`>`；属性值还会转义 `"` 和会被
+/// 属性值规范化吞掉的制表符与换行。
+fn escape_into(output: &mut String, text: &str, is_attribute: bool) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' if is_attribute => output.push_str("&quot;"),
+            '\t' if is_attribute => output.push_str("&#9;"),
+            '\n' if is_attribute => output.push_str("&#10;"),
+            '\r' if is_attribute => output.push_str("&#13;"),
+            _ => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::{
+        parsers::ttml_parser::parse_ttml,
+        types::{LyricLine, TtmlParsingOptions},
+    };
+
+    fn parse_lines(content: &str) -> Vec<LyricLine> {
+        parse_ttml(content, &TtmlParsingOptions::default())
+            .expect("解析 TTML 失败")
+            .lines
+    }
+
+    const FORMATTED_INPUT: &str = r#"<?xml version='1.0' encoding='utf-8'?>
+<tt xmlns:tm="http://www.w3.org/ns/ttml#metadata" xmlns="http://www.w3.org/ns/ttml" xmlns:itunes="http://music.apple.com/lyric-ttml-internal" itunes:timing="Word">
+    <head>
+        <metadata>
+            <tm:agent type="person" xml:id="v1"></tm:agent>
+        </metadata>
+    </head>
+    <body dur="00:03.000">
+        <div end="00:03.000" begin="00:00.000">
+            <p tm:agent="v1" end="00:03.000" begin="00:00.000" itunes:key="L1">
+                <span end="00:01.000" begin="00:00.000">Rock</span> <span begin="00:01.000" end="00:02.000">&amp;</span>
+                <span begin="00:02.000" end="00:03.000">roll</span>
+            </p>
+        </div>
+    </body>
+</tt>"#;
+
+    #[test]
+    fn test_format_and_minify_are_stable() {
+        let formatted = format_ttml(FORMATTED_INPUT).unwrap();
+        let minified = minify_ttml(FORMATTED_INPUT).unwrap();
+
+        assert!(!minified.contains('\n'));
+        assert_eq!(format_ttml(&minified).unwrap(), formatted);
+        assert_eq!(minify_ttml(&formatted).unwrap(), minified);
+        assert_eq!(format_ttml(&formatted).unwrap(), formatted);
+
+        let lines = parse_lines(&formatted);
+        assert_eq!(parse_lines(&minified), lines);
+        // 解析器按原始名称匹配 `ttm:agent`，统一前缀后才能识别出演唱者
+        assert_eq!(lines[0].agent.as_deref(), Some("v1"));
+        let syllables = &lines[0].tracks[0].content.words[0].syllables;
+        assert!(syllables[0].ends_with_space);
+        assert!(!syllables[1].ends_with_space);
+    }
+
+    #[test]
+    fn test_normalizes_prefixes_and_attribute_order() {
+        let formatted = format_ttml(FORMATTED_INPUT).unwrap();
+
+        assert!(formatted.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<tt xmlns=\"http://www.w3.org/ns/ttml\" xmlns:itunes=\"http://music.apple.com/lyric-ttml-internal\" xmlns:ttm=\"http://www.w3.org/ns/ttml#metadata\" itunes:timing=\"Word\">\n  <head>"));
+        assert!(formatted.contains("\n      <ttm:agent type=\"person\" xml:id=\"v1\"/>\n"));
+        assert!(formatted.contains(
+            "\n    <div begin=\"00:00.000\" end=\"00:03.000\">\n      <p begin=\"00:00.000\" end=\"00:03.000\" itunes:key=\"L1\" ttm:agent=\"v1\"><span begin=\"00:00.000\" end=\"00:01.000\">Rock</span> <span begin=\"00:01.000\" end=\"00:02.000\">&amp;</span><span begin=\"00:02.000\" end=\"00:03.000\">roll</span></p>\n"
+        ));
+        assert!(!formatted.contains(" tm:") && !formatted.contains("<tm:"));
+    }
+
+    #[test]
+    fn test_only_newline_whitespace_is_dropped() {
+        let input = "<tt xmlns=\"http://www.w3.org/ns/ttml\"><body><div><p begin=\"0\" end=\"3\"><span begin=\"0\" end=\"1\">a</span>\n<span begin=\"1\" end=\"2\">b</span>\t<span begin=\"2\" end=\"3\">c</span></p></div></body></tt>";
+
+        let minified = minify_ttml(input).unwrap();
+
+        assert!(minified.contains("a</span><span"));
+        assert!(minified.contains("b</span>\t<span"));
+        assert_eq!(parse_lines(&minified), parse_lines(input));
+        assert_eq!(
+            parse_lines(&format_ttml(input).unwrap()),
+            parse_lines(input)
+        );
+    }
+}
@@ -0,0 +1,632 @@
+//! AMLL TTML 数据库投稿规范检查器。
+//!
+//! 依据 `docs/ttml-specification.md` 中的硬性规则，检查原始 TTML 文本以及
+//! [`parse_ttml`](crate::converter::parsers::ttml_parser::parse_ttml) 的解析结果，
+//! 返回带有规则编号和位置的违规列表，方便投稿前自查。
+//!
+//! 与文件结构、时间戳格式和空白字符有关的规则在原始文本上检查，因此能给出准确的行列位置；
+//! 与元数据和歌词内容有关的规则则使用解析结果。
+
+use std::fmt;
+use std::sync::LazyLock;
+
+use quick_xml::{
+    Reader,
+    events::{BytesStart, Event},
+};
+use regex::Regex;
+
+use crate::converter::{
+    parsers::ttml_parser::{parse_ttml, parse_ttml_time_to_ms},
+    types::{ParsedSourceData, TtmlParsingOptions},
+};
+
+/// 必须提供的 AMLL 元数据键。
+const REQUIRED_METADATA_KEYS: &[&str] = &["musicName", "artists", "album"];
+
+/// 平台 ID 元数据键，至少需要提供一个。
+const PLATFORM_ID_KEYS: &[&str] = &["ncmMusicId", "qqMusicId", "spotifyId", "appleMusicId"];
+
+/// BCP-47 语言标签的语法（不检查子标签是否已在 IANA 注册）。
+static BCP47_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^(?:(?:[a-z]{2,3}(?:-[a-z]{3}){0,3}|[a-z]{4,8})(?:-[a-z]{4})?(?:-(?:[a-z]{2}|\d{3}))?(?:-(?:[a-z\d]{5,8}|\d[a-z\d]{3}))*(?:-[0-9a-wy-z](?:-[a-z\d]{2,8})+)*(?:-x(?:-[a-z\d]{1,8})+)?|x(?:-[a-z\d]{1,8})+)$",
+    )
+    .expect("编译 BCP47_REGEX 失败")
+});
+
+/// 规范中的规则。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TtmlRule {
+    /// 文件以 BOM 开头（§2）。
+    Bom,
+    /// 不是合法的 XML 文档（§2）。
+    InvalidXml,
+    /// 缺少必需的 `musicName`、`artists` 或 `album` 元数据（§3.2）。
+    MissingRequiredMetadata,
+    /// 没有提供任何平台 ID（§3.2）。
+    MissingPlatformId,
+    /// `<ttm:title>` 与 `musicName` 的值重复（§3.1）。
+    DuplicatedTitle,
+    /// 时间戳格式无效（§4.4）。
+    InvalidTimestamp,
+    /// `begin` 不早于 `end`（§4 第 1 条）。
+    BeginNotBeforeEnd,
+    /// 子元素的时间超出了父元素的时间范围（§4 第 2 条）。
+    OutsideParentTiming,
+    /// `<body>` 的 `dur` 早于最后一个时间戳（§2、§4 第 3 条）。
+    DurBeforeLastTimestamp,
+    /// `xml:lang` 不是合法的 BCP-47 语言标签（§7）。
+    InvalidLanguageTag,
+    /// 逐字模式下音节 `<span>` 内有前导空白或非单个半角空格的空白（§4.1.1）。
+    WhitespaceInsideSpan,
+    /// 两个音节之间只有带换行的空白，格式化后这个空格会丢失（§6.1）。
+    LostWhitespaceBetweenSpans,
+}
+
+impl TtmlRule {
+    /// 规则编号。
+    #[must_use]
+    pub fn code(self) -> &'static str {
+        match self {
+            TtmlRule::Bom => "TTML001",
+            TtmlRule::InvalidXml => "TTML002",
+            TtmlRule::MissingRequiredMetadata => "TTML010",
+            TtmlRule::MissingPlatformId => "TTML011",
+            TtmlRule::DuplicatedTitle => "TTML012",
+            TtmlRule::InvalidTimestamp => "TTML020",
+            TtmlRule::BeginNotBeforeEnd => "TTML021",
+            TtmlRule::OutsideParentTiming => "TTML022",
+            TtmlRule::DurBeforeLastTimestamp => "TTML023",
+            TtmlRule::InvalidLanguageTag => "TTML030",
+            TtmlRule::WhitespaceInsideSpan => "TTML040",
+            TtmlRule::LostWhitespaceBetweenSpans => "TTML041",
+        }
+    }
+
+    /// 规则的严重程度。空白字符规则会被自动修正或只影响显示，因此只作为警告。
+    #[must_use]
+    pub fn severity(self) -> TtmlSeverity {
+        match self {
+            TtmlRule::WhitespaceInsideSpan | TtmlRule::LostWhitespaceBetweenSpans => {
+                TtmlSeverity::Warning
+            }
+            _ => TtmlSeverity::Error,
+        }
+    }
+}
+
+/// 违规的严重程度。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TtmlSeverity {
+    /// 不符合规范，投稿会被拒绝。
+    Error,
+    /// 可以被自动修正或可能导致显示问题。
+    Warning,
+}
+
+/// 违规在原始文本中的位置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtmlPosition {
+    /// 字节偏移。
+    pub offset: usize,
+    /// 行号，从 1 开始。
+    pub line: usize,
+    /// 列号（按字符计），从 1 开始。
+    pub column: usize,
+}
+
+impl TtmlPosition {
+    fn at(content: &str, offset: usize) -> Self {
+        let offset = offset.min(content.len());
+        let before = content.get(..offset).unwrap_or(content);
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// 一条违规。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TtmlViolation {
+    /// 违反的规则。
+    pub rule: TtmlRule,
+    /// 违规所在位置。整份文件层面的问题（如缺少元数据）可能没有具体位置。
+    pub position: Option<TtmlPosition>,
+    /// 描述信息。
+    pub message: String,
+}
+
+impl fmt::Display for TtmlViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.rule.severity() {
+            TtmlSeverity::Error => "错误",
+            TtmlSeverity::Warning => "警告",
+        };
+        write!(f, "[{}] {severity}", self.rule.code())?;
+        if let Some(position) = self.position {
+            write!(f, " ({}:{})", position.line, position.column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// 解析并检查一份 TTML 文本。
+///
+/// 文本无法被 `ttml_parser` 解析时，只返回原始文本层面的检查结果。
+#[must_use]
+pub fn validate_ttml_content(content: &str) -> Vec<TtmlViolation> {
+    match parse_ttml(content, &TtmlParsingOptions::default()) {
+        Ok(parsed) => validate_ttml(content, &parsed),
+        Err(_) => RawScanner::new(content).scan().violations,
+    }
+}
+
+/// 依据 AMLL TTML 规范检查原始 TTML 文本及其解析结果。
+///
+/// # 参数
+/// * `content` - 原始 TTML 文本。
+/// * `parsed` - `content` 经 `ttml_parser` 解析后的结果。
+#[must_use]
+pub fn validate_ttml(content: &str, parsed: &ParsedSourceData) -> Vec<TtmlViolation> {
+    let scan = RawScanner::new(content).scan();
+    let mut violations = scan.violations;
+    let metadata_position = scan.metadata_position;
+
+    let values_of = |key: &str| -> Vec<&str> {
+        parsed
+            .raw_metadata
+            .get(key)
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|v| v.trim())
+                    .filter(|v| !v.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    for key in REQUIRED_METADATA_KEYS {
+        if values_of(key).is_empty() {
+            violations.push(TtmlViolation {
+                rule: TtmlRule::MissingRequiredMetadata,
+                position: metadata_position,
+                message: format!("缺少必需的元数据 `<amll:meta key=\"{key}\">`。"),
+            });
+        }
+    }
+
+    if PLATFORM_ID_KEYS.iter().all(|key| values_of(key).is_empty()) {
+        violations.push(TtmlViolation {
+            rule: TtmlRule::MissingPlatformId,
+            position: metadata_position,
+            message: format!(
+                "至少需要提供一个平台 ID（{}）。",
+                PLATFORM_ID_KEYS.join("、")
+            ),
+        });
+    }
+
+    let music_names = values_of("musicName");
+    for (title, position) in &scan.titles {
+        if music_names.contains(&title.trim()) {
+            violations.push(TtmlViolation {
+                rule: TtmlRule::DuplicatedTitle,
+                position: Some(*position),
+                message: format!("`<ttm:title>` 与 `musicName` 重复: \"{}\"。", title.trim()),
+            });
+        }
+    }
+
+    if let Some((dur_ms, position)) = scan.body_dur {
+        let last_timestamp = parsed
+            .lines
+            .iter()
+            .flat_map(|line| {
+                let syllable_ends = line
+                    .tracks
+                    .iter()
+                    .flat_map(|t| &t.content.words)
+                    .flat_map(|w| &w.syllables)
+                    .map(|s| s.end_ms);
+                std::iter::once(line.end_ms).chain(syllable_ends)
+            })
+            .max();
+        if let Some(last_ms) = last_timestamp.filter(|last| *last > dur_ms) {
+            violations.push(TtmlViolation {
+                rule: TtmlRule::DurBeforeLastTimestamp,
+                position: Some(position),
+                message: format!("`<body>` 的 dur ({dur_ms}ms) 早于最后一个时间戳 ({last_ms}ms)。"),
+            });
+        }
+    }
+
+    let word_timed = scan.word_timing.unwrap_or(!parsed.is_line_timed_source);
+    violations.extend(
+        scan.whitespace_violations
+            .into_iter()
+            .filter(|_| word_timed),
+    );
+
+    violations.sort_by_key(|v| v.position.map_or(usize::MAX, |p| p.offset));
+    violations
+}
+
+/// 原始文本扫描结果。
+#[derive(Default)]
+struct RawScan {
+    violations: Vec<TtmlViolation>,
+    /// 只在逐字模式下才有意义的空白字符违规。
+    whitespace_violations: Vec<TtmlViolation>,
+    /// `<tt itunes:timing>` 声明的计时模式，`Some(true)` 为逐字。
+    word_timing: Option<bool>,
+    metadata_position: Option<TtmlPosition>,
+    titles: Vec<(String, TtmlPosition)>,
+    body_dur: Option<(u64, TtmlPosition)>,
+}
+
+/// 扫描过程中打开的一个元素。
+struct Frame {
+    name: Vec<u8>,
+    offset: usize,
+    timing: Option<(u64, u64)>,
+    /// 是否为逐字音节 `<span>`（带时间且没有 `ttm:role`）。
+    is_syllable: bool,
+    text: String,
+    /// 上一个已结束的音节子元素的文本，以及之后累积的空白文本。
+    previous_syllable: Option<(String, String)>,
+}
+
+struct RawScanner<'a> {
+    content: &'a str,
+    scan: RawScan,
+    stack: Vec<Frame>,
+}
+
+impl<'a> RawScanner<'a> {
+    fn new(content: &'a str) -> Self {
+        Self {
+            content,
+            scan: RawScan::default(),
+            stack: Vec::new(),
+        }
+    }
+
+    fn position(&self, offset: usize) -> TtmlPosition {
+        TtmlPosition::at(self.content, offset)
+    }
+
+    fn push(&mut self, rule: TtmlRule, offset: usize, message: String) {
+        let violation = TtmlViolation {
+            rule,
+            position: Some(self.position(offset)),
+            message,
+        };
+        match rule {
+            TtmlRule::WhitespaceInsideSpan | TtmlRule::LostWhitespaceBetweenSpans => {
+                self.scan.whitespace_violations.push(violation);
+            }
+            _ => self.scan.violations.push(violation),
+        }
+    }
+
+    fn scan(mut self) -> RawScan {
+        let bom_len = if self.content.starts_with('\u{feff}') {
+            self.push(TtmlRule::Bom, 0, "文件不能以 BOM 开头。".to_string());
+            '\u{feff}'.len_utf8()
+        } else {
+            0
+        };
+
+        let mut reader = Reader::from_str(&self.content[bom_len..]);
+        reader.config_mut().trim_text(false);
+
+        loop {
+            let offset = bom_len + usize::try_from(reader.buffer_position()).unwrap_or(0);
+            let event = match reader.read_event() {
+                Ok(event) => event,
+                Err(e) => {
+                    let error_offset =
+                        bom_len + usize::try_from(reader.error_position()).unwrap_or(0);
+                    self.push(
+                        TtmlRule::InvalidXml,
+                        error_offset,
+                        format!("XML 解析失败: {e}"),
+                    );
+                    break;
+                }
+            };
+            match event {
+                Event::Start(e) => self.start_element(&e, offset, false),
+                Event::Empty(e) => self.start_element(&e, offset, true),
+                Event::End(_) => self.end_element(),
+                Event::Text(e) => {
+                    let text = e
+                        .decode()
+                        .map(std::borrow::Cow::into_owned)
+                        .unwrap_or_default();
+                    self.text(&text);
+                }
+                Event::GeneralRef(_) => self.text("&"),
+                Event::CData(e) => self.text(&String::from_utf8_lossy(&e)),
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        self.scan
+    }
+
+    fn start_element(&mut self, e: &BytesStart<'_>, offset: usize, is_empty: bool) {
+        let name = e.name().as_ref().to_vec();
+        let local = e.local_name().as_ref().to_vec();
+
+        let mut begin = None;
+        let mut end = None;
+        let mut has_role = false;
+        for attr in e.attributes().flatten() {
+            let Ok(value) = attr.unescape_value() else {
+                continue;
+            };
+            match attr.key.as_ref() {
+                b"begin" => begin = self.parse_time(&value, offset),
+                b"end" => end = self.parse_time(&value, offset),
+                b"dur" if local == b"body" => {
+                    if let Some(dur) = self.parse_time(&value, offset) {
+                        self.scan.body_dur = Some((dur, self.position(offset)));
+                    }
+                }
+                b"xml:lang" if !BCP47_REGEX.is_match(value.trim()) => self.push(
+                    TtmlRule::InvalidLanguageTag,
+                    offset,
+                    format!("\"{value}\" 不是合法的 BCP-47 语言标签。"),
+                ),
+                b"itunes:timing" if local == b"tt" => {
+                    self.scan.word_timing = Some(value.eq_ignore_ascii_case("word"));
+                }
+                b"ttm:role" | b"role" => has_role = true,
+                _ => {}
+            }
+        }
+
+        match local.as_slice() {
+            b"metadata" => {
+                self.scan.metadata_position = Some(self.position(offset));
+            }
+            b"head" | b"tt" if self.scan.metadata_position.is_none() => {
+                self.scan.metadata_position = Some(self.position(offset));
+            }
+            _ => {}
+        }
+
+        let timing = begin.zip(end);
+        if let Some((begin_ms, end_ms)) = timing {
+            self.check_timing(&local, begin_ms, end_ms, offset);
+        }
+
+        let is_syllable = local == b"span" && timing.is_some() && !has_role;
+        if !is_syllable && let Some(parent) = self.stack.last_mut() {
+            parent.previous_syllable = None;
+        }
+
+        let frame = Frame {
+            name,
+            offset,
+            timing,
+            is_syllable,
+            text: String::new(),
+            previous_syllable: None,
+        };
+        if is_empty {
+            self.finish_frame(frame);
+        } else {
+            self.stack.push(frame);
+        }
+    }
+
+    fn end_element(&mut self) {
+        if let Some(frame) = self.stack.pop() {
+            self.finish_frame(frame);
+        }
+    }
+
+    fn finish_frame(&mut self, frame: Frame) {
+        if frame.name == b"ttm:title" {
+            self.scan
+                .titles
+                .push((frame.text.clone(), self.position(frame.offset)));
+        }
+        if !frame.is_syllable {
+            return;
+        }
+
+        let text = &frame.text;
+        let bad_leading = text.starts_with(char::is_whitespace);
+        let trailing = &text[text.trim_end().len()..];
+        let bad_trailing = !trailing.is_empty() && trailing != " ";
+        if !text.trim().is_empty() && (bad_leading || bad_trailing) {
+            self.push(
+                TtmlRule::WhitespaceInsideSpan,
+                frame.offset,
+                format!(
+                    "音节 \"{}\" 内有多余的空白，只允许在末尾保留一个半角空格。",
+                    text.trim()
+                ),
+            );
+        }
+
+        if let Some(parent) = self.stack.last()
+            && let Some((previous, gap)) = &parent.previous_syllable
+            && gap.contains('\n')
+            && !previous.ends_with(char::is_whitespace)
+            && !text.starts_with(char::is_whitespace)
+            && previous
+                .chars()
+                .next_back()
+                .zip(text.chars().next())
+                .is_some_and(|(a, b)| is_spaced_word_char(a) && is_spaced_word_char(b))
+        {
+            let message = format!(
+                "\"{}\" 与 \"{}\" 之间只有换行，这个空格会在规范化时丢失；请把空格写进 `<span>` 内。",
+                previous.trim(),
+                text.trim()
+            );
+            self.push(TtmlRule::LostWhitespaceBetweenSpans, frame.offset, message);
+        }
+
+        if let Some(parent) = self.stack.last_mut() {
+            parent.previous_syllable = Some((frame.text, String::new()));
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        let Some(frame) = self.stack.last_mut() else {
+            return;
+        };
+        frame.text.push_str(text);
+        if let Some((_, gap)) = frame.previous_syllable.as_mut() {
+            if text.trim().is_empty() {
+                gap.push_str(text);
+            } else {
+                frame.previous_syllable = None;
+            }
+        }
+    }
+
+    fn parse_time(&mut self, value: &str, offset: usize) -> Option<u64> {
+        match parse_ttml_time_to_ms(value.trim()) {
+            Ok(ms) => Some(ms),
+            Err(e) => {
+                self.push(
+                    TtmlRule::InvalidTimestamp,
+                    offset,
+                    format!("时间戳 \"{value}\" 无效: {e}"),
+                );
+                None
+            }
+        }
+    }
+
+    fn check_timing(&mut self, local: &[u8], begin_ms: u64, end_ms: u64, offset: usize) {
+        let element = String::from_utf8_lossy(local).into_owned();
+        // 单独的空格 `<span>` 允许零时长（§4.1.1）
+        let allows_zero = local == b"span";
+        if begin_ms > end_ms || (begin_ms == end_ms && !allows_zero) {
+            self.push(
+                TtmlRule::BeginNotBeforeEnd,
+                offset,
+                format!("<{element}> 的 begin ({begin_ms}ms) 必须早于 end ({end_ms}ms)。"),
+            );
+        }
+
+        let is_placeholder = begin_ms == 0 && end_ms == 0;
+        if let Some((parent_begin, parent_end)) =
+            self.stack.iter().rev().find_map(|frame| frame.timing)
+            && !is_placeholder
+            && (begin_ms < parent_begin || end_ms > parent_end)
+        {
+            self.push(
+                TtmlRule::OutsideParentTiming,
+                offset,
+                format!(
+                    "<{element}> 的时间 [{begin_ms}ms, {end_ms}ms] 超出了父元素的范围 [{parent_begin}ms, {parent_end}ms]。"
+                ),
+            );
+        }
+    }
+}
+
+/// 词与词之间通常需要空格的字符：字母和数字，但不包括汉字和假名。
+fn is_spaced_word_char(c: char) -> bool {
+    c.is_alphanumeric()
+        && !matches!(
+            c,
+            '\u{3040}'..='\u{30FF}'
+                | '\u{3400}'..='\u{4DBF}'
+                | '\u{4E00}'..='\u{9FFF}'
+                | '\u{F900}'..='\u{FAFF}'
+                | '\u{20000}'..='\u{2FA1F}'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(violations: &[TtmlViolation]) -> Vec<(&'static str, Option<usize>)> {
+        violations
+            .iter()
+            .map(|v| (v.rule.code(), v.position.map(|p| p.line)))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_file_has_no_violations() {
+        let content = r#"<tt xmlns="http://www.w3.org/ns/ttml" xmlns:ttm="http://www.w3.org/ns/ttml#metadata" xmlns:amll="http://www.example.com/ns/amll" xmlns:itunes="http://itunes.apple.com/lyric-ttml-extensions" xml:lang="en" itunes:timing="Word"><head><metadata><ttm:agent type="person" xml:id="v1"/><amll:meta key="musicName" value="Song"/><amll:meta key="artists" value="Artist"/><amll:meta key="album" value="Song"/><amll:meta key="ncmMusicId" value="1"/></metadata></head><body dur="00:05.000"><div begin="00:01.000" end="00:03.000"><p begin="00:01.000" end="00:03.000" ttm:agent="v1"><span begin="00:01.000" end="00:02.000">Hello</span> <span begin="00:02.000" end="00:03.000">world</span></p></div></body></tt>"#;
+
+        assert_eq!(validate_ttml_content(content), Vec::new());
+    }
+
+    #[test]
+    fn test_rule_violations_with_positions() {
+        let content = "\u{feff}<tt xmlns=\"http://www.w3.org/ns/ttml\" xmlns:ttm=\"http://www.w3.org/ns/ttml#metadata\" xmlns:amll=\"http://www.example.com/ns/amll\" xml:lang=\"english!\">
+<head><metadata>
+<ttm:title>Song</ttm:title>
+<amll:meta key=\"musicName\" value=\"Song\"/>
+</metadata></head>
+<body dur=\"00:02.000\">
+<p begin=\"00:01.000\" end=\"00:03.000\">
+<span begin=\"00:01.000\" end=\"00:01.500\">Hello</span>
+<span begin=\"00:01.500\" end=\"00:04.000\"> world</span>
+<span begin=\"00:01:75.000\" end=\"00:03.000\">x</span>
+</p>
+</body></tt>";
+
+        let violations = validate_ttml_content(content);
+
+        assert_eq!(
+            codes(&violations),
+            vec![
+                ("TTML001", Some(1)),
+                ("TTML030", Some(1)),
+                ("TTML010", Some(2)),
+                ("TTML010", Some(2)),
+                ("TTML011", Some(2)),
+                ("TTML012", Some(3)),
+                ("TTML023", Some(6)),
+                ("TTML022", Some(9)),
+                ("TTML040", Some(9)),
+                ("TTML020", Some(10)),
+            ]
+        );
+        assert_eq!(violations[7].position.map(|p| p.column), Some(1));
+        assert!(
+            violations[2]
+                .to_string()
+                .starts_with("[TTML010] 错误 (2:7)")
+        );
+    }
+
+    #[test]
+    fn test_formatted_whitespace_is_lost() {
+        let content = "<tt xmlns=\"http://www.w3.org/ns/ttml\" itunes:timing=\"Word\" xmlns:itunes=\"http://itunes.apple.com/lyric-ttml-extensions\"><body><p begin=\"1s\" end=\"3s\">
+    <span begin=\"1s\" end=\"2s\">Gold</span>
+    <span begin=\"2s\" end=\"3s\">jewelry</span>
+</p><p begin=\"4s\" end=\"6s\">
+    <span begin=\"4s\" end=\"5s\">你</span>
+    <span begin=\"5s\" end=\"6s\">好</span>
+</p></body></tt>";
+
+        let lost: Vec<_> = validate_ttml_content(content)
+            .into_iter()
+            .filter(|v| v.rule == TtmlRule::LostWhitespaceBetweenSpans)
+            .collect();
+
+        assert_eq!(lost.len(), 1);
+        assert_eq!(lost[0].position.map(|p| p.line), Some(3));
+    }
+}
@@ -76,6 +76,9 @@ pub enum ConvertError {
     /// 轨道合并错误
     #[error("轨道合并失败: {0}")]
     TrackMergeError(String),
+    /// 加密格式的加解密错误
+    #[error("歌词加解密失败: {0}")]
+    Crypto(String),
 }
 
 impl ConvertError {
@@ -123,6 +126,8 @@ pub enum LyricFormat {
     Qrc,
     /// 网易云音乐 YRC 格式。
     Yrc,
+    /// 网易云音乐早期的 klyric 逐字格式。
+    Klyric,
     /// `Lyricify Lines` 格式。
     Lyl,
     /// `Salt Player Lyrics` 格式。
@@ -131,6 +136,20 @@ pub enum LyricFormat {
     Lqe,
     /// 酷狗 KRC 格式。
     Krc,
+    /// 酷狗客户端的加密 KRC 文件（`krc1` 头部 + 异或 + Zlib）。
+    EncryptedKrc,
+    /// QQ 音乐的加密 QRC（Zlib + 私有的三重 DES），可以是原始字节或十六进制文本。
+    EncryptedQrc,
+    /// Karaoke MIDI (.kar) 格式，即带有歌词文本事件的标准 MIDI 文件。
+    Kar,
+    /// `UltraStar` TXT 格式，K 歌游戏使用的带音高的逐音符格式。
+    UltraStar,
+    /// SAMI (.smi) 字幕格式，以语言类区分多语言字幕。
+    Sami,
+    /// `YouTube` timed-text 的 `srv3` XML 字幕格式（仅支持导入）。
+    YouTubeSrv3,
+    /// `YouTube` timed-text 的 `json3` JSON 字幕格式（仅支持导入）。
+    YouTubeJson3,
 }
 
 impl LyricFormat {
@@ -144,17 +163,26 @@ impl LyricFormat {
             LyricFormat::Lys => "lys",
             LyricFormat::Lrc => "lrc",
             LyricFormat::EnhancedLrc => "elrc",
-            LyricFormat::Qrc => "qrc",
+            LyricFormat::Qrc | LyricFormat::EncryptedQrc => "qrc",
             LyricFormat::Yrc => "yrc",
+            LyricFormat::Klyric => "klyric",
             LyricFormat::Lyl => "lyl",
             LyricFormat::Spl => "spl",
             LyricFormat::Lqe => "lqe",
-            LyricFormat::Krc => "krc",
+            LyricFormat::Krc | LyricFormat::EncryptedKrc => "krc",
+            LyricFormat::Kar => "kar",
+            LyricFormat::UltraStar => "txt",
+            LyricFormat::Sami => "smi",
+            LyricFormat::YouTubeSrv3 => "srv3",
+            LyricFormat::YouTubeJson3 => "json3",
         }
     }
 
     /// 从字符串（通常是文件扩展名或用户输入）解析歌词格式枚举。
     /// 此方法不区分大小写，并会移除输入字符串中的空格和点。
+    ///
+    /// `txt` 不对应任何格式：`UltraStar` 文件需要通过内容识别，
+    /// 见 [`crate::converter::utils::is_ultrastar_content`]。
     pub fn from_string(s: &str) -> Option<Self> {
         let normalized_s = s.to_uppercase().replace([' ', '.'], "");
         match normalized_s.as_str() {
@@ -165,11 +193,19 @@ impl LyricFormat {
             "LRC" => Some(LyricFormat::Lrc),
             "ENHANCEDLRC" | "LRCX" | "ELRC" | "ALRC" => Some(LyricFormat::EnhancedLrc),
             "QRC" => Some(LyricFormat::Qrc),
+            "ENCRYPTEDQRC" | "QRCENCRYPTED" => Some(LyricFormat::EncryptedQrc),
             "YRC" => Some(LyricFormat::Yrc),
+            "KLYRIC" | "KLRC" => Some(LyricFormat::Klyric),
             "LYL" | "LYRICIFYLINES" => Some(LyricFormat::Lyl),
             "SPL" => Some(LyricFormat::Spl),
             "LQE" | "LYRICIFYQUICKEXPORT" => Some(LyricFormat::Lqe),
             "KRC" => Some(LyricFormat::Krc),
+            "ENCRYPTEDKRC" | "KRCENCRYPTED" => Some(LyricFormat::EncryptedKrc),
+            "KAR" | "MIDI" | "MID" => Some(LyricFormat::Kar),
+            "ULTRASTAR" | "USDX" => Some(LyricFormat::UltraStar),
+            "SAMI" | "SMI" => Some(LyricFormat::Sami),
+            "SRV3" | "YOUTUBESRV3" => Some(LyricFormat::YouTubeSrv3),
+            "JSON3" | "YOUTUBEJSON3" => Some(LyricFormat::YouTubeJson3),
             _ => {
                 warn!("[LyricFormat] 未知的格式字符串: {}", s);
                 None
@@ -189,10 +225,18 @@ impl fmt::Display for LyricFormat {
             LyricFormat::EnhancedLrc => write!(f, "Enhanced LRC"),
             LyricFormat::Qrc => write!(f, "QRC"),
             LyricFormat::Yrc => write!(f, "YRC"),
+            LyricFormat::Klyric => write!(f, "Klyric"),
             LyricFormat::Lyl => write!(f, "Lyricify Lines"),
             LyricFormat::Spl => write!(f, "SPL"),
             LyricFormat::Lqe => write!(f, "Lyricify Quick Export"),
             LyricFormat::Krc => write!(f, "KRC"),
+            LyricFormat::EncryptedKrc => write!(f, "KRC (加密)"),
+            LyricFormat::EncryptedQrc => write!(f, "QRC (加密)"),
+            LyricFormat::Kar => write!(f, "Karaoke MIDI"),
+            LyricFormat::UltraStar => write!(f, "UltraStar"),
+            LyricFormat::Sami => write!(f, "SAMI"),
+            LyricFormat::YouTubeSrv3 => write!(f, "YouTube srv3"),
+            LyricFormat::YouTubeJson3 => write!(f, "YouTube json3"),
         }
     }
 }
@@ -283,7 +327,7 @@ pub enum AgentType {
 }
 
 /// 表示歌词中的演唱者。
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Agent {
     /// 内部ID, 例如 "v1"
     pub id: String,
@@ -291,6 +335,23 @@ pub struct Agent {
     pub name: Option<String>,
     /// Agent 的类型
     pub agent_type: AgentType,
+    /// 可选的显示颜色，例如 "#FF8800"。对应 TTML 中 `<p>` 通过 `style` 引用的 `tts:color` 样式。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// 可选的默认显示位置，行没有设置 `view_side` 时使用。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view_side: Option<ViewSide>,
+}
+
+impl Agent {
+    /// 创建一个只有 ID 的单人演唱者。
+    #[must_use]
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
 }
 
 /// 用于存储歌词轨道中识别到的所有演唱者。
@@ -301,36 +362,47 @@ pub struct AgentStore {
 }
 
 impl AgentStore {
-    /// 从歌词行列表中构建 `AgentStore`
+    /// 从元数据中的 `agent` 定义构建 `AgentStore`。
     #[must_use]
     pub fn from_metadata_store(metadata_store: &MetadataStore) -> Self {
+        metadata_store
+            .get_multiple_values_by_key("agent")
+            .map(|definitions| Self::from_definitions(definitions))
+            .unwrap_or_default()
+    }
+
+    /// 从 `id` 或 `id=名称` 形式的定义构建 `AgentStore`。
+    ///
+    /// ID 为 `v1000` 或名称为“合”/“合唱”的定义视为合唱。
+    #[must_use]
+    pub fn from_definitions<S: AsRef<str>>(definitions: &[S]) -> Self {
         let mut store = AgentStore::default();
 
-        if let Some(agent_definitions) = metadata_store.get_multiple_values_by_key("agent") {
-            for def_string in agent_definitions {
-                let (id, parsed_name) = match def_string.split_once('=') {
-                    Some((id, name)) => (id.to_string(), Some(name.to_string())),
-                    None => (def_string.clone(), None),
-                };
+        for def_string in definitions {
+            let def_string = def_string.as_ref();
+            let (id, parsed_name) = match def_string.split_once('=') {
+                Some((id, name)) => (id.to_string(), Some(name.to_string())),
+                None => (def_string.to_string(), None),
+            };
 
-                let is_chorus = id == "v1000"
-                    || parsed_name.as_deref() == Some("合")
-                    || parsed_name.as_deref() == Some("合唱");
+            let is_chorus = id == "v1000"
+                || parsed_name.as_deref() == Some("合")
+                || parsed_name.as_deref() == Some("合唱");
 
-                let final_name = if is_chorus { None } else { parsed_name };
-                let agent_type = if is_chorus {
-                    AgentType::Group
-                } else {
-                    AgentType::Person
-                };
-
-                let agent = Agent {
-                    id: id.clone(),
-                    name: final_name,
-                    agent_type,
-                };
-                store.agents_by_id.insert(id, agent);
-            }
+            let final_name = if is_chorus { None } else { parsed_name };
+            let agent_type = if is_chorus {
+                AgentType::Group
+            } else {
+                AgentType::Person
+            };
+
+            let agent = Agent {
+                id: id.clone(),
+                name: final_name,
+                agent_type,
+                ..Default::default()
+            };
+            store.agents_by_id.insert(id, agent);
         }
         store
     }
@@ -339,6 +411,87 @@ impl AgentStore {
     pub fn all_agents(&self) -> impl Iterator<Item = &Agent> {
         self.agents_by_id.values()
     }
+
+    /// 按 ID 查找演唱者。
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<&Agent> {
+        self.agents_by_id.get(id)
+    }
+
+    /// 添加或替换一个演唱者。
+    pub fn insert(&mut self, agent: Agent) {
+        self.agents_by_id.insert(agent.id.clone(), agent);
+    }
+
+    /// 用另一个 `AgentStore` 中的定义覆盖同 ID 的演唱者。
+    ///
+    /// 名称和类型以 `other` 为准；`other` 中没有提供的颜色和显示位置沿用原有的值。
+    pub fn merge(&mut self, other: AgentStore) {
+        for (id, mut agent) in other.agents_by_id {
+            if let Some(existing) = self.agents_by_id.get(&id) {
+                agent.color = agent.color.or_else(|| existing.color.clone());
+                agent.view_side = agent.view_side.or(existing.view_side);
+            }
+            self.agents_by_id.insert(id, agent);
+        }
+    }
+
+    /// 为没有设置 `view_side` 的行填入其主要演唱者的默认显示位置。
+    pub fn apply_view_sides(&self, lines: &mut [LyricLine]) {
+        for line in lines.iter_mut().filter(|l| l.view_side.is_none()) {
+            line.view_side = line
+                .primary_agent()
+                .and_then(|id| self.get(id))
+                .and_then(|agent| agent.view_side);
+        }
+    }
+
+    /// 为歌词行和音节中引用、但尚未定义的演唱者补充默认的单人演唱者记录。
+    pub fn register_referenced(&mut self, lines: &[LyricLine]) {
+        for line in lines {
+            let syllable_agents = line
+                .tracks
+                .iter()
+                .flat_map(|t| &t.content.words)
+                .flat_map(|w| &w.syllables)
+                .filter_map(|s| s.agent.as_deref());
+            for id in line.agent.as_deref().into_iter().chain(syllable_agents) {
+                if !self.agents_by_id.contains_key(id) {
+                    self.insert(Agent::new(id));
+                }
+            }
+        }
+    }
+}
+
+/// 歌词行在对唱界面中的显示位置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ViewSide {
+    /// 靠左显示。
+    Left,
+    /// 靠右显示。
+    Right,
+}
+
+impl ViewSide {
+    /// 对应的 TTML `tts:textAlign` 值。
+    #[must_use]
+    pub fn text_align(self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Right => "right",
+        }
+    }
+
+    /// 从 TTML `tts:textAlign` 值解析显示位置，`start`/`end` 按从左到右的书写方向处理。
+    #[must_use]
+    pub fn from_text_align(value: &str) -> Option<Self> {
+        match value.trim() {
+            "left" | "start" => Some(Self::Left),
+            "right" | "end" => Some(Self::Right),
+            _ => None,
+        }
+    }
 }
 
 /// 歌词行结构，作为多个并行带注解轨道的容器。
@@ -363,6 +516,25 @@ pub struct LyricLine {
     /// 可选的 iTunes Key (如 "L1", "L2")。
     #[builder(setter(into, strip_option = false))]
     pub itunes_key: Option<String>,
+    /// 可选的显示位置。
+    ///
+    /// 对应 LYS 行属性中的视图部分，以及 TTML 中 `<p>` 所在区域的 `tts:textAlign`。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option = false))]
+    pub view_side: Option<ViewSide>,
+    /// 可选的背景人声显示位置，未设置时与 `view_side` 相同。
+    ///
+    /// 对应 LYS 背景行属性中的视图部分，以及 TTML 中 `<span ttm:role="x-bg">`
+    /// 所引用样式的 `tts:textAlign`。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option = false))]
+    pub background_view_side: Option<ViewSide>,
+    /// 解析 TTML 时 `<p>` 上未识别的属性，生成 TTML 时原样写回。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_attributes: Vec<(String, String)>,
+    /// 解析 TTML 时 `<p>` 中未识别的子元素，生成 TTML 时写回到 `<p>` 的末尾。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_elements: Vec<XmlElement>,
 }
 
 impl LyricTrack {
@@ -396,6 +568,20 @@ impl LyricLine {
         }
     }
 
+    /// 该行的主要演唱者。
+    ///
+    /// 优先使用行的 `agent`；未设置时使用主歌词中第一个带有演唱者的音节。
+    /// 用于不支持音节级演唱者的格式（如 ASS、LYS）。
+    #[must_use]
+    pub fn primary_agent(&self) -> Option<&str> {
+        self.agent.as_deref().or_else(|| {
+            self.main_tracks()
+                .flat_map(|t| &t.content.words)
+                .flat_map(|w| &w.syllables)
+                .find_map(|s| s.agent.as_deref())
+        })
+    }
+
     /// 返回一个迭代器，用于遍历所有指定内容类型的带注解轨道。
     pub fn tracks_by_type(
         &self,
@@ -562,6 +748,38 @@ pub struct LyricSyllable {
     pub duration_ms: Option<u64>,
     /// 指示该音节后是否应有空格。
     pub ends_with_space: bool,
+    /// 可选的音高，以相对于中央 C (C4) 的半音数表示，与 `UltraStar` 的约定一致。
+    /// 来自其他格式（如 KRC）的音高值会原样保留。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(setter(strip_option))]
+    pub pitch: Option<i32>,
+    /// 可选的音符类型，用于卡拉 OK 游戏格式（如 `UltraStar`）。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(setter(strip_option))]
+    pub note_type: Option<NoteType>,
+    /// 可选的演唱者 ID，覆盖所在行的 `agent`，用于一行内由多位演唱者交替演唱的情况。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option))]
+    pub agent: Option<String>,
+    /// 解析 TTML 时音节 `<span>` 上未识别的属性，生成 TTML 时原样写回。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_attributes: Vec<(String, String)>,
+}
+
+/// 卡拉 OK 游戏中音符的类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum NoteType {
+    /// 普通音符。
+    #[default]
+    Normal,
+    /// 金色音符，唱准时得分更高。
+    Golden,
+    /// 自由音符，不参与评分。
+    Freestyle,
+    /// 说唱音符，只评判节奏不评判音高。
+    Rap,
+    /// 金色说唱音符。
+    RapGolden,
 }
 
 //=============================================================================
@@ -583,6 +801,14 @@ pub enum CanonicalMetadataKey {
     Offset,
     /// 词曲作者。
     Songwriter,
+    /// 作词者。
+    Lyricist,
+    /// 作曲者。
+    Composer,
+    /// 编曲者。
+    Arranger,
+    /// 制作人。
+    Producer,
     /// 网易云音乐 ID。
     NcmMusicId,
     /// QQ音乐 ID。
@@ -611,6 +837,10 @@ impl fmt::Display for CanonicalMetadataKey {
             CanonicalMetadataKey::Language => "Language",
             CanonicalMetadataKey::Offset => "Offset",
             CanonicalMetadataKey::Songwriter => "Songwriter",
+            CanonicalMetadataKey::Lyricist => "Lyricist",
+            CanonicalMetadataKey::Composer => "Composer",
+            CanonicalMetadataKey::Arranger => "Arranger",
+            CanonicalMetadataKey::Producer => "Producer",
             CanonicalMetadataKey::NcmMusicId => "NcmMusicId",
             CanonicalMetadataKey::QqMusicId => "QqMusicId",
             CanonicalMetadataKey::SpotifyId => "SpotifyId",
@@ -625,6 +855,15 @@ impl fmt::Display for CanonicalMetadataKey {
 }
 
 impl CanonicalMetadataKey {
+    /// 是否为演职员信息（词曲作者、作词、作曲、编曲、制作人）
+    #[must_use]
+    pub fn is_credit(&self) -> bool {
+        matches!(
+            self,
+            Self::Songwriter | Self::Lyricist | Self::Composer | Self::Arranger | Self::Producer
+        )
+    }
+
     /// 定义哪些键应该被显示出来
     #[must_use]
     pub fn is_public(&self) -> bool {
@@ -633,6 +872,11 @@ impl CanonicalMetadataKey {
             Self::Title
                 | Self::Artist
                 | Self::Album
+                | Self::Songwriter
+                | Self::Lyricist
+                | Self::Composer
+                | Self::Arranger
+                | Self::Producer
                 | Self::NcmMusicId
                 | Self::QqMusicId
                 | Self::SpotifyId
@@ -655,7 +899,11 @@ impl FromStr for CanonicalMetadataKey {
             "by" | "ttmlauthorgithublogin" => Ok(Self::TtmlAuthorGithubLogin),
             "language" | "lang" => Ok(Self::Language),
             "offset" => Ok(Self::Offset),
-            "songwriter" | "songwriters" => Ok(Self::Songwriter),
+            "au" | "songwriter" | "songwriters" => Ok(Self::Songwriter),
+            "lyricist" => Ok(Self::Lyricist),
+            "composer" => Ok(Self::Composer),
+            "arranger" => Ok(Self::Arranger),
+            "producer" => Ok(Self::Producer),
             "ncmmusicid" => Ok(Self::NcmMusicId),
             "qqmusicid" => Ok(Self::QqMusicId),
             "spotifyid" => Ok(Self::SpotifyId),
@@ -699,6 +947,55 @@ pub struct ParsedSourceData {
     pub detected_formatted_ttml_input: Option<bool>,
     /// 提供商名称
     pub source_name: String,
+    /// 解析 TTML 时文档级别未识别的内容，生成 TTML 时原样写回。
+    #[serde(default)]
+    pub ttml_extensions: TtmlExtensions,
+}
+
+/// 原样保留的 XML 节点。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum XmlNode {
+    /// 子元素。
+    Element(XmlElement),
+    /// 文本内容（已反转义）。
+    Text(String),
+}
+
+/// 原样保留的 XML 元素。
+///
+/// TTML 解析器无法识别的元素会以这种形式保存下来，生成 TTML 时再原样写回。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XmlElement {
+    /// 带前缀的元素名，例如 `tts:styling`。
+    pub name: String,
+    /// 按原始顺序排列的属性（值已反转义）。
+    pub attributes: Vec<(String, String)>,
+    /// 子节点。
+    pub children: Vec<XmlNode>,
+}
+
+/// TTML 文档级别中解析器无法识别的内容。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TtmlExtensions {
+    /// `<tt>` 上未识别的属性，包括额外的命名空间声明。
+    pub root_attributes: Vec<(String, String)>,
+    /// `<head>` 中 `<metadata>` 以外的元素，例如 `<styling>`、`<layout>`。
+    pub head_elements: Vec<XmlElement>,
+    /// `<metadata>` 中未识别的元素，包括使用自定义键的 `<amll:meta>`。
+    pub metadata_elements: Vec<XmlElement>,
+    /// `<body>` 上未识别的属性。
+    pub body_attributes: Vec<(String, String)>,
+}
+
+impl TtmlExtensions {
+    /// 是否没有保留任何内容。
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.root_attributes.is_empty()
+            && self.head_elements.is_empty()
+            && self.metadata_elements.is_empty()
+            && self.body_attributes.is_empty()
+    }
 }
 
 //=============================================================================
@@ -885,6 +1182,11 @@ pub struct InputFile {
     /// 可选的原始文件名。
     /// 可用于日志记录、元数据提取或某些特定转换逻辑。
     pub filename: Option<String>,
+    /// 可选的原始字节内容，用于加密格式（如 `EncryptedKrc`、`EncryptedQrc`）、二进制格式（如 `Kar`）
+    /// 以及需要自行检测文本编码的格式（如 `Sami`）。
+    /// 设置后将优先于 `content` 使用。
+    #[serde(default)]
+    pub raw_bytes: Option<Vec<u8>>,
 }
 
 impl InputFile {
@@ -910,6 +1212,30 @@ impl InputFile {
             format,
             language,
             filename,
+            raw_bytes: None,
+        }
+    }
+
+    /// 从原始字节创建一个 `InputFile` 实例，用于读取加密格式的文件。
+    ///
+    /// # 参数
+    /// * `bytes` - 文件的原始字节。
+    /// * `format` - 歌词的格式 (`LyricFormat` 枚举)。
+    /// * `language` - 可选的语言代码。
+    /// * `filename` - 可选的原始文件名。
+    #[must_use]
+    pub fn from_bytes(
+        bytes: Vec<u8>,
+        format: LyricFormat,
+        language: Option<String>,
+        filename: Option<String>,
+    ) -> Self {
+        Self {
+            content: String::new(),
+            format,
+            language,
+            filename,
+            raw_bytes: Some(bytes),
         }
     }
 }
@@ -924,6 +1250,7 @@ impl Default for InputFile {
     /// - `format`: `LyricFormat` 的默认值，即 TTML
     /// - `language`: None
     /// - `filename`: None
+    /// - `raw_bytes`: None
     ///
     fn default() -> Self {
         Self {
@@ -931,6 +1258,7 @@ impl Default for InputFile {
             format: LyricFormat::default(),
             language: None,
             filename: None,
+            raw_bytes: None,
         }
     }
 }
@@ -1011,6 +1339,25 @@ impl Default for TtmlGenerationOptions {
     }
 }
 
+/// AMLL TTML 数据库投稿包生成选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmllSubmissionOptions {
+    /// 生成 TTML 时使用的选项。`format` 会被忽略，投稿文件总是不格式化输出（规范 §6）。
+    pub ttml: TtmlGenerationOptions,
+    /// 缺少专辑名时是否以歌曲名代替（规范 §3.2 中单曲的写法）。
+    pub album_fallback_to_title: bool,
+}
+
+impl Default for AmllSubmissionOptions {
+    fn default() -> Self {
+        Self {
+            ttml: TtmlGenerationOptions::default(),
+            album_fallback_to_title: true,
+        }
+    }
+}
+
 /// TTML 解析时使用的默认语言选项
 /// 当TTML本身未指定语言时，解析器可以使用这些值。
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -1111,6 +1458,9 @@ pub struct ConversionOptions {
     pub lqe: LqeGenerationOptions,
     /// ASS 转换选项
     pub ass: AssGenerationOptions,
+    /// SAMI 生成选项
+    #[serde(default)]
+    pub sami: SamiGenerationOptions,
     /// LRC 转换选项
     #[serde(default)]
     pub lrc: LrcGenerationOptions,
@@ -1125,6 +1475,23 @@ pub struct ConversionOptions {
     /// 辅助歌词（如翻译）的匹配策略
     #[serde(default)]
     pub matching_strategy: AuxiliaryLineMatchingStrategy,
+    /// 时间轴检查与修复选项
+    #[serde(default)]
+    pub timing_repair: TimingRepairOptions,
+    /// 歌曲结构识别选项
+    #[serde(default)]
+    pub song_structure: SongStructureOptions,
+    /// 文本规范化选项
+    #[serde(default)]
+    pub text_normalization: TextNormalizationOptions,
+    /// 是否在生成的歌词头部输出作词、作曲、编曲、制作人等演职员信息。默认关闭。
+    ///
+    /// LRC 类格式（LRC、增强型 LRC、QRC、KRC、LYS、LQE、SPL）写为 `au`、`lyricist`、
+    /// `composer`、`arranger`、`producer` 标签，ASS 写为元数据注释行。
+    /// TTML 不受此选项影响：启用 Apple 格式规则时，词曲作者、作词和作曲者总是写入
+    /// `<songwriters>`；编曲和制作人没有对应的元素，不会写出。
+    #[serde(default)]
+    pub write_credits: bool,
 }
 
 /// ASS 生成转换选项
@@ -1139,6 +1506,28 @@ pub struct AssGenerationOptions {
     pub styles: Option<String>,
 }
 
+/// SAMI 生成选项
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SamiGenerationOptions {
+    /// 元数据和轨道都没有语言信息时，主歌词使用的语言代码。
+    pub default_main_language: String,
+    /// 翻译轨道没有语言信息时使用的语言代码。
+    pub default_translation_language: String,
+    /// 是否将罗马音轨道也写为独立的语言类。
+    pub include_romanizations: bool,
+}
+
+impl Default for SamiGenerationOptions {
+    fn default() -> Self {
+        Self {
+            default_main_language: "ko-KR".to_string(),
+            default_translation_language: "en-US".to_string(),
+            include_romanizations: false,
+        }
+    }
+}
+
 bitflags! {
     /// 元数据清理器的配置标志
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -1151,6 +1540,8 @@ bitflags! {
         const ENABLE_REGEX_STRIPPING  = 1 << 2;
         /// 正则表达式匹配区分大小写
         const REGEX_CASE_SENSITIVE    = 1 << 3;
+        /// 移除前先将演职员信息行（如 "作词：xxx"）解析为结构化元数据
+        const EXTRACT_CREDITS         = 1 << 4;
     }
 }
 
@@ -1225,6 +1616,23 @@ pub struct ChineseConversionOptions {
     /// 指定转换模式，默认为直接替换
     #[serde(default)]
     pub mode: ChineseConversionMode,
+
+    /// 背景人声轨道使用的 `OpenCC` 配置，总是以直接替换的方式转换。
+    /// 为 `None` 时不转换背景人声。
+    #[serde(default)]
+    pub background_config: Option<ferrous_opencc::config::BuiltinConfig>,
+
+    /// 中文翻译轨道使用的 `OpenCC` 配置，总是以直接替换的方式转换。
+    /// 只处理语言标签为空或以 "zh" 开头的翻译；带有语言标签的翻译会同时更新为推断出的目标标签。
+    /// 为 `None` 时不转换翻译。
+    #[serde(default)]
+    pub translation_config: Option<ferrous_opencc::config::BuiltinConfig>,
+
+    /// 用户自定义的词组替换表，例如艺人名或俚语。
+    /// 在 `OpenCC` 之前应用，匹配到的词组直接替换为对应的值，不再经过 `OpenCC` 转换。
+    /// 多个词组重叠时，优先匹配较长的词组。
+    #[serde(default)]
+    pub custom_phrases: HashMap<String, String>,
 }
 
 /// LRC 生成时，背景人声的输出方式
@@ -1254,6 +1662,22 @@ pub enum LrcEndTimeOutputMode {
     },
 }
 
+/// LRC 生成时，行首演唱者标记的输出方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LrcAgentMarkerStyle {
+    /// LRC 使用 Walaoke 标记，增强型 LRC 使用 `v1:` 标记
+    Auto,
+    /// Walaoke 扩展标记：`M:`（编号最小的演唱者）、`F:`（其他演唱者）、`D:`（合唱）。
+    ///
+    /// 演唱者没有性别信息，`M:` 和 `F:` 只用于区分演唱者，并不表示性别。
+    Walaoke,
+    /// 以 Agent ID 作为标记，如 `v1:`、`v2:`、`v1000:`
+    Voice,
+    /// [默认] 不输出演唱者标记
+    #[default]
+    Disabled,
+}
+
 /// LRC 生成选项
 #[derive(Debug, Clone, Serialize, Deserialize, Builder)]
 #[builder(setter(into), default)]
@@ -1262,6 +1686,9 @@ pub struct LrcGenerationOptions {
     pub sub_lines_output_mode: LrcSubLinesOutputMode,
     /// 控制行结束时间标记的输出方式
     pub end_time_output_mode: LrcEndTimeOutputMode,
+    /// 控制演唱者标记的输出方式，只在歌词行带有演唱者时生效
+    #[serde(default)]
+    pub agent_marker_style: LrcAgentMarkerStyle,
 }
 
 impl Default for LrcGenerationOptions {
@@ -1269,6 +1696,7 @@ impl Default for LrcGenerationOptions {
         Self {
             sub_lines_output_mode: LrcSubLinesOutputMode::Ignore,
             end_time_output_mode: LrcEndTimeOutputMode::Never,
+            agent_marker_style: LrcAgentMarkerStyle::Disabled,
         }
     }
 }
@@ -1310,7 +1738,12 @@ pub enum ConversionResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FullConversionResult {
     /// 最终生成的歌词字符串。
+    /// 对于加密格式，这是加密字节的文本编码（KRC 为 Base64，QRC 为十六进制）；
+    /// 对于 Karaoke MIDI 等二进制格式，这是文件字节的 Base64 编码。
     pub output_lyrics: String,
+    /// 对于加密格式和二进制格式，这是可以直接写入文件的字节；其他格式为 `None`。
+    #[serde(default)]
+    pub output_bytes: Option<Vec<u8>>,
     /// 在转换开始时从输入解析出的源数据。
     pub source_data: ParsedSourceData,
 }
@@ -1343,3 +1776,349 @@ impl Default for SyllableSmoothingOptions {
         }
     }
 }
+
+// =============================================================================
+// 10. 时间轴检查与修复选项
+// =============================================================================
+
+/// 修复相邻歌词行时间重叠的策略。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum OverlapRepairStrategy {
+    /// [默认] 截断前一行，使其在后一行开始时结束。
+    #[default]
+    Trim,
+    /// 在重叠区间的中点处分割，前一行提前结束，后一行推迟开始。
+    Split,
+}
+
+/// 控制时间轴修复的选项。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Builder)]
+#[builder(setter(into), default)]
+#[serde(default)]
+pub struct TimingRepairOptions {
+    /// 是否在转换时自动修复时间轴问题。默认关闭。
+    pub enabled: bool,
+    /// 修复行重叠时使用的策略。
+    pub overlap_strategy: OverlapRepairStrategy,
+    /// 是否允许不同演唱者的行互相重叠（对唱中很常见）。
+    pub allow_cross_agent_overlap: bool,
+    /// 无法从下一行推断结束时间时，使用的默认行时长（毫秒）。
+    pub default_line_duration_ms: u64,
+    /// 修复零时长或负时长音节时，赋予的最小时长（毫秒）。
+    pub min_syllable_duration_ms: u64,
+}
+
+impl Default for TimingRepairOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            overlap_strategy: OverlapRepairStrategy::Trim,
+            allow_cross_agent_overlap: true,
+            default_line_duration_ms: 5000,
+            min_syllable_duration_ms: 50,
+        }
+    }
+}
+
+// =============================================================================
+// 11. 歌曲结构识别选项
+// =============================================================================
+
+/// 歌曲的组成部分，对应 TTML 中 `itunes:song-part` 属性的常用取值。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum SongPart {
+    /// 前奏
+    Intro,
+    /// 主歌
+    Verse,
+    /// 预副歌
+    PreChorus,
+    /// 副歌
+    Chorus,
+    /// 桥段
+    Bridge,
+    /// 尾奏
+    Outro,
+}
+
+impl SongPart {
+    /// 获取写入 `itunes:song-part` 时使用的字符串。
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            SongPart::Intro => "Intro",
+            SongPart::Verse => "Verse",
+            SongPart::PreChorus => "PreChorus",
+            SongPart::Chorus => "Chorus",
+            SongPart::Bridge => "Bridge",
+            SongPart::Outro => "Outro",
+        }
+    }
+}
+
+impl fmt::Display for SongPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 用户指定的歌曲组成部分标记，会覆盖自动识别的结果。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SongPartOverride {
+    /// 区间开始时间（毫秒，包含）。
+    pub start_ms: u64,
+    /// 区间结束时间（毫秒，不包含）。
+    pub end_ms: u64,
+    /// 要设置的标记，例如 "Chorus"。可以是任意字符串。
+    pub part: String,
+}
+
+/// 控制歌曲结构识别的选项。
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(setter(into), default)]
+pub struct SongStructureOptions {
+    /// 是否自动识别歌曲结构并填充 `LyricLine::song_part`。默认关闭。
+    pub enabled: bool,
+    /// 是否覆盖源文件中已有的标记（例如来自 TTML 的 `itunes:song-part`）。
+    pub overwrite_existing: bool,
+    /// 两行文本被视为重复时所需的最低相似度 (0.0 ~ 1.0)。
+    pub similarity_threshold: f64,
+    /// 两行之间的间隔超过该值（毫秒）时，视为段落边界。
+    pub section_gap_ms: u64,
+    /// 一段连续重复的行至少需要的行数，才会被视为重复段落。
+    pub min_repeat_lines: usize,
+    /// 用户指定的标记，按时间区间应用于开始时间落在区间内的行。
+    pub overrides: Vec<SongPartOverride>,
+}
+
+impl Default for SongStructureOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            overwrite_existing: false,
+            similarity_threshold: 0.8,
+            section_gap_ms: 4000,
+            min_repeat_lines: 2,
+            overrides: Vec::new(),
+        }
+    }
+}
+
+// =============================================================================
+// 12. 文本规范化选项
+// =============================================================================
+
+/// 全角/半角字符的折叠方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum WidthFolding {
+    /// [默认] 保持原样。
+    #[default]
+    Keep,
+    /// 只将全角字母、数字和全角空格折叠为半角，保留全角标点。
+    Alphanumeric,
+    /// 将所有全角 ASCII 字符（包括标点）和全角空格折叠为半角。
+    All,
+}
+
+/// 引号的统一样式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum QuoteStyle {
+    /// [默认] 保持原样。
+    #[default]
+    Keep,
+    /// 统一为直引号 `"` 和 `'`。
+    Straight,
+    /// 统一为弯引号 `“”` 和 `‘’`，直引号按出现顺序交替视为左、右引号。
+    Curly,
+}
+
+bitflags! {
+    /// 文本规范化的配置标志
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct TextNormalizationFlags: u8 {
+        /// 启用文本规范化功能
+        const ENABLED             = 1 << 0;
+        /// 在中日韩文字与拉丁字母、数字之间插入空格
+        const PANGU_SPACING       = 1 << 1;
+        /// 移除零宽字符（U+200B、U+2060、U+FEFF），保留 ZWJ 和 ZWNJ
+        const REMOVE_ZERO_WIDTH   = 1 << 2;
+        /// 去除首尾空白并将连续空白合并为一个空格
+        const COLLAPSE_WHITESPACE = 1 << 3;
+        /// 将文本转换为 Unicode NFC 形式
+        const UNICODE_NFC         = 1 << 4;
+    }
+}
+
+impl Default for TextNormalizationFlags {
+    fn default() -> Self {
+        Self::REMOVE_ZERO_WIDTH | Self::COLLAPSE_WHITESPACE | Self::UNICODE_NFC
+    }
+}
+
+/// 控制文本规范化的选项。
+///
+/// 规范化只修改音节文本和 `ends_with_space`，不会改变任何时间戳，
+/// 因此可以安全地用于逐字歌词。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Builder)]
+#[builder(setter(into), default)]
+pub struct TextNormalizationOptions {
+    /// 用于控制规范化行为的位标志。默认不包含 `ENABLED`，即默认关闭。
+    #[serde(default)]
+    pub flags: TextNormalizationFlags,
+    /// 全角/半角字符的折叠方式。
+    #[serde(default)]
+    pub width_folding: WidthFolding,
+    /// 引号的统一样式。
+    #[serde(default)]
+    pub quote_style: QuoteStyle,
+}
+
+// =============================================================================
+// 13. TTML 规范化排版选项
+// =============================================================================
+
+/// 规范化 TTML 的排版方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TtmlLayout {
+    /// [默认] 格式化输出：只包含子元素的元素逐行缩进，含有文本的元素（包括所有
+    /// `<p>`、`<span>` 和 `<text>`）保持在同一行内，以免引入新的空白。
+    #[default]
+    Formatted,
+    /// 压缩输出：去除所有用于排版的空白。
+    Minified,
+}
+
+/// 控制 TTML 规范化的选项。
+///
+/// 无论排版方式如何，属性顺序和命名空间前缀都会被统一。
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(setter(into), default)]
+pub struct TtmlCanonicalizeOptions {
+    /// 输出的排版方式。
+    #[serde(default)]
+    pub layout: TtmlLayout,
+    /// 格式化输出时每一级缩进的空格数。
+    #[serde(default = "default_ttml_indent_size")]
+    pub indent_size: usize,
+}
+
+fn default_ttml_indent_size() -> usize {
+    2
+}
+
+impl Default for TtmlCanonicalizeOptions {
+    fn default() -> Self {
+        Self {
+            layout: TtmlLayout::default(),
+            indent_size: default_ttml_indent_size(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_with_agents(line_agent: Option<&str>, syllable_agents: &[Option<&str>]) -> LyricLine {
+        let syllables = syllable_agents
+            .iter()
+            .map(|agent| LyricSyllable {
+                text: "a".to_string(),
+                agent: agent.map(str::to_string),
+                ..Default::default()
+            })
+            .collect();
+        LyricLine {
+            agent: line_agent.map(str::to_string),
+            tracks: vec![AnnotatedTrack {
+                content_type: ContentType::Main,
+                content: LyricTrack {
+                    words: vec![Word {
+                        syllables,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_agent_store_merge() {
+        let mut store = AgentStore::default();
+        store.insert(Agent {
+            id: "v1".to_string(),
+            name: Some("Alice".to_string()),
+            color: Some("#FF8800".to_string()),
+            view_side: Some(ViewSide::Right),
+            ..Default::default()
+        });
+        store.insert(Agent::new("v2"));
+
+        store.merge(AgentStore::from_definitions(&["v1=Carol", "v3=合"]));
+
+        let v1 = store.get("v1").unwrap();
+        assert_eq!(v1.name.as_deref(), Some("Carol"));
+        assert_eq!(v1.color.as_deref(), Some("#FF8800"));
+        assert_eq!(v1.view_side, Some(ViewSide::Right));
+        assert!(store.get("v2").is_some());
+        assert_eq!(store.get("v3").unwrap().agent_type, AgentType::Group);
+        assert_eq!(store.get("v3").unwrap().name, None);
+    }
+
+    #[test]
+    fn test_agent_store_register_referenced() {
+        let mut store = AgentStore::default();
+        store.insert(Agent {
+            id: "v1".to_string(),
+            name: Some("Alice".to_string()),
+            ..Default::default()
+        });
+        let lines = [
+            line_with_agents(Some("v1"), &[None, Some("v2")]),
+            line_with_agents(None, &[Some("v3")]),
+        ];
+
+        store.register_referenced(&lines);
+
+        assert_eq!(store.agents_by_id.len(), 3);
+        assert_eq!(store.get("v1").unwrap().name.as_deref(), Some("Alice"));
+        assert_eq!(store.get("v2").unwrap(), &Agent::new("v2"));
+        assert_eq!(store.get("v3").unwrap().agent_type, AgentType::Person);
+    }
+
+    #[test]
+    fn test_agent_store_apply_view_sides() {
+        let mut store = AgentStore::default();
+        store.insert(Agent {
+            id: "v2".to_string(),
+            view_side: Some(ViewSide::Right),
+            ..Default::default()
+        });
+        store.insert(Agent::new("v1"));
+        let mut explicit = line_with_agents(Some("v2"), &[]);
+        explicit.view_side = Some(ViewSide::Left);
+        let mut lines = [
+            line_with_agents(Some("v2"), &[]),
+            line_with_agents(None, &[Some("v2")]),
+            line_with_agents(Some("v1"), &[]),
+            explicit,
+        ];
+
+        store.apply_view_sides(&mut lines);
+
+        let sides: Vec<_> = lines.iter().map(|l| l.view_side).collect();
+        assert_eq!(
+            sides,
+            [
+                Some(ViewSide::Right),
+                Some(ViewSide::Right),
+                None,
+                Some(ViewSide::Left)
+            ]
+        );
+    }
+}
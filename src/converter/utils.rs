@@ -4,7 +4,7 @@ use regex::Regex;
 use std::collections::HashMap;
 use std::sync::LazyLock;
 
-use crate::converter::{LyricLine, LyricSyllable};
+use crate::converter::{LyricFormat, LyricLine, LyricSyllable};
 
 /// 辅助函数，用于安全地将偏移量应用到 u64 时间戳上
 fn offset_timestamp(timestamp: u64, offset: i64) -> u64 {
@@ -112,6 +112,114 @@ pub(crate) fn normalize_text_whitespace(text: &str) -> String {
     trimmed.split_whitespace().collect::<Vec<&str>>().join(" ")
 }
 
+/// 逐行特征检测使用的正则表达式，按优先级排列。
+static FORMAT_LINE_SIGNATURES: LazyLock<Vec<(LyricFormat, Regex)>> = LazyLock::new(|| {
+    [
+        (LyricFormat::Krc, r"^\[\d+,\d+]<\d+,\d+,-?\d+>"),
+        (LyricFormat::Yrc, r"^\[\d+,\d+]\(\d+,\d+,0\)"),
+        (
+            LyricFormat::Klyric,
+            r"^\[\d+,\d+][(<]\d+,\d+(?:,-?\d+)?[)>]",
+        ),
+        (LyricFormat::Qrc, r"^\[\d+,\d+].*\(\d+,\d+\)"),
+        (LyricFormat::Lys, r"^\[\d+].*\(\d+,\d+\)"),
+        (
+            LyricFormat::EnhancedLrc,
+            r"^\[\d{2,}:\d{2}[.:]\d{2,3}].*<\d{2,}:\d{2}[.:]\d{2,3}>",
+        ),
+        (LyricFormat::Lrc, r"^\[\d{2,}:\d{2}[.:]\d{2,3}]"),
+        (LyricFormat::Lyl, r"^\[\d+,\d+]"),
+    ]
+    .into_iter()
+    .map(|(format, pattern)| {
+        (
+            format,
+            Regex::new(pattern).expect("编译 FORMAT_LINE_SIGNATURES 失败"),
+        )
+    })
+    .collect()
+});
+
+/// 判断字符是否属于中日韩文字（汉字、假名、注音和谚文）。
+#[must_use]
+pub fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{2E80}'..='\u{2FDF}'
+            | '\u{3040}'..='\u{30FF}'
+            | '\u{3100}'..='\u{312F}'
+            | '\u{3190}'..='\u{31FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{AC00}'..='\u{D7AF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// 判断文本是否为 `UltraStar` 格式，即开头的 `#KEY:VALUE` 头部中包含 `#TITLE:` 或 `#BPM:`。
+///
+/// `UltraStar` 文件通常使用 `.txt` 扩展名，无法仅凭扩展名与普通文本区分。
+#[must_use]
+pub fn is_ultrastar_content(content: &str) -> bool {
+    content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take_while(|line| line.starts_with('#'))
+        .any(|line| {
+            let upper = line.to_ascii_uppercase();
+            upper.starts_with("#TITLE:") || upper.starts_with("#BPM:")
+        })
+}
+
+/// 根据文本内容推测歌词格式。
+///
+/// 先检查具有明确文件头的格式（如 TTML、ASS、LQE），再逐行匹配时间戳特征。
+/// 无法识别时（例如不带时间戳的纯文本）返回 `None`。
+#[must_use]
+pub fn detect_lyric_format(content: &str) -> Option<LyricFormat> {
+    let trimmed = content.trim_start_matches('\u{feff}').trim_start();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    if trimmed.starts_with("[Lyricify Quick Export]") {
+        return Some(LyricFormat::Lqe);
+    }
+    if trimmed.starts_with('<') && trimmed.to_ascii_uppercase().contains("<SAMI") {
+        return Some(LyricFormat::Sami);
+    }
+    if trimmed.starts_with('<') && trimmed.contains("<timedtext") {
+        return Some(LyricFormat::YouTubeSrv3);
+    }
+    if trimmed.starts_with('<') && trimmed.contains("<tt") {
+        return Some(LyricFormat::Ttml);
+    }
+    if trimmed.starts_with('{') && trimmed.contains("\"ttml") {
+        return Some(LyricFormat::AppleMusicJson);
+    }
+    if trimmed.starts_with('{')
+        && trimmed.contains("\"events\"")
+        && trimmed.contains("\"tStartMs\"")
+    {
+        return Some(LyricFormat::YouTubeJson3);
+    }
+    if trimmed.contains("[Script Info]") || trimmed.contains("[Events]") {
+        return Some(LyricFormat::Ass);
+    }
+    if is_ultrastar_content(trimmed) {
+        return Some(LyricFormat::UltraStar);
+    }
+
+    let lines: Vec<&str> = trimmed.lines().map(str::trim).collect();
+    FORMAT_LINE_SIGNATURES
+        .iter()
+        .find(|(_, regex)| lines.iter().any(|line| regex.is_match(line)))
+        .map(|(format, _)| *format)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,4 +370,58 @@ mod tests {
         assert_eq!(syllables.len(), 1);
         assert!(syllables[0].ends_with_space, "尾随空格标志应保持不变");
     }
+
+    #[test]
+    fn test_detect_lyric_format() {
+        let cases = [
+            ("[ti:Song]\n[00:01.00]Hello", Some(LyricFormat::Lrc)),
+            (
+                "[00:01.00]<00:01.00>He<00:01.50>llo",
+                Some(LyricFormat::EnhancedLrc),
+            ),
+            (
+                "[1000,500]<0,250,0>He<250,250,0>llo",
+                Some(LyricFormat::Krc),
+            ),
+            (
+                "[1000,500](1000,250,0)He(1250,250,0)llo",
+                Some(LyricFormat::Yrc),
+            ),
+            ("[1000,500](0,250)He(250,250)llo", Some(LyricFormat::Klyric)),
+            (
+                "<SAMI>\n<BODY>\n<SYNC Start=1000><P Class=KRCC>Hello\n</BODY>\n</SAMI>",
+                Some(LyricFormat::Sami),
+            ),
+            (
+                "<?xml version=\"1.0\"?><timedtext format=\"3\"><body></body></timedtext>",
+                Some(LyricFormat::YouTubeSrv3),
+            ),
+            (
+                "{\"events\":[{\"tStartMs\":0,\"segs\":[{\"utf8\":\"Hi\"}]}]}",
+                Some(LyricFormat::YouTubeJson3),
+            ),
+            (
+                "[1000,500]He(1000,250)llo(1250,250)",
+                Some(LyricFormat::Qrc),
+            ),
+            ("[4]He(1000,250)llo(1250,250)", Some(LyricFormat::Lys)),
+            (
+                "<?xml version=\"1.0\"?><tt xmlns=\"http://www.w3.org/ns/ttml\"></tt>",
+                Some(LyricFormat::Ttml),
+            ),
+            (
+                "#TITLE:Song\n#BPM:300\n: 0 4 0 Hi",
+                Some(LyricFormat::UltraStar),
+            ),
+            (
+                "#TITLE:Song\n#ARTIST:Someone\n: 0 4 0 Hi",
+                Some(LyricFormat::UltraStar),
+            ),
+            ("Just some\nplain lyrics", None),
+            ("Just some\n#BPM: in the middle", None),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(detect_lyric_format(content), expected, "内容: {content}");
+        }
+    }
 }
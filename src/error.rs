@@ -117,6 +117,7 @@ impl From<ConvertError> for LyricsHelperError {
             }
             ConvertError::FuriganaParsingError(s) => Self::Parser(format!("振假名解析失败: {s}")),
             ConvertError::TrackMergeError(s) => Self::Internal(format!("轨道合并失败: {s}")),
+            ConvertError::Crypto(s) => Self::Decryption(s),
         }
     }
 }
@@ -2,6 +2,7 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::too_many_lines)]
 #![allow(clippy::missing_errors_doc)]
+#![allow(clippy::missing_panics_doc)]
 #![allow(clippy::ignore_without_reason)]
 
 //! # Lyrics Helper RS
@@ -181,7 +182,10 @@ use crate::{
         LyricFormat,
         types::{ConversionInput, ConversionOptions, FullConversionResult, ParsedSourceData},
     },
-    model::{generic::CoverSize, track::FullLyricsResult},
+    model::{
+        generic::CoverSize,
+        track::{FullLyricsResult, PlatformId},
+    },
     providers::{
         Provider, amll_ttml_database::AmllTtmlDatabase, kugou::KugouMusic, netease::NeteaseClient,
         qq::QQMusic,
@@ -383,7 +387,7 @@ impl LyricsHelper {
                 .collect();
 
             let mut sorted_results = all_results;
-            sorted_results.sort_by(|a, b| b.match_type.cmp(&a.match_type));
+            sorted_results.sort_by_key(|r| std::cmp::Reverse(r.match_type));
 
             let mut unique_results = Vec::new();
             let mut seen_keys = HashSet::new();
@@ -483,6 +487,22 @@ impl LyricsHelper {
         &self,
         track_meta: &Track<'a>,
         mode: SearchMode,
+    ) -> Result<SearchLyricsFuture<'a>> {
+        self.search_lyrics_with_platform_ids(track_meta, &[], mode)
+    }
+
+    /// 与 [`Self::search_lyrics`] 相同，但会先尝试按已知的外部平台 ID 精确获取歌词，
+    /// 都失败后再按标题等信息搜索。
+    ///
+    /// # 参数
+    /// * `track_meta` - 包含歌曲标题、艺术家等信息的 `Track` 结构体引用。
+    /// * `platform_ids` - 已知的外部平台歌曲 ID，例如网易云或 QQ 音乐的歌曲 ID。
+    /// * `mode` - `SearchMode` 枚举，用于定义搜索策略。
+    pub fn search_lyrics_with_platform_ids<'a>(
+        &self,
+        track_meta: &Track<'a>,
+        platform_ids: &[PlatformId<'a>],
+        mode: SearchMode,
     ) -> Result<SearchLyricsFuture<'a>> {
         if self.providers.is_empty() {
             return Err(LyricsHelperError::ProvidersNotInitialized);
@@ -500,8 +520,13 @@ impl LyricsHelper {
         );
 
         let track_meta = track_meta.clone();
+        let platform_ids = platform_ids.to_vec();
 
         Ok(Box::pin(async move {
+            if let Some(result) = search_by_platform_ids(&providers_to_search, &platform_ids).await
+            {
+                return Ok(Some(result));
+            }
             match mode {
                 SearchMode::Ordered | SearchMode::Specific(_) => {
                     search_ordered(&providers_to_search, &track_meta).await
@@ -698,6 +723,43 @@ async fn search_and_fetch_from_provider(
     Ok(None)
 }
 
+/// 依次在各提供商中按外部平台 ID 精确获取歌词。没有提供 ID 或都未找到时返回 `None`。
+async fn search_by_platform_ids(
+    providers: &[Arc<dyn Provider + Send + Sync>],
+    platform_ids: &[PlatformId<'_>],
+) -> Option<model::track::LyricsAndMetadata> {
+    if platform_ids.is_empty() {
+        return None;
+    }
+
+    for provider in providers {
+        for platform_id in platform_ids {
+            match provider.get_lyrics_by_platform_id(platform_id).await {
+                Ok(result) => {
+                    tracing::info!(
+                        "在 '{}' 通过 {:?} ID {} 获取到歌词。",
+                        provider.name(),
+                        platform_id.platform,
+                        platform_id.id
+                    );
+                    return Some(result);
+                }
+                Err(LyricsHelperError::ProviderNotSupported(_)) => break,
+                Err(LyricsHelperError::LyricNotFound) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        "在 '{}' 通过平台 ID 获取歌词失败: {}，继续尝试。",
+                        provider.name(),
+                        e
+                    );
+                }
+            }
+        }
+    }
+    tracing::debug!("未能通过平台 ID 获取歌词，改为按歌曲信息搜索。");
+    None
+}
+
 /// 在提供商上按顺序执行搜索和获取。
 async fn search_ordered(
     providers: &[Arc<dyn Provider + Send + Sync>],
@@ -733,7 +795,7 @@ async fn search_lyrics_parallel(
         .flatten()
         .collect();
     let mut sorted_results = all_results;
-    sorted_results.sort_by(|a, b| b.match_type.cmp(&a.match_type));
+    sorted_results.sort_by_key(|r| std::cmp::Reverse(r.match_type));
 
     if let Some(best_match) = sorted_results.first() {
         tracing::info!(
@@ -785,7 +847,7 @@ async fn search_comprehensive_unified(
         .collect();
 
     let mut sorted_candidates = all_candidates;
-    sorted_candidates.sort_by(|a, b| b.match_type.cmp(&a.match_type));
+    sorted_candidates.sort_by_key(|r| std::cmp::Reverse(r.match_type));
 
     if sorted_candidates.is_empty() {
         tracing::info!("所有提供商都未找到任何搜索结果。");
@@ -39,6 +39,28 @@ pub struct Track<'a> {
     pub duration: Option<u64>,
 }
 
+/// 外部音乐平台。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExternalPlatform {
+    /// 网易云音乐。
+    Netease,
+    /// QQ 音乐。
+    Qq,
+    /// Apple Music。
+    AppleMusic,
+    /// Spotify。
+    Spotify,
+}
+
+/// 某个外部平台上的歌曲 ID。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlatformId<'a> {
+    /// 所属平台。
+    pub platform: ExternalPlatform,
+    /// 该平台上的歌曲 ID，例如网易云的数字 ID 或 QQ 音乐的 songmid。
+    pub id: &'a str,
+}
+
 /// 代表一个标准化的搜索结果条目。
 ///
 /// 这是所有 Provider 的 `search_songs` 方法需要返回的类型。
@@ -1,13 +1,20 @@
 //! 此模块实现了与 AMLL TTML Database 进行交互的 `Provider`。
 
-use std::{path::Path, sync::Arc, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{
+    Client, StatusCode,
+    header::{CONTENT_RANGE, RANGE},
+};
 use serde::Deserialize;
 use tokio::{
     fs,
-    io::{AsyncBufReadExt, BufReader},
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
 };
 
 use crate::{
@@ -20,22 +27,31 @@ use crate::{
     model::{
         generic::{self, CoverSize},
         match_type::MatchScorable,
-        track::{FullLyricsResult, MatchType, RawLyrics, SearchResult, Track},
+        track::{
+            ExternalPlatform, FullLyricsResult, LyricsAndMetadata, MatchType, PlatformId,
+            RawLyrics, SearchResult, Track,
+        },
     },
     providers::{Provider, amll_ttml_database::types::SearchField},
     search::matcher::compare_track,
 };
 
+pub mod search_index;
+pub mod submission;
 mod types;
+use search_index::{AmllSearchHit, AmllSearchIndex, AmllSearchQuery};
 use types::IndexEntry;
 
 const GITHUB_API_BASE_URL: &str = "https://api.github.com";
 const RAW_CONTENT_BASE_URL: &str = "https://raw.githubusercontent.com";
 const INDEX_FILE_PATH_IN_REPO: &str = "metadata/raw-lyrics-index.jsonl";
+const LYRICS_DIR_IN_REPO: &str = "raw-lyrics";
 const REPO_OWNER: &str = "Steve-xmh";
 const REPO_NAME: &str = "amll-ttml-db";
 const REPO_BRANCH: &str = "main";
 const USER_AGENT: &str = "lyrics-helper-rs/0.1.0";
+/// 增量更新索引时与本地缓存重叠下载的字节数，用于确认远程文件没有被改写。
+const INDEX_OVERLAP_BYTES: usize = 4096;
 
 /// 用于反序列化 GitHub commit API 响应的辅助结构体。
 #[derive(Deserialize)]
@@ -45,20 +61,29 @@ struct GitHubCommitInfo {
 
 /// AMLL TTML Database 提供商的实现。
 pub struct AmllTtmlDatabase {
-    index: Arc<Vec<IndexEntry>>,
+    index: Arc<AmllSearchIndex>,
     http_client: Client,
     lyrics_url_template: String,
+    /// 已下载歌词的缓存目录，按索引的 commit SHA 区分版本。
+    lyrics_cache_dir: Option<PathBuf>,
+    /// 本地数据库副本的目录。
+    local_database: Option<PathBuf>,
+    offline: bool,
 }
 
 impl AmllTtmlDatabase {
     /// 创建一个新的 `AmllTtmlDatabase` 实例。
     ///
-    /// # 缓存逻辑
-    /// 1. 检查远程索引文件的最新 commit SHA。
-    /// 2. 与本地缓存的 SHA (`index.jsonl.head`) 进行比较。
-    /// 3. 如果 SHA 不同或本地缓存不存在，则从 GitHub 下载最新的 `index.jsonl` 文件，并更新缓存和 SHA。
-    /// 4. 如果 SHA 相同，或因 API 速率限制无法检查更新，则直接从本地缓存加载索引。
-    /// 5. 如果被速率限制且无本地缓存，则初始化失败。
+    /// # 索引加载逻辑
+    /// 1. 如果配置了 `local_database`，直接从本地数据库副本加载索引，不进行网络请求。
+    /// 2. 离线模式下只从本地缓存加载索引，没有缓存时初始化失败。
+    /// 3. 否则检查远程索引文件的最新 commit SHA，并与本地缓存的 SHA (`index.jsonl.head`) 比较：
+    ///    - SHA 相同时直接使用本地缓存；
+    ///    - SHA 不同时只下载索引文件新增的尾部（HTTP Range），失败时回退到完整下载；
+    ///    - 无法检查更新（例如被速率限制）时使用本地缓存，没有缓存则直接下载完整索引。
+    ///
+    /// 下载过的歌词会按索引的 commit SHA 分目录缓存到本地，之后会直接从磁盘读取。
+    /// 索引更新后，旧版本的歌词缓存会被清理，避免继续使用仓库中已修改的歌词。
     pub async fn new(config: &AmllConfig) -> Result<Self> {
         let (index_url, lyrics_url_template) = match &config.mirror {
             AmllMirror::GitHub => (
@@ -91,44 +116,102 @@ impl AmllTtmlDatabase {
 
         let http_client = Client::new();
 
-        let remote_head_result = fetch_remote_index_head(&http_client).await;
-
-        let (should_update, remote_head) = match remote_head_result {
-            Ok(sha) => {
-                let local_head = load_cached_index_head(&index_cache_path).await?;
-                (Some(sha.clone()) != local_head, Some(sha))
+        let index_entries = if let Some(local_database) = &config.local_database {
+            let index_path = local_database.join(INDEX_FILE_PATH_IN_REPO);
+            tracing::info!("[AMLL] 从本地数据库副本加载索引: {}", index_path.display());
+            load_index_from_cache(&index_path).await?
+        } else if config.offline {
+            if !index_cache_path.exists() {
+                return Err(LyricsHelperError::Internal(
+                    "AMLL 数据库初始化失败：离线模式下没有可用的本地索引缓存。".to_string(),
+                ));
             }
-            Err(LyricsHelperError::RateLimited(msg)) => {
-                tracing::warn!("[AMLL] {msg}");
-                tracing::warn!("[AMLL] 无法检查索引更新，将使用本地缓存（如果存在）。");
-                (false, None)
-            }
-            Err(e) => return Err(e),
-        };
-
-        let index_entries = if !should_update && index_cache_path.exists() {
-            tracing::info!("[AMLL] 索引缓存有效或无法检查更新，从本地加载...");
-            load_index_from_cache(&index_cache_path).await?
-        } else if let Some(sha) = remote_head {
-            tracing::info!("[AMLL] 索引已过期或不存在，正在从 {} 下载...", index_url);
-            download_and_parse_index(&index_cache_path, &sha, &http_client, &index_url).await?
-        } else if index_cache_path.exists() {
-            tracing::warn!("[AMLL] 将使用可能已过期的本地缓存。");
+            tracing::info!("[AMLL] 离线模式，从本地缓存加载索引...");
             load_index_from_cache(&index_cache_path).await?
         } else {
-            return Err(LyricsHelperError::Internal(
-                "AMLL 数据库初始化失败：被速率限制且无本地缓存可用。".to_string(),
-            ));
+            load_or_update_index(&index_cache_path, &http_client, &index_url).await?
         };
 
         tracing::info!("[AMLL] 索引加载完成，共 {} 条记录。", index_entries.len());
+        let index_head = load_cached_index_head(&index_cache_path).await?;
+        let lyrics_cache_root = cache_dir.join(LYRICS_DIR_IN_REPO);
+        let lyrics_cache_dir =
+            lyrics_cache_root.join(index_head.as_deref().unwrap_or("unversioned"));
+        if index_head.is_some()
+            && !config.offline
+            && let Err(e) = prune_lyrics_cache(&lyrics_cache_root, &lyrics_cache_dir).await
+        {
+            tracing::warn!("[AMLL] 清理过期的歌词缓存失败: {e}");
+        }
+        let index = tokio::task::spawn_blocking(move || AmllSearchIndex::new(index_entries))
+            .await
+            .map_err(|e| LyricsHelperError::Internal(format!("建立 AMLL 搜索索引失败: {e}")))?;
         Ok(Self {
-            index: Arc::new(index_entries),
+            index: Arc::new(index),
             http_client,
             lyrics_url_template,
+            lyrics_cache_dir: Some(lyrics_cache_dir),
+            local_database: config.local_database.clone(),
+            offline: config.offline,
         })
     }
 
+    /// 获取 TTML 文件内容。
+    ///
+    /// 依次尝试本地数据库副本、歌词缓存和远程下载，下载成功后写入歌词缓存。
+    async fn fetch_ttml(&self, song_id: &str) -> Result<String> {
+        // song_id 是仓库中的文件名，不能包含路径分隔符
+        let is_plain_file_name = Path::new(song_id)
+            .file_name()
+            .is_some_and(|name| name == song_id);
+
+        if is_plain_file_name {
+            if let Some(local_database) = &self.local_database {
+                let local_path = local_database.join(LYRICS_DIR_IN_REPO).join(song_id);
+                if local_path.exists() {
+                    tracing::info!("[AMLL] 从本地数据库副本读取 TTML: {}", local_path.display());
+                    return Ok(fs::read_to_string(&local_path).await?);
+                }
+            }
+            if let Some(cache_dir) = &self.lyrics_cache_dir {
+                let cached_path = cache_dir.join(song_id);
+                if cached_path.exists() {
+                    tracing::info!("[AMLL] 从缓存读取 TTML: {}", cached_path.display());
+                    return Ok(fs::read_to_string(&cached_path).await?);
+                }
+            }
+        }
+
+        if self.offline {
+            tracing::warn!("[AMLL] 离线模式下本地没有歌词文件 {song_id}。");
+            return Err(LyricsHelperError::LyricNotFound);
+        }
+
+        let ttml_url = self.lyrics_url_template.replace("{song_id}", song_id);
+        tracing::info!("[AMLL] 下载 TTML: {}", ttml_url);
+        let response_text = self
+            .http_client
+            .get(&ttml_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        if is_plain_file_name && let Some(cache_dir) = &self.lyrics_cache_dir {
+            let write_result = async {
+                fs::create_dir_all(cache_dir).await?;
+                fs::write(cache_dir.join(song_id), &response_text).await
+            }
+            .await;
+            if let Err(e) = write_result {
+                tracing::warn!("[AMLL] 写入歌词缓存失败: {e}");
+            }
+        }
+
+        Ok(response_text)
+    }
+
     /// 根据特定字段进行精确或模糊搜索。
     ///
     /// 这是一个此 Provider 特有的高级搜索功能。
@@ -149,32 +232,70 @@ impl AmllTtmlDatabase {
         let metadata_key = field.to_metadata_key();
 
         self.index
+            .entries()
             .iter()
             .filter(|entry| {
-                if let Some(values) = entry.metadata.get(metadata_key) {
-                    match field {
-                        SearchField::NcmMusicId
-                        | SearchField::QqMusicId
-                        | SearchField::SpotifyId
-                        | SearchField::AppleMusicId
-                        | SearchField::Isrc
-                        | SearchField::TtmlAuthorGithub
-                        | SearchField::TtmlAuthorGithubLogin => {
-                            values.iter().any(|v| v.to_lowercase() == lower_query)
-                        }
-                        SearchField::MusicName | SearchField::Artists | SearchField::Album => {
-                            values
-                                .iter()
-                                .any(|v| v.to_lowercase().contains(&lower_query))
-                        }
+                entry.get_meta_vec(metadata_key).is_some_and(|values| {
+                    if field.is_text_field() {
+                        values
+                            .iter()
+                            .any(|v| v.to_lowercase().contains(&lower_query))
+                    } else {
+                        values.iter().any(|v| v.to_lowercase() == lower_query)
                     }
-                } else {
-                    false
-                }
+                })
             })
             .cloned()
             .collect()
     }
+
+    /// 根据特定字段进行模糊搜索。
+    ///
+    /// 与 [`Self::search_by_field`] 不同，文本字段支持繁简互通、拼音和少量错字，
+    /// 详见 [`search_index`] 模块。
+    ///
+    /// # 返回
+    /// 返回一个包含所有匹配条目的 `Vec<IndexEntry>`，按匹配程度从高到低排列。
+    #[must_use]
+    pub fn fuzzy_search_by_field(&self, query: &str, field: &SearchField) -> Vec<IndexEntry> {
+        let query = AmllSearchQuery::new().field(field.clone(), query).limit(0);
+        self.search(&query)
+            .into_iter()
+            .map(|hit| hit.entry)
+            .collect()
+    }
+
+    /// 将索引条目转换为 `SearchResult`。
+    fn to_search_result(&self, entry: &IndexEntry) -> SearchResult {
+        SearchResult {
+            provider_id: entry.raw_lyric_file.clone(),
+            title: entry
+                .get_meta_str("musicName")
+                .unwrap_or_default()
+                .to_string(),
+            artists: entry
+                .get_meta_vec("artists")
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|name| generic::Artist {
+                    id: String::new(),
+                    name,
+                })
+                .collect(),
+            album: entry.get_meta_str("album").map(String::from),
+            provider_name: self.name().to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 执行一个多字段组合查询，例如同时指定歌曲名、艺术家和专辑。
+    ///
+    /// 文本字段支持繁简互通、拼音和少量错字，详见 [`search_index`] 模块。
+    #[must_use]
+    pub fn search(&self, query: &AmllSearchQuery) -> Vec<AmllSearchHit> {
+        self.index.search(query)
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -191,21 +312,22 @@ impl Provider for AmllTtmlDatabase {
         if title_to_search.trim().is_empty() {
             return Ok(vec![]);
         }
-        let lower_title_to_search = title_to_search.to_lowercase();
 
-        // 快速从索引中找出所有标题可能相关的条目
+        // 用倒排索引找出可能相关的条目，再交给通用的匹配器评分
+        let mut query = AmllSearchQuery::new()
+            .field(SearchField::MusicName, title_to_search)
+            .limit(100);
+        for artist in track.artists.unwrap_or_default() {
+            query = query.field(SearchField::Artists, *artist);
+        }
+        if let Some(album) = track.album {
+            query = query.field(SearchField::Album, album);
+        }
         let candidates: Vec<IndexEntry> = self
             .index
-            .iter()
-            .rev()
-            .filter(|entry| {
-                entry.get_meta_vec("musicName").is_some_and(|titles| {
-                    titles
-                        .iter()
-                        .any(|v| v.to_lowercase().contains(&lower_title_to_search))
-                })
-            })
-            .cloned()
+            .search(&query)
+            .into_iter()
+            .map(|hit| hit.entry)
             .collect();
 
         if candidates.is_empty() {
@@ -240,31 +362,12 @@ impl Provider for AmllTtmlDatabase {
             .filter(|(_, match_type)| *match_type != MatchType::None)
             .collect();
 
-        scored_results.sort_by(|a, b| b.1.get_score().cmp(&a.1.get_score()));
+        scored_results.sort_by_key(|(_, match_type)| std::cmp::Reverse(match_type.get_score()));
 
         let final_results = scored_results
             .into_iter()
             .take(20)
-            .map(|(entry, _)| SearchResult {
-                provider_id: entry.raw_lyric_file.clone(),
-                title: entry
-                    .get_meta_str("musicName")
-                    .unwrap_or_default()
-                    .to_string(),
-                artists: entry
-                    .get_meta_vec("artists")
-                    .cloned()
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|name| generic::Artist {
-                        id: String::new(),
-                        name,
-                    })
-                    .collect(),
-                album: entry.get_meta_str("album").map(String::from),
-                provider_name: self.name().to_string(),
-                ..Default::default()
-            })
+            .map(|(entry, _)| self.to_search_result(&entry))
             .collect();
 
         Ok(final_results)
@@ -272,17 +375,7 @@ impl Provider for AmllTtmlDatabase {
 
     /// 获取并解析完整的 TTML 歌词文件。
     async fn get_full_lyrics(&self, song_id: &str) -> Result<FullLyricsResult> {
-        let ttml_url = self.lyrics_url_template.replace("{song_id}", song_id);
-        tracing::info!("[AMLL] 下载并解析 TTML: {}", ttml_url);
-
-        let response_text = self
-            .http_client
-            .get(&ttml_url)
-            .send()
-            .await?
-            .error_for_status()?
-            .text()
-            .await?;
+        let response_text = self.fetch_ttml(song_id).await?;
 
         let conversion_input = ConversionInput {
             main_lyric: InputFile {
@@ -290,6 +383,7 @@ impl Provider for AmllTtmlDatabase {
                 format: LyricFormat::Ttml,
                 language: None,
                 filename: Some(song_id.to_string()),
+                raw_bytes: None,
             },
             translations: vec![],
             romanizations: vec![],
@@ -321,6 +415,40 @@ impl Provider for AmllTtmlDatabase {
         })
     }
 
+    /// 通过索引中记录的平台 ID 精确查找歌词。同一 ID 有多个条目时使用最新的一个。
+    async fn get_lyrics_by_platform_id(
+        &self,
+        platform_id: &PlatformId<'_>,
+    ) -> Result<LyricsAndMetadata> {
+        let field = match platform_id.platform {
+            ExternalPlatform::Netease => SearchField::NcmMusicId,
+            ExternalPlatform::Qq => SearchField::QqMusicId,
+            ExternalPlatform::AppleMusic => SearchField::AppleMusicId,
+            ExternalPlatform::Spotify => SearchField::SpotifyId,
+        };
+        // 索引文件按提交顺序追加，最后一个条目即最新的
+        let entry = self
+            .search_by_field(platform_id.id, &field)
+            .pop()
+            .ok_or(LyricsHelperError::LyricNotFound)?;
+        tracing::info!(
+            "[AMLL] 通过 {:?} ID {} 找到歌词 {}",
+            platform_id.platform,
+            platform_id.id,
+            entry.raw_lyric_file
+        );
+
+        let source_track = SearchResult {
+            match_type: MatchType::Perfect,
+            ..self.to_search_result(&entry)
+        };
+        let lyrics = self.get_full_lyrics(&entry.raw_lyric_file).await?;
+        Ok(LyricsAndMetadata {
+            lyrics,
+            source_track,
+        })
+    }
+
     /// 获取歌词。`song_id` 就是 TTML 文件名。
     async fn get_lyrics(&self, song_id: &str) -> Result<ParsedSourceData> {
         // 普通歌词和完整歌词没有区别，都返回最完整的 TTML 解析结果。
@@ -429,6 +557,28 @@ async fn load_cached_index_head(cache_file_path: &Path) -> Result<Option<String>
     })
 }
 
+/// 删除歌词缓存根目录下除 `current` 以外的所有内容，即其他索引版本的歌词缓存。
+async fn prune_lyrics_cache(lyrics_cache_root: &Path, current: &Path) -> Result<()> {
+    let mut dir = match fs::read_dir(lyrics_cache_root).await {
+        Ok(dir) => dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    while let Some(entry) = dir.next_entry().await? {
+        let path = entry.path();
+        if path == current {
+            continue;
+        }
+        if entry.file_type().await?.is_dir() {
+            fs::remove_dir_all(&path).await?;
+        } else {
+            fs::remove_file(&path).await?;
+        }
+        tracing::debug!("[AMLL] 已删除过期的歌词缓存: {}", path.display());
+    }
+    Ok(())
+}
+
 /// 从本地缓存文件加载索引。
 async fn load_index_from_cache(cache_file_path: &Path) -> Result<Vec<IndexEntry>> {
     let file = fs::File::open(cache_file_path).await?;
@@ -445,23 +595,139 @@ async fn load_index_from_cache(cache_file_path: &Path) -> Result<Vec<IndexEntry>
     Ok(entries)
 }
 
-/// 下载、解析索引文件，并更新本地缓存。
-async fn download_and_parse_index(
+/// 检查远程索引版本，按需增量或完整地更新本地缓存，并返回索引条目。
+async fn load_or_update_index(
+    cache_file_path: &Path,
+    http_client: &Client,
+    index_url: &str,
+) -> Result<Vec<IndexEntry>> {
+    let remote_head = match fetch_remote_index_head(http_client).await {
+        Ok(sha) => Some(sha),
+        Err(LyricsHelperError::RateLimited(msg)) => {
+            tracing::warn!("[AMLL] {msg}");
+            None
+        }
+        Err(e) => {
+            tracing::warn!("[AMLL] 检查索引更新失败: {e}");
+            None
+        }
+    };
+    let has_cache = cache_file_path.exists();
+
+    match remote_head {
+        Some(sha) if has_cache => {
+            if load_cached_index_head(cache_file_path).await?.as_deref() == Some(sha.as_str()) {
+                tracing::info!("[AMLL] 索引缓存有效，从本地加载...");
+                return load_index_from_cache(cache_file_path).await;
+            }
+            tracing::info!("[AMLL] 索引已过期，尝试增量更新...");
+            match update_index_incrementally(cache_file_path, &sha, http_client, index_url).await {
+                Ok(entries) => Ok(entries),
+                Err(e) => {
+                    tracing::warn!("[AMLL] 增量更新失败，将下载完整索引: {e}");
+                    download_and_parse_index(cache_file_path, Some(&sha), http_client, index_url)
+                        .await
+                }
+            }
+        }
+        Some(sha) => {
+            tracing::info!("[AMLL] 索引不存在，正在从 {} 下载...", index_url);
+            download_and_parse_index(cache_file_path, Some(&sha), http_client, index_url).await
+        }
+        None if has_cache => {
+            tracing::warn!("[AMLL] 无法检查索引更新，将使用可能已过期的本地缓存。");
+            load_index_from_cache(cache_file_path).await
+        }
+        None => {
+            tracing::warn!("[AMLL] 无法检查索引更新且没有本地缓存，直接下载完整索引。");
+            download_and_parse_index(cache_file_path, None, http_client, index_url).await
+        }
+    }
+}
+
+/// 只下载索引文件新增的尾部，追加到本地缓存中。
+///
+/// 请求的范围从本地文件末尾之前 [`INDEX_OVERLAP_BYTES`] 字节开始，要求服务器返回的
+/// `Content-Range` 起点与请求一致、总长度不小于本地文件，并且重叠部分与本地缓存逐字节相同，
+/// 以此确认远程文件只是在末尾追加了内容。服务器不支持 Range、远程文件被改写或变短时返回错误，
+/// 由调用方回退到完整下载。
+async fn update_index_incrementally(
     cache_file_path: &Path,
     remote_head_sha: &str,
     http_client: &Client,
     index_url: &str,
 ) -> Result<Vec<IndexEntry>> {
-    let response_text = http_client
+    let local_content = fs::read(cache_file_path).await?;
+    if local_content.last() != Some(&b'\n') {
+        return Err(LyricsHelperError::Internal(
+            "本地索引缓存不完整，无法增量更新".into(),
+        ));
+    }
+
+    let overlap_start = local_content.len().saturating_sub(INDEX_OVERLAP_BYTES);
+    let response = http_client
         .get(index_url)
+        .header(RANGE, format!("bytes={overlap_start}-"))
         .timeout(Duration::from_secs(30))
         .send()
-        .await?
-        .error_for_status()?
-        .text()
         .await?;
 
-    let entries: Vec<IndexEntry> = response_text
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        return Err(LyricsHelperError::Network(format!(
+            "服务器不支持增量下载索引 (HTTP {})",
+            response.status()
+        )));
+    }
+
+    let content_range = response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_content_range);
+    match content_range {
+        Some((start, total)) if start == overlap_start && total >= local_content.len() => {}
+        _ => {
+            return Err(LyricsHelperError::Internal(
+                "远程索引的 Content-Range 与本地缓存不匹配".into(),
+            ));
+        }
+    }
+
+    let tail = response.bytes().await?;
+    let Some(appended) = tail.strip_prefix(&local_content[overlap_start..]) else {
+        return Err(LyricsHelperError::Internal(
+            "远程索引与本地缓存不一致".into(),
+        ));
+    };
+    let appended_text = std::str::from_utf8(appended)
+        .map_err(|e| LyricsHelperError::Parser(format!("索引文件不是有效的 UTF-8: {e}")))?;
+
+    let mut entries = parse_index_lines(&String::from_utf8_lossy(&local_content));
+    let new_entries = parse_index_lines(appended_text);
+    tracing::info!("[AMLL] 增量更新索引，新增 {} 条记录。", new_entries.len());
+    entries.extend(new_entries);
+
+    let mut file = fs::OpenOptions::new()
+        .append(true)
+        .open(cache_file_path)
+        .await?;
+    file.write_all(appended).await?;
+    file.flush().await?;
+    save_index_head(cache_file_path, Some(remote_head_sha)).await?;
+
+    Ok(entries)
+}
+
+/// 解析形如 `bytes 100-199/1000` 的 `Content-Range` 响应头，返回起始位置和总长度。
+fn parse_content_range(value: &str) -> Option<(usize, usize)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()?))
+}
+
+/// 解析 JSONL 格式的索引内容，忽略损坏的行。
+fn parse_index_lines(content: &str) -> Vec<IndexEntry> {
+    content
         .lines()
         .filter(|line| !line.trim().is_empty())
         .fold(Vec::new(), |mut acc, line| {
@@ -474,7 +740,28 @@ async fn download_and_parse_index(
                 }
             }
             acc
-        });
+        })
+}
+
+/// 下载、解析索引文件，并更新本地缓存。
+///
+/// `remote_head_sha` 为 `None` 时不记录版本，下次启动会重新检查更新。
+async fn download_and_parse_index(
+    cache_file_path: &Path,
+    remote_head_sha: Option<&str>,
+    http_client: &Client,
+    index_url: &str,
+) -> Result<Vec<IndexEntry>> {
+    let response_text = http_client
+        .get(index_url)
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    let entries = parse_index_lines(&response_text);
 
     if entries.is_empty() && !response_text.trim().is_empty() {
         return Err(LyricsHelperError::Internal(
@@ -487,13 +774,26 @@ async fn download_and_parse_index(
 }
 
 /// 将下载的内容和最新的 SHA 写入本地缓存文件。
-async fn save_index_to_cache(cache_file_path: &Path, content: &str, head_sha: &str) -> Result<()> {
+async fn save_index_to_cache(
+    cache_file_path: &Path,
+    content: &str,
+    head_sha: Option<&str>,
+) -> Result<()> {
     if let Some(parent) = cache_file_path.parent() {
         fs::create_dir_all(parent).await?;
     }
     fs::write(cache_file_path, content).await?;
+    save_index_head(cache_file_path, head_sha).await
+}
+
+/// 写入或删除索引缓存对应的 `.head` 文件。
+async fn save_index_head(cache_file_path: &Path, head_sha: Option<&str>) -> Result<()> {
     let head_file_path = cache_file_path.with_extension("jsonl.head");
-    fs::write(&head_file_path, head_sha).await?;
+    match head_sha {
+        Some(sha) => fs::write(&head_file_path, sha).await?,
+        None if head_file_path.exists() => fs::remove_file(&head_file_path).await?,
+        None => {}
+    }
     Ok(())
 }
 
@@ -508,11 +808,14 @@ mod tests {
         let index_entry: IndexEntry = serde_json::from_str(sample_json).unwrap();
 
         let provider = AmllTtmlDatabase {
-            index: Arc::new(vec![index_entry.clone()]),
+            index: Arc::new(AmllSearchIndex::new(vec![index_entry.clone()])),
             http_client: Client::new(),
             lyrics_url_template: format!(
                 "{RAW_CONTENT_BASE_URL}/{REPO_OWNER}/{REPO_NAME}/{REPO_BRANCH}/raw-lyrics/{{song_id}}"
             ),
+            lyrics_cache_dir: None,
+            local_database: None,
+            offline: false,
         };
 
         (provider, index_entry)
@@ -633,5 +936,232 @@ mod tests {
 
         let results4 = provider.search_by_field("1234567890", &SearchField::NcmMusicId);
         assert!(results4.is_empty(), "用错误的 ID 搜索应该找不到结果");
+
+        let results5 = provider.search_by_field("李宇", &SearchField::Artists);
+        assert_eq!(results5.len(), 1, "艺术家字段应该支持子串匹配");
+
+        let results6 = provider.search_by_field("李宇唇", &SearchField::Artists);
+        assert!(results6.is_empty(), "精确搜索不应该容忍错字");
+
+        let results7 = provider.fuzzy_search_by_field("liyuchun", &SearchField::Artists);
+        assert_eq!(results7.len(), 1, "模糊搜索应该支持拼音");
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("amll-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 启动一个只响应一次请求的 HTTP 服务器，按 `Range` 请求头返回 `content` 的尾部。
+    async fn serve_range_once(content: &'static str) -> String {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+            let start: usize = request
+                .split("range: bytes=")
+                .nth(1)
+                .and_then(|rest| rest.split('-').next())
+                .and_then(|num| num.trim().parse().ok())
+                .unwrap();
+            let body = &content.as_bytes()[start..];
+            let header = format!(
+                "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {start}-{}/{}\r\nConnection: close\r\n\r\n",
+                body.len(),
+                content.len() - 1,
+                content.len()
+            );
+            socket.write_all(header.as_bytes()).await.unwrap();
+            socket.write_all(body).await.unwrap();
+        });
+        format!("http://{addr}/raw-lyrics-index.jsonl")
+    }
+
+    #[tokio::test]
+    async fn test_incremental_index_update() {
+        const OLD_LINE: &str = r#"{"metadata":[["musicName",["Old"]]],"rawLyricFile":"old.ttml"}"#;
+        const REMOTE: &str = concat!(
+            r#"{"metadata":[["musicName",["Old"]]],"rawLyricFile":"old.ttml"}"#,
+            "\n",
+            r#"{"metadata":[["musicName",["New"]]],"rawLyricFile":"new.ttml"}"#,
+            "\n"
+        );
+
+        let dir = temp_dir("incremental");
+        let cache_path = dir.join("index.jsonl");
+        std::fs::write(&cache_path, format!("{OLD_LINE}\n")).unwrap();
+        let index_url = serve_range_once(REMOTE).await;
+
+        let entries = update_index_incrementally(&cache_path, "abc", &Client::new(), &index_url)
+            .await
+            .unwrap();
+
+        let files: Vec<&str> = entries.iter().map(|e| e.raw_lyric_file.as_str()).collect();
+        assert_eq!(files, ["old.ttml", "new.ttml"]);
+        assert_eq!(std::fs::read_to_string(&cache_path).unwrap(), REMOTE);
+        assert_eq!(
+            load_cached_index_head(&cache_path)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("abc")
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_update_rejects_rewritten_index() {
+        const REMOTE: &str = concat!(
+            r#"{"metadata":[["musicName",["Odd"]]],"rawLyricFile":"old.ttml"}"#,
+            "\n",
+            r#"{"metadata":[["musicName",["New"]]],"rawLyricFile":"new.ttml"}"#,
+            "\n"
+        );
+
+        let dir = temp_dir("rewritten");
+        let cache_path = dir.join("index.jsonl");
+        let local = r#"{"metadata":[["musicName",["Old"]]],"rawLyricFile":"old.ttml"}"#;
+        std::fs::write(&cache_path, format!("{local}\n")).unwrap();
+        let index_url = serve_range_once(REMOTE).await;
+
+        let result =
+            update_index_incrementally(&cache_path, "abc", &Client::new(), &index_url).await;
+
+        assert!(result.is_err(), "改写过的远程索引不应该被增量追加");
+        assert_eq!(
+            std::fs::read_to_string(&cache_path).unwrap(),
+            format!("{local}\n")
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 100-199/1000"), Some((100, 1000)));
+        assert_eq!(parse_content_range("bytes 0-0/*"), None);
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+    }
+
+    #[tokio::test]
+    async fn test_prune_lyrics_cache_keeps_current_version() {
+        let dir = temp_dir("prune");
+        let current = dir.join("new-sha");
+        std::fs::create_dir_all(&current).unwrap();
+        std::fs::write(current.join("a.ttml"), "<tt/>").unwrap();
+        std::fs::create_dir_all(dir.join("old-sha")).unwrap();
+        std::fs::write(dir.join("old-sha").join("a.ttml"), "<tt/>").unwrap();
+        std::fs::write(dir.join("legacy.ttml"), "<tt/>").unwrap();
+
+        prune_lyrics_cache(&dir, &current).await.unwrap();
+
+        assert!(current.join("a.ttml").exists());
+        assert!(!dir.join("old-sha").exists());
+        assert!(!dir.join("legacy.ttml").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_offline_reads_local_database_and_cache() {
+        let dir = temp_dir("offline");
+        let local_database = dir.join("db");
+        std::fs::create_dir_all(local_database.join(LYRICS_DIR_IN_REPO)).unwrap();
+        std::fs::write(
+            local_database.join(LYRICS_DIR_IN_REPO).join("a.ttml"),
+            "<tt/>",
+        )
+        .unwrap();
+        let cache_dir = dir.join("cache");
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        std::fs::write(cache_dir.join("b.ttml"), "<tt>cached</tt>").unwrap();
+
+        let (mut provider, _) = create_test_provider();
+        provider.offline = true;
+        provider.local_database = Some(local_database);
+        provider.lyrics_cache_dir = Some(cache_dir);
+
+        assert_eq!(provider.fetch_ttml("a.ttml").await.unwrap(), "<tt/>");
+        assert_eq!(
+            provider.fetch_ttml("b.ttml").await.unwrap(),
+            "<tt>cached</tt>"
+        );
+        assert!(matches!(
+            provider.fetch_ttml("missing.ttml").await,
+            Err(LyricsHelperError::LyricNotFound)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_get_lyrics_by_platform_id() {
+        let dir = temp_dir("platform-id");
+        let (mut provider, entry) = create_test_provider();
+        let lyrics_dir = dir.join(LYRICS_DIR_IN_REPO);
+        std::fs::create_dir_all(&lyrics_dir).unwrap();
+        std::fs::write(
+            lyrics_dir.join(&entry.raw_lyric_file),
+            r#"<tt xmlns="http://www.w3.org/ns/ttml" itunes:timing="Line"><body><div><p begin="1.000" end="2.000">明明</p></div></body></tt>"#,
+        )
+        .unwrap();
+        provider.offline = true;
+        provider.local_database = Some(dir.clone());
+
+        let result = provider
+            .get_lyrics_by_platform_id(&PlatformId {
+                platform: ExternalPlatform::Qq,
+                id: "000pF84f1Mqkf7",
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.source_track.provider_id, entry.raw_lyric_file);
+        assert_eq!(result.source_track.match_type, MatchType::Perfect);
+        assert_eq!(result.lyrics.parsed.lines.len(), 1);
+
+        let missing = provider
+            .get_lyrics_by_platform_id(&PlatformId {
+                platform: ExternalPlatform::Netease,
+                id: "1",
+            })
+            .await;
+        assert!(matches!(missing, Err(LyricsHelperError::LyricNotFound)));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_get_lyrics_by_platform_id_prefers_newest_entry() {
+        let dir = temp_dir("platform-id-newest");
+        let (mut provider, older) = create_test_provider();
+        let newer = IndexEntry {
+            raw_lyric_file: "1756678978875-108002475-1b1fc192.ttml".to_string(),
+            ..older.clone()
+        };
+        provider.index = Arc::new(AmllSearchIndex::new(vec![older.clone(), newer.clone()]));
+        let lyrics_dir = dir.join(LYRICS_DIR_IN_REPO);
+        std::fs::create_dir_all(&lyrics_dir).unwrap();
+        for entry in [&older, &newer] {
+            std::fs::write(
+                lyrics_dir.join(&entry.raw_lyric_file),
+                r#"<tt xmlns="http://www.w3.org/ns/ttml" itunes:timing="Line"><body><div><p begin="1.000" end="2.000">明明</p></div></body></tt>"#,
+            )
+            .unwrap();
+        }
+        provider.offline = true;
+        provider.local_database = Some(dir.clone());
+
+        let result = provider
+            .get_lyrics_by_platform_id(&PlatformId {
+                platform: ExternalPlatform::Netease,
+                id: "2642164541",
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.source_track.provider_id, newer.raw_lyric_file);
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
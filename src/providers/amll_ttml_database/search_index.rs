@@ -0,0 +1,533 @@
+//! AMLL TTML Database 索引的内存倒排索引。
+//!
+//! 加载索引时，歌曲名、艺术家和专辑会被规范化（繁体转简体、全角转半角、转为小写并去掉
+//! 标点和空白），并额外生成一份拼音形式的键。两种键的字符二元组（bigram）构成倒排表，
+//! 查询时先用倒排表找出候选条目，再按子串和编辑距离计算相似度并排序。
+//! 因此繁简不同、用拼音输入或带有少量错字的查询也能找到对应的歌曲。
+//!
+//! 各平台 ID 和投稿者信息只做大小写不敏感的精确匹配。
+
+use std::collections::{HashMap, HashSet};
+
+use ferrous_opencc::config::BuiltinConfig;
+use pinyin::ToPinyin;
+
+use super::types::{IndexEntry, SearchField};
+use crate::converter::{processors::chinese_conversion_processor, utils::is_cjk};
+
+/// 默认的最低相似度。
+const DEFAULT_MIN_SCORE: f64 = 0.6;
+
+/// 纯编辑距离匹配的得分上限，保证模糊匹配的结果排在子串匹配之后。
+const FUZZY_SCORE_CAP: f64 = 0.8;
+
+/// 一个多字段组合查询，例如“歌曲名 + 艺术家 + 专辑”。
+#[derive(Debug, Clone)]
+pub struct AmllSearchQuery {
+    /// 查询条件，同一字段可以出现多次（例如多位艺术家）。
+    pub terms: Vec<(SearchField, String)>,
+    /// 单个字段被视为匹配所需的最低相似度，取值范围 `0.0..=1.0`。
+    pub min_score: f64,
+    /// 最多返回的结果数量，为 0 时不限制。
+    pub limit: usize,
+}
+
+impl Default for AmllSearchQuery {
+    fn default() -> Self {
+        Self {
+            terms: Vec::new(),
+            min_score: DEFAULT_MIN_SCORE,
+            limit: 20,
+        }
+    }
+}
+
+impl AmllSearchQuery {
+    /// 创建一个空查询。
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一个查询条件。空白的查询文本会被忽略。
+    #[must_use]
+    pub fn field(mut self, field: SearchField, text: impl Into<String>) -> Self {
+        let text = text.into();
+        if !text.trim().is_empty() {
+            self.terms.push((field, text));
+        }
+        self
+    }
+
+    /// 设置最多返回的结果数量。
+    #[must_use]
+    pub const fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// 设置单个字段的最低相似度。
+    #[must_use]
+    pub const fn min_score(mut self, min_score: f64) -> Self {
+        self.min_score = min_score;
+        self
+    }
+}
+
+/// 一条搜索结果。
+#[derive(Debug, Clone)]
+pub struct AmllSearchHit {
+    /// 匹配到的索引条目。
+    pub entry: IndexEntry,
+    /// 综合得分，取值范围 `0.0..=1.0`。
+    pub score: f64,
+}
+
+/// 规范化后的字段值。
+#[derive(Debug, Clone)]
+struct NormalizedText {
+    /// 繁转简、全角转半角、小写并去掉非字母数字字符后的文本。
+    compact: String,
+    /// 将汉字替换为无声调拼音后的 `compact`。不含汉字时为空。
+    pinyin: String,
+}
+
+impl NormalizedText {
+    fn new(text: &str) -> Self {
+        let compact = normalize_text(text);
+        let pinyin = if compact.chars().any(is_cjk) {
+            to_pinyin_key(&compact)
+        } else {
+            String::new()
+        };
+        Self { compact, pinyin }
+    }
+
+    fn has_cjk(&self) -> bool {
+        !self.pinyin.is_empty()
+    }
+
+    /// 用于建立倒排表的键。
+    fn keys(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.compact.as_str()).chain(self.has_cjk().then_some(self.pinyin.as_str()))
+    }
+}
+
+/// 单个文本字段的倒排索引。
+#[derive(Debug, Default)]
+struct TextFieldIndex {
+    /// 二元组 -> 包含它的条目下标（升序、去重）。
+    postings: HashMap<String, Vec<u32>>,
+    /// 每个条目在该字段下的所有规范化值。
+    values: Vec<Vec<NormalizedText>>,
+}
+
+impl TextFieldIndex {
+    fn insert(&mut self, entry_idx: u32, raw_values: &[String]) {
+        let values: Vec<NormalizedText> = raw_values
+            .iter()
+            .map(|value| NormalizedText::new(value))
+            .filter(|value| !value.compact.is_empty())
+            .collect();
+
+        let mut grams = HashSet::new();
+        for value in &values {
+            for key in value.keys() {
+                grams.extend(bigrams(key));
+            }
+        }
+        for gram in grams {
+            self.postings.entry(gram).or_default().push(entry_idx);
+        }
+        self.values.push(values);
+    }
+
+    /// 通过倒排表找出候选条目。单字查询没有二元组，此时退化为全量扫描。
+    fn candidates(&self, query: &NormalizedText, output: &mut HashSet<u32>) {
+        // 字段值的拼音键也在倒排表中，因此拼音查询同样能找到候选
+        let grams: HashSet<String> = bigrams(&query.compact).collect();
+        if grams.is_empty() {
+            output.extend((0..self.values.len()).filter_map(|i| u32::try_from(i).ok()));
+            return;
+        }
+
+        let mut shared: HashMap<u32, usize> = HashMap::new();
+        for gram in &grams {
+            if let Some(postings) = self.postings.get(gram) {
+                for &entry_idx in postings {
+                    *shared.entry(entry_idx).or_default() += 1;
+                }
+            }
+        }
+        // 至少共享三分之一的二元组，容忍少量错字
+        let min_shared = grams.len().div_ceil(3);
+        output.extend(
+            shared
+                .into_iter()
+                .filter(|&(_, count)| count >= min_shared)
+                .map(|(entry_idx, _)| entry_idx),
+        );
+    }
+
+    fn score(&self, entry_idx: u32, query: &NormalizedText) -> f64 {
+        self.values.get(entry_idx as usize).map_or(0.0, |values| {
+            values
+                .iter()
+                .map(|value| score_value(query, value))
+                .fold(0.0, f64::max)
+        })
+    }
+}
+
+/// AMLL 索引的内存倒排索引。
+#[derive(Debug, Default)]
+pub struct AmllSearchIndex {
+    entries: Vec<IndexEntry>,
+    text_fields: HashMap<&'static str, TextFieldIndex>,
+    /// 字段名 -> 小写的值 -> 条目下标。
+    exact_fields: HashMap<&'static str, HashMap<String, Vec<u32>>>,
+}
+
+impl AmllSearchIndex {
+    /// 为给定的索引条目建立倒排索引。
+    #[must_use]
+    pub fn new(entries: Vec<IndexEntry>) -> Self {
+        let mut text_fields: HashMap<&'static str, TextFieldIndex> = HashMap::new();
+        let mut exact_fields: HashMap<&'static str, HashMap<String, Vec<u32>>> = HashMap::new();
+
+        for (idx, entry) in entries.iter().enumerate() {
+            let Ok(entry_idx) = u32::try_from(idx) else {
+                tracing::warn!("[AMLL] 索引条目过多，超出部分不会被搜索到。");
+                break;
+            };
+            for field in SearchField::ALL {
+                let key = field.to_metadata_key();
+                let values = entry.get_meta_vec(key).map_or(&[][..], Vec::as_slice);
+                if field.is_text_field() {
+                    text_fields
+                        .entry(key)
+                        .or_default()
+                        .insert(entry_idx, values);
+                } else {
+                    let field_map = exact_fields.entry(key).or_default();
+                    for value in values {
+                        let value = value.trim().to_lowercase();
+                        if !value.is_empty() {
+                            field_map.entry(value).or_default().push(entry_idx);
+                        }
+                    }
+                }
+            }
+        }
+
+        Self {
+            entries,
+            text_fields,
+            exact_fields,
+        }
+    }
+
+    /// 所有索引条目，顺序与索引文件相同。
+    #[must_use]
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// 索引条目的数量。
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 索引是否为空。
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 执行一个组合查询，按得分从高到低返回结果。
+    ///
+    /// 每个条件按字段权重（歌曲名和 ID 最高，其次是艺术家，最后是专辑）加权平均，
+    /// 未匹配的条件记 0 分。只要有一个条件匹配，条目就会出现在结果中。
+    /// 得分相同时，索引中靠后（较新）的条目排在前面。
+    #[must_use]
+    pub fn search(&self, query: &AmllSearchQuery) -> Vec<AmllSearchHit> {
+        let terms: Vec<(&SearchField, PreparedTerm)> = query
+            .terms
+            .iter()
+            .filter(|(_, text)| !text.trim().is_empty())
+            .map(|(field, text)| (field, PreparedTerm::new(field, text)))
+            .collect();
+        if terms.is_empty() {
+            return vec![];
+        }
+
+        let mut candidates = HashSet::new();
+        for (field, term) in &terms {
+            self.collect_candidates(field, term, &mut candidates);
+        }
+
+        let total_weight: f64 = terms.iter().map(|(field, _)| field.search_weight()).sum();
+        let mut scored: Vec<(u32, f64)> = candidates
+            .into_iter()
+            .filter_map(|entry_idx| {
+                let mut any_matched = false;
+                let weighted: f64 = terms
+                    .iter()
+                    .map(|(field, term)| {
+                        let score = self.score_term(entry_idx, field, term);
+                        if score >= query.min_score {
+                            any_matched = true;
+                            score * field.search_weight()
+                        } else {
+                            0.0
+                        }
+                    })
+                    .sum();
+                any_matched.then_some((entry_idx, weighted / total_weight))
+            })
+            .collect();
+
+        scored.sort_by(|(idx_a, score_a), (idx_b, score_b)| {
+            score_b.total_cmp(score_a).then_with(|| idx_b.cmp(idx_a))
+        });
+        if query.limit > 0 {
+            scored.truncate(query.limit);
+        }
+
+        scored
+            .into_iter()
+            .map(|(entry_idx, score)| AmllSearchHit {
+                entry: self.entries[entry_idx as usize].clone(),
+                score,
+            })
+            .collect()
+    }
+
+    fn collect_candidates(
+        &self,
+        field: &SearchField,
+        term: &PreparedTerm,
+        output: &mut HashSet<u32>,
+    ) {
+        let key = field.to_metadata_key();
+        match term {
+            PreparedTerm::Text(text) => {
+                if let Some(index) = self.text_fields.get(key) {
+                    index.candidates(text, output);
+                }
+            }
+            PreparedTerm::Exact(value) => {
+                if let Some(postings) = self.exact_fields.get(key).and_then(|m| m.get(value)) {
+                    output.extend(postings);
+                }
+            }
+        }
+    }
+
+    fn score_term(&self, entry_idx: u32, field: &SearchField, term: &PreparedTerm) -> f64 {
+        let key = field.to_metadata_key();
+        match term {
+            PreparedTerm::Text(text) => self
+                .text_fields
+                .get(key)
+                .map_or(0.0, |index| index.score(entry_idx, text)),
+            PreparedTerm::Exact(value) => {
+                let matched = self
+                    .exact_fields
+                    .get(key)
+                    .and_then(|m| m.get(value))
+                    .is_some_and(|postings| postings.binary_search(&entry_idx).is_ok());
+                if matched { 1.0 } else { 0.0 }
+            }
+        }
+    }
+}
+
+/// 预处理后的查询条件。
+enum PreparedTerm {
+    Text(NormalizedText),
+    Exact(String),
+}
+
+impl PreparedTerm {
+    fn new(field: &SearchField, text: &str) -> Self {
+        if field.is_text_field() {
+            Self::Text(NormalizedText::new(text))
+        } else {
+            Self::Exact(text.trim().to_lowercase())
+        }
+    }
+}
+
+/// 计算查询与单个字段值的相似度。
+///
+/// 含汉字的查询只与规范化文本比较；不含汉字的查询还会与字段值的拼音比较，
+/// 以支持用拼音搜索中文歌曲。
+fn score_value(query: &NormalizedText, value: &NormalizedText) -> f64 {
+    let direct = similarity(&query.compact, &value.compact);
+    if query.has_cjk() || !value.has_cjk() {
+        return direct;
+    }
+    direct.max(similarity(&query.compact, &value.pinyin))
+}
+
+/// 完全相同为 1 分，子串匹配按长度比例在 0.8 到 1 之间，
+/// 否则取查询与字段值中等长窗口的最大编辑距离相似度，上限为 [`FUZZY_SCORE_CAP`]。
+fn similarity(query: &str, value: &str) -> f64 {
+    if query.is_empty() || value.is_empty() {
+        return 0.0;
+    }
+    if query == value {
+        return 1.0;
+    }
+
+    let query_len = query.chars().count();
+    let value_chars: Vec<char> = value.chars().collect();
+    #[allow(clippy::cast_precision_loss)]
+    if value.contains(query) {
+        return 0.2f64.mul_add(query_len as f64 / value_chars.len() as f64, 0.8);
+    }
+
+    let mut best: f64 = 0.0;
+    for window_len in query_len.saturating_sub(1).max(1)..=query_len + 1 {
+        if window_len > value_chars.len() {
+            best = best.max(strsim::normalized_levenshtein(query, value));
+            break;
+        }
+        for window in value_chars.windows(window_len) {
+            let window: String = window.iter().collect();
+            best = best.max(strsim::normalized_levenshtein(query, &window));
+        }
+    }
+    best * FUZZY_SCORE_CAP
+}
+
+/// 繁体转简体、全角转半角、转为小写，并只保留字母和数字。
+fn normalize_text(text: &str) -> String {
+    let folded: String = text
+        .chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .filter(|c| c.is_alphanumeric())
+        .collect();
+    if folded.chars().any(is_cjk) {
+        chinese_conversion_processor::convert(&folded, BuiltinConfig::T2s)
+    } else {
+        folded
+    }
+}
+
+/// 将文本中的汉字替换为无声调拼音，其他字符保持不变。
+fn to_pinyin_key(text: &str) -> String {
+    let mut key = String::with_capacity(text.len() * 3);
+    for (c, pinyin) in text.chars().zip(text.to_pinyin()) {
+        match pinyin {
+            Some(pinyin) => key.push_str(pinyin.plain()),
+            None => key.push(c),
+        }
+    }
+    key
+}
+
+/// 文本的字符二元组。
+fn bigrams(text: &str) -> impl Iterator<Item = String> {
+    let chars: Vec<char> = text.chars().collect();
+    (0..chars.len().saturating_sub(1)).map(move |i| chars[i..i + 2].iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str, artists: &[&str], album: &str, ncm_id: &str) -> IndexEntry {
+        IndexEntry {
+            metadata: HashMap::from([
+                ("musicName".to_string(), vec![title.to_string()]),
+                (
+                    "artists".to_string(),
+                    artists.iter().map(ToString::to_string).collect(),
+                ),
+                ("album".to_string(), vec![album.to_string()]),
+                ("ncmMusicId".to_string(), vec![ncm_id.to_string()]),
+            ]),
+            raw_lyric_file: format!("{ncm_id}.ttml"),
+        }
+    }
+
+    fn sample_index() -> AmllSearchIndex {
+        AmllSearchIndex::new(vec![
+            entry(
+                "明明 (深爱着你) (Live)",
+                &["李宇春", "丁肆Dicey"],
+                "有歌2024",
+                "1",
+            ),
+            entry("Shape of You", &["Ed Sheeran"], "÷", "2"),
+            entry("後來", &["劉若英"], "我等你", "3"),
+            entry("Shape of My Heart", &["Sting"], "Ten Summoner's Tales", "4"),
+        ])
+    }
+
+    fn top_file(index: &AmllSearchIndex, query: &AmllSearchQuery) -> Option<String> {
+        index
+            .search(query)
+            .first()
+            .map(|hit| hit.entry.raw_lyric_file.clone())
+    }
+
+    #[test]
+    fn test_cross_script_and_width_folding() {
+        let index = sample_index();
+
+        let simplified = AmllSearchQuery::new().field(SearchField::MusicName, "后来");
+        assert_eq!(top_file(&index, &simplified).as_deref(), Some("3.ttml"));
+
+        let artist = AmllSearchQuery::new().field(SearchField::Artists, "刘若英");
+        assert_eq!(top_file(&index, &artist).as_deref(), Some("3.ttml"));
+
+        let fullwidth =
+            AmllSearchQuery::new().field(SearchField::MusicName, "ＳＨＡＰＥ ＯＦ ＹＯＵ");
+        let hits = index.search(&fullwidth);
+        assert_eq!(hits[0].entry.raw_lyric_file, "2.ttml");
+        assert!((hits[0].score - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_pinyin_query() {
+        let index = sample_index();
+        let query = AmllSearchQuery::new().field(SearchField::MusicName, "ming ming");
+        assert_eq!(top_file(&index, &query).as_deref(), Some("1.ttml"));
+
+        let query = AmllSearchQuery::new().field(SearchField::Artists, "liuruoying");
+        assert_eq!(top_file(&index, &query).as_deref(), Some("3.ttml"));
+    }
+
+    #[test]
+    fn test_fuzzy_query_tolerates_typos() {
+        let index = sample_index();
+        let query = AmllSearchQuery::new().field(SearchField::MusicName, "Shap of Yuo");
+        assert_eq!(top_file(&index, &query).as_deref(), Some("2.ttml"));
+
+        let unrelated = AmllSearchQuery::new().field(SearchField::MusicName, "不爱");
+        assert!(index.search(&unrelated).is_empty());
+    }
+
+    #[test]
+    fn test_combined_query_ranks_by_all_fields() {
+        let index = sample_index();
+        let query = AmllSearchQuery::new()
+            .field(SearchField::MusicName, "Shape of")
+            .field(SearchField::Artists, "Sting");
+        let hits = index.search(&query);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].entry.raw_lyric_file, "4.ttml");
+        assert!(hits[0].score > hits[1].score);
+
+        let by_id = AmllSearchQuery::new().field(SearchField::NcmMusicId, "3");
+        assert_eq!(top_file(&index, &by_id).as_deref(), Some("3.ttml"));
+    }
+}
@@ -0,0 +1,406 @@
+//! AMLL TTML 数据库投稿包生成。
+//!
+//! 根据 `ParsedSourceData`、投稿者信息和平台 ID 生成符合 `docs/ttml-specification.md`
+//! 的 TTML 文件，并附带一份规范检查报告。网易云和 QQ 音乐的 ID 可以从提供商的搜索结果中
+//! 自动提取，Spotify、Apple Music ID 和 ISRC 可以从 AMLL 索引中同一首歌的条目中提取。
+//! 整个过程不需要联网。
+
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    converter::{
+        generators::ttml_generator::{AMLL_META_KEYS, generate_ttml_with_extensions},
+        processors::{
+            metadata_processor::MetadataStore,
+            ttml_validator::{TtmlSeverity, TtmlViolation, validate_ttml_content},
+        },
+        types::{AmllSubmissionOptions, CanonicalMetadataKey, ParsedSourceData},
+    },
+    error::Result,
+    model::track::SearchResult,
+};
+
+use super::types::IndexEntry;
+
+/// 投稿者的 GitHub 信息，对应 `ttmlAuthorGithub` 和 `ttmlAuthorGithubLogin`。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AmllAuthor {
+    /// GitHub 用户的数字 ID。
+    pub github_id: String,
+    /// GitHub 用户名。
+    pub github_login: String,
+}
+
+/// 各平台的歌曲 ID。每个平台可以有多个 ID（例如同一首歌的不同版本）。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AmllPlatformIds {
+    /// 网易云音乐歌曲 ID。
+    pub ncm_music_id: Vec<String>,
+    /// QQ 音乐歌曲 mid。
+    pub qq_music_id: Vec<String>,
+    /// Spotify 歌曲 ID。
+    pub spotify_id: Vec<String>,
+    /// Apple Music 歌曲 ID。
+    pub apple_music_id: Vec<String>,
+    /// ISRC 编码。
+    pub isrc: Vec<String>,
+}
+
+impl AmllPlatformIds {
+    /// 从提供商的搜索结果中提取平台 ID。
+    ///
+    /// 只使用来自网易云和 QQ 音乐的结果。调用方应只传入
+    /// 已确认是同一首歌的结果，例如 `search_track` 返回的高匹配度候选。
+    #[must_use]
+    pub fn from_search_results(results: &[SearchResult]) -> Self {
+        let mut ids = Self::default();
+        for result in results {
+            ids.add_search_result(result);
+        }
+        ids
+    }
+
+    /// 添加一条搜索结果中的平台 ID。返回该结果是否被使用。
+    pub fn add_search_result(&mut self, result: &SearchResult) -> bool {
+        let id = match (result.provider_name.as_str(), result.provider_id_num) {
+            ("netease", Some(num)) => num.to_string(),
+            _ => result.provider_id.trim().to_string(),
+        };
+        if id.is_empty() {
+            return false;
+        }
+        let target = match result.provider_name.as_str() {
+            "netease" => &mut self.ncm_music_id,
+            "qq" => &mut self.qq_music_id,
+            _ => return false,
+        };
+        if !target.contains(&id) {
+            target.push(id);
+        }
+        true
+    }
+
+    /// 从 AMLL 索引条目中提取所有平台 ID 和 ISRC。
+    ///
+    /// 适用于为已收录歌曲的新版本投稿，例如 `search_by_field` 或 `search` 命中的条目。
+    #[must_use]
+    pub fn from_index_entry(entry: &IndexEntry) -> Self {
+        let mut ids = Self::default();
+        ids.add_index_entry(entry);
+        ids
+    }
+
+    /// 合并一个 AMLL 索引条目中的平台 ID 和 ISRC，已有的 ID 不会重复添加。
+    pub fn add_index_entry(&mut self, entry: &IndexEntry) {
+        for (key, target) in self.entries_mut() {
+            for id in entry.get_meta_vec(key).into_iter().flatten() {
+                let id = id.trim();
+                if !id.is_empty() && !target.iter().any(|existing| existing == id) {
+                    target.push(id.to_string());
+                }
+            }
+        }
+    }
+
+    /// 是否没有任何可用于规范 §3.2 的平台 ID（ISRC 不计入）。
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ncm_music_id.is_empty()
+            && self.qq_music_id.is_empty()
+            && self.spotify_id.is_empty()
+            && self.apple_music_id.is_empty()
+    }
+
+    fn entries(&self) -> [(&'static str, &Vec<String>); 5] {
+        [
+            ("ncmMusicId", &self.ncm_music_id),
+            ("qqMusicId", &self.qq_music_id),
+            ("spotifyId", &self.spotify_id),
+            ("appleMusicId", &self.apple_music_id),
+            ("isrc", &self.isrc),
+        ]
+    }
+
+    fn entries_mut(&mut self) -> [(&'static str, &mut Vec<String>); 5] {
+        [
+            ("ncmMusicId", &mut self.ncm_music_id),
+            ("qqMusicId", &mut self.qq_music_id),
+            ("spotifyId", &mut self.spotify_id),
+            ("appleMusicId", &mut self.apple_music_id),
+            ("isrc", &mut self.isrc),
+        ]
+    }
+}
+
+/// 一份可以直接上传的投稿包。
+#[derive(Debug, Clone)]
+pub struct AmllSubmission {
+    /// 未格式化的 TTML 文本。
+    pub ttml: String,
+    /// 建议的文件名，形如 `艺术家 - 歌曲名.ttml`。
+    pub file_name: String,
+    /// 写入 `<amll:meta>` 的元数据，按输出顺序排列。
+    pub metadata: Vec<(String, Vec<String>)>,
+    /// 对生成结果的规范检查报告。
+    pub report: Vec<TtmlViolation>,
+}
+
+impl AmllSubmission {
+    /// 报告中是否没有错误级别的违规，即可以直接投稿。
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.report
+            .iter()
+            .all(|v| v.rule.severity() != TtmlSeverity::Error)
+    }
+
+    /// 将检查报告格式化为文本，每条违规一行。
+    #[must_use]
+    pub fn report_text(&self) -> String {
+        let mut text = String::new();
+        for violation in &self.report {
+            let _ = writeln!(text, "{violation}");
+        }
+        text
+    }
+
+    /// 将 TTML 文件写入指定目录，检查报告不为空时一并写出同名的 `.report.txt` 文件。
+    ///
+    /// 返回写出的 TTML 文件路径。
+    pub fn write_to_dir(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let ttml_path = dir.join(&self.file_name);
+        std::fs::write(&ttml_path, &self.ttml)?;
+        if !self.report.is_empty() {
+            std::fs::write(ttml_path.with_extension("report.txt"), self.report_text())?;
+        }
+        Ok(ttml_path)
+    }
+}
+
+/// 生成 AMLL TTML 数据库投稿包。
+///
+/// 已有的元数据会保留，`platform_ids` 中的 ID 会追加进去，投稿者信息会覆盖原有值。
+/// 生成结果总是不格式化的（规范 §6），并使用 `validate_ttml_content` 进行检查。
+pub fn build_amll_submission(
+    parsed: &ParsedSourceData,
+    author: &AmllAuthor,
+    platform_ids: &AmllPlatformIds,
+    options: &AmllSubmissionOptions,
+) -> Result<AmllSubmission> {
+    let mut metadata_store = MetadataStore::from(parsed);
+
+    for (key, ids) in platform_ids.entries() {
+        for id in ids {
+            let _ = metadata_store.add(key, id);
+        }
+    }
+    if !author.github_id.trim().is_empty() {
+        metadata_store.set_single("ttmlAuthorGithub", &author.github_id);
+    }
+    if !author.github_login.trim().is_empty() {
+        metadata_store.set_single("ttmlAuthorGithubLogin", &author.github_login);
+    }
+    if options.album_fallback_to_title
+        && metadata_store
+            .get_multiple_values(&CanonicalMetadataKey::Album)
+            .is_none_or(|v| v.iter().all(|s| s.trim().is_empty()))
+        && let Some(title) = metadata_store
+            .get_single_value(&CanonicalMetadataKey::Title)
+            .cloned()
+    {
+        metadata_store.set_single("album", &title);
+    }
+    metadata_store.deduplicate_values();
+
+    let mut ttml_options = options.ttml.clone();
+    ttml_options.format = false;
+    let mut agent_store = parsed.agents.clone();
+    agent_store.register_referenced(&parsed.lines);
+    let ttml = generate_ttml_with_extensions(
+        &parsed.lines,
+        &metadata_store,
+        &agent_store,
+        &parsed.ttml_extensions,
+        &ttml_options,
+    )?;
+    let report = validate_ttml_content(&ttml);
+
+    let metadata = AMLL_META_KEYS
+        .iter()
+        .filter_map(|(name, key)| {
+            metadata_store
+                .get_multiple_values(key)
+                .map(|values| ((*name).to_string(), values.clone()))
+        })
+        .collect();
+
+    Ok(AmllSubmission {
+        file_name: suggested_file_name(&metadata_store),
+        ttml,
+        metadata,
+        report,
+    })
+}
+
+/// 由歌曲名和艺术家生成文件名，并替换文件系统不允许的字符。
+fn suggested_file_name(metadata_store: &MetadataStore) -> String {
+    let title = metadata_store.get_single_value(&CanonicalMetadataKey::Title);
+    let artists = metadata_store
+        .get_multiple_values(&CanonicalMetadataKey::Artist)
+        .map(|values| values.join(", "));
+    let stem = match (artists, title) {
+        (Some(artists), Some(title)) => format!("{artists} - {title}"),
+        (None, Some(title)) => title.clone(),
+        _ => "lyrics".to_string(),
+    };
+    let stem: String = stem
+        .chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("{}.ttml", stem.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        converter::{
+            processors::ttml_validator::TtmlRule,
+            types::{ContentType, LyricLine},
+        },
+        model::track::MatchType,
+    };
+
+    fn sample_source() -> ParsedSourceData {
+        let mut line = LyricLine::new(1000, 3000);
+        line.add_content_track(ContentType::Main, "Hello world");
+        ParsedSourceData {
+            lines: vec![line],
+            raw_metadata: HashMap::from([
+                ("title".to_string(), vec!["Song/Name".to_string()]),
+                ("artist".to_string(), vec!["Artist".to_string()]),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_submission_fills_metadata() {
+        let results = vec![
+            SearchResult {
+                provider_name: "netease".to_string(),
+                provider_id: "ignored".to_string(),
+                provider_id_num: Some(123),
+                match_type: MatchType::Perfect,
+                ..Default::default()
+            },
+            SearchResult {
+                provider_name: "qq".to_string(),
+                provider_id: "000abc".to_string(),
+                match_type: MatchType::High,
+                ..Default::default()
+            },
+            SearchResult {
+                provider_name: "kugou".to_string(),
+                provider_id: "hash".to_string(),
+                match_type: MatchType::Perfect,
+                ..Default::default()
+            },
+        ];
+        let mut ids = AmllPlatformIds::from_search_results(&results);
+        ids.isrc.push("CNUM72400589".to_string());
+        let author = AmllAuthor {
+            github_id: "108002475".to_string(),
+            github_login: "someone".to_string(),
+        };
+
+        let submission = build_amll_submission(
+            &sample_source(),
+            &author,
+            &ids,
+            &AmllSubmissionOptions::default(),
+        )
+        .unwrap();
+
+        assert!(submission.is_ready(), "{}", submission.report_text());
+        assert!(!submission.ttml.contains('\n'));
+        assert_eq!(submission.file_name, "Artist - Song_Name.ttml");
+        let keys: Vec<&str> = submission
+            .metadata
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                "musicName",
+                "artists",
+                "album",
+                "isrc",
+                "ncmMusicId",
+                "qqMusicId",
+                "ttmlAuthorGithub",
+                "ttmlAuthorGithubLogin"
+            ]
+        );
+        assert!(
+            submission
+                .ttml
+                .contains(r#"<amll:meta key="album" value="Song/Name"/>"#)
+        );
+        assert!(
+            submission
+                .ttml
+                .contains(r#"<amll:meta key="ncmMusicId" value="123"/>"#)
+        );
+    }
+
+    #[test]
+    fn test_build_submission_reports_missing_platform_id() {
+        let submission = build_amll_submission(
+            &sample_source(),
+            &AmllAuthor::default(),
+            &AmllPlatformIds::default(),
+            &AmllSubmissionOptions::default(),
+        )
+        .unwrap();
+
+        assert!(!submission.is_ready());
+        assert!(
+            submission
+                .report
+                .iter()
+                .any(|v| v.rule == TtmlRule::MissingPlatformId)
+        );
+    }
+
+    #[test]
+    fn test_platform_ids_from_index_entry() {
+        let entry: IndexEntry = serde_json::from_str(
+            r#"{"metadata":[["musicName",["明明"]],["ncmMusicId",["2642164541"]],["qqMusicId",["000pF84f1Mqkf7"]],["spotifyId",["29OlvJxVuNd8BJazjvaYpP"]],["appleMusicId",["1741234567"," "]],["isrc",["CNUM72400589"]]],"rawLyricFile":"a.ttml"}"#,
+        )
+        .unwrap();
+
+        let mut ids = AmllPlatformIds::from_index_entry(&entry);
+        assert_eq!(ids.ncm_music_id, ["2642164541"]);
+        assert_eq!(ids.qq_music_id, ["000pF84f1Mqkf7"]);
+        assert_eq!(ids.spotify_id, ["29OlvJxVuNd8BJazjvaYpP"]);
+        assert_eq!(ids.apple_music_id, ["1741234567"]);
+        assert_eq!(ids.isrc, ["CNUM72400589"]);
+
+        ids.add_index_entry(&entry);
+        assert_eq!(ids, AmllPlatformIds::from_index_entry(&entry));
+    }
+}
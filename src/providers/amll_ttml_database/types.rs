@@ -61,6 +61,34 @@ pub enum SearchField {
 }
 
 impl SearchField {
+    /// 所有可搜索的字段。
+    pub const ALL: [Self; 10] = [
+        Self::MusicName,
+        Self::Artists,
+        Self::Album,
+        Self::NcmMusicId,
+        Self::QqMusicId,
+        Self::SpotifyId,
+        Self::AppleMusicId,
+        Self::Isrc,
+        Self::TtmlAuthorGithub,
+        Self::TtmlAuthorGithubLogin,
+    ];
+
+    /// 是否为需要模糊匹配的文本字段。其他字段（各种 ID）只做精确匹配。
+    pub const fn is_text_field(&self) -> bool {
+        matches!(self, Self::MusicName | Self::Artists | Self::Album)
+    }
+
+    /// 组合查询中该字段的权重。
+    pub const fn search_weight(&self) -> f64 {
+        match self {
+            Self::Artists => 2.0,
+            Self::Album => 1.0,
+            _ => 3.0,
+        }
+    }
+
     /// 将枚举成员转换为在索引元数据中对应的 key 字符串。
     pub fn to_metadata_key(&self) -> &'static str {
         match self {
@@ -38,7 +38,7 @@ use crate::{
     providers::Provider,
 };
 
-pub mod decrypter;
+pub use crate::converter::codecs::krc as decrypter;
 pub mod models;
 pub mod signature;
 
@@ -549,6 +549,7 @@ impl Provider for KugouMusic {
                 format: LyricFormat::Krc,
                 language: None,
                 filename: None,
+                raw_bytes: None,
             },
             translations: Vec::new(),
             romanizations: Vec::new(),
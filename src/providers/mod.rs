@@ -6,10 +6,10 @@ use async_trait::async_trait;
 
 use crate::{
     converter::types::ParsedSourceData,
-    error::Result,
+    error::{LyricsHelperError, Result},
     model::{
         generic::{self, CoverSize},
-        track::{FullLyricsResult, SearchResult},
+        track::{FullLyricsResult, LyricsAndMetadata, PlatformId, SearchResult},
     },
 };
 
@@ -74,6 +74,28 @@ pub trait Provider: Send + Sync {
         Ok(self.get_full_lyrics(song_id).await?.parsed)
     }
 
+    ///
+    /// 根据外部平台（如网易云、QQ 音乐）的歌曲 ID 精确获取歌词，无需按标题搜索。
+    ///
+    /// 默认实现返回 `ProviderNotSupported`。
+    ///
+    /// # 参数
+    /// * `platform_id` - 外部平台及其歌曲 ID。
+    ///
+    /// # 返回
+    /// 一个 `Result`，成功时包含歌词和对应的歌曲元数据。找不到对应歌曲时返回 `LyricNotFound`。
+    ///
+    async fn get_lyrics_by_platform_id(
+        &self,
+        platform_id: &PlatformId<'_>,
+    ) -> Result<LyricsAndMetadata> {
+        let _ = platform_id;
+        Err(LyricsHelperError::ProviderNotSupported(format!(
+            "{} 不支持按外部平台 ID 获取歌词",
+            self.name()
+        )))
+    }
+
     ///
     /// 根据专辑 ID 获取专辑的详细信息，包括专辑封面、发行日期和歌曲列表等。
     ///
@@ -379,6 +379,7 @@ impl Provider for MusixmatchClient {
             format: raw_main_format,
             language: None,
             filename: None,
+            raw_bytes: None,
         };
 
         let mut translations = Vec::new();
@@ -388,6 +389,7 @@ impl Provider for MusixmatchClient {
                 format: LyricFormat::Lrc,
                 language: Some("zh-Hans".to_string()),
                 filename: None,
+                raw_bytes: None,
             });
         }
 
@@ -545,6 +547,7 @@ async fn get_lrc_input(client: &MusixmatchClient, id: &str) -> Result<InputFile>
         format: LyricFormat::Lrc,
         language: None,
         filename: None,
+        raw_bytes: None,
     })
 }
 
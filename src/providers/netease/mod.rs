@@ -433,6 +433,13 @@ impl Provider for NeteaseClient {
                 Some(d.lyric.clone())
             }
         });
+        let klyric_content = resp.klyric.as_ref().and_then(|d| {
+            if d.lyric.is_empty() {
+                None
+            } else {
+                Some(d.lyric.clone())
+            }
+        });
         let lrc_content = resp.lrc.as_ref().and_then(|d| {
             if d.lyric.is_empty() {
                 None
@@ -457,6 +464,8 @@ impl Provider for NeteaseClient {
 
         let (main_format, main_content) = if let Some(content) = yrc_content {
             (LyricFormat::Yrc, content)
+        } else if let Some(content) = klyric_content {
+            (LyricFormat::Klyric, content)
         } else if let Some(content) = lrc_content {
             (LyricFormat::Lrc, content)
         } else {
@@ -468,6 +477,7 @@ impl Provider for NeteaseClient {
             format: main_format,
             language: None,
             filename: None,
+            raw_bytes: None,
         };
 
         let mut translations = Vec::new();
@@ -477,6 +487,7 @@ impl Provider for NeteaseClient {
                 format: LyricFormat::Lrc,
                 language: Some("zh-Hans".to_string()),
                 filename: None,
+                raw_bytes: None,
             });
         }
 
@@ -487,6 +498,7 @@ impl Provider for NeteaseClient {
                 format: LyricFormat::Lrc,
                 language: Some("ja-Latn".to_string()),
                 filename: None,
+                raw_bytes: None,
             });
         }
 
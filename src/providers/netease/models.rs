@@ -44,6 +44,8 @@ pub struct LyricResult {
     pub romalrc: Option<LyricData>,
     /// 逐字 YRC 歌词。
     pub yrc: Option<LyricData>,
+    /// 早期的逐字 klyric 歌词，没有 YRC 的歌曲可能仍有此格式。
+    pub klyric: Option<LyricData>,
     /// 另一种格式的翻译歌词（内容通常与 `tlyric` 相同）。
     pub ytlrc: Option<Value>,
     /// 另一种格式的罗马音歌词（内容通常与 `romalrc` 相同）。
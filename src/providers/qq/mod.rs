@@ -33,7 +33,7 @@ use crate::{
 pub mod device;
 pub mod models;
 pub mod qimei;
-pub mod qrc_codec;
+pub use crate::converter::codecs::qrc as qrc_codec;
 
 const MUSIC_U_FCG_URL: &str = "https://u.y.qq.com/cgi-bin/musicu.fcg";
 
@@ -61,17 +61,6 @@ const GET_SONG_DETAIL_METHOD: &str = "get_song_detail_yqq";
 const GET_PLAYLIST_DETAIL_MODULE: &str = "music.srfDissInfo.DissInfo";
 const GET_PLAYLIST_DETAIL_METHOD: &str = "CgiGetDiss";
 
-static QRC_LYRIC_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r#"LyricContent="([^"]*)""#).unwrap());
-
-static AMP_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"&(?![a-zA-Z]{2,6};|#[0-9]{2,4};)").unwrap());
-
-static QUOT_RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r#"(?P<attr>\s+[\w:.-]+\s*=\s*")(?P<value>(?:[^"]|"(?!\s+[\w:.-]+\s*=\s*"|\s*(?:/?|\?)>))*)"#)
-        .unwrap()
-});
-
 static YUE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[a-zA-Z]+[1-6]").unwrap());
 
 /// QQ 音乐的提供商实现。
@@ -637,43 +626,6 @@ impl QQMusic {
         Ok(results)
     }
 
-    /// 从解密后的 QRC 歌词文本中提取核心的 `LyricContent` 内容。
-    ///
-    /// 这个函数会尝试修复文本中不规范的 XML 特殊字符。
-    ///
-    /// # 参数
-    /// * `decrypted_text` - 已经解密的歌词字符串。
-    ///
-    /// # 返回
-    /// * `String` - 提取出的 `LyricContent` 内容，或在某些情况下的原始文本。
-    fn extract_from_qrc_wrapper(decrypted_text: &str) -> String {
-        if decrypted_text.is_empty() {
-            return String::new();
-        }
-
-        if !decrypted_text.starts_with("<?xml") {
-            return decrypted_text.to_string();
-        }
-
-        // 修复独立的 '&' 符号
-        let replaced_amp = AMP_RE.replace_all(decrypted_text, "&amp;");
-
-        // 修复未转义的双引号
-        let fixed_text = QUOT_RE.replace_all(&replaced_amp, |caps: &fancy_regex::Captures| {
-            let attr = &caps["attr"];
-            let value = &caps["value"];
-            format!("{}{}\"", attr, value.replace('"', "&quot;"))
-        });
-
-        if let Ok(Some(caps)) = QRC_LYRIC_RE.captures(&fixed_text)
-            && let Some(content) = caps.get(1)
-        {
-            return content.as_str().to_string();
-        }
-
-        decrypted_text.to_string()
-    }
-
     async fn try_get_lyrics_internal(&self, song_id: &str) -> Result<FullLyricsResult> {
         let mut param_map = serde_json::Map::new();
         if song_id.parse::<u64>().is_ok() {
@@ -724,7 +676,7 @@ impl QQMusic {
         };
 
         let main_lyrics_content = if main_lyric_format == LyricFormat::Qrc {
-            Self::extract_from_qrc_wrapper(&main_lyrics_decrypted)
+            qrc_codec::extract_from_qrc_wrapper(&main_lyrics_decrypted)
         } else {
             // 如果是 LRC 或其他格式，解密后的文本就是最终内容
             main_lyrics_decrypted.clone()
@@ -737,6 +689,7 @@ impl QQMusic {
                 format: LyricFormat::Lrc,
                 language: Some("zh-Hans".to_string()),
                 filename: None,
+                raw_bytes: None,
             });
         }
 
@@ -749,6 +702,7 @@ impl QQMusic {
             format: main_lyric_format,
             language: None,
             filename: None,
+            raw_bytes: None,
         };
 
         let conversion_input = ConversionInput {
@@ -858,13 +812,14 @@ impl QQMusic {
 
         let language_tag = Self::detect_romanization_language(roma_lyrics_decrypted);
 
-        let content = Self::extract_from_qrc_wrapper(roma_lyrics_decrypted);
+        let content = qrc_codec::extract_from_qrc_wrapper(roma_lyrics_decrypted);
 
         Some(InputFile {
             content,
             format: LyricFormat::Qrc,
             language: Some(language_tag),
             filename: None,
+            raw_bytes: None,
         })
     }
 
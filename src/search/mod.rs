@@ -87,7 +87,7 @@ fn finalize_multi_provider_results(results: Vec<SearchResult>) -> Vec<SearchResu
 
     let mut unique_results: Vec<SearchResult> = best_results_map.into_values().collect();
 
-    unique_results.sort_unstable_by(|a, b| b.match_type.cmp(&a.match_type));
+    unique_results.sort_unstable_by_key(|r| std::cmp::Reverse(r.match_type));
 
     unique_results
 }
@@ -194,7 +194,7 @@ fn finalize_single_provider_results(results: Vec<SearchResult>) -> Vec<SearchRes
 
     let mut unique_results: Vec<SearchResult> = best_results_map.into_values().collect();
 
-    unique_results.sort_unstable_by(|a, b| b.match_type.cmp(&a.match_type));
+    unique_results.sort_unstable_by_key(|r| std::cmp::Reverse(r.match_type));
 
     unique_results
 }
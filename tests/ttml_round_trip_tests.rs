@@ -0,0 +1,330 @@
+use lyrics_helper_rs::converter::{
+    generate_from_parsed,
+    parsers::{lys_parser::parse_lys, ttml_parser::parse_ttml},
+    processors::ttml_canonicalizer::{format_ttml, minify_ttml},
+    types::{
+        AgentType, ContentType, ConversionOptions, LyricFormat, ParsedSourceData, TrackMetadataKey,
+        TtmlGenerationOptionsBuilder, TtmlParsingOptions, TtmlTimingMode, ViewSide,
+    },
+};
+
+use std::{collections::HashMap, path::Path};
+
+fn load_test_data(filename: &str) -> String {
+    let path = Path::new("tests/test_data/round_trip").join(filename);
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("读取测试文件 '{:?}' 失败: {}", path, e))
+}
+
+/// 去掉包含换行的空白（格式化缩进），其余字符保持不变。
+fn strip_formatting(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut pending_whitespace = String::new();
+    for c in content.chars() {
+        if c.is_whitespace() {
+            pending_whitespace.push(c);
+            continue;
+        }
+        if !pending_whitespace.contains('\n') {
+            output.push_str(&pending_whitespace);
+        }
+        pending_whitespace.clear();
+        output.push(c);
+    }
+    output
+}
+
+fn regenerate(parsed: ParsedSourceData, use_apple_format_rules: bool) -> String {
+    let timing_mode = if parsed.is_line_timed_source {
+        TtmlTimingMode::Line
+    } else {
+        TtmlTimingMode::Word
+    };
+    let options = ConversionOptions {
+        ttml: TtmlGenerationOptionsBuilder::default()
+            .timing_mode(timing_mode)
+            .use_apple_format_rules(use_apple_format_rules)
+            .build()
+            .unwrap(),
+        ..Default::default()
+    };
+    generate_from_parsed(
+        parsed,
+        LyricFormat::Ttml,
+        &options,
+        &None::<HashMap<String, Vec<String>>>,
+    )
+    .unwrap()
+    .output_lyrics
+}
+
+fn round_trip(filename: &str, use_apple_format_rules: bool) -> (String, String) {
+    let content = load_test_data(filename);
+    let parsed = parse_ttml(&content, &TtmlParsingOptions::default()).unwrap();
+    let generated = regenerate(parsed, use_apple_format_rules);
+    (strip_formatting(&content), strip_formatting(&generated))
+}
+
+#[test]
+fn test_round_trip_amll_extensions() {
+    let (expected, generated) = round_trip("amll_extensions.ttml", false);
+    assert_eq!(generated, expected);
+}
+
+#[test]
+fn test_round_trip_apple_extensions() {
+    let (expected, generated) = round_trip("apple_extensions.ttml", true);
+    assert_eq!(generated, expected);
+}
+
+#[test]
+fn test_round_trip_apple_word_translations() {
+    let (expected, generated) = round_trip("apple_word_translations.ttml", true);
+    assert_eq!(generated, expected);
+}
+
+#[test]
+fn test_apple_word_translations_are_imported() {
+    let content = load_test_data("apple_word_translations.ttml");
+    let parsed = parse_ttml(&content, &TtmlParsingOptions::default()).unwrap();
+
+    let first_line = &parsed.lines[0];
+    let main = first_line
+        .tracks
+        .iter()
+        .find(|at| at.content_type == ContentType::Main)
+        .unwrap();
+    let background = first_line
+        .tracks
+        .iter()
+        .find(|at| at.content_type == ContentType::Background)
+        .unwrap();
+    assert_eq!(
+        main.translations[0].words[0].syllables[0].text,
+        "I'm not afraid"
+    );
+    assert_eq!(
+        background.translations[0].words[0].syllables[0].text,
+        "(Just interesting)"
+    );
+
+    let bg_romanization = &background.romanizations[0];
+    assert_eq!(
+        bg_romanization.metadata.get(&TrackMetadataKey::Language),
+        Some(&"ko-Latn".to_string())
+    );
+    let syllables = &bg_romanization.words[0].syllables;
+    assert_eq!(syllables.len(), 2);
+    assert_eq!(
+        (syllables[0].start_ms, syllables[0].end_ms),
+        (11_000, 11_400)
+    );
+    assert!(syllables[0].ends_with_space);
+
+    let second_line_main = &parsed.lines[1].tracks[0];
+    let translation_syllables = &second_line_main.translations[0].words[0].syllables;
+    assert_eq!(translation_syllables.len(), 2);
+    assert_eq!(translation_syllables[1].start_ms, 12_500);
+}
+
+#[test]
+fn test_round_trip_multi_agent() {
+    let (expected, generated) = round_trip("multi_agent.ttml", false);
+    assert_eq!(generated, expected);
+}
+
+#[test]
+fn test_per_syllable_agents_are_imported() {
+    let content = load_test_data("multi_agent.ttml");
+    let parsed = parse_ttml(&content, &TtmlParsingOptions::default()).unwrap();
+
+    let agent = parsed.agents.get("v2").unwrap();
+    assert_eq!(agent.name.as_deref(), Some("Bob"));
+    assert_eq!(
+        parsed.agents.get("v1000").unwrap().agent_type,
+        AgentType::Group
+    );
+
+    let first_line = &parsed.lines[0];
+    let syllables = &first_line.main_track().unwrap().content.words[0].syllables;
+    assert_eq!(first_line.agent.as_deref(), Some("v1"));
+    assert_eq!(syllables[0].agent, None);
+    assert_eq!(syllables[1].agent.as_deref(), Some("v2"));
+
+    let background = parsed.lines[1].background_track().unwrap();
+    assert_eq!(
+        background.content.words[0].syllables[0].agent.as_deref(),
+        Some("v2")
+    );
+}
+
+#[test]
+fn test_formats_without_syllable_agents_fall_back_to_line() {
+    let content = load_test_data("multi_agent.ttml");
+    let mut parsed = parse_ttml(&content, &TtmlParsingOptions::default()).unwrap();
+    parsed.lines[0].agent = None;
+    parsed.lines[0].tracks[0].content.words[0].syllables[0].agent = Some("v1".to_string());
+    assert_eq!(parsed.lines[0].primary_agent(), Some("v1"));
+
+    let mut agent = parsed.agents.get("v1").unwrap().clone();
+    agent.view_side = Some(ViewSide::Right);
+    parsed.agents.insert(agent);
+
+    let lys = generate_from_parsed(
+        parsed,
+        LyricFormat::Lys,
+        &ConversionOptions::default(),
+        &None::<HashMap<String, Vec<String>>>,
+    )
+    .unwrap()
+    .output_lyrics;
+    assert!(lys.contains("[5]I"));
+}
+
+#[test]
+fn test_round_trip_is_stable() {
+    for (filename, use_apple_format_rules) in [
+        ("amll_extensions.ttml", false),
+        ("apple_extensions.ttml", true),
+        ("apple_word_translations.ttml", true),
+        ("multi_agent.ttml", false),
+    ] {
+        let (_, first) = round_trip(filename, use_apple_format_rules);
+        let reparsed = parse_ttml(&first, &TtmlParsingOptions::default()).unwrap();
+        let second = regenerate(reparsed, use_apple_format_rules);
+        assert_eq!(second, first, "{filename} 第二次往返的结果与第一次不同");
+    }
+}
+
+#[test]
+fn test_canonical_layouts_preserve_semantics() {
+    for filename in [
+        "amll_extensions.ttml",
+        "apple_extensions.ttml",
+        "apple_word_translations.ttml",
+    ] {
+        let content = load_test_data(filename);
+        let formatted = format_ttml(&content).unwrap();
+        let minified = minify_ttml(&content).unwrap();
+
+        assert_eq!(format_ttml(&minified).unwrap(), formatted, "{filename}");
+        assert_eq!(minify_ttml(&formatted).unwrap(), minified, "{filename}");
+
+        let options = TtmlParsingOptions::default();
+        let original = parse_ttml(&content, &options).unwrap();
+        for canonical in [&formatted, &minified] {
+            let parsed = parse_ttml(canonical, &options).unwrap();
+            assert_eq!(parsed.lines, original.lines, "{filename}");
+            assert_eq!(parsed.raw_metadata, original.raw_metadata, "{filename}");
+        }
+    }
+}
+
+#[test]
+fn test_lys_view_sides_survive_ttml() {
+    let lys = "[4]Left(100,400)\n[5]Right(600,400)\n[8](Echo)(800,200)\n[3]Unset(1200,400)\n";
+    let parsed = parse_lys(lys).unwrap();
+    let ttml = regenerate(parsed, false);
+
+    assert!(ttml.contains(r#"<style xml:id="s-left" tts:textAlign="left"/>"#));
+    assert!(ttml.contains(r#"<region xml:id="r-right" style="s-right"/>"#));
+    assert!(ttml.contains(r#"ttm:agent="v2" region="r-right""#));
+
+    let reparsed = parse_ttml(&ttml, &TtmlParsingOptions::default()).unwrap();
+    let sides: Vec<_> = reparsed.lines.iter().map(|l| l.view_side).collect();
+    assert_eq!(sides, [Some(ViewSide::Left), Some(ViewSide::Right), None]);
+    assert!(reparsed.lines.iter().all(|l| l.extra_attributes.is_empty()));
+
+    let lys_again = generate_from_parsed(
+        reparsed,
+        LyricFormat::Lys,
+        &ConversionOptions::default(),
+        &None::<HashMap<String, Vec<String>>>,
+    )
+    .unwrap()
+    .output_lyrics;
+    assert!(lys_again.contains("[4]Left"));
+    assert!(lys_again.contains("[5]Right"));
+    assert!(lys_again.contains("\n[8]("));
+}
+
+#[test]
+fn test_lys_background_view_side_survives_ttml() {
+    let lys = "[4]Main(100,400)\n[8](Echo)(500,200)\n";
+    let parsed = parse_lys(lys).unwrap();
+    assert_eq!(parsed.lines[0].view_side, Some(ViewSide::Left));
+    assert_eq!(parsed.lines[0].background_view_side, Some(ViewSide::Right));
+
+    let ttml = regenerate(parsed, false);
+    assert!(ttml.contains(r#"<style xml:id="s-bg-right" tts:textAlign="right"/>"#));
+    assert!(ttml.contains(r#"ttm:role="x-bg" style="s-bg-right""#));
+
+    let reparsed = parse_ttml(&ttml, &TtmlParsingOptions::default()).unwrap();
+    assert_eq!(reparsed.lines[0].view_side, Some(ViewSide::Left));
+    assert_eq!(
+        reparsed.lines[0].background_view_side,
+        Some(ViewSide::Right)
+    );
+    assert!(reparsed.lines[0].extra_attributes.is_empty());
+    let regenerated = regenerate(reparsed.clone(), false);
+    assert_eq!(regenerated.matches(r#"xml:id="s-bg-right""#).count(), 1);
+
+    let lys_again = generate_from_parsed(
+        reparsed,
+        LyricFormat::Lys,
+        &ConversionOptions::default(),
+        &None::<HashMap<String, Vec<String>>>,
+    )
+    .unwrap()
+    .output_lyrics;
+    assert!(lys_again.contains("[4]Main"));
+    assert!(lys_again.contains("\n[8]("));
+}
+
+#[test]
+fn test_agent_colors_round_trip() {
+    let content = r##"<tt xmlns="http://www.w3.org/ns/ttml" xmlns:itunes="http://music.apple.com/lyric-ttml-internal" xmlns:ttm="http://www.w3.org/ns/ttml#metadata" xmlns:tts="http://www.w3.org/ns/ttml#styling" itunes:timing="Line"><head><metadata><ttm:agent type="person" xml:id="v1"/><ttm:agent type="person" xml:id="v2"/></metadata><styling><style xml:id="orange" tts:color="#FF8800"/></styling></head><body dur="4.000"><div begin="0.000" end="4.000"><p begin="0.000" end="2.000" itunes:key="L1" ttm:agent="v1" style="orange">Hello</p><p begin="2.000" end="4.000" itunes:key="L2" ttm:agent="v2">World</p></div></body></tt>"##;
+
+    let parsed = parse_ttml(content, &TtmlParsingOptions::default()).unwrap();
+    assert_eq!(
+        parsed.agents.get("v1").unwrap().color.as_deref(),
+        Some("#FF8800")
+    );
+    assert_eq!(parsed.agents.get("v2").unwrap().color, None);
+
+    // 源文件中的样式原样保留
+    let mut parsed_again = parsed.clone();
+    let generated = regenerate(parsed, false);
+    assert!(generated.contains(r#"ttm:agent="v1" style="orange""#));
+    assert!(!generated.contains("s-agent-"));
+
+    // 没有样式的行按演唱者颜色生成样式，重新解析后可以再次生成
+    parsed_again.lines[0].extra_attributes.clear();
+    parsed_again.ttml_extensions.head_elements.clear();
+    let generated = regenerate(parsed_again, false);
+    assert!(generated.contains(r##"<style xml:id="s-agent-v1" tts:color="#FF8800"/>"##));
+    assert!(generated.contains(r#"ttm:agent="v1" style="s-agent-v1""#));
+    assert!(!generated.contains("s-agent-v2"));
+
+    let reparsed = parse_ttml(&generated, &TtmlParsingOptions::default()).unwrap();
+    assert_eq!(
+        reparsed.agents.get("v1").unwrap().color.as_deref(),
+        Some("#FF8800")
+    );
+    assert!(reparsed.lines[0].extra_attributes.is_empty());
+    assert!(reparsed.ttml_extensions.head_elements.is_empty());
+    assert_eq!(regenerate(reparsed, false), generated);
+}
+
+#[test]
+fn test_custom_alignment_region_is_preserved() {
+    let content = r#"<tt xmlns="http://www.w3.org/ns/ttml" xmlns:itunes="http://music.apple.com/lyric-ttml-internal" xmlns:ttm="http://www.w3.org/ns/ttml#metadata" xmlns:tts="http://www.w3.org/ns/ttml#styling" itunes:timing="Line"><head><metadata><ttm:agent type="person" xml:id="v1"/></metadata><layout><region xml:id="duet" tts:origin="50% 80%" tts:textAlign="end"/></layout></head><body dur="2.000"><div begin="0.000" end="2.000"><p begin="0.000" end="2.000" itunes:key="L1" ttm:agent="v1" region="duet">Hello</p></div></body></tt>"#;
+
+    let parsed = parse_ttml(content, &TtmlParsingOptions::default()).unwrap();
+    assert_eq!(parsed.lines[0].view_side, Some(ViewSide::Right));
+
+    let generated = regenerate(parsed, false);
+    assert!(generated.contains(r#"region="duet""#));
+    assert!(!generated.contains("r-right"));
+    assert!(!generated.contains("<styling"));
+}